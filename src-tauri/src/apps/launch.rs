@@ -0,0 +1,255 @@
+use std::path::Path;
+#[cfg(not(target_os = "windows"))]
+use std::process::Command;
+use tauri::{AppHandle, Runtime};
+
+use crate::platform::App;
+
+#[cfg(target_os = "linux")]
+use crate::{platform::desktop::DesktopEntry, shell};
+#[cfg(target_os = "linux")]
+use std::{
+    ffi::OsStr,
+    process::Stdio,
+    thread,
+    time::{Duration, Instant},
+};
+
+// 启动后等待这么久，期间失败退出的视为启动失败
+#[cfg(target_os = "linux")]
+const STARTUP_CHECK: Duration = Duration::from_millis(300);
+
+// 启动后不等待子进程结束，只报告无法启动或马上失败退出的情况
+#[cfg(target_os = "linux")]
+pub fn spawn_detached(mut command: Command, program: &str) -> Result<(), String> {
+    use std::os::unix::process::CommandExt;
+    // 放到新的进程组中，toolbox 退出或收到信号时不影响应用
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    let started = Instant::now();
+    while started.elapsed() < STARTUP_CHECK {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("{} exited with {}", program, status)),
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    // 回收子进程，避免留下僵尸进程
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// .desktop 文件按其中的 Exec 启动，Terminal=true 时在终端中运行，Path 为工作目录
+// 其它文件当作可执行文件直接运行
+#[cfg(target_os = "linux")]
+pub fn launch<R: Runtime>(
+    app: &AppHandle<R>,
+    target: &App,
+    files: &[String],
+    args: &[String],
+) -> Result<(), String> {
+    let path = target
+        .path
+        .as_deref()
+        .ok_or("App has no path".to_string())?;
+    if path.extension() != Some(OsStr::new("desktop")) {
+        let mut command = Command::new(path);
+        command.args(files).args(args);
+        return spawn_detached(command, &path.to_string_lossy());
+    }
+    let entry = DesktopEntry::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    // Path 指向的目录不存在时在当前目录启动
+    let cwd = entry.path.as_deref().filter(|dir| dir.is_dir());
+    for line in entry.command_lines(path, files, args)? {
+        let mut command = if entry.terminal {
+            shell::terminal_command(app, &line)?
+        } else {
+            let mut command = Command::new(&line[0]);
+            command.args(&line[1..]);
+            command
+        };
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+        spawn_detached(command, &line[0])?;
+    }
+    Ok(())
+}
+
+// 通过 `open -a` 启动可执行文件所在的 .app 包，由 launchd 启动，不是 toolbox 的子进程
+#[cfg(target_os = "macos")]
+pub fn launch<R: Runtime>(
+    _app: &AppHandle<R>,
+    target: &App,
+    files: &[String],
+    args: &[String],
+) -> Result<(), String> {
+    let path = target
+        .path
+        .as_deref()
+        .ok_or("App has no path".to_string())?;
+    let bundle = path
+        .ancestors()
+        .find(|dir| dir.extension().is_some_and(|extension| extension == "app"))
+        .unwrap_or(path);
+    let mut command = Command::new("open");
+    command.arg("-a").arg(bundle).args(files);
+    if !args.is_empty() {
+        command.arg("--args").args(args);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to start {}: {}", bundle.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to start {}: {}",
+            bundle.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// 按 CommandLineToArgvW 的规则给参数加引号
+#[cfg(target_os = "windows")]
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        if c == '"' {
+            quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+        } else {
+            quoted.push_str(&"\\".repeat(backslashes));
+        }
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+// 和资源管理器一样通过 ShellExecute 启动快捷方式的目标，带上快捷方式中的参数和起始位置
+// 控制台程序会有自己的控制台窗口，非可执行文件按文件关联打开
+#[cfg(target_os = "windows")]
+pub fn launch<R: Runtime>(
+    _app: &AppHandle<R>,
+    target: &App,
+    files: &[String],
+    args: &[String],
+) -> Result<(), String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let path = target
+        .path
+        .as_deref()
+        .ok_or("App has no path".to_string())?;
+    let parameters: Vec<String> = target
+        .arguments
+        .iter()
+        .filter(|arguments| !arguments.is_empty())
+        .cloned()
+        .chain(files.iter().chain(args.iter()).map(|arg| quote(arg)))
+        .collect();
+    let parameters = HSTRING::from(parameters.join(" "));
+    let directory = target
+        .working_directory
+        .as_deref()
+        .filter(|dir| dir.is_dir())
+        .or(path.parent())
+        .map(|dir| HSTRING::from(dir.as_os_str()));
+    let result = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            &HSTRING::from("open"),
+            &HSTRING::from(path.as_os_str()),
+            &parameters,
+            directory
+                .as_ref()
+                .map_or(PCWSTR::null(), |dir| PCWSTR(dir.as_ptr())),
+            SW_SHOWNORMAL,
+        )
+    };
+    // 返回值不大于 32 时为错误码
+    if result.0 as isize <= 32 {
+        return Err(format!(
+            "Failed to start {}: error {}",
+            path.display(),
+            result.0 as isize
+        ));
+    }
+    Ok(())
+}
+
+// 用系统默认的程序打开文件或目录
+#[cfg(target_os = "linux")]
+pub fn open(path: &Path) -> Result<(), String> {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    spawn_detached(command, "xdg-open")
+}
+
+#[cfg(target_os = "macos")]
+pub fn open(path: &Path) -> Result<(), String> {
+    let output = Command::new("open")
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to open {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// 路径直接交给 ShellExecute，不经过 cmd 解析
+#[cfg(target_os = "windows")]
+pub fn open(path: &Path) -> Result<(), String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let result = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            &HSTRING::from("open"),
+            &HSTRING::from(path.as_os_str()),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // 返回值不大于 32 时为错误码
+    if result.0 as isize <= 32 {
+        return Err(format!(
+            "Failed to open {}: error {}",
+            path.display(),
+            result.0 as isize
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,356 @@
+use notify::RecommendedWatcher;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::icons::{self, IconService};
+use crate::platform::{App, Installed};
+use crate::search::{self, watcher};
+use crate::utils::store;
+
+mod launch;
+
+pub use launch::open;
+#[cfg(target_os = "linux")]
+pub use launch::spawn_detached;
+
+// 应用列表缓存文件，位于 app_cache_dir 中
+const CACHE_FILE: &str = "apps.json";
+// 应用配置在 store 中的键
+pub const CONFIG_KEY: &str = "apps";
+// 应用列表变化时发出的事件
+pub const CHANGED_EVENT: &str = "apps-changed";
+
+// 应用列表的变化，按路径比较
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsChanged {
+    pub added: Vec<App>,
+    pub removed: Vec<App>,
+    pub updated: Vec<App>,
+}
+
+impl AppsChanged {
+    fn between(old: &[App], new: &[App]) -> Self {
+        let old_apps: HashMap<String, &App> = old.iter().map(|app| (key(app), app)).collect();
+        let new_keys: HashMap<String, &App> = new.iter().map(|app| (key(app), app)).collect();
+        let mut changed = AppsChanged::default();
+        for app in new.iter() {
+            match old_apps.get(&key(app)) {
+                None => changed.added.push(app.clone()),
+                Some(old) if *old != app => changed.updated.push(app.clone()),
+                Some(_) => {}
+            }
+        }
+        changed.removed = old
+            .iter()
+            .filter(|app| !new_keys.contains_key(&key(app)))
+            .cloned()
+            .collect();
+        changed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+// 没有路径的应用按名称区分
+fn key(app: &App) -> String {
+    match app.path.as_ref() {
+        Some(path) => path.to_string_lossy().to_string(),
+        None => app.name.clone().unwrap_or_default(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppsConfig {
+    // 查找 AppImage 的目录，只在 Linux 下使用
+    pub appimage_dirs: Vec<PathBuf>,
+}
+
+impl Default for AppsConfig {
+    fn default() -> Self {
+        AppsConfig {
+            appimage_dirs: std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join("Applications"))
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl AppsConfig {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        store::get(app, CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppCatalogStatus {
+    pub apps: usize,
+    pub scanning: bool,
+    // 最近一次扫描完成的时间（秒）
+    pub last_scan: u64,
+    // 最近一次扫描中读取失败的目录或应用
+    pub warnings: Vec<String>,
+}
+
+// 本机安装的应用，启动时读取缓存，后台扫描后更新
+#[derive(Default)]
+pub struct AppCatalog {
+    apps: RwLock<Vec<App>>,
+    config: RwLock<AppsConfig>,
+    warnings: RwLock<Vec<String>>,
+    scanning: AtomicBool,
+    // 有待处理的扫描请求，正在扫描时由扫描的线程接着再扫描一次
+    pending: AtomicBool,
+    last_scan: AtomicU64,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl AppCatalog {
+    pub fn list(&self) -> Vec<App> {
+        self.apps.read().unwrap().clone()
+    }
+
+    pub fn status(&self) -> AppCatalogStatus {
+        AppCatalogStatus {
+            apps: self.apps.read().unwrap().len(),
+            scanning: self.scanning.load(Ordering::SeqCst),
+            last_scan: self.last_scan.load(Ordering::SeqCst),
+            warnings: self.warnings.read().unwrap().clone(),
+        }
+    }
+}
+
+fn cache_file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, apps: &[App]) {
+    let result = cache_file(app).and_then(|file| {
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string(apps).map_err(|e| e.to_string())?;
+        fs::write(file, content).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to save app list: {}", e);
+    }
+}
+
+// 图标换成缓存中已有的 PNG，还没有生成的图标在后台扫描后补上
+fn present<R: Runtime>(app: &AppHandle<R>, apps: &[App]) -> Vec<App> {
+    let icons = app.try_state::<IconService>();
+    apps.iter()
+        .cloned()
+        .map(|mut item| {
+            item.icon = match (item.icon.take(), icons.as_ref()) {
+                (Some(source), Some(icons)) => icons.cached(&source, icons::DEFAULT_SIZE),
+                _ => None,
+            };
+            item
+        })
+        .collect()
+}
+
+// 重新扫描应用，有变化时更新缓存、清空搜索索引并发出事件
+pub fn refresh<R: Runtime>(app: &AppHandle<R>) {
+    let catalog = app.state::<AppCatalog>();
+    // 先设置 pending 再尝试开始扫描，正在扫描的线程一定能看到这次请求
+    catalog.pending.store(true, Ordering::SeqCst);
+    loop {
+        if catalog.scanning.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        while catalog.pending.swap(false, Ordering::SeqCst) {
+            #[allow(unused_mut)]
+            let mut installed = Installed::new();
+            #[cfg(target_os = "linux")]
+            installed.add_appimages(&catalog.config.read().unwrap().appimage_dirs);
+            for warning in installed.warnings.iter() {
+                eprintln!("Failed to read app: {}", warning);
+            }
+            *catalog.warnings.write().unwrap() = installed.warnings;
+            let changed = AppsChanged::between(&catalog.list(), &installed.apps);
+            if !changed.is_empty() {
+                // 先生成图标，前端收到事件后即可显示
+                if let Some(icons) = app.try_state::<IconService>() {
+                    for item in changed.added.iter().chain(changed.updated.iter()) {
+                        if let Some(source) = item.icon.as_ref() {
+                            icons.resolve(source, icons::DEFAULT_SIZE);
+                        }
+                    }
+                    icons.save();
+                }
+                save(app, &installed.apps);
+                *catalog.apps.write().unwrap() = installed.apps;
+                search::invalidate(app);
+                let event = AppsChanged {
+                    added: present(app, &changed.added),
+                    removed: present(app, &changed.removed),
+                    updated: present(app, &changed.updated),
+                };
+                if let Err(e) = app.emit(CHANGED_EVENT, event) {
+                    eprintln!("Failed to emit {}: {}", CHANGED_EVENT, e);
+                }
+            }
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or_default();
+            catalog.last_scan.store(now, Ordering::SeqCst);
+        }
+        catalog.scanning.store(false, Ordering::SeqCst);
+        // 退出循环后、清除 scanning 前到达的请求由这里接着处理
+        if !catalog.pending.load(Ordering::SeqCst) {
+            return;
+        }
+    }
+}
+
+// 监听应用目录，目录配置变化后重新监听
+fn watch<R: Runtime>(app: &AppHandle<R>) {
+    let catalog = app.state::<AppCatalog>();
+    #[allow(unused_mut)]
+    let mut dirs = Installed::dirs();
+    #[cfg(target_os = "linux")]
+    dirs.extend(catalog.config.read().unwrap().appimage_dirs.iter().cloned());
+    let dirs: Vec<PathBuf> = dirs.into_iter().filter(|dir| dir.is_dir()).collect();
+    let handle = app.clone();
+    let watched = watcher::watch(&dirs, move |changes| {
+        if changes.rescan || !changes.paths.is_empty() {
+            refresh(&handle);
+        }
+        true
+    });
+    match watched {
+        Ok(watched) => {
+            *catalog.watcher.lock().unwrap() = Some(watched);
+        }
+        Err(e) => eprintln!("Failed to watch app directories: {}", e),
+    }
+}
+
+// 启动时读取缓存，在后台扫描并监听应用目录
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let cached: Vec<App> = cache_file(app)
+        .ok()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let catalog = app.state::<AppCatalog>();
+    *catalog.apps.write().unwrap() = cached;
+    *catalog.config.write().unwrap() = AppsConfig::load(app);
+
+    let handle = app.clone();
+    thread::spawn(move || {
+        refresh(&handle);
+        watch(&handle);
+    });
+}
+
+// 配置在别处修改后（如导入备份）重新读取，并在后台重新扫描和监听
+pub fn reload<R: Runtime>(app: &AppHandle<R>) {
+    *app.state::<AppCatalog>().config.write().unwrap() = AppsConfig::load(app);
+    let handle = app.clone();
+    thread::spawn(move || {
+        refresh(&handle);
+        watch(&handle);
+    });
+}
+
+// 获取本机安装的 app 列表，直接返回缓存，变化通过 apps-changed 事件通知
+#[command]
+pub fn get_installed_apps(app: AppHandle, catalog: State<'_, AppCatalog>) -> Vec<App> {
+    present(&app, &catalog.apps.read().unwrap())
+}
+
+#[command]
+pub fn get_apps_status(catalog: State<'_, AppCatalog>) -> AppCatalogStatus {
+    catalog.status()
+}
+
+#[command]
+pub fn get_apps_config(catalog: State<'_, AppCatalog>) -> AppsConfig {
+    catalog.config.read().unwrap().clone()
+}
+
+// 保存配置，重新扫描并监听应用目录
+#[command]
+pub async fn set_apps_config(
+    app: AppHandle,
+    config: AppsConfig,
+) -> Result<AppCatalogStatus, String> {
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store::set(&app, CONFIG_KEY, Some(value))?;
+    *app.state::<AppCatalog>().config.write().unwrap() = config;
+    tauri::async_runtime::spawn_blocking(move || {
+        refresh(&app);
+        watch(&app);
+        app.state::<AppCatalog>().status()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// 立即重新扫描，返回扫描后的应用数量
+#[command]
+pub async fn refresh_apps(app: AppHandle) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        refresh(&app);
+        app.state::<AppCatalog>().apps.read().unwrap().len()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// 启动应用，app_id 为搜索结果的 id（`app:路径`）或应用的路径
+// files 为要用应用打开的文件，args 为追加在最后的参数
+#[command]
+pub async fn launch_app(
+    app: AppHandle,
+    app_id: String,
+    files: Option<Vec<String>>,
+    args: Option<Vec<String>>,
+) -> Result<(), String> {
+    let id = app_id.strip_prefix("app:").unwrap_or(&app_id);
+    let target = app
+        .state::<AppCatalog>()
+        .apps
+        .read()
+        .unwrap()
+        .iter()
+        .find(|item| key(item) == id)
+        .cloned()
+        .ok_or(format!("App `{}` not found", app_id))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        launch::launch(
+            &app,
+            &target,
+            &files.unwrap_or_default(),
+            &args.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
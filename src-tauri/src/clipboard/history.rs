@@ -0,0 +1,475 @@
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// 历史记录文件和图片目录，位于 app_data_dir/clipboard 中
+const HISTORY_FILE: &str = "history.json";
+const IMAGE_DIR: &str = "images";
+// 搜索结果标题的最大长度
+const MAX_TITLE_CHARS: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipKind {
+    Text,
+    Image,
+    Files,
+}
+
+// 从剪贴板读取的内容
+#[derive(Debug, Clone)]
+pub enum ClipContent {
+    Text(String),
+    // RGBA 像素
+    Image {
+        width: usize,
+        height: usize,
+        bytes: Vec<u8>,
+    },
+    Files(Vec<PathBuf>),
+}
+
+impl ClipContent {
+    pub fn kind(&self) -> ClipKind {
+        match self {
+            ClipContent::Text(_) => ClipKind::Text,
+            ClipContent::Image { .. } => ClipKind::Image,
+            ClipContent::Files(_) => ClipKind::Files,
+        }
+    }
+
+    // 内容的哈希，相同的内容只保留一条
+    pub fn hash(&self) -> String {
+        let mut context = md5::Context::new();
+        match self {
+            ClipContent::Text(text) => {
+                context.consume(b"text:");
+                context.consume(text.as_bytes());
+            }
+            ClipContent::Image {
+                width,
+                height,
+                bytes,
+            } => {
+                context.consume(format!("image:{}x{}:", width, height).as_bytes());
+                context.consume(bytes);
+            }
+            ClipContent::Files(files) => {
+                context.consume(b"files:");
+                for file in files {
+                    context.consume(file.to_string_lossy().as_bytes());
+                    context.consume(b"\n");
+                }
+            }
+        }
+        format!("{:x}", context.compute())
+    }
+}
+
+// 一条剪贴板历史
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipEntry {
+    // 内容的哈希
+    pub id: String,
+    pub kind: ClipKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PathBuf>,
+    // 图片保存为 png 文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<PathBuf>,
+    #[serde(default)]
+    pub width: usize,
+    #[serde(default)]
+    pub height: usize,
+    // 复制时的前台应用
+    pub source: Option<String>,
+    pub pinned: bool,
+    pub created: i64,
+    pub last_used: i64,
+    pub count: u32,
+}
+
+impl ClipEntry {
+    // 显示在搜索结果中的标题
+    pub fn title(&self) -> String {
+        match self.kind {
+            ClipKind::Text => {
+                let text = self.text.as_deref().unwrap_or_default().trim();
+                let line = text.lines().next().unwrap_or_default();
+                let mut title: String = line.chars().take(MAX_TITLE_CHARS).collect();
+                if title.len() < text.len() {
+                    title.push('…');
+                }
+                title
+            }
+            ClipKind::Image => format!("图片 {}×{}", self.width, self.height),
+            ClipKind::Files => self
+                .files
+                .iter()
+                .map(|file| {
+                    file.file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or(file.to_string_lossy().to_string())
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    // 参与搜索的文本
+    fn search_text(&self) -> String {
+        let mut text = match self.kind {
+            ClipKind::Text => self.text.clone().unwrap_or_default(),
+            ClipKind::Image => self.title(),
+            ClipKind::Files => self
+                .files
+                .iter()
+                .map(|file| file.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        if let Some(source) = self.source.as_ref() {
+            text.push('\n');
+            text.push_str(source);
+        }
+        text.to_lowercase()
+    }
+
+    // 重新写入剪贴板的内容，图片从文件读取
+    pub fn content(&self) -> Result<ClipContent, String> {
+        match self.kind {
+            ClipKind::Text => Ok(ClipContent::Text(self.text.clone().unwrap_or_default())),
+            ClipKind::Files => Ok(ClipContent::Files(self.files.clone())),
+            ClipKind::Image => {
+                let path = self.image.as_ref().ok_or("Image file is missing")?;
+                let image = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+                Ok(ClipContent::Image {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes: image.into_raw(),
+                })
+            }
+        }
+    }
+}
+
+// 剪贴板历史，最新的在前
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClipHistory {
+    pub entries: Vec<ClipEntry>,
+}
+
+impl ClipHistory {
+    pub fn load(dir: &Path) -> Self {
+        fs::read_to_string(dir.join(HISTORY_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(dir.join(HISTORY_FILE), content).map_err(|e| e.to_string())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ClipEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    // 添加一条记录，已有相同内容时移到最前面
+    pub fn add(
+        &mut self,
+        dir: &Path,
+        content: ClipContent,
+        source: Option<String>,
+    ) -> Result<ClipEntry, String> {
+        let id = content.hash();
+        let now = chrono::Local::now().timestamp_millis();
+        if let Some(pos) = self.entries.iter().position(|entry| entry.id == id) {
+            let mut entry = self.entries.remove(pos);
+            entry.last_used = now;
+            entry.count += 1;
+            if source.is_some() {
+                entry.source = source;
+            }
+            self.entries.insert(0, entry.clone());
+            return Ok(entry);
+        }
+        let mut entry = ClipEntry {
+            id: id.clone(),
+            kind: content.kind(),
+            text: None,
+            files: Vec::new(),
+            image: None,
+            width: 0,
+            height: 0,
+            source,
+            pinned: false,
+            created: now,
+            last_used: now,
+            count: 1,
+        };
+        match content {
+            ClipContent::Text(text) => entry.text = Some(text),
+            ClipContent::Files(files) => entry.files = files,
+            ClipContent::Image {
+                width,
+                height,
+                bytes,
+            } => {
+                let image = RgbaImage::from_raw(width as u32, height as u32, bytes)
+                    .ok_or("Invalid image data")?;
+                let image_dir = dir.join(IMAGE_DIR);
+                fs::create_dir_all(&image_dir).map_err(|e| e.to_string())?;
+                let path = image_dir.join(format!("{}.png", id));
+                image.save(&path).map_err(|e| e.to_string())?;
+                entry.image = Some(path);
+                entry.width = width;
+                entry.height = height;
+            }
+        }
+        self.entries.insert(0, entry.clone());
+        Ok(entry)
+    }
+
+    // 重新复制某条记录后移到最前面
+    pub fn touch(&mut self, id: &str) {
+        if let Some(pos) = self.entries.iter().position(|entry| entry.id == id) {
+            let mut entry = self.entries.remove(pos);
+            entry.last_used = chrono::Local::now().timestamp_millis();
+            self.entries.insert(0, entry);
+        }
+    }
+
+    pub fn set_pinned(&mut self, id: &str, pinned: bool) -> Result<(), String> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(format!("Clipboard entry `{}` not found", id))?;
+        entry.pinned = pinned;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        let removed: Vec<ClipEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.id == id)
+            .cloned()
+            .collect();
+        self.entries.retain(|entry| entry.id != id);
+        remove_images(&removed);
+    }
+
+    // 清空历史，固定的记录保留
+    pub fn clear(&mut self) {
+        let (pinned, removed): (Vec<ClipEntry>, Vec<ClipEntry>) =
+            self.entries.drain(..).partition(|entry| entry.pinned);
+        self.entries = pinned;
+        remove_images(&removed);
+    }
+
+    // 超过上限时删除最久未使用的记录，固定的记录不计入上限
+    pub fn prune(&mut self, max_items: usize) {
+        let mut count = 0;
+        let mut removed = Vec::new();
+        self.entries.retain(|entry| {
+            if entry.pinned {
+                return true;
+            }
+            count += 1;
+            if count > max_items {
+                removed.push(entry.clone());
+                return false;
+            }
+            true
+        });
+        remove_images(&removed);
+    }
+
+    // 按关键词搜索，所有关键词都要包含，固定的记录在前
+    pub fn search(&self, query: &str, limit: usize) -> Vec<ClipEntry> {
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect();
+        let mut results: Vec<ClipEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                if words.is_empty() {
+                    return true;
+                }
+                let text = entry.search_text();
+                words.iter().all(|word| text.contains(word.as_str()))
+            })
+            .cloned()
+            .collect();
+        // 稳定排序，同组内保持从新到旧
+        results.sort_by_key(|entry| !entry.pinned);
+        results.truncate(limit);
+        results
+    }
+}
+
+fn remove_images(entries: &[ClipEntry]) {
+    for path in entries.iter().filter_map(|entry| entry.image.as_ref()) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "toolbox-clipboard-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn text(value: &str) -> ClipContent {
+        ClipContent::Text(value.to_string())
+    }
+
+    fn image() -> ClipContent {
+        ClipContent::Image {
+            width: 2,
+            height: 1,
+            bytes: vec![255, 0, 0, 255, 0, 0, 255, 255],
+        }
+    }
+
+    fn texts(entries: &[ClipEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| entry.text.clone().unwrap_or_else(|| entry.title()))
+            .collect()
+    }
+
+    #[test]
+    fn deduplicates_by_hash() {
+        let temp = TempDir::new("dedup");
+        let mut history = ClipHistory::default();
+        let first = history.add(&temp.0, text("hello"), None).unwrap();
+        history.add(&temp.0, text("world"), None).unwrap();
+        let again = history
+            .add(&temp.0, text("hello"), Some("Editor".to_string()))
+            .unwrap();
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.count, 2);
+        assert_eq!(again.source.as_deref(), Some("Editor"));
+        assert_eq!(texts(&history.entries), ["hello", "world"]);
+
+        // 内容相同但类型不同的不合并
+        let path = ClipContent::Files(vec![PathBuf::from("hello")]);
+        assert_ne!(path.hash(), text("hello").hash());
+        history.add(&temp.0, path, None).unwrap();
+        assert_eq!(history.entries.len(), 3);
+
+        let entry = history.add(&temp.0, image(), None).unwrap();
+        assert!(entry.image.as_ref().unwrap().is_file());
+        assert_eq!(entry.title(), "图片 2×1");
+        history.add(&temp.0, image(), None).unwrap();
+        assert_eq!(history.entries.len(), 4);
+
+        history.save(&temp.0).unwrap();
+        let loaded = ClipHistory::load(&temp.0);
+        assert_eq!(texts(&loaded.entries), texts(&history.entries));
+        assert_eq!(loaded.get(&first.id).unwrap().count, 2);
+    }
+
+    #[test]
+    fn prunes_unpinned_entries() {
+        let temp = TempDir::new("prune");
+        let mut history = ClipHistory::default();
+        let image = history.add(&temp.0, image(), None).unwrap();
+        let pinned = history.add(&temp.0, text("pinned"), None).unwrap();
+        for value in ["a", "b", "c"] {
+            history.add(&temp.0, text(value), None).unwrap();
+        }
+        history.set_pinned(&pinned.id, true).unwrap();
+        assert!(history.set_pinned("missing", true).is_err());
+
+        // 固定的记录不计入上限，超出的从最旧的开始删除
+        history.prune(2);
+        assert_eq!(texts(&history.entries), ["c", "b", "pinned"]);
+        assert!(!image.image.unwrap().exists());
+        history.prune(0);
+        assert_eq!(texts(&history.entries), ["pinned"]);
+    }
+
+    #[test]
+    fn clears_all_but_pinned_entries() {
+        let temp = TempDir::new("clear");
+        let mut history = ClipHistory::default();
+        let pinned = history.add(&temp.0, image(), None).unwrap();
+        let removed = history.add(&temp.0, text("a"), None).unwrap();
+        history.add(&temp.0, text("b"), None).unwrap();
+        history.set_pinned(&pinned.id, true).unwrap();
+
+        history.remove(&removed.id);
+        assert_eq!(texts(&history.entries), ["b", "图片 2×1"]);
+        history.clear();
+        assert_eq!(history.entries.len(), 1);
+        assert!(history.entries[0].pinned);
+        assert!(pinned.image.unwrap().is_file());
+    }
+
+    #[test]
+    fn searches_pinned_first() {
+        let temp = TempDir::new("search");
+        let mut history = ClipHistory::default();
+        for value in ["Rust book", "rust nightly", "go", "Rust pinned"] {
+            history.add(&temp.0, text(value), None).unwrap();
+        }
+        let source = history
+            .add(&temp.0, text("notes"), Some("RustRover".to_string()))
+            .unwrap();
+        let pinned = history.entries[1].id.clone();
+        history.set_pinned(&pinned, true).unwrap();
+
+        assert_eq!(
+            texts(&history.search("rust", 10)),
+            ["Rust pinned", "notes", "rust nightly", "Rust book"]
+        );
+        assert_eq!(texts(&history.search("RUST  book", 10)), ["Rust book"]);
+        assert_eq!(texts(&history.search("rust", 2)), ["Rust pinned", "notes"]);
+        assert_eq!(history.search("", 10).len(), 5);
+        assert!(history.search("python", 10).is_empty());
+
+        // 重新复制后移到最前面
+        history.touch(&history.entries[4].id.clone());
+        assert_eq!(
+            texts(&history.search("", 10)),
+            ["Rust pinned", "Rust book", "notes", "go", "rust nightly"]
+        );
+        assert_eq!(
+            history.get(&source.id).unwrap().source.as_deref(),
+            Some("RustRover")
+        );
+    }
+}
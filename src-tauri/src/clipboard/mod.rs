@@ -0,0 +1,385 @@
+use arboard::{Clipboard, ImageData};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{Mutex, RwLock},
+    thread,
+    time::Duration,
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use crate::search::index::{ResultKind, SearchResult};
+use crate::utils::store;
+
+pub mod history;
+mod monitor;
+#[cfg(target_os = "linux")]
+mod x11;
+
+pub use history::{ClipContent, ClipEntry, ClipHistory};
+
+// 搜索框中以此开头时搜索剪贴板历史
+pub const QUERY_PREFIX: &str = "cb:";
+// 新增记录时发送给前端的事件
+pub const CHANGED_EVENT: &str = "clipboard-changed";
+// 配置在 store 中的键
+pub const CONFIG_KEY: &str = "clipboard";
+// 历史数据目录，位于 app_data_dir 中
+const DATA_DIR: &str = "clipboard";
+// 粘贴前等待焦点回到原来的窗口
+const PASTE_DELAY: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardConfig {
+    pub enabled: bool,
+    // 最多保留的记录数，固定的记录不计入
+    pub max_items: usize,
+    // 文本的最大字节数
+    pub max_text_size: usize,
+    // 图片的最大字节数（RGBA 像素）
+    pub max_image_size: usize,
+    pub record_images: bool,
+    pub record_files: bool,
+    // 是否记录 X11 的主选区（选中即复制）
+    pub watch_primary: bool,
+    // 忽略密码管理器等标记为敏感的内容
+    pub ignore_sensitive: bool,
+    // 不记录这些应用中复制的内容，不区分大小写
+    pub exclude_apps: Vec<String>,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        ClipboardConfig {
+            enabled: true,
+            max_items: 500,
+            max_text_size: 1024 * 1024,
+            max_image_size: 32 * 1024 * 1024,
+            record_images: true,
+            record_files: true,
+            watch_primary: false,
+            ignore_sensitive: true,
+            exclude_apps: ["KeePassXC", "KeePass", "1Password", "Bitwarden"]
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl ClipboardConfig {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        store::get(app, CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_excluded(&self, source: &str) -> bool {
+        self.exclude_apps
+            .iter()
+            .any(|app| app.eq_ignore_ascii_case(source))
+    }
+}
+
+// 剪贴板历史服务
+#[derive(Default)]
+pub struct ClipboardService {
+    history: Mutex<ClipHistory>,
+    config: RwLock<ClipboardConfig>,
+    // 写入剪贴板用，保持存活以免 X11 下内容随实例释放而丢失
+    clipboard: Mutex<Option<Clipboard>>,
+    // 最近一次记录或写入的内容哈希
+    last_hash: Mutex<Option<String>>,
+}
+
+impl ClipboardService {
+    // 记录剪贴板内容，与上一次相同时返回 None
+    pub fn record<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        content: ClipContent,
+        source: Option<String>,
+    ) -> Result<Option<ClipEntry>, String> {
+        let hash = content.hash();
+        {
+            let mut last_hash = self.last_hash.lock().unwrap();
+            if last_hash.as_deref() == Some(hash.as_str()) {
+                return Ok(None);
+            }
+            *last_hash = Some(hash);
+        }
+        let dir = data_dir(app)?;
+        let max_items = self.config.read().unwrap().max_items;
+        let mut history = self.history.lock().unwrap();
+        let entry = history.add(&dir, content, source)?;
+        history.prune(max_items);
+        history.save(&dir)?;
+        Ok(Some(entry))
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.history
+            .lock()
+            .unwrap()
+            .search(query, limit)
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let time = chrono::DateTime::from_timestamp_millis(entry.last_used)
+                    .map(|time| {
+                        time.with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                let subtitle = match entry.source.as_ref() {
+                    Some(source) => format!("{} · {}", time, source),
+                    None => time,
+                };
+                SearchResult {
+                    id: format!("clipboard:{}", entry.id),
+                    kind: ResultKind::Clipboard,
+                    title: entry.title(),
+                    subtitle: Some(subtitle),
+                    icon: entry.image.clone(),
+                    // 已按固定和时间排序
+                    score: 1000.0 - i as f64,
+                    highlights: Vec::new(),
+                    data: json!(entry),
+                    labels: Vec::new(),
+                    actions: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    // 把一条记录重新写入剪贴板，并移到最前面
+    pub fn copy<R: Runtime>(&self, app: &AppHandle<R>, id: &str) -> Result<(), String> {
+        let content = {
+            let history = self.history.lock().unwrap();
+            history
+                .get(id)
+                .ok_or(format!("Clipboard entry `{}` not found", id))?
+                .content()?
+        };
+        *self.last_hash.lock().unwrap() = Some(content.hash());
+        self.with_clipboard(|clipboard| match content {
+            ClipContent::Text(text) => clipboard.set_text(text),
+            ClipContent::Files(files) => clipboard.set().file_list(&files),
+            ClipContent::Image {
+                width,
+                height,
+                bytes,
+            } => clipboard.set_image(ImageData {
+                width,
+                height,
+                bytes: Cow::Owned(bytes),
+            }),
+        })?;
+        let mut history = self.history.lock().unwrap();
+        history.touch(id);
+        history.save(&data_dir(app)?)
+    }
+
+    // 写入文本，会和普通复制一样记录到历史
+    pub fn set_text(&self, text: &str) -> Result<(), String> {
+        self.with_clipboard(|clipboard| clipboard.set_text(text))
+    }
+
+    pub fn get_text(&self) -> Option<String> {
+        self.with_clipboard(|clipboard| clipboard.get_text()).ok()
+    }
+
+    fn with_clipboard<T>(
+        &self,
+        f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, String> {
+        let mut clipboard = self.clipboard.lock().unwrap();
+        if clipboard.is_none() {
+            *clipboard = Some(Clipboard::new().map_err(|e| e.to_string())?);
+        }
+        f(clipboard.as_mut().unwrap()).map_err(|e| e.to_string())
+    }
+
+    fn update<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        f: impl FnOnce(&mut ClipHistory) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut history = self.history.lock().unwrap();
+        f(&mut history)?;
+        history.save(&data_dir(app)?)
+    }
+}
+
+fn data_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DATA_DIR))
+        .map_err(|e| e.to_string())
+}
+
+// 启动时调用：读取历史并开始监听剪贴板
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let service = app.state::<ClipboardService>();
+    *service.config.write().unwrap() = ClipboardConfig::load(app);
+    if let Ok(dir) = data_dir(app) {
+        *service.history.lock().unwrap() = ClipHistory::load(&dir);
+    }
+    monitor::start(app);
+}
+
+// 配置在别处修改后（如导入备份）重新读取，历史记录按新的数量上限清理
+pub fn reload<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let service = app.state::<ClipboardService>();
+    let config = ClipboardConfig::load(app);
+    let max_items = config.max_items;
+    *service.config.write().unwrap() = config;
+    service.update(app, |history| {
+        history.prune(max_items);
+        Ok(())
+    })
+}
+
+// 模拟粘贴快捷键，把剪贴板内容粘贴到当前窗口
+fn send_paste() -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::send_paste()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("osascript")
+            .args([
+                "-e",
+                "tell application \"System Events\" to keystroke \"v\" using command down",
+            ])
+            .status()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::mem::size_of;
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+            KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_CONTROL,
+        };
+        let key = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        };
+        let v = VIRTUAL_KEY(b'V' as u16);
+        let inputs = [
+            key(VK_CONTROL, KEYBD_EVENT_FLAGS(0)),
+            key(v, KEYBD_EVENT_FLAGS(0)),
+            key(v, KEYEVENTF_KEYUP),
+            key(VK_CONTROL, KEYEVENTF_KEYUP),
+        ];
+        let sent = unsafe { SendInput(&inputs, size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            return Err("Failed to send paste keys".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[command]
+pub fn get_clipboard_history(
+    service: State<'_, ClipboardService>,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Vec<ClipEntry> {
+    service
+        .history
+        .lock()
+        .unwrap()
+        .search(query.as_deref().unwrap_or_default(), limit.unwrap_or(100))
+}
+
+#[command]
+pub fn copy_clipboard_entry(
+    app: AppHandle,
+    service: State<'_, ClipboardService>,
+    id: String,
+) -> Result<(), String> {
+    service.copy(&app, &id)
+}
+
+// 写入剪贴板后粘贴到之前的窗口，调用前前端需要先隐藏搜索窗口
+#[command]
+pub async fn paste_clipboard_entry(
+    app: AppHandle,
+    service: State<'_, ClipboardService>,
+    id: String,
+) -> Result<(), String> {
+    service.copy(&app, &id)?;
+    thread::sleep(PASTE_DELAY);
+    send_paste()
+}
+
+#[command]
+pub fn pin_clipboard_entry(
+    app: AppHandle,
+    service: State<'_, ClipboardService>,
+    id: String,
+    pinned: bool,
+) -> Result<(), String> {
+    service.update(&app, |history| history.set_pinned(&id, pinned))
+}
+
+#[command]
+pub fn delete_clipboard_entry(
+    app: AppHandle,
+    service: State<'_, ClipboardService>,
+    id: String,
+) -> Result<(), String> {
+    service.update(&app, |history| {
+        history.remove(&id);
+        Ok(())
+    })
+}
+
+// 清空历史，固定的记录保留
+#[command]
+pub fn clear_clipboard_history(
+    app: AppHandle,
+    service: State<'_, ClipboardService>,
+) -> Result<(), String> {
+    service.update(&app, |history| {
+        history.clear();
+        Ok(())
+    })
+}
+
+#[command]
+pub fn get_clipboard_config(service: State<'_, ClipboardService>) -> ClipboardConfig {
+    service.config.read().unwrap().clone()
+}
+
+#[command]
+pub fn set_clipboard_config(
+    app: AppHandle,
+    service: State<'_, ClipboardService>,
+    config: ClipboardConfig,
+) -> Result<(), String> {
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store::set(&app, CONFIG_KEY, Some(value))?;
+    let max_items = config.max_items;
+    *service.config.write().unwrap() = config;
+    service.update(&app, |history| {
+        history.prune(max_items);
+        Ok(())
+    })
+}
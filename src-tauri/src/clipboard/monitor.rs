@@ -0,0 +1,299 @@
+use arboard::Clipboard;
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use super::history::ClipContent;
+use super::{ClipboardConfig, ClipboardService, CHANGED_EVENT};
+
+// 没有系统通知时轮询剪贴板的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+// 连续变化时等待稳定，鼠标拖动选择文字时主选区会频繁变化
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+// 密码管理器等标记为敏感内容的剪贴板格式
+// KeePassXC 等使用 x-kde-passwordManagerHint，Windows 上使用后两种
+pub const SENSITIVE_FORMATS: [&str; 3] = [
+    "x-kde-passwordManagerHint",
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "Clipboard Viewer Ignore",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Clipboard,
+    // X11 的主选区，选中文字即复制
+    #[allow(dead_code)]
+    Primary,
+}
+
+// 剪贴板的一次变化
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub selection: Selection,
+    pub sensitive: bool,
+    // 复制时的前台应用
+    pub source: Option<String>,
+}
+
+// 启动监听线程，剪贴板变化时记录到历史
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        #[cfg(target_os = "linux")]
+        if let Err(e) = super::x11::watch(true, sender.clone()) {
+            eprintln!(
+                "Failed to watch X11 selections, fall back to polling: {}",
+                e
+            );
+        }
+        poll(sender);
+    });
+    let app = app.clone();
+    thread::spawn(move || run(app, receiver));
+}
+
+// 没有系统通知时定时检查，有剪贴板序号时比较序号，否则比较内容的哈希
+fn poll(sender: Sender<Change>) {
+    let mut last_sequence = None;
+    let mut last_hash = None;
+    let mut clipboard: Option<Clipboard> = None;
+    loop {
+        let changed = match sequence() {
+            Some(current) => last_sequence.replace(current) != Some(current),
+            None => {
+                if clipboard.is_none() {
+                    clipboard = Clipboard::new().ok();
+                }
+                let hash = clipboard.as_mut().and_then(content_hash);
+                let changed = hash.is_some() && hash != last_hash;
+                last_hash = hash;
+                changed
+            }
+        };
+        if changed {
+            let change = Change {
+                selection: Selection::Clipboard,
+                sensitive: is_sensitive(),
+                source: foreground_app(),
+            };
+            if sender.send(change).is_err() {
+                return;
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn run<R: Runtime>(app: AppHandle<R>, receiver: Receiver<Change>) {
+    let mut clipboard: Option<Clipboard> = None;
+    while let Ok(mut change) = receiver.recv() {
+        // 只处理稳定后的最后一次变化，剪贴板的变化优先于主选区
+        let mut clipboard_change = None;
+        loop {
+            if change.selection == Selection::Clipboard {
+                clipboard_change = Some(change.clone());
+            }
+            match receiver.recv_timeout(SETTLE_DELAY) {
+                Ok(next) => change = next,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        let change = clipboard_change.unwrap_or(change);
+
+        let service = app.state::<ClipboardService>();
+        let config = service.config.read().unwrap().clone();
+        if !accepts(&config, &change) {
+            continue;
+        }
+        if clipboard.is_none() {
+            clipboard = Clipboard::new().ok();
+        }
+        let content = match clipboard.as_mut() {
+            Some(clipboard) => read(clipboard, change.selection, &config),
+            None => continue,
+        };
+        let content = match content {
+            Some(content) => content,
+            None => continue,
+        };
+        match service.record(&app, content, change.source) {
+            Ok(Some(entry)) => {
+                let _ = app.emit(CHANGED_EVENT, entry);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to record clipboard: {}", e),
+        }
+    }
+}
+
+// 按配置过滤：是否启用、主选区、敏感内容和排除的应用
+fn accepts(config: &ClipboardConfig, change: &Change) -> bool {
+    if !config.enabled {
+        return false;
+    }
+    if change.selection == Selection::Primary && !config.watch_primary {
+        return false;
+    }
+    if change.sensitive && config.ignore_sensitive {
+        return false;
+    }
+    match change.source.as_ref() {
+        Some(source) => !config.is_excluded(source),
+        None => true,
+    }
+}
+
+// 读取剪贴板内容，依次尝试文件列表、文本和图片
+fn read(
+    clipboard: &mut Clipboard,
+    selection: Selection,
+    config: &ClipboardConfig,
+) -> Option<ClipContent> {
+    if selection == Selection::Clipboard && config.record_files {
+        if let Ok(files) = clipboard.get().file_list() {
+            if !files.is_empty() {
+                return Some(ClipContent::Files(files));
+            }
+        }
+    }
+    let text = {
+        #[cfg(target_os = "linux")]
+        {
+            use arboard::{GetExtLinux, LinuxClipboardKind};
+            let kind = match selection {
+                Selection::Clipboard => LinuxClipboardKind::Clipboard,
+                Selection::Primary => LinuxClipboardKind::Primary,
+            };
+            clipboard.get().clipboard(kind).text()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            clipboard.get().text()
+        }
+    };
+    if let Ok(text) = text {
+        if text.trim().is_empty() || text.len() > config.max_text_size {
+            return None;
+        }
+        return Some(ClipContent::Text(text));
+    }
+    if selection == Selection::Clipboard && config.record_images {
+        if let Ok(image) = clipboard.get().image() {
+            if image.bytes.len() > config.max_image_size {
+                return None;
+            }
+            return Some(ClipContent::Image {
+                width: image.width,
+                height: image.height,
+                bytes: image.bytes.into_owned(),
+            });
+        }
+    }
+    None
+}
+
+// 剪贴板内容的哈希，只用于判断内容是否变化，不按配置过滤
+fn content_hash(clipboard: &mut Clipboard) -> Option<String> {
+    if let Ok(files) = clipboard.get().file_list() {
+        if !files.is_empty() {
+            return Some(ClipContent::Files(files).hash());
+        }
+    }
+    if let Ok(text) = clipboard.get_text() {
+        return Some(ClipContent::Text(text).hash());
+    }
+    let image = clipboard.get_image().ok()?;
+    let content = ClipContent::Image {
+        width: image.width,
+        height: image.height,
+        bytes: image.bytes.into_owned(),
+    };
+    Some(content.hash())
+}
+
+// 剪贴板的序号，每次变化加一；不支持时返回 None，每次都读取内容比较
+#[cfg(target_os = "windows")]
+fn sequence() -> Option<u32> {
+    use windows::Win32::System::DataExchange::GetClipboardSequenceNumber;
+    Some(unsafe { GetClipboardSequenceNumber() })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn sequence() -> Option<u32> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn is_sensitive() -> bool {
+    use windows::core::HSTRING;
+    use windows::Win32::System::DataExchange::{
+        IsClipboardFormatAvailable, RegisterClipboardFormatW,
+    };
+    SENSITIVE_FORMATS.iter().any(|name| unsafe {
+        let format = RegisterClipboardFormatW(&HSTRING::from(*name));
+        format != 0 && IsClipboardFormatAvailable(format).is_ok()
+    })
+}
+
+// 通过 X11 读取剪贴板支持的格式，XWayland 下也可用
+#[cfg(target_os = "linux")]
+fn is_sensitive() -> bool {
+    super::x11::clipboard_sensitive()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn is_sensitive() -> bool {
+    false
+}
+
+// 前台窗口所属程序的文件名（不含扩展名）
+#[cfg(target_os = "windows")]
+fn foreground_app() -> Option<String> {
+    use std::path::Path;
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+    unsafe {
+        let window = GetForegroundWindow();
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(window, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+        let path = String::from_utf16_lossy(&buffer[..size as usize]);
+        Path::new(&path)
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+}
+
+// _NET_ACTIVE_WINDOW 指向的窗口的 WM_CLASS
+#[cfg(target_os = "linux")]
+fn foreground_app() -> Option<String> {
+    super::x11::active_app()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn foreground_app() -> Option<String> {
+    None
+}
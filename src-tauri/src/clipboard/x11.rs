@@ -0,0 +1,410 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::Sender,
+    thread,
+    time::{Duration, Instant},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xfixes::{ConnectionExt as _, SelectionEventMask},
+        xproto::{
+            Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, Window, WindowClass,
+            KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+        },
+        xtest::ConnectionExt as _,
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+use super::monitor::{Change, Selection, SENSITIVE_FORMATS};
+
+// 等待剪贴板所有者回应 TARGETS 的最长时间
+const TARGETS_TIMEOUT: Duration = Duration::from_millis(500);
+
+const XK_CONTROL_L: u32 = 0xffe3;
+const XK_V: u32 = 0x0076;
+
+struct Atoms {
+    clipboard: Atom,
+    targets: Atom,
+    property: Atom,
+    active_window: Atom,
+}
+
+fn intern(conn: &RustConnection, name: &str) -> Result<Atom, String> {
+    Ok(conn
+        .intern_atom(false, name.as_bytes())
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?
+        .atom)
+}
+
+// 到 X 服务器的连接和用于接收选区数据的隐藏窗口
+struct Probe {
+    conn: RustConnection,
+    root: Window,
+    window: Window,
+    atoms: Atoms,
+}
+
+impl Probe {
+    // display 为 None 时使用 DISPLAY 环境变量
+    fn connect(display: Option<&str>) -> Result<Self, String> {
+        let (conn, screen_num) = x11rb::connect(display).map_err(|e| e.to_string())?;
+        let root = conn.setup().roots[screen_num].root;
+        let visual = conn.setup().roots[screen_num].root_visual;
+        let atoms = Atoms {
+            clipboard: intern(&conn, "CLIPBOARD")?,
+            targets: intern(&conn, "TARGETS")?,
+            property: intern(&conn, "TOOLBOX_CLIPBOARD_TARGETS")?,
+            active_window: intern(&conn, "_NET_ACTIVE_WINDOW")?,
+        };
+        let window = conn.generate_id().map_err(|e| e.to_string())?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            visual,
+            &CreateWindowAux::new(),
+        )
+        .map_err(|e| e.to_string())?;
+        conn.flush().map_err(|e| e.to_string())?;
+        Ok(Probe {
+            conn,
+            root,
+            window,
+            atoms,
+        })
+    }
+
+    // 选区的所有者是否标记为敏感内容，等待期间收到的其他事件放入 pending
+    fn sensitive(&self, selection: Atom, pending: &mut VecDeque<Event>) -> bool {
+        targets(&self.conn, self.window, selection, &self.atoms, pending)
+            .unwrap_or_default()
+            .iter()
+            .any(|target| SENSITIVE_FORMATS.contains(&target.as_str()))
+    }
+
+    fn active_class(&self) -> Option<String> {
+        active_window_class(&self.conn, self.root, &self.atoms)
+    }
+}
+
+thread_local! {
+    // 轮询时复用的连接，连接不上时为 None
+    static POLL_PROBE: Option<Probe> = Probe::connect(None).ok();
+}
+
+// 轮询剪贴板时使用：剪贴板内容是否为敏感内容
+pub fn clipboard_sensitive() -> bool {
+    POLL_PROBE.with(|probe| {
+        probe
+            .as_ref()
+            .is_some_and(|probe| probe.sensitive(probe.atoms.clipboard, &mut VecDeque::new()))
+    })
+}
+
+// 轮询剪贴板时使用：当前活动窗口的类名
+pub fn active_app() -> Option<String> {
+    POLL_PROBE.with(|probe| probe.as_ref()?.active_class())
+}
+
+// 通过 XFixes 监听选区所有者的变化，阻塞当前线程
+// 连接不上 X 服务器（如纯 Wayland）时返回错误，由调用方改为轮询
+pub fn watch(primary: bool, sender: Sender<Change>) -> Result<(), String> {
+    watch_display(None, primary, sender)
+}
+
+fn watch_display(
+    display: Option<&str>,
+    primary: bool,
+    sender: Sender<Change>,
+) -> Result<(), String> {
+    let probe = Probe::connect(display)?;
+    let conn = &probe.conn;
+    conn.xfixes_query_version(5, 0)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let mask = SelectionEventMask::SET_SELECTION_OWNER
+        | SelectionEventMask::SELECTION_WINDOW_DESTROY
+        | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+    conn.xfixes_select_selection_input(probe.window, probe.atoms.clipboard, mask)
+        .map_err(|e| e.to_string())?;
+    if primary {
+        conn.xfixes_select_selection_input(probe.window, AtomEnum::PRIMARY.into(), mask)
+            .map_err(|e| e.to_string())?;
+    }
+    conn.flush().map_err(|e| e.to_string())?;
+
+    let mut pending: VecDeque<Event> = VecDeque::new();
+    loop {
+        let event = match pending.pop_front() {
+            Some(event) => event,
+            None => conn.wait_for_event().map_err(|e| e.to_string())?,
+        };
+        let notify = match event {
+            Event::XfixesSelectionNotify(notify) => notify,
+            _ => continue,
+        };
+        // 选区被清空
+        if notify.owner == NONE {
+            continue;
+        }
+        let selection = if notify.selection == probe.atoms.clipboard {
+            Selection::Clipboard
+        } else {
+            Selection::Primary
+        };
+        let change = Change {
+            selection,
+            sensitive: probe.sensitive(notify.selection, &mut pending),
+            source: probe.active_class(),
+        };
+        if sender.send(change).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+// 读取选区支持的格式，等待期间收到的其他事件放入 pending
+fn targets(
+    conn: &RustConnection,
+    window: Window,
+    selection: Atom,
+    atoms: &Atoms,
+    pending: &mut VecDeque<Event>,
+) -> Result<Vec<String>, String> {
+    conn.convert_selection(
+        window,
+        selection,
+        atoms.targets,
+        atoms.property,
+        CURRENT_TIME,
+    )
+    .map_err(|e| e.to_string())?;
+    conn.flush().map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + TARGETS_TIMEOUT;
+    loop {
+        match conn.poll_for_event().map_err(|e| e.to_string())? {
+            Some(Event::SelectionNotify(notify)) if notify.selection == selection => {
+                if notify.property == NONE {
+                    return Ok(Vec::new());
+                }
+                break;
+            }
+            Some(event) => pending.push_back(event),
+            None => {
+                if Instant::now() >= deadline {
+                    return Ok(Vec::new());
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+    let reply = conn
+        .get_property(true, window, atoms.property, AtomEnum::ATOM, 0, 1024)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let mut names = Vec::new();
+    for atom in reply.value32().into_iter().flatten() {
+        if let Ok(cookie) = conn.get_atom_name(atom) {
+            if let Ok(reply) = cookie.reply() {
+                names.push(String::from_utf8_lossy(&reply.name).to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+// 当前活动窗口的 WM_CLASS，优先返回类名
+fn active_window_class(conn: &RustConnection, root: Window, atoms: &Atoms) -> Option<String> {
+    let active = conn
+        .get_property(false, root, atoms.active_window, AtomEnum::WINDOW, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()?;
+    if active == NONE {
+        return None;
+    }
+    let reply = conn
+        .get_property(false, active, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+        .ok()?
+        .reply()
+        .ok()?;
+    // 格式为 `instance\0class\0`
+    let value = String::from_utf8_lossy(&reply.value).to_string();
+    let mut parts = value.split('\0').filter(|part| !part.is_empty());
+    let instance = parts.next();
+    parts.next().or(instance).map(|name| name.to_string())
+}
+
+// 通过 XTEST 模拟 Ctrl+V，把剪贴板内容粘贴到当前窗口
+pub fn send_paste() -> Result<(), String> {
+    let (conn, screen_num) = x11rb::connect(None).map_err(|e| e.to_string())?;
+    let root = conn.setup().roots[screen_num].root;
+    let control = keycode(&conn, XK_CONTROL_L)?;
+    let v = keycode(&conn, XK_V)?;
+    for (event, code) in [
+        (KEY_PRESS_EVENT, control),
+        (KEY_PRESS_EVENT, v),
+        (KEY_RELEASE_EVENT, v),
+        (KEY_RELEASE_EVENT, control),
+    ] {
+        conn.xtest_fake_input(event, code, CURRENT_TIME, root, 0, 0, 0)
+            .map_err(|e| e.to_string())?;
+    }
+    conn.sync().map_err(|e| e.to_string())
+}
+
+fn keycode(conn: &RustConnection, keysym: u32) -> Result<u8, String> {
+    let setup = conn.setup();
+    let (min, max) = (setup.min_keycode, setup.max_keycode);
+    let mapping = conn
+        .get_keyboard_mapping(min, max - min + 1)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let per_keycode = mapping.keysyms_per_keycode.max(1) as usize;
+    mapping
+        .keysyms
+        .chunks(per_keycode)
+        .position(|keysyms| keysyms.contains(&keysym))
+        .map(|index| min + index as u8)
+        .ok_or(format!("No keycode for keysym {:#x}", keysym))
+}
+
+// 需要 Xvfb，默认不运行：cargo test clipboard::x11 -- --ignored
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::xvfb::Xvfb;
+    use std::sync::mpsc;
+    use x11rb::protocol::xproto::{
+        EventMask, PropMode, SelectionNotifyEvent, SELECTION_NOTIFY_EVENT,
+    };
+
+    // 在另一个连接中占有剪贴板并设为活动窗口，回应 TARGETS 请求直到 X 服务器退出
+    fn own_clipboard(display: &str, targets: &[&str], class: &str) {
+        let (conn, screen_num) = x11rb::connect(Some(display)).unwrap();
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn.generate_id().unwrap();
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            conn.setup().roots[screen_num].root_visual,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        let wm_class = format!("{}\0{}\0", class.to_lowercase(), class);
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            wm_class.as_bytes(),
+        )
+        .unwrap();
+        let active = intern(&conn, "_NET_ACTIVE_WINDOW").unwrap();
+        conn.change_property32(PropMode::REPLACE, root, active, AtomEnum::WINDOW, &[window])
+            .unwrap();
+        let atoms: Vec<Atom> = targets
+            .iter()
+            .map(|target| intern(&conn, target).unwrap())
+            .collect();
+        let clipboard = intern(&conn, "CLIPBOARD").unwrap();
+        conn.set_selection_owner(window, clipboard, CURRENT_TIME)
+            .unwrap();
+        conn.sync().unwrap();
+        thread::spawn(move || {
+            while let Ok(event) = conn.wait_for_event() {
+                let Event::SelectionRequest(request) = event else {
+                    continue;
+                };
+                let _ = conn.change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    request.property,
+                    AtomEnum::ATOM,
+                    &atoms,
+                );
+                let notify = SelectionNotifyEvent {
+                    response_type: SELECTION_NOTIFY_EVENT,
+                    sequence: 0,
+                    time: request.time,
+                    requestor: request.requestor,
+                    selection: request.selection,
+                    target: request.target,
+                    property: request.property,
+                };
+                let _ = conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify);
+                let _ = conn.flush();
+            }
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn detects_password_manager_content() {
+        let xvfb = Xvfb::start(91);
+        own_clipboard(
+            &xvfb.display,
+            &["TARGETS", "UTF8_STRING", "x-kde-passwordManagerHint"],
+            "KeePassXC",
+        );
+        let probe = Probe::connect(Some(&xvfb.display)).unwrap();
+        assert!(probe.sensitive(probe.atoms.clipboard, &mut VecDeque::new()));
+        assert_eq!(probe.active_class().as_deref(), Some("KeePassXC"));
+    }
+
+    #[test]
+    #[ignore]
+    fn accepts_ordinary_content() {
+        let xvfb = Xvfb::start(92);
+        own_clipboard(&xvfb.display, &["TARGETS", "UTF8_STRING"], "Gedit");
+        let probe = Probe::connect(Some(&xvfb.display)).unwrap();
+        assert!(!probe.sensitive(probe.atoms.clipboard, &mut VecDeque::new()));
+        assert_eq!(probe.active_class().as_deref(), Some("Gedit"));
+    }
+
+    #[test]
+    #[ignore]
+    fn reports_owner_changes() {
+        let xvfb = Xvfb::start(93);
+        let (sender, receiver) = mpsc::channel();
+        let display = xvfb.display.clone();
+        thread::spawn(move || watch_display(Some(&display), false, sender));
+        // 等待开始监听
+        thread::sleep(Duration::from_millis(500));
+        own_clipboard(
+            &xvfb.display,
+            &["TARGETS", "x-kde-passwordManagerHint"],
+            "KeePassXC",
+        );
+        let change = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(change.selection, Selection::Clipboard);
+        assert!(change.sensitive);
+        assert_eq!(change.source.as_deref(), Some("KeePassXC"));
+    }
+}
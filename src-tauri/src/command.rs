@@ -1,25 +1,13 @@
-use std::process::{Command, Stdio};
-use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::{command, path::BaseDirectory, AppHandle, Manager};
 
 use memmap2::MmapMut;
 use std::fs::OpenOptions;
 
-#[path = "platform/mod.rs"]
-mod platform;
-use platform::{App, Installed, Screenshot};
+use crate::platform::Screenshot;
+use crate::utils;
 
-#[path = "utils/mod.rs"]
-mod utils;
-
-// 获取本机安装的 app 列表
-#[command]
-pub fn get_installed_apps() -> Vec<App> {
-    let result = Installed::new();
-    let apps = result.apps;
-    return apps;
-}
+use crate::plugins::plugins::run_plugin_process;
 
 // 获取屏幕截图
 #[command]
@@ -38,6 +26,7 @@ pub fn screenshot_desktop(app: AppHandle) -> Result<String, String> {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .open(file_path.clone())
         .map_err(|e| e.to_string())?;
 
@@ -54,48 +43,22 @@ pub fn screenshot_desktop(app: AppHandle) -> Result<String, String> {
     // 返回内存映射文件的路径，让前端能够访问
     Ok(file_path.to_string_lossy().to_string())
 }
-// 执行外部程序
+// 执行外部程序，plugin_id 不为空时按插件声明的资源限制执行并记录日志
 #[command]
-pub fn run_external_program(executable_path: String, args: Vec<String>) -> Result<String, String> {
-    let result = Arc::new(Mutex::new(String::new()));
-    let status = Arc::new(Mutex::new("success".to_string()));
-
-    let result_clone = Arc::clone(&result);
-    let status_clone = Arc::clone(&status);
-
+pub fn run_external_program(
+    app: AppHandle,
+    executable_path: String,
+    args: Vec<String>,
+    plugin_id: Option<String>,
+) -> Result<String, String> {
     let handle = thread::spawn(move || {
-        let child = Command::new(executable_path)
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .expect("Failed to start process");
-
-        let output = child.wait_with_output().expect("Failed to wait on child");
-
-        if output.status.success() {
-            println!("Output: {}", String::from_utf8_lossy(&output.stdout));
-            let mut result = result_clone.lock().unwrap();
-            *result = String::from_utf8_lossy(&output.stdout).to_string();
-            let mut status = status_clone.lock().unwrap();
-            *status = "success".to_string();
-        } else {
-            eprintln!("Error: {}", String::from_utf8_lossy(&output.stderr));
-            let mut result = result_clone.lock().unwrap();
-            *result = String::from_utf8_lossy(&output.stderr).to_string();
-            let mut status = status_clone.lock().unwrap();
-            *status = "error".to_string();
-        }
+        run_plugin_process(&app, plugin_id.as_deref(), &executable_path, args)
     });
 
-    handle.join().expect("Thread panicked");
-
-    let status = status.lock().unwrap();
-    let result = result.lock().unwrap();
-
-    if *status == "success" {
-        Ok(result.clone())
-    } else {
-        Err(result.clone())
+    let result = handle.join().expect("Thread panicked");
+    match &result {
+        Ok(output) => println!("Output: {}", output),
+        Err(error) => eprintln!("Error: {}", error),
     }
+    result
 }
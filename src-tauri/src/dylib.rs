@@ -3,54 +3,48 @@ use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 
 use std::path::PathBuf;
+use std::time::Instant;
+use tauri::AppHandle;
+
+use crate::plugins::discovery;
+use crate::plugins::logs::{self, PluginLogEntry};
 
 fn get_lib_ext() -> String {
     if cfg!(target_os = "windows") {
-        return String::from(".dll");
+        String::from(".dll")
     } else if cfg!(target_os = "macos") {
-        return String::from(".dylib");
+        String::from(".dylib")
     } else {
-        return String::from(".so");
+        String::from(".so")
     }
 }
-fn collect_dylib() -> HashMap<String, PathBuf> {
+fn collect_dylib(app: &AppHandle) -> HashMap<String, PathBuf> {
     let ext = get_lib_ext();
     let mut handlers: HashMap<String, PathBuf> = HashMap::new();
-    let plugins_dir = std::env::current_exe()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("plugins");
-
-    plugins_dir
-        .read_dir()
-        .unwrap()
-        .for_each(|entry| match entry {
-            Ok(entry) => {
-                let path = entry.path();
 
-                if path.is_dir() {
-                    // let tmp_path = plugins_dir.clone();
-                    let lib_path = path.join("lib").join(format!("index{}", ext));
-                    if lib_path.exists() {
-                        handlers.insert(
-                            path.file_name().unwrap().to_str().unwrap().to_string(),
-                            lib_path.clone(),
-                        );
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to read directory: {}", e);
-            }
-        });
-    return handlers;
+    for plugin in discovery::discover(app).plugins {
+        let lib_path = plugin.dir.join("lib").join(format!("index{}", ext));
+        if lib_path.exists() {
+            handlers.insert(plugin.id, lib_path);
+        }
+    }
+    handlers
 }
 #[tauri::command]
-pub fn dynamic_command(plugin: String, fn_name: String) -> Result<String, String> {
-    let lib_path_map = collect_dylib();
+pub fn dynamic_command(app: AppHandle, plugin: String, fn_name: String) -> Result<String, String> {
+    let started = Instant::now();
+    let result = call_dylib(&app, &plugin, &fn_name);
+    let entry = PluginLogEntry::from_result(&fn_name, &[], &result, started.elapsed());
+    logs::record(&app, &plugin, &entry);
+    result
+}
+
+fn call_dylib(app: &AppHandle, plugin: &str, fn_name: &str) -> Result<String, String> {
+    let lib_path_map = collect_dylib(app);
 
-    let lib_path = lib_path_map.get(&plugin).unwrap();
+    let lib_path = lib_path_map
+        .get(plugin)
+        .ok_or(format!("Plugin `{}` has no dynamic library", plugin))?;
     // 动态生成函数名并转换为以空终止符结尾的 CString
     let func_name_cstr = CString::new(fn_name).map_err(|e| e.to_string())?;
     unsafe {
@@ -0,0 +1,227 @@
+use image::{DynamicImage, RgbaImage};
+
+// PNG 文件头，新版 icns 中的图标直接以 PNG 保存
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+type Kind = [u8; 4];
+
+// 图标类型、像素大小和旧格式对应的遮罩类型
+const ENTRIES: [(&Kind, u32, Option<&Kind>); 16] = [
+    (b"ic10", 1024, None),
+    (b"ic09", 512, None),
+    (b"ic14", 512, None),
+    (b"ic08", 256, None),
+    (b"ic13", 256, None),
+    (b"ic07", 128, None),
+    (b"it32", 128, Some(b"t8mk")),
+    (b"ic12", 64, None),
+    (b"icp6", 64, None),
+    (b"ih32", 48, Some(b"h8mk")),
+    (b"ic11", 32, None),
+    (b"icp5", 32, None),
+    (b"il32", 32, Some(b"l8mk")),
+    (b"ic05", 32, None),
+    (b"icp4", 16, None),
+    (b"is32", 16, Some(b"s8mk")),
+];
+
+// 解析 icns 文件，返回能解码的最大的图标
+pub fn decode(data: &[u8]) -> Result<DynamicImage, String> {
+    if data.len() < 8 || &data[..4] != b"icns" {
+        return Err("Not an icns file".to_string());
+    }
+    let elements = elements(data);
+    let find = |kind: &Kind| {
+        elements
+            .iter()
+            .find(|(k, _)| k == kind)
+            .map(|(_, body)| *body)
+    };
+    for (kind, size, mask) in ENTRIES {
+        let body = match find(kind) {
+            Some(body) => body,
+            None => continue,
+        };
+        let image = if body.starts_with(PNG_SIGNATURE) {
+            image::load_from_memory(body).ok()
+        } else if body.starts_with(b"ARGB") {
+            argb(&body[4..], size)
+        } else if let Some(mask) = mask {
+            // it32 的数据前有 4 个字节的 0
+            let body = if kind == b"it32" && body.len() > 4 {
+                &body[4..]
+            } else {
+                body
+            };
+            rgb(body, find(mask), size)
+        } else {
+            // JPEG 2000 等不支持的格式
+            None
+        };
+        if let Some(image) = image {
+            return Ok(image);
+        }
+    }
+    Err("No supported image in icns file".to_string())
+}
+
+// 按 类型(4) + 长度(4，包括这 8 个字节) + 数据 分开各个元素
+fn elements(data: &[u8]) -> Vec<(Kind, &[u8])> {
+    let mut elements = Vec::new();
+    let mut offset = 8;
+    while offset + 8 <= data.len() {
+        let kind = [
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ];
+        let len = u32::from_be_bytes([
+            data[offset + 4],
+            data[offset + 5],
+            data[offset + 6],
+            data[offset + 7],
+        ]) as usize;
+        if len < 8 || offset + len > data.len() {
+            break;
+        }
+        elements.push((kind, &data[offset + 8..offset + len]));
+        offset += len;
+    }
+    elements
+}
+
+// 旧格式的图标，按通道分别压缩
+fn unpack(data: &[u8], channels: usize, pixels: usize) -> Option<Vec<Vec<u8>>> {
+    // 没有压缩
+    if data.len() == channels * pixels {
+        return Some(data.chunks(pixels).map(<[u8]>::to_vec).collect());
+    }
+    let mut planes = Vec::with_capacity(channels);
+    let mut i = 0;
+    for _ in 0..channels {
+        let mut plane = Vec::with_capacity(pixels);
+        while plane.len() < pixels {
+            let header = *data.get(i)? as usize;
+            i += 1;
+            if header < 0x80 {
+                // 接下来的 header + 1 个字节原样复制
+                plane.extend_from_slice(data.get(i..i + header + 1)?);
+                i += header + 1;
+            } else {
+                // 下一个字节重复 header - 125 次
+                let value = *data.get(i)?;
+                i += 1;
+                plane.extend(std::iter::repeat_n(value, header - 125));
+            }
+        }
+        plane.truncate(pixels);
+        planes.push(plane);
+    }
+    Some(planes)
+}
+
+fn rgb(data: &[u8], mask: Option<&[u8]>, size: u32) -> Option<DynamicImage> {
+    let pixels = (size * size) as usize;
+    let planes = unpack(data, 3, pixels)?;
+    let mask = mask.filter(|mask| mask.len() >= pixels);
+    let buffer = (0..pixels)
+        .flat_map(|i| {
+            let alpha = mask.map_or(255, |mask| mask[i]);
+            [planes[0][i], planes[1][i], planes[2][i], alpha]
+        })
+        .collect();
+    RgbaImage::from_raw(size, size, buffer).map(DynamicImage::ImageRgba8)
+}
+
+fn argb(data: &[u8], size: u32) -> Option<DynamicImage> {
+    let pixels = (size * size) as usize;
+    let planes = unpack(data, 4, pixels)?;
+    let buffer = (0..pixels)
+        .flat_map(|i| [planes[1][i], planes[2][i], planes[3][i], planes[0][i]])
+        .collect();
+    RgbaImage::from_raw(size, size, buffer).map(DynamicImage::ImageRgba8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 16x16 的 is32 图标：RGB 三个通道分别压缩，每个通道 256 个像素
+    fn is32(red: u8, green: u8, blue: u8) -> Vec<u8> {
+        let mut body = Vec::new();
+        for value in [red, green, blue] {
+            // 重复 130 次，再重复 126 次
+            body.extend_from_slice(&[0xff, value, 0xfb, value]);
+        }
+        body
+    }
+
+    fn icns(elements: &[(&Kind, &[u8])]) -> Vec<u8> {
+        let mut data = b"icns".to_vec();
+        let len: usize = 8 + elements
+            .iter()
+            .map(|(_, body)| body.len() + 8)
+            .sum::<usize>();
+        data.extend_from_slice(&(len as u32).to_be_bytes());
+        for (kind, body) in elements {
+            data.extend_from_slice(*kind);
+            data.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+            data.extend_from_slice(body);
+        }
+        data
+    }
+
+    #[test]
+    fn unpacks_runs() {
+        // 2 个原样复制的字节，再把 7 重复 3 次
+        let data = [0x01, 1, 2, 0x80, 7, 0x00, 9, 0x81, 5];
+        assert_eq!(
+            unpack(&data, 2, 5),
+            Some(vec![vec![1, 2, 7, 7, 7], vec![9, 5, 5, 5, 5]])
+        );
+        // 长度正好时没有压缩
+        assert_eq!(
+            unpack(&[1, 2, 3, 4], 2, 2),
+            Some(vec![vec![1, 2], vec![3, 4]])
+        );
+        // 超出的部分被截掉
+        assert_eq!(unpack(&[0xff, 1], 1, 4), Some(vec![vec![1; 4]]));
+        assert_eq!(unpack(&data[..7], 2, 5), None);
+        assert_eq!(unpack(&[0x05, 1, 2], 1, 6), None);
+    }
+
+    #[test]
+    fn decodes_rle_icons() {
+        let mask = vec![128; 256];
+        let data = icns(&[(b"is32", &is32(10, 20, 30)), (b"s8mk", &mask)]);
+        let image = decode(&data).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (16, 16));
+        assert_eq!(image.get_pixel(15, 15).0, [10, 20, 30, 128]);
+
+        // 没有遮罩时不透明，更大的图标优先
+        let data = icns(&[(b"is32", &is32(1, 2, 3)), (b"il32", &[0xff, 9].repeat(24))]);
+        let image = decode(&data).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (32, 32));
+        assert_eq!(image.get_pixel(0, 0).0, [9, 9, 9, 255]);
+    }
+
+    #[test]
+    fn rejects_truncated_icns() {
+        let body = is32(10, 20, 30);
+        let data = icns(&[(b"is32", &body[..body.len() - 1])]);
+        assert_eq!(
+            decode(&data).unwrap_err(),
+            "No supported image in icns file"
+        );
+        // 长度超出文件的元素被忽略
+        let mut data = icns(&[(b"is32", &body)]);
+        data.truncate(data.len() - 1);
+        assert_eq!(
+            decode(&data).unwrap_err(),
+            "No supported image in icns file"
+        );
+        assert_eq!(decode(b"icns").unwrap_err(), "Not an icns file");
+        assert_eq!(decode(b"ICNS\0\0\0\x08").unwrap_err(), "Not an icns file");
+    }
+}
@@ -0,0 +1,348 @@
+use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
+    time::UNIX_EPOCH,
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use crate::search::SearchResult;
+use crate::utils;
+
+mod icns;
+mod pe;
+#[cfg(target_os = "linux")]
+mod theme;
+
+// 缓存目录，位于 app_cache_dir 中，每个图标按内容的哈希保存为 {hash}/{size}.png
+const CACHE_DIR: &str = "icons";
+// 图标来源到哈希的对应关系，避免每次都重新读取来源
+const SOURCES_FILE: &str = "sources.json";
+// 生成的 PNG 大小
+pub const SIZES: [u32; 4] = [32, 64, 128, 256];
+// 搜索结果和应用列表使用的大小
+pub const DEFAULT_SIZE: u32 = 64;
+// 不读取超过该大小的图标文件，exe/dll 除外
+const MAX_ICON_FILE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSource {
+    modified: u64,
+    // 解码失败时为 None，来源修改后再重试
+    hash: Option<String>,
+}
+
+#[derive(Default)]
+pub struct IconService {
+    dir: RwLock<Option<PathBuf>>,
+    sources: Mutex<HashMap<String, CachedSource>>,
+    // 来源记录有变化，需要写入文件
+    dirty: AtomicBool,
+    // 来源对应的文件，按图标名查找主题较慢
+    located: Mutex<HashMap<PathBuf, Option<(PathBuf, i32)>>>,
+}
+
+impl IconService {
+    // 把图标来源转换为缓存中指定大小的 PNG，来源可以是图片文件、图标名或 `path,index` 形式的资源
+    pub fn resolve(&self, source: &Path, size: u32) -> Option<PathBuf> {
+        let dir = self.dir.read().unwrap().clone()?;
+        let size = standard_size(size);
+        let (path, index) = self.locate(source)?;
+        let modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        let key = format!("{},{}", path.to_string_lossy(), index);
+        let cached = self
+            .sources
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|cached| cached.modified == modified)
+            .cloned();
+        let hash = match cached {
+            Some(CachedSource { hash: None, .. }) => return None,
+            Some(CachedSource {
+                hash: Some(hash), ..
+            }) if dir.join(&hash).join(format!("{}.png", size)).is_file() => hash,
+            _ => {
+                let hash = render(&dir, &path, index)
+                    .map_err(|e| eprintln!("Failed to load icon {}: {}", key, e))
+                    .ok();
+                self.sources.lock().unwrap().insert(
+                    key,
+                    CachedSource {
+                        modified,
+                        hash: hash.clone(),
+                    },
+                );
+                self.dirty.store(true, Ordering::SeqCst);
+                hash?
+            }
+        };
+        Some(dir.join(hash).join(format!("{}.png", size)))
+    }
+
+    // 只返回已经生成的 PNG，不读取来源
+    pub fn cached(&self, source: &Path, size: u32) -> Option<PathBuf> {
+        let dir = self.dir.read().unwrap().clone()?;
+        let (path, index) = self.locate(source)?;
+        let hash = self
+            .sources
+            .lock()
+            .unwrap()
+            .get(&format!("{},{}", path.to_string_lossy(), index))?
+            .hash
+            .clone()?;
+        let file = dir.join(hash).join(format!("{}.png", standard_size(size)));
+        file.is_file().then_some(file)
+    }
+
+    fn locate(&self, source: &Path) -> Option<(PathBuf, i32)> {
+        if let Some(located) = self.located.lock().unwrap().get(source) {
+            return located.clone();
+        }
+        let located = locate(source);
+        self.located
+            .lock()
+            .unwrap()
+            .insert(source.to_path_buf(), located.clone());
+        located
+    }
+
+    // 替换搜索结果中的图标来源
+    pub fn attach(&self, results: &mut [SearchResult]) {
+        for result in results.iter_mut() {
+            if let Some(source) = result.icon.take() {
+                result.icon = self.resolve(&source, DEFAULT_SIZE);
+            }
+        }
+        self.save();
+    }
+
+    // 来源记录有变化时写入文件
+    pub fn save(&self) {
+        let dir = match self.dir.read().unwrap().clone() {
+            Some(dir) => dir,
+            None => return,
+        };
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let result = serde_json::to_string(&*self.sources.lock().unwrap())
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                fs::write(dir.join(SOURCES_FILE), content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            eprintln!("Failed to save icon cache: {}", e);
+        }
+    }
+}
+
+// 启动时确定缓存目录并读取来源记录
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let dir = match app.path().app_cache_dir() {
+        Ok(dir) => dir.join(CACHE_DIR),
+        Err(e) => {
+            eprintln!("Failed to get icon cache dir: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create icon cache dir: {}", e);
+        return;
+    }
+    let sources = fs::read_to_string(dir.join(SOURCES_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let icons = app.state::<IconService>();
+    *icons.sources.lock().unwrap() = sources;
+    *icons.dir.write().unwrap() = Some(dir);
+}
+
+// 取不小于 size 的标准大小
+fn standard_size(size: u32) -> u32 {
+    SIZES
+        .iter()
+        .copied()
+        .find(|s| *s >= size)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+// 找到图标文件，返回文件路径和资源下标
+fn locate(source: &Path) -> Option<(PathBuf, i32)> {
+    let text = source.to_string_lossy();
+    let text = text.trim().trim_matches('"');
+    if text.is_empty() {
+        return None;
+    }
+    let text = utils::expand_env(text);
+    let path = Path::new(&text);
+    if path.is_file() {
+        return Some((path.to_path_buf(), 0));
+    }
+    // Windows 的图标位置：`C:\Windows\system32\shell32.dll,-21`
+    if let Some((file, index)) = text.rsplit_once(',') {
+        if let Ok(index) = index.trim().parse::<i32>() {
+            let file = Path::new(file.trim().trim_matches('"'));
+            if file.is_file() {
+                return Some((file.to_path_buf(), index));
+            }
+        }
+    }
+    // macOS 的 CFBundleIconFile 可以省略扩展名
+    if path.is_absolute() {
+        let icns = path.with_extension("icns");
+        return icns.is_file().then_some((icns, 0));
+    }
+    #[cfg(target_os = "linux")]
+    if !text.contains('/') {
+        return theme::lookup(&text).map(|path| (path, 0));
+    }
+    None
+}
+
+enum Decoded {
+    Svg(Box<resvg::usvg::Tree>),
+    Image(DynamicImage),
+}
+
+fn read_icon(path: &Path) -> Result<Vec<u8>, String> {
+    if fs::metadata(path).map_err(|e| e.to_string())?.len() > MAX_ICON_FILE {
+        return Err("Icon file is too large".to_string());
+    }
+    fs::read(path).map_err(|e| e.to_string())
+}
+
+// 读取并解码图标，返回图标数据本身（用于计算哈希）和解码后的图片
+fn decode(path: &Path, index: i32) -> Result<(Vec<u8>, Decoded), String> {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if matches!(ext.as_str(), "exe" | "dll" | "icl" | "cpl" | "mun") {
+        let file = fs::File::open(path).map_err(|e| e.to_string())?;
+        // exe/dll 可能很大，只映射到内存中读取资源
+        let data = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| e.to_string())?;
+        let ico = pe::extract(&data, index)?;
+        let image = image::load_from_memory_with_format(&ico, ImageFormat::Ico)
+            .map_err(|e| e.to_string())?;
+        return Ok((ico, Decoded::Image(image)));
+    }
+    // AppImage 使用其中嵌入的图标，按图标的格式解码
+    #[cfg(target_os = "linux")]
+    let (data, ext) = if ext == "appimage" {
+        crate::platform::appimage::icon(path)?
+    } else {
+        (read_icon(path)?, ext)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let data = read_icon(path)?;
+    let decoded = match ext.as_str() {
+        "svg" | "svgz" => Decoded::Svg(Box::new(
+            resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default())
+                .map_err(|e| e.to_string())?,
+        )),
+        "icns" => Decoded::Image(icns::decode(&data)?),
+        _ => Decoded::Image(image::load_from_memory(&data).map_err(|e| e.to_string())?),
+    };
+    Ok((data, decoded))
+}
+
+// 把图标按所有大小保存到缓存中，返回内容的哈希
+fn render(dir: &Path, path: &Path, index: i32) -> Result<String, String> {
+    let (data, decoded) = decode(path, index)?;
+    let hash = format!("{:x}", md5::compute(&data));
+    let target = dir.join(&hash);
+    if SIZES
+        .iter()
+        .all(|size| target.join(format!("{}.png", size)).is_file())
+    {
+        return Ok(hash);
+    }
+    fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    for size in SIZES {
+        let image = match &decoded {
+            Decoded::Svg(tree) => rasterize(tree, size)?,
+            Decoded::Image(image) => fit(image, size),
+        };
+        image
+            .save_with_format(target.join(format!("{}.png", size)), ImageFormat::Png)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(hash)
+}
+
+// 等比缩放后居中放到正方形中
+fn fit(image: &DynamicImage, size: u32) -> RgbaImage {
+    let scaled = image.resize(size, size, FilterType::Lanczos3).to_rgba8();
+    let mut canvas = RgbaImage::new(size, size);
+    let x = (size - scaled.width()) / 2;
+    let y = (size - scaled.height()) / 2;
+    image::imageops::overlay(&mut canvas, &scaled, x as i64, y as i64);
+    canvas
+}
+
+fn rasterize(tree: &resvg::usvg::Tree, size: u32) -> Result<RgbaImage, String> {
+    use resvg::tiny_skia::{Pixmap, Transform};
+    let tree_size = tree.size();
+    let scale = size as f32 / tree_size.width().max(tree_size.height());
+    let dx = (size as f32 - tree_size.width() * scale) / 2.0;
+    let dy = (size as f32 - tree_size.height() * scale) / 2.0;
+    let mut pixmap = Pixmap::new(size, size).ok_or("Invalid icon size")?;
+    resvg::render(
+        tree,
+        Transform::from_scale(scale, scale).post_translate(dx, dy),
+        &mut pixmap.as_mut(),
+    );
+    // tiny-skia 中为预乘透明度的颜色
+    let buffer = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(size, size, buffer).ok_or_else(|| "Invalid icon size".to_string())
+}
+
+// 取图标来源对应的 PNG 路径，前端通过 convertFileSrc 显示
+#[command]
+pub async fn get_icon(
+    app: AppHandle,
+    source: String,
+    size: Option<u32>,
+) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let icons = app.state::<IconService>();
+        let icon = icons.resolve(Path::new(&source), size.unwrap_or(DEFAULT_SIZE));
+        icons.save();
+        icon
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// 删除缓存的图标，下次使用时重新生成
+#[command]
+pub fn clear_icon_cache(icons: State<'_, IconService>) -> Result<(), String> {
+    let dir = match icons.dir.read().unwrap().clone() {
+        Some(dir) => dir,
+        None => return Ok(()),
+    };
+    icons.sources.lock().unwrap().clear();
+    icons.located.lock().unwrap().clear();
+    fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())
+}
@@ -0,0 +1,290 @@
+// 从 exe/dll 的资源中取出图标组，拼成 ico 文件
+
+// 资源类型
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+// 数据目录中资源表的下标
+const RESOURCE_DIRECTORY: usize = 2;
+
+// 文件中的偏移和长度都不可信，越界或溢出时返回 None
+fn slice(data: &[u8], offset: usize, len: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(len)?)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    slice(data, offset, 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    slice(data, offset, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_size: u32,
+    raw_offset: u32,
+}
+
+struct Image<'a> {
+    data: &'a [u8],
+    sections: Vec<Section>,
+    // 资源表在文件中的位置
+    resources: usize,
+}
+
+impl<'a> Image<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.get(..2)? != b"MZ" {
+            return None;
+        }
+        let pe = u32_at(data, 0x3c)? as usize;
+        if slice(data, pe, 4)? != b"PE\0\0" {
+            return None;
+        }
+        let coff = pe + 4;
+        let section_count = u16_at(data, coff + 2)? as usize;
+        let optional_size = u16_at(data, coff + 16)? as usize;
+        let optional = coff + 20;
+        // PE32 和 PE32+ 的数据目录位置不同
+        let (count_offset, directories) = match u16_at(data, optional)? {
+            0x10b => (92, 96),
+            0x20b => (108, 112),
+            _ => return None,
+        };
+        if u32_at(data, optional + count_offset)? as usize <= RESOURCE_DIRECTORY {
+            return None;
+        }
+        let resource_rva = u32_at(data, optional + directories + RESOURCE_DIRECTORY * 8)?;
+        let sections = (0..section_count)
+            .map(|i| {
+                let header = optional + optional_size + i * 40;
+                Some(Section {
+                    virtual_size: u32_at(data, header + 8)?,
+                    virtual_address: u32_at(data, header + 12)?,
+                    raw_size: u32_at(data, header + 16)?,
+                    raw_offset: u32_at(data, header + 20)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let mut image = Image {
+            data,
+            sections,
+            resources: 0,
+        };
+        image.resources = image.offset(resource_rva)?;
+        Some(image)
+    }
+
+    // 相对虚拟地址在文件中的位置
+    fn offset(&self, rva: u32) -> Option<usize> {
+        let section = self.sections.iter().find(|section| {
+            let size = section.virtual_size.max(section.raw_size);
+            rva >= section.virtual_address && rva - section.virtual_address < size
+        })?;
+        (rva - section.virtual_address)
+            .checked_add(section.raw_offset)
+            .map(|offset| offset as usize)
+    }
+
+    // 资源目录中的项：(名称或 id, 偏移)，名称为字符串的项 id 为 None
+    fn entries(&self, directory: usize) -> Option<Vec<(Option<u32>, u32)>> {
+        let base = self.resources + directory;
+        let named = u16_at(self.data, base + 12)? as usize;
+        let ids = u16_at(self.data, base + 14)? as usize;
+        (0..named + ids)
+            .map(|i| {
+                let entry = base + 16 + i * 8;
+                let name = u32_at(self.data, entry)?;
+                let offset = u32_at(self.data, entry + 4)?;
+                let id = (name & 0x8000_0000 == 0).then_some(name);
+                Some((id, offset))
+            })
+            .collect()
+    }
+
+    fn subdirectory(&self, offset: u32) -> Option<usize> {
+        (offset & 0x8000_0000 != 0).then_some((offset & 0x7fff_ffff) as usize)
+    }
+
+    // 某个类型下的所有资源，每个取第一个语言的数据
+    fn resources(&self, kind: u32) -> Option<Vec<(Option<u32>, &'a [u8])>> {
+        let (_, types) = self
+            .entries(0)?
+            .into_iter()
+            .find(|(id, _)| *id == Some(kind))?;
+        let names = self.entries(self.subdirectory(types)?)?;
+        Some(
+            names
+                .into_iter()
+                .filter_map(|(id, offset)| {
+                    let languages = self.entries(self.subdirectory(offset)?)?;
+                    let (_, entry) = languages.first()?;
+                    let entry = self.resources + (*entry & 0x7fff_ffff) as usize;
+                    let start = self.offset(u32_at(self.data, entry)?)?;
+                    let size = u32_at(self.data, entry + 4)? as usize;
+                    Some((id, slice(self.data, start, size)?))
+                })
+                .collect(),
+        )
+    }
+}
+
+// 按 ExtractIcon 的规则取图标组：index 不小于 0 时为第几个图标组，小于 0 时为 id 等于 -index 的图标组
+pub fn extract(data: &[u8], index: i32) -> Result<Vec<u8>, String> {
+    let image = Image::parse(data).ok_or("No resources in file")?;
+    let groups = image.resources(RT_GROUP_ICON).unwrap_or_default();
+    let group = if index < 0 {
+        groups
+            .iter()
+            .find(|(id, _)| *id == Some(index.unsigned_abs()))
+    } else {
+        groups.get(index as usize)
+    };
+    let (_, group) = group.ok_or("Icon not found in file")?;
+    let icons = image.resources(RT_ICON).unwrap_or_default();
+
+    // GRPICONDIR 的每一项为 14 个字节，ico 文件中为 16 个字节，最后 2 个字节由 id 换成数据的位置
+    let count = u16_at(group, 4).ok_or("Invalid icon group")? as usize;
+    let mut entries = Vec::new();
+    for i in 0..count {
+        let entry = group
+            .get(6 + i * 14..6 + i * 14 + 14)
+            .ok_or("Invalid icon group")?;
+        let id = u16_at(entry, 12).unwrap_or_default() as u32;
+        if let Some((_, icon)) = icons.iter().find(|(icon_id, _)| *icon_id == Some(id)) {
+            entries.push((&entry[..8], *icon));
+        }
+    }
+    if entries.is_empty() {
+        return Err("Icon not found in file".to_string());
+    }
+    let mut ico = Vec::new();
+    ico.extend_from_slice(&[0, 0, 1, 0]);
+    ico.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    let mut offset = 6 + entries.len() * 16;
+    for (header, icon) in entries.iter() {
+        ico.extend_from_slice(header);
+        ico.extend_from_slice(&(icon.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += icon.len();
+    }
+    for (_, icon) in entries {
+        ico.extend_from_slice(icon);
+    }
+    Ok(ico)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 资源节的虚拟地址和在文件中的位置
+    const SECTION_RVA: u32 = 0x1000;
+    const SECTION_OFFSET: usize = 0x200;
+    const ICON: &[u8] = b"ICONDATA";
+
+    fn put(data: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+        if data.len() < offset + bytes.len() {
+            data.resize(offset + bytes.len(), 0);
+        }
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    // 资源目录，entries 为 (id, 偏移)，子目录的偏移带最高位
+    fn directory(data: &mut Vec<u8>, offset: usize, entries: &[(u32, u32)]) {
+        put(data, offset + 14, &(entries.len() as u16).to_le_bytes());
+        for (i, (id, target)) in entries.iter().enumerate() {
+            put(data, offset + 16 + i * 8, &id.to_le_bytes());
+            put(data, offset + 20 + i * 8, &target.to_le_bytes());
+        }
+    }
+
+    // 一个 PE32 文件，只有一个资源节，其中有 id 为 1 的图标组和图标
+    fn image(raw_offset: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        put(&mut data, 0, b"MZ");
+        put(&mut data, 0x3c, &0x40u32.to_le_bytes());
+        put(&mut data, 0x40, b"PE\0\0");
+        let coff = 0x44;
+        put(&mut data, coff + 2, &1u16.to_le_bytes());
+        put(&mut data, coff + 16, &120u16.to_le_bytes());
+        let optional = coff + 20;
+        put(&mut data, optional, &0x10bu16.to_le_bytes());
+        put(&mut data, optional + 92, &3u32.to_le_bytes());
+        put(&mut data, optional + 96 + 16, &SECTION_RVA.to_le_bytes());
+        let header = optional + 120;
+        put(&mut data, header + 8, &0x100u32.to_le_bytes());
+        put(&mut data, header + 12, &SECTION_RVA.to_le_bytes());
+        put(&mut data, header + 16, &0x100u32.to_le_bytes());
+        put(&mut data, header + 20, &raw_offset.to_le_bytes());
+
+        let base = SECTION_OFFSET;
+        let sub = |offset: u32| offset | 0x8000_0000;
+        directory(
+            &mut data,
+            base,
+            &[(RT_GROUP_ICON, sub(0x20)), (RT_ICON, sub(0x60))],
+        );
+        directory(&mut data, base + 0x20, &[(1, sub(0x40))]);
+        directory(&mut data, base + 0x40, &[(0x409, 0xa0)]);
+        directory(&mut data, base + 0x60, &[(1, sub(0x80))]);
+        directory(&mut data, base + 0x80, &[(0x409, 0xb0)]);
+        // 数据项：RVA 和长度
+        put(&mut data, base + 0xa0, &(SECTION_RVA + 0xc0).to_le_bytes());
+        put(&mut data, base + 0xa4, &20u32.to_le_bytes());
+        put(&mut data, base + 0xb0, &(SECTION_RVA + 0xe0).to_le_bytes());
+        put(&mut data, base + 0xb4, &(ICON.len() as u32).to_le_bytes());
+        // GRPICONDIR：1 个 16x16、32 位的图标，id 为 1
+        put(&mut data, base + 0xc0, &[0, 0, 1, 0, 1, 0]);
+        put(&mut data, base + 0xc6, &[16, 16, 0, 0, 1, 0, 32, 0]);
+        put(&mut data, base + 0xce, &(ICON.len() as u32).to_le_bytes());
+        put(&mut data, base + 0xd2, &1u16.to_le_bytes());
+        put(&mut data, base + 0xe0, ICON);
+        data
+    }
+
+    #[test]
+    fn extracts_icon_groups() {
+        let data = image(SECTION_OFFSET as u32);
+        let ico = extract(&data, 0).unwrap();
+        assert_eq!(&ico[..6], &[0, 0, 1, 0, 1, 0]);
+        assert_eq!(&ico[6..14], &[16, 16, 0, 0, 1, 0, 32, 0]);
+        assert_eq!(u32_at(&ico, 14), Some(ICON.len() as u32));
+        assert_eq!(u32_at(&ico, 18), Some(22));
+        assert_eq!(&ico[22..], ICON);
+        assert_eq!(extract(&data, -1).unwrap(), ico);
+        assert_eq!(extract(&data, 1).unwrap_err(), "Icon not found in file");
+        assert_eq!(extract(&data, -2).unwrap_err(), "Icon not found in file");
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = image(SECTION_OFFSET as u32);
+        assert_eq!(
+            extract(&data[..0x80], 0).unwrap_err(),
+            "No resources in file"
+        );
+        assert_eq!(
+            extract(&data[..SECTION_OFFSET + 0x40], 0).unwrap_err(),
+            "Icon not found in file"
+        );
+        assert_eq!(
+            extract(&data[..SECTION_OFFSET + 0xe4], 0).unwrap_err(),
+            "Icon not found in file"
+        );
+        assert!(extract(b"MZ", 0).is_err());
+        assert!(extract(b"", 0).is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_offsets() {
+        let data = image(u32::MAX);
+        let parsed = Image::parse(&data).unwrap();
+        assert_eq!(parsed.offset(SECTION_RVA), Some(u32::MAX as usize));
+        assert_eq!(parsed.offset(SECTION_RVA + 1), None);
+        assert_eq!(parsed.offset(SECTION_RVA + 0x100), None);
+        assert!(u32_at(&data, usize::MAX - 1).is_none());
+        assert!(extract(&data, 0).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// 按 freedesktop 图标主题规范查找图标名对应的文件
+
+const FALLBACK_THEME: &str = "hicolor";
+const EXTENSIONS: [&str; 2] = ["svg", "png"];
+
+// 图标主题所在的目录，前面的优先
+fn base_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let mut dirs = Vec::new();
+    if let Some(home) = home.as_ref() {
+        dirs.push(home.join(".icons"));
+    }
+    match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => dirs.push(PathBuf::from(dir).join("icons")),
+        _ => dirs.extend(home.map(|home| home.join(".local/share/icons"))),
+    }
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join("icons")),
+    );
+    dirs
+}
+
+// 读取 ini 格式的文件，返回 节 -> 键 -> 值
+fn read_ini(path: &Path) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return sections,
+    };
+    let mut current = String::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.to_string();
+        } else if let Some((key, value)) = line.split_once('=') {
+            sections
+                .entry(current.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+// 当前使用的图标主题，只读取 GTK 的配置
+fn current_theme() -> Option<String> {
+    let config = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    ["gtk-4.0", "gtk-3.0"].iter().find_map(|gtk| {
+        read_ini(&config.join(gtk).join("settings.ini"))
+            .get("Settings")?
+            .get("gtk-icon-theme-name")
+            .map(|name| name.trim_matches('"').to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+struct Theme {
+    // 主题在各个目录中的位置
+    roots: Vec<PathBuf>,
+    // 子目录和其中图标的大小，可缩放的为 None
+    directories: Vec<(String, Option<u32>)>,
+    inherits: Vec<String>,
+}
+
+impl Theme {
+    fn load(name: &str, bases: &[PathBuf]) -> Option<Theme> {
+        let roots: Vec<PathBuf> = bases
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+        let index = roots
+            .iter()
+            .map(|root| read_ini(&root.join("index.theme")))
+            .find(|index| index.contains_key("Icon Theme"))?;
+        let theme = index.get("Icon Theme")?;
+        let list = |key: &str| -> Vec<String> {
+            theme
+                .get(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let mut directory_names = list("Directories");
+        directory_names.extend(list("ScaledDirectories"));
+        let directories = directory_names
+            .into_iter()
+            .map(|directory| {
+                let section = index.get(&directory);
+                let scalable = section
+                    .and_then(|s| s.get("Type"))
+                    .is_some_and(|t| t == "Scalable");
+                let size = section
+                    .and_then(|s| s.get("Size"))
+                    .and_then(|s| s.parse::<u32>().ok())
+                    .unwrap_or_default();
+                let scale = section
+                    .and_then(|s| s.get("Scale"))
+                    .and_then(|s| s.parse::<u32>().ok())
+                    .unwrap_or(1);
+                (directory, (!scalable).then_some(size * scale))
+            })
+            .collect();
+        Some(Theme {
+            roots,
+            directories,
+            inherits: list("Inherits"),
+        })
+    }
+
+    // 优先可缩放的图标，其次是最大的
+    fn find(&self, name: &str) -> Option<PathBuf> {
+        let mut best: Option<(u32, PathBuf)> = None;
+        for root in self.roots.iter() {
+            for (directory, size) in self.directories.iter() {
+                for ext in EXTENSIONS {
+                    let path = root.join(directory).join(format!("{}.{}", name, ext));
+                    let rank = size.unwrap_or(u32::MAX);
+                    if best.as_ref().is_some_and(|(best, _)| *best >= rank) {
+                        continue;
+                    }
+                    if path.is_file() {
+                        best = Some((rank, path));
+                    }
+                }
+            }
+        }
+        best.map(|(_, path)| path)
+    }
+}
+
+// 依次在当前主题、继承的主题、hicolor 和 /usr/share/pixmaps 中查找
+pub fn lookup(name: &str) -> Option<PathBuf> {
+    let name = EXTENSIONS
+        .iter()
+        .chain(["xpm"].iter())
+        .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+        .unwrap_or(name);
+    let bases = base_dirs();
+    let mut queue: Vec<String> = current_theme().into_iter().collect();
+    queue.push(FALLBACK_THEME.to_string());
+    let mut visited = Vec::new();
+    while !queue.is_empty() {
+        let theme_name = queue.remove(0);
+        if visited.contains(&theme_name) {
+            continue;
+        }
+        visited.push(theme_name.clone());
+        let theme = match Theme::load(&theme_name, &bases) {
+            Some(theme) => theme,
+            None => continue,
+        };
+        if let Some(path) = theme.find(name) {
+            return Some(path);
+        }
+        // 继承的主题在 hicolor 之前查找
+        let position = queue.len().saturating_sub(1);
+        for (i, parent) in theme.inherits.into_iter().enumerate() {
+            queue.insert(position + i, parent);
+        }
+    }
+    EXTENSIONS.iter().find_map(|ext| {
+        let path = Path::new("/usr/share/pixmaps").join(format!("{}.{}", name, ext));
+        path.is_file().then_some(path)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                env::temp_dir().join(format!("toolbox-theme-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write(&self, path: &str, content: &str) {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const INDEX: &str = "[Icon Theme]
+Name=Paper
+Inherits=Adwaita, hicolor
+Directories=16x16/apps,24x24@2/apps,scalable/apps
+
+[16x16/apps]
+Size=16
+
+[24x24@2/apps]
+Size=24
+Scale=2
+
+[scalable/apps]
+Size=48
+Type=Scalable
+";
+
+    #[test]
+    fn reads_theme_index() {
+        let temp = TempDir::new("index");
+        temp.write("icons/Paper/index.theme", INDEX);
+        let theme = Theme::load("Paper", &[temp.0.join("missing"), temp.0.join("icons")]).unwrap();
+        assert_eq!(theme.roots, [temp.0.join("icons/Paper")]);
+        assert_eq!(theme.inherits, ["Adwaita", "hicolor"]);
+        assert_eq!(
+            theme.directories,
+            [
+                ("16x16/apps".to_string(), Some(16)),
+                ("24x24@2/apps".to_string(), Some(48)),
+                ("scalable/apps".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_scalable_then_largest_icons() {
+        let temp = TempDir::new("find");
+        temp.write("icons/Paper/index.theme", INDEX);
+        for file in [
+            "16x16/apps/editor.png",
+            "24x24@2/apps/editor.png",
+            "scalable/apps/editor.svg",
+            "16x16/apps/terminal.png",
+            "24x24@2/apps/terminal.png",
+            "16x16/apps/files.png",
+        ] {
+            temp.write(&format!("icons/Paper/{}", file), "");
+        }
+        let theme = Theme::load("Paper", &[temp.0.join("icons")]).unwrap();
+        let root = temp.0.join("icons/Paper");
+        assert_eq!(
+            theme.find("editor"),
+            Some(root.join("scalable/apps/editor.svg"))
+        );
+        assert_eq!(
+            theme.find("terminal"),
+            Some(root.join("24x24@2/apps/terminal.png"))
+        );
+        assert_eq!(theme.find("files"), Some(root.join("16x16/apps/files.png")));
+        assert_eq!(theme.find("missing"), None);
+    }
+
+    #[test]
+    fn rejects_incomplete_themes() {
+        let temp = TempDir::new("incomplete");
+        // 没有 [Icon Theme] 节
+        temp.write("icons/Broken/index.theme", "Name=Broken\nDirectories=16x16");
+        temp.write("icons/Empty/index.theme", "");
+        assert!(Theme::load("Broken", &[temp.0.join("icons")]).is_none());
+        assert!(Theme::load("Empty", &[temp.0.join("icons")]).is_none());
+        assert!(Theme::load("Missing", &[temp.0.join("icons")]).is_none());
+
+        // 没有大小的目录按 0 处理，不认识的行被忽略
+        temp.write(
+            "icons/Bare/index.theme",
+            "[Icon Theme]\nDirectories=apps,\n[apps]\nSize=big\nnot a pair",
+        );
+        let theme = Theme::load("Bare", &[temp.0.join("icons")]).unwrap();
+        assert_eq!(theme.directories, [("apps".to_string(), Some(0))]);
+        assert!(theme.inherits.is_empty());
+    }
+}
@@ -7,19 +7,77 @@ use tauri_plugin_store::StoreExt;
 
 // 插件相关
 mod plugins;
-use plugins::{run_node_script, run_php_script, run_python_script};
+use plugins::{
+    bus_publish, bus_subscribe, bus_unsubscribe, get_plugin_dir, get_plugin_logs, get_plugins,
+    get_user_plugins_dir, run_node_script, run_php_script, run_python_script, set_user_plugins_dir,
+};
 // 动态库相关/ffi
 mod dylib;
 use dylib::dynamic_command;
 
+mod profile;
+use profile::{export_profile, import_profile};
+
 mod command;
-use command::{get_installed_apps, run_external_program, screenshot_desktop};
+use command::{run_external_program, screenshot_desktop};
+
+mod apps;
+use apps::{
+    get_apps_config, get_apps_status, get_installed_apps, launch_app, refresh_apps, set_apps_config,
+};
 
 mod utils;
-use utils::{capability, shortcut};
+use utils::{capability, plugin_server_config, shortcut};
 
 mod platform;
 
+// Windows 的快捷方式解析，其它平台上只在测试中使用
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+mod lnk;
+
+mod icons;
+use icons::{clear_icon_cache, get_icon};
+
+mod clipboard;
+use clipboard::{
+    clear_clipboard_history, copy_clipboard_entry, delete_clipboard_entry, get_clipboard_config,
+    get_clipboard_history, paste_clipboard_entry, pin_clipboard_entry, set_clipboard_config,
+};
+
+mod snippets;
+use snippets::{
+    copy_snippet, delete_snippet, export_snippets, get_snippets, import_snippets, render_snippet,
+    save_snippet,
+};
+
+mod shell;
+use shell::{
+    cancel_shell_command, clear_shell_history, get_shell_config, get_shell_history,
+    open_in_terminal, run_shell_command, set_shell_config,
+};
+
+mod search;
+use search::actions::{execute_action, plugin_output};
+use search::aliases::{delete_alias, export_aliases, get_aliases, import_aliases, save_alias};
+use search::bookmarks::{
+    get_bookmark_config, get_bookmark_status, open_url, refresh_bookmarks, set_bookmark_config,
+};
+use search::calc::{add_calc_history, calculate, clear_calc_history, get_calc_history};
+use search::content::{
+    get_content_index_config, get_content_index_status, rebuild_content_index, search_content,
+    set_content_index_config,
+};
+use search::files::{
+    get_file_index_config, get_file_index_status, open_path, rebuild_file_index, reveal_path,
+    search_files, set_file_index_config,
+};
+use search::recent::open_recent;
+use search::switcher::{activate_window, get_open_windows};
+use search::{
+    clear_search_history, export_search_history, parse_query, record_launch, refresh_search_index,
+    search,
+};
+
 #[command]
 fn add_acl() {
     let capability = tauri::ipc::CapabilityBuilder::new("plugin-b");
@@ -32,7 +90,7 @@ fn add_acl() {
 #[command]
 fn add_capabilities(window: String, webview: String, permissions: Vec<String>) {
     let mut capability = tauri::ipc::CapabilityBuilder::new(window);
-    if webview != "" {
+    if !webview.is_empty() {
         capability = capability.webview(webview);
     }
     for permission in permissions {
@@ -57,27 +115,43 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_cli::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .manage(plugins::EventBus::default())
+        .manage(icons::IconService::default())
+        .manage(apps::AppCatalog::default())
+        .manage(search::SearchEngine::default())
+        .manage(search::actions::ActionService::default())
+        .manage(search::aliases::AliasStore::default())
+        .manage(search::files::FileSearch::default())
+        .manage(search::content::ContentSearch::default())
+        .manage(search::bookmarks::BookmarkSearch::default())
+        .manage(search::recent::RecentDocuments::default())
+        .manage(clipboard::ClipboardService::default())
+        .manage(snippets::SnippetStore::default())
+        .manage(shell::ShellService::default())
         .setup(|app| {
             let store_path = app
                 .path()
                 .resolve("config/store.bin", BaseDirectory::Resource)?;
             // 初始化 store
-            let store = app
-                .handle()
-                .store_builder(store_path)
-                .build();
-            
-            
+            let store = app.handle().store_builder(store_path).build();
+
             // 获取版本信息，以便初始化一些参数
             let mut version = utils::get_app_version(app.handle().clone());
-            let _ = match store.get("version".to_string()) {
+            match store.get("version") {
                 Some(tmp) => version = tmp.to_string(),
                 None => {
-                    store.set("version".to_string(), json!(version));
+                    store.set("version", json!(version));
                 }
             };
             println!("store version: {}", version);
-            let _ = match store.get("local_http_server_pid".to_string()) {
+            // 命令行导入/导出配置，完成后直接退出
+            if let Ok(matches) = app.cli().matches() {
+                if let Some(code) = profile::run_cli(app.handle(), &matches) {
+                    let _ = store.save();
+                    std::process::exit(code);
+                }
+            }
+            match store.get("local_http_server_pid") {
                 Some(tmp) => {
                     let pid: u32 = tmp.as_u64().unwrap() as u32;
                     utils::kill_local_http_server(app.handle().clone(), pid);
@@ -91,25 +165,33 @@ pub fn run() {
                 }
             };
 
-            let _ = store.save()?;
-            
+            store.save()?;
+
             // 创建托盘
             tray::create_tray(app)?;
             // 生成插件的权限文件
-            capability::generate(app)?;
+            capability::generate(app.handle())?;
             // 添加插件的权限
-            capability::add(app);
+            capability::add(app.handle());
+            icons::init(app.handle());
+            // 读取应用列表缓存并在后台扫描
+            apps::init(app.handle());
+            // 加载文件索引并监听变化
+            search::files::init(app.handle());
+            search::content::init(app.handle());
+            search::bookmarks::init(app.handle());
+            search::aliases::init(app.handle());
+            clipboard::init(app.handle());
+            snippets::init(app.handle());
+            shell::init(app.handle());
             // cli
-            match app.cli().matches() {
-                // `matches` here is a Struct with { args, subcommand }.
-                // `args` is `HashMap<String, ArgData>` where `ArgData` is a struct with { value, occurrences }.
-                // `subcommand` is `Option<Box<SubcommandMatches>>` where `SubcommandMatches` is a struct with { name, matches }.
-                Ok(matches) => {
-                    matches.args.iter().for_each(|(key, value)| {
-                        println!("{}: {:?}", key, value);
-                    });
-                }
-                Err(_) => {}
+            // `matches` here is a Struct with { args, subcommand }.
+            // `args` is `HashMap<String, ArgData>` where `ArgData` is a struct with { value, occurrences }.
+            // `subcommand` is `Option<Box<SubcommandMatches>>` where `SubcommandMatches` is a struct with { name, matches }.
+            if let Ok(matches) = app.cli().matches() {
+                matches.args.iter().for_each(|(key, value)| {
+                    println!("{}: {:?}", key, value);
+                });
             }
             // 绑定全局快捷键
             shortcut::bind(app.handle().clone())?;
@@ -123,6 +205,9 @@ pub fn run() {
                 }
                 utils::kill_server_by_name("caddy");
             }
+            tauri::WindowEvent::Destroyed => {
+                plugins::bus::remove_window(window.app_handle(), window.label());
+            }
             _ => {}
         })
         .invoke_handler(tauri::generate_handler![
@@ -134,7 +219,84 @@ pub fn run() {
             add_acl,
             add_capabilities,
             get_installed_apps,
-            screenshot_desktop
+            get_apps_status,
+            get_apps_config,
+            set_apps_config,
+            refresh_apps,
+            launch_app,
+            get_icon,
+            clear_icon_cache,
+            screenshot_desktop,
+            get_plugins,
+            get_plugin_dir,
+            get_user_plugins_dir,
+            set_user_plugins_dir,
+            get_plugin_logs,
+            plugin_server_config,
+            export_profile,
+            import_profile,
+            bus_publish,
+            bus_subscribe,
+            bus_unsubscribe,
+            search,
+            parse_query,
+            refresh_search_index,
+            record_launch,
+            clear_search_history,
+            export_search_history,
+            execute_action,
+            plugin_output,
+            get_aliases,
+            save_alias,
+            delete_alias,
+            import_aliases,
+            export_aliases,
+            search_files,
+            get_file_index_status,
+            get_file_index_config,
+            set_file_index_config,
+            rebuild_file_index,
+            open_path,
+            reveal_path,
+            search_content,
+            get_content_index_status,
+            get_content_index_config,
+            set_content_index_config,
+            rebuild_content_index,
+            get_bookmark_status,
+            get_bookmark_config,
+            set_bookmark_config,
+            refresh_bookmarks,
+            open_url,
+            open_recent,
+            get_open_windows,
+            activate_window,
+            calculate,
+            get_calc_history,
+            add_calc_history,
+            clear_calc_history,
+            get_clipboard_history,
+            copy_clipboard_entry,
+            paste_clipboard_entry,
+            pin_clipboard_entry,
+            delete_clipboard_entry,
+            clear_clipboard_history,
+            get_clipboard_config,
+            set_clipboard_config,
+            get_snippets,
+            save_snippet,
+            delete_snippet,
+            render_snippet,
+            copy_snippet,
+            import_snippets,
+            export_snippets,
+            run_shell_command,
+            cancel_shell_command,
+            open_in_terminal,
+            get_shell_history,
+            clear_shell_history,
+            get_shell_config,
+            set_shell_config
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,550 @@
+// 按 MS-SHLLINK 解析快捷方式（.lnk），不依赖 COM，在任何平台上都可以解析和测试
+use std::{fs, path::Path};
+
+const HEADER_SIZE: usize = 0x4c;
+// CLSID_ShellLink {00021401-0000-0000-C000-000000000046}
+const LINK_CLSID: u128 = 0x00021401_0000_0000_c000_000000000046;
+// 快捷方式文件不会很大，超过的不是快捷方式
+const MAX_LINK_FILE: u64 = 1024 * 1024;
+
+// LinkFlags
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x1;
+const HAS_LINK_INFO: u32 = 0x2;
+const HAS_NAME: u32 = 0x4;
+const HAS_RELATIVE_PATH: u32 = 0x8;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+const FORCE_NO_LINK_INFO: u32 = 0x100;
+const HAS_EXP_STRING: u32 = 0x200;
+const HAS_EXP_ICON: u32 = 0x4000;
+
+// LinkInfoFlags
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK: u32 = 0x2;
+
+// ExtraData 的签名
+const ENVIRONMENT_BLOCK: u32 = 0xa000_0001;
+const SPECIAL_FOLDER_BLOCK: u32 = 0xa000_0005;
+const ICON_ENVIRONMENT_BLOCK: u32 = 0xa000_0007;
+const KNOWN_FOLDER_BLOCK: u32 = 0xa000_000b;
+
+// IDList 中的根项为“此电脑”时，下一项为驱动器
+const MY_COMPUTER: u128 = 0x20d04fe0_3aea_1069_a2d8_08002b30309d;
+// 文件项中保存长文件名的扩展块
+const FILE_ENTRY_EXTENSION: u32 = 0xbeef_0004;
+
+// 已知文件夹对应的路径，由调用方展开环境变量
+const KNOWN_FOLDERS: [(u128, &str); 16] = [
+    (0x905e63b6_c1bf_494e_b29c_65b732d3d21a, "%ProgramFiles%"),
+    (
+        0x7c5a40ef_a0fb_4bfc_874a_c0f2e0b9fa8e,
+        "%ProgramFiles(x86)%",
+    ),
+    (0x6d809377_6af0_444b_8957_a3773f02200e, "%ProgramW6432%"),
+    (
+        0xf7f1ed05_9f6d_47a2_aaae_29d317c6f066,
+        "%CommonProgramFiles%",
+    ),
+    (
+        0x5cd7aeee_2219_4a67_b85d_6c9ce15660cb,
+        "%LOCALAPPDATA%\\Programs",
+    ),
+    (0xf38bf404_1d43_42f2_9305_67de0b28fc23, "%SystemRoot%"),
+    (
+        0x1ac14e77_02e7_4e5d_b744_2eb1ae5198b7,
+        "%SystemRoot%\\System32",
+    ),
+    (
+        0xd65231b0_b2f1_4857_a4ce_a8e7c6ea7d27,
+        "%SystemRoot%\\SysWOW64",
+    ),
+    (0x3eb685db_65f9_4cf6_a03a_e3ef65729f3d, "%APPDATA%"),
+    (0xf1b32785_6fba_4fcf_9d55_7b8e7f157091, "%LOCALAPPDATA%"),
+    (0x62ab5d82_fdc1_4dc3_a9dd_070d1d495d97, "%ProgramData%"),
+    (0x5e6c858f_0e22_4760_9afe_ea3317b67173, "%USERPROFILE%"),
+    (
+        0xb4bfcc3a_db2c_424c_b029_7fe99a87c641,
+        "%USERPROFILE%\\Desktop",
+    ),
+    (
+        0xfdd39ad0_238f_46af_adb4_6c85480369c7,
+        "%USERPROFILE%\\Documents",
+    ),
+    (
+        0x374de290_123f_4565_9164_39c4925e467b,
+        "%USERPROFILE%\\Downloads",
+    ),
+    (
+        0xa77f5d77_2e2b_44c3_a6a2_aba601054a51,
+        "%APPDATA%\\Microsoft\\Windows\\Start Menu\\Programs",
+    ),
+];
+
+// SpecialFolderDataBlock 中的 CSIDL
+const SPECIAL_FOLDERS: [(u32, &str); 12] = [
+    (0x05, "%USERPROFILE%\\Documents"),
+    (0x10, "%USERPROFILE%\\Desktop"),
+    (0x1a, "%APPDATA%"),
+    (0x1c, "%LOCALAPPDATA%"),
+    (0x23, "%ProgramData%"),
+    (0x24, "%SystemRoot%"),
+    (0x25, "%SystemRoot%\\System32"),
+    (0x26, "%ProgramFiles%"),
+    (0x28, "%USERPROFILE%"),
+    (0x29, "%SystemRoot%\\SysWOW64"),
+    (0x2a, "%ProgramFiles(x86)%"),
+    (0x2b, "%CommonProgramFiles%"),
+];
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// GUID 的前三部分为小端序，后 8 个字节按顺序保存
+fn guid_at(data: &[u8], offset: usize) -> Option<u128> {
+    let bytes = data.get(offset..offset + 16)?;
+    let data1 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u128;
+    let data2 = u16::from_le_bytes([bytes[4], bytes[5]]) as u128;
+    let data3 = u16::from_le_bytes([bytes[6], bytes[7]]) as u128;
+    let data4 = bytes[8..]
+        .iter()
+        .fold(0u128, |value, byte| value << 8 | *byte as u128);
+    Some(data1 << 96 | data2 << 80 | data3 << 64 | data4)
+}
+
+// 以 0 结尾的 UTF-16 字符串
+fn utf16_z(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// 以 0 结尾的 ANSI 字符串，系统代码页未知，非 ASCII 字符按 UTF-8 尽量解码
+fn ansi_z(data: &[u8]) -> String {
+    let len = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..len]).to_string()
+}
+
+fn non_empty(text: String) -> Option<String> {
+    (!text.is_empty()).then_some(text)
+}
+
+// 以 `\` 连接路径
+fn join(base: &str, name: &str) -> String {
+    if base.is_empty() || base.ends_with('\\') {
+        format!("{}{}", base, name)
+    } else {
+        format!("{}\\{}", base, name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShellLink {
+    pub icon_index: i32,
+    // 由 LinkTargetIDList 得到的路径，可能包含 `%ProgramFiles%` 等环境变量
+    pub id_list_path: Option<String>,
+    // LinkInfo 中的本地路径或网络路径
+    pub link_info_path: Option<String>,
+    // StringData
+    pub name: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub icon_location: Option<String>,
+    // 环境变量块中的目标和图标，包含未展开的环境变量
+    pub environment_target: Option<String>,
+    pub environment_icon: Option<String>,
+}
+
+impl ShellLink {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+        if size > MAX_LINK_FILE {
+            return Err("Not a shell link".to_string());
+        }
+        let data = fs::read(path).map_err(|e| e.to_string())?;
+        Self::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if u32_at(data, 0) != Some(HEADER_SIZE as u32) || guid_at(data, 4) != Some(LINK_CLSID) {
+            return Err("Not a shell link".to_string());
+        }
+        let invalid = || "Invalid shell link".to_string();
+        let flags = u32_at(data, 20).ok_or_else(invalid)?;
+        let mut link = ShellLink {
+            icon_index: u32_at(data, 56).ok_or_else(invalid)? as i32,
+            ..Default::default()
+        };
+        // 头部之后依次为 LinkTargetIDList、LinkInfo、StringData 和 ExtraData，前三者按标志可选
+        let mut offset = HEADER_SIZE;
+
+        // IDList 中每一项的位置和内容，位置相对于第一项
+        let mut items: Vec<(usize, &[u8])> = Vec::new();
+        if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let size = u16_at(data, offset).ok_or_else(invalid)? as usize;
+            let list = data
+                .get(offset + 2..offset + 2 + size)
+                .ok_or_else(invalid)?;
+            let mut position = 0;
+            while let Some(item_size) = u16_at(list, position).map(|size| size as usize) {
+                if item_size < 2 {
+                    break;
+                }
+                let item = list
+                    .get(position..position + item_size)
+                    .ok_or_else(invalid)?;
+                items.push((position, item));
+                position += item_size;
+            }
+            offset += 2 + size;
+        }
+
+        if flags & HAS_LINK_INFO != 0 {
+            let size = u32_at(data, offset).ok_or_else(invalid)? as usize;
+            let info = data.get(offset..offset + size).ok_or_else(invalid)?;
+            if flags & FORCE_NO_LINK_INFO == 0 {
+                link.link_info_path = link_info_path(info);
+            }
+            offset += size;
+        }
+
+        // StringData 按固定的顺序出现
+        let unicode = flags & IS_UNICODE != 0;
+        let mut read_string = |flag: u32| -> Result<Option<String>, String> {
+            if flags & flag == 0 {
+                return Ok(None);
+            }
+            let count = u16_at(data, offset).ok_or_else(invalid)? as usize;
+            let len = if unicode { count * 2 } else { count };
+            let bytes = data.get(offset + 2..offset + 2 + len).ok_or_else(invalid)?;
+            offset += 2 + len;
+            Ok(non_empty(if unicode {
+                utf16_z(bytes)
+            } else {
+                ansi_z(bytes)
+            }))
+        };
+        link.name = read_string(HAS_NAME)?;
+        link.relative_path = read_string(HAS_RELATIVE_PATH)?;
+        link.working_dir = read_string(HAS_WORKING_DIR)?;
+        link.arguments = read_string(HAS_ARGUMENTS)?;
+        link.icon_location = read_string(HAS_ICON_LOCATION)?;
+
+        // ExtraData，长度小于 4 的块为结束标记
+        // 已知文件夹的路径和 IDList 中第几项开始为其下的路径
+        let mut folder: Option<(&str, usize)> = None;
+        while let Some(size) = u32_at(data, offset).map(|size| size as usize) {
+            if size < 8 {
+                break;
+            }
+            let Some(block) = data.get(offset..offset + size) else {
+                break;
+            };
+            match u32_at(block, 4) {
+                Some(ENVIRONMENT_BLOCK) if flags & HAS_EXP_STRING != 0 => {
+                    link.environment_target = environment_string(block);
+                }
+                Some(ICON_ENVIRONMENT_BLOCK) if flags & HAS_EXP_ICON != 0 => {
+                    link.environment_icon = environment_string(block);
+                }
+                Some(KNOWN_FOLDER_BLOCK) => {
+                    let id = guid_at(block, 8);
+                    let path = KNOWN_FOLDERS
+                        .iter()
+                        .find(|(known, _)| Some(*known) == id)
+                        .map(|(_, path)| *path);
+                    if let (Some(path), Some(item)) = (path, u32_at(block, 24)) {
+                        folder = Some((path, item as usize));
+                    }
+                }
+                // 已知文件夹块更准确，两者都有时使用已知文件夹
+                Some(SPECIAL_FOLDER_BLOCK) if folder.is_none() => {
+                    let id = u32_at(block, 8);
+                    let path = SPECIAL_FOLDERS
+                        .iter()
+                        .find(|(special, _)| Some(*special) == id)
+                        .map(|(_, path)| *path);
+                    if let (Some(path), Some(item)) = (path, u32_at(block, 12)) {
+                        folder = Some((path, item as usize));
+                    }
+                }
+                _ => {}
+            }
+            offset += size;
+        }
+
+        link.id_list_path = id_list_path(&items, folder);
+        Ok(link)
+    }
+
+    // 目标路径：环境变量块、LinkInfo、IDList 依次优先，都没有时按相对路径从快捷方式所在的目录找
+    // 返回的路径可能包含未展开的环境变量
+    pub fn target(&self, lnk: &Path) -> Option<String> {
+        self.environment_target
+            .clone()
+            .or_else(|| self.link_info_path.clone())
+            .or_else(|| self.id_list_path.clone())
+            .or_else(|| {
+                let relative = self.relative_path.as_ref()?;
+                let dir = lnk.parent()?.to_string_lossy().to_string();
+                Some(join(&dir, relative))
+            })
+    }
+
+    // 图标位置，优先使用环境变量块中的路径
+    pub fn icon(&self) -> Option<String> {
+        self.environment_icon
+            .clone()
+            .or_else(|| self.icon_location.clone())
+    }
+}
+
+// LinkInfo 中的路径，本地路径为 LocalBasePath + CommonPathSuffix，网络路径为 NetName\CommonPathSuffix
+fn link_info_path(info: &[u8]) -> Option<String> {
+    let header_size = u32_at(info, 4)? as usize;
+    let flags = u32_at(info, 8)?;
+    // 头部不小于 0x24 个字节时有 Unicode 版本的路径
+    let unicode_offset = |field: usize| {
+        (header_size >= 0x24)
+            .then(|| u32_at(info, field))
+            .flatten()
+            .filter(|offset| *offset != 0)
+            .and_then(|offset| info.get(offset as usize..))
+            .map(utf16_z)
+    };
+    let ansi_offset = |field: usize| {
+        u32_at(info, field)
+            .filter(|offset| *offset != 0)
+            .and_then(|offset| info.get(offset as usize..))
+            .map(ansi_z)
+    };
+    let suffix = unicode_offset(32)
+        .or_else(|| ansi_offset(24))
+        .unwrap_or_default();
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        if let Some(base) = unicode_offset(28).or_else(|| ansi_offset(16)) {
+            return non_empty(format!("{}{}", base, suffix));
+        }
+    }
+    if flags & COMMON_NETWORK_RELATIVE_LINK != 0 {
+        let link = info.get(u32_at(info, 20)? as usize..)?;
+        let name_offset = u32_at(link, 8)? as usize;
+        let name = if name_offset > 0x14 {
+            utf16_z(link.get(u32_at(link, 20)? as usize..)?)
+        } else {
+            ansi_z(link.get(name_offset..)?)
+        };
+        return non_empty(join(&name, &suffix));
+    }
+    None
+}
+
+// EnvironmentVariableDataBlock 和 IconEnvironmentDataBlock：260 字节的 ANSI 字符串，之后为 520 字节的 Unicode 字符串
+fn environment_string(block: &[u8]) -> Option<String> {
+    block
+        .get(268..788)
+        .map(utf16_z)
+        .and_then(non_empty)
+        .or_else(|| block.get(8..268).map(ansi_z).and_then(non_empty))
+}
+
+// 由 IDList 拼出文件系统路径，只处理驱动器和文件项，其它虚拟文件夹返回 None
+fn id_list_path(items: &[(usize, &[u8])], folder: Option<(&str, usize)>) -> Option<String> {
+    // 有已知文件夹时从已知文件夹之后的项开始
+    let (mut path, rest) = match folder
+        .and_then(|(path, item)| Some((path, items.iter().position(|(p, _)| *p == item)?)))
+    {
+        Some((path, index)) => (Some(path.to_string()), &items[index..]),
+        None => (None, items),
+    };
+    for (_, item) in rest {
+        let kind = *item.get(2)?;
+        match kind & 0x70 {
+            // 根文件夹，只认识“此电脑”
+            0x10 if path.is_none() => {
+                if guid_at(item, 4)? != MY_COMPUTER {
+                    return None;
+                }
+            }
+            // 驱动器，如 `C:\`
+            0x20 => path = Some(ansi_z(item.get(3..)?)),
+            0x30 => {
+                let name = file_entry_name(item)?;
+                path = Some(join(path.as_deref()?, &name));
+            }
+            _ => return None,
+        }
+    }
+    path.filter(|path| !path.is_empty())
+}
+
+// 文件项：短文件名之后的扩展块中有长文件名，没有扩展块时使用短文件名
+fn file_entry_name(item: &[u8]) -> Option<String> {
+    let kind = *item.get(2)?;
+    let short = item.get(14..)?;
+    // 类型中有 0x04 时短文件名为 Unicode
+    // 长度按原始数据中结尾的 0 计算，解码时替换的字符会改变字符串的长度
+    let (short_name, short_len) = if kind & 0x04 != 0 {
+        let units = short
+            .chunks_exact(2)
+            .position(|unit| unit == [0, 0])
+            .unwrap_or(short.len() / 2);
+        (utf16_z(short), units * 2 + 2)
+    } else {
+        let len = short.iter().position(|b| *b == 0).unwrap_or(short.len());
+        (ansi_z(short), len + 1)
+    };
+    // 扩展块按 2 字节对齐
+    let extension = 14 + short_len + (short_len % 2);
+    let long_name = (|| {
+        let block = item.get(extension..)?;
+        if u32_at(block, 4)? != FILE_ENTRY_EXTENSION {
+            return None;
+        }
+        let version = u16_at(block, 2)?;
+        let mut offset = 18;
+        if version >= 7 {
+            offset += 18;
+        }
+        if version >= 3 {
+            offset += 2;
+        }
+        if version >= 9 {
+            offset += 4;
+        }
+        if version >= 8 {
+            offset += 4;
+        }
+        block.get(offset..).map(utf16_z).and_then(non_empty)
+    })();
+    long_name.or(non_empty(short_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用的快捷方式由 tests/fixtures/lnk/gen.py 生成
+    macro_rules! fixture {
+        ($name:literal) => {
+            ShellLink::parse(include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/lnk/",
+                $name,
+                ".lnk"
+            )))
+        };
+    }
+
+    #[test]
+    fn parses_all_sections() {
+        let link = fixture!("full").unwrap();
+        let lnk = Path::new("C:/Users/me/Desktop/app.lnk");
+        assert_eq!(
+            link.target(lnk).as_deref(),
+            Some("%ProgramFiles%\\我的应用\\app.exe")
+        );
+        assert_eq!(
+            link.icon().as_deref(),
+            Some("%ProgramFiles%\\我的应用\\app.ico")
+        );
+        assert_eq!(link.icon_index, 3);
+        assert_eq!(link.name.as_deref(), Some("My App 描述"));
+        assert_eq!(
+            link.working_dir.as_deref(),
+            Some("%ProgramFiles%\\我的应用")
+        );
+        assert_eq!(link.arguments.as_deref(), Some("--flag \"a b\""));
+        assert_eq!(
+            link.relative_path.as_deref(),
+            Some("..\\..\\我的应用\\app.exe")
+        );
+        assert_eq!(
+            link.icon_location.as_deref(),
+            Some("%SystemRoot%\\system32\\shell32.dll")
+        );
+        assert_eq!(
+            link.link_info_path.as_deref(),
+            Some("C:\\Program Files\\我的应用\\app.exe")
+        );
+        assert_eq!(
+            link.id_list_path.as_deref(),
+            Some("C:\\Program Files\\我的应用\\app.exe")
+        );
+    }
+
+    #[test]
+    fn resolves_known_folder() {
+        let link = fixture!("known_folder").unwrap();
+        assert_eq!(
+            link.id_list_path.as_deref(),
+            Some("%ProgramFiles%\\我的应用\\app.exe")
+        );
+    }
+
+    #[test]
+    fn builds_path_from_id_list() {
+        let link = fixture!("idlist").unwrap();
+        assert_eq!(
+            link.id_list_path.as_deref(),
+            Some("D:\\Tools\\tool long.exe")
+        );
+        assert_eq!(link.link_info_path, None);
+    }
+
+    #[test]
+    fn ignores_link_info_when_forced() {
+        let link = fixture!("force_no_info").unwrap();
+        assert_eq!(link.link_info_path, None);
+        assert_eq!(
+            link.target(Path::new("a.lnk")).as_deref(),
+            Some("D:\\Tools\\tool long.exe")
+        );
+    }
+
+    #[test]
+    fn parses_network_path_and_ansi_strings() {
+        let link = fixture!("network_ansi").unwrap();
+        assert_eq!(
+            link.link_info_path.as_deref(),
+            Some("\\\\server\\share\\dir\\prog.exe")
+        );
+        assert_eq!(link.name.as_deref(), Some("Net prog"));
+        assert_eq!(link.arguments.as_deref(), Some("-x"));
+    }
+
+    #[test]
+    fn falls_back_to_relative_path() {
+        let link = fixture!("relative").unwrap();
+        assert_eq!(
+            link.target(Path::new("C:/links/rel.lnk")).as_deref(),
+            Some("C:/links\\..\\bin\\rel.exe")
+        );
+    }
+
+    // 非 UTF-8 的短文件名解码后长度改变，不能影响扩展块的位置
+    #[test]
+    fn finds_long_name_after_ansi_short_name() {
+        let link = fixture!("gbk_short_name").unwrap();
+        assert_eq!(link.id_list_path.as_deref(), Some("C:\\程序\\run.exe"));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        assert_eq!(fixture!("not_link").unwrap_err(), "Not a shell link");
+        assert_eq!(fixture!("truncated").unwrap_err(), "Invalid shell link");
+        assert_eq!(ShellLink::parse(&[]).unwrap_err(), "Not a shell link");
+    }
+}
@@ -0,0 +1,183 @@
+// 读取 type 2 AppImage 中嵌入的 .desktop 文件和图标，不运行 AppImage
+// AppImage 由 ELF 格式的 runtime 和紧随其后的 squashfs 组成
+
+use std::{fs::File, os::unix::fs::FileExt, path::Path};
+
+use super::{desktop::DesktopEntry, squashfs::SquashFs};
+
+// .desktop 文件和图标的大小上限
+const MAX_DESKTOP_FILE: u64 = 1024 * 1024;
+const MAX_ICON_FILE: u64 = 16 * 1024 * 1024;
+
+// squashfs 的位置在 ELF 节头表之后
+fn squashfs_offset(header: &[u8; 64]) -> Result<u64, String> {
+    if &header[..4] != b"\x7fELF" {
+        return Err("Not an ELF file".to_string());
+    }
+    // 第 8 到 10 字节为 AppImage 的魔数，type 1 是 ISO 9660 格式
+    if &header[8..11] == b"AI\x01" {
+        return Err("Type 1 AppImage is not supported".to_string());
+    }
+    let big_endian = header[5] == 2;
+    let u16_at = |at: usize| {
+        let bytes = [header[at], header[at + 1]];
+        if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    };
+    let u32_at = |at: usize| {
+        let bytes = header[at..at + 4].try_into().unwrap();
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    let u64_at = |at: usize| {
+        let bytes = header[at..at + 8].try_into().unwrap();
+        if big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        }
+    };
+    // 32 位和 64 位的 ELF 头中字段位置不同
+    let (section_offset, entry_size, count) = match header[4] {
+        1 => (u32_at(0x20) as u64, u16_at(0x2e), u16_at(0x30)),
+        2 => (u64_at(0x28), u16_at(0x3a), u16_at(0x3c)),
+        _ => return Err("Invalid ELF file".to_string()),
+    };
+    Ok(section_offset + entry_size as u64 * count as u64)
+}
+
+fn open(path: &Path) -> Result<SquashFs, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut header = [0u8; 64];
+    file.read_exact_at(&mut header, 0)
+        .map_err(|e| e.to_string())?;
+    let offset = squashfs_offset(&header)?;
+    SquashFs::open(file, offset)
+}
+
+// 根目录中的 .desktop 文件
+fn read_entry(image: &SquashFs) -> Result<DesktopEntry, String> {
+    let name = image
+        .root_entries()?
+        .into_iter()
+        .find(|name| name.ends_with(".desktop"))
+        .ok_or("No .desktop file in AppImage".to_string())?;
+    let data = image
+        .read(&name, MAX_DESKTOP_FILE)?
+        .ok_or(format!("Failed to read {}", name))?;
+    DesktopEntry::parse_current(&String::from_utf8_lossy(&data))
+}
+
+pub fn desktop_entry(path: &Path) -> Result<DesktopEntry, String> {
+    read_entry(&open(path)?)
+}
+
+// 图标内容和格式对应的扩展名，优先使用 .desktop 中 Icon 指定的根目录中的文件，其次为 .DirIcon
+pub fn icon(path: &Path) -> Result<(Vec<u8>, String), String> {
+    let image = open(path)?;
+    let mut candidates = Vec::new();
+    if let Some(icon) = read_entry(&image).ok().and_then(|entry| entry.icon) {
+        for ext in ["png", "svg", "svgz"] {
+            candidates.push(format!("{}.{}", icon, ext));
+        }
+        candidates.push(icon);
+    }
+    candidates.push(".DirIcon".to_string());
+    for candidate in candidates {
+        let Some(data) = image.read(&candidate, MAX_ICON_FILE)? else {
+            continue;
+        };
+        // .DirIcon 没有扩展名，按内容判断是否为 SVG
+        let ext = match Path::new(&candidate).extension() {
+            Some(ext) => ext.to_string_lossy().to_lowercase(),
+            None if data.starts_with(&[0x1f, 0x8b]) => "svgz".to_string(),
+            None if String::from_utf8_lossy(&data[..data.len().min(1024)]).contains("<svg") => {
+                "svg".to_string()
+            }
+            None => "png".to_string(),
+        };
+        return Ok((data, ext));
+    }
+    Err("No icon in AppImage".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/appimage")
+            .join(name)
+    }
+
+    #[test]
+    fn reads_desktop_entry() {
+        let entry = DesktopEntry::parse(
+            &String::from_utf8_lossy(
+                &open(&fixture("Firefox.AppImage"))
+                    .unwrap()
+                    .read("firefox.desktop", MAX_DESKTOP_FILE)
+                    .unwrap()
+                    .unwrap(),
+            ),
+            Some("zh_CN"),
+        )
+        .unwrap();
+        assert_eq!(entry.name.as_deref(), Some("火狐"));
+        assert_eq!(entry.default_name.as_deref(), Some("Firefox"));
+        assert_eq!(entry.appimage_version.as_deref(), Some("128.0"));
+        assert_eq!(entry.categories, ["Network"]);
+        let entry = desktop_entry(&fixture("Raw.AppImage")).unwrap();
+        assert_eq!(entry.icon.as_deref(), Some("firefox"));
+    }
+
+    #[test]
+    fn reads_icon() {
+        let (data, ext) = icon(&fixture("Firefox.AppImage")).unwrap();
+        assert_eq!(ext, "png");
+        assert_eq!(data.len(), 256 * 40 + 4);
+        assert!(data.ends_with(b"TAIL"));
+    }
+
+    #[test]
+    fn finds_squashfs_after_section_headers() {
+        let mut header = [0u8; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[4] = 2;
+        header[5] = 1;
+        header[8..11].copy_from_slice(b"AI\x02");
+        header[0x28..0x30].copy_from_slice(&1000u64.to_le_bytes());
+        header[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        header[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+        assert_eq!(squashfs_offset(&header), Ok(1192));
+
+        // 32 位大端序
+        let mut header32 = [0u8; 64];
+        header32[..4].copy_from_slice(b"\x7fELF");
+        header32[4] = 1;
+        header32[5] = 2;
+        header32[0x20..0x24].copy_from_slice(&500u32.to_be_bytes());
+        header32[0x2e..0x30].copy_from_slice(&40u16.to_be_bytes());
+        header32[0x30..0x32].copy_from_slice(&2u16.to_be_bytes());
+        assert_eq!(squashfs_offset(&header32), Ok(580));
+
+        header[8..11].copy_from_slice(b"AI\x01");
+        assert!(squashfs_offset(&header).is_err());
+        assert!(squashfs_offset(&[0u8; 64]).is_err());
+    }
+
+    #[test]
+    fn rejects_broken_images() {
+        assert!(desktop_entry(&fixture("BadDir.AppImage")).is_err());
+        assert!(desktop_entry(&fixture("missing.AppImage")).is_err());
+        assert!(desktop_entry(Path::new(file!())).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{appimage, desktop::DesktopEntry};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Installed {
+    pub apps: Vec<App>,
+    // 读取失败的目录或 .desktop 文件
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+// path 为 .desktop 文件，启动时按其中的 Exec 执行
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct App {
+    pub name: Option<String>,
+    pub path: Option<PathBuf>,
+    pub icon: Option<PathBuf>,
+    // 版本号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // desktop id，如 `firefox.desktop`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // 应用的说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    // 额外参与搜索的关键词
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    // 应用的其它名称，如未翻译的名称，name 为当前语言的名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+    // 安装来源，如 Flatpak、Snap、AppImage，系统包管理器安装的为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl App {
+    fn from_entry(entry: DesktopEntry) -> Self {
+        let mut keywords = entry.generic_name.clone();
+        keywords.extend(entry.keywords.iter().cloned());
+        App {
+            names: entry
+                .default_name
+                .iter()
+                .filter(|name| entry.name.as_ref() != Some(*name))
+                .cloned()
+                .collect(),
+            name: entry.name,
+            icon: entry.icon.as_ref().map(PathBuf::from),
+            version: entry.appimage_version,
+            description: entry.comment,
+            categories: entry.categories,
+            keywords,
+            ..Default::default()
+        }
+    }
+}
+
+// applications 目录对应的安装来源
+fn source(dir: &Path) -> Option<String> {
+    if dir.ends_with("flatpak/exports/share/applications") {
+        Some("Flatpak".to_string())
+    } else if dir.starts_with("/var/lib/snapd") {
+        Some("Snap".to_string())
+    } else {
+        None
+    }
+}
+
+// linux 下获取应用程序列表是通过读取 XDG 数据目录中的 .desktop 文件实现的
+impl Installed {
+    pub fn new() -> Self {
+        let mut installed = Self::default();
+        let desktops: Vec<String> = env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .split(':')
+            .filter(|desktop| !desktop.is_empty())
+            .map(str::to_string)
+            .collect();
+        // 同一个 desktop id 只取优先级最高的目录中的文件，隐藏的文件也会覆盖后面的同名文件
+        let mut ids = HashSet::new();
+        for dir in Self::dirs() {
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut files = Vec::new();
+            Self::traverse_dir(&dir, &mut files, &mut installed.warnings);
+            for file in files {
+                let id = file
+                    .strip_prefix(&dir)
+                    .unwrap_or(&file)
+                    .to_string_lossy()
+                    .replace('/', "-");
+                if !ids.insert(id.clone()) {
+                    continue;
+                }
+                let entry = match DesktopEntry::read(&file) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        installed.warnings.push(format!(
+                            "Failed to read {}: {}",
+                            file.display(),
+                            e
+                        ));
+                        continue;
+                    }
+                };
+                if !entry.visible(&desktops) {
+                    continue;
+                }
+                installed.apps.push(App {
+                    path: Some(file),
+                    id: Some(id),
+                    source: source(&dir),
+                    ..App::from_entry(entry)
+                });
+            }
+        }
+        installed
+    }
+
+    // 按优先级排列的 applications 目录，这些目录变化时需要重新获取
+    // Flatpak 和 Snap 的目录一般已经在 XDG_DATA_DIRS 中，不在时也要读取
+    pub fn dirs() -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        let data_home = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => Some(PathBuf::from(dir)),
+            None => env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")),
+        };
+        if let Some(data_home) = data_home {
+            dirs.push(data_home.clone());
+            dirs.push(data_home.join("flatpak/exports/share"));
+        }
+        let data_dirs = env::var_os("XDG_DATA_DIRS")
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+        dirs.extend(env::split_paths(&data_dirs));
+        dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+        dirs.push(PathBuf::from("/var/lib/snapd/desktop"));
+        let mut unique: Vec<PathBuf> = Vec::new();
+        for dir in dirs {
+            let dir = dir.join("applications");
+            if !unique.contains(&dir) {
+                unique.push(dir);
+            }
+        }
+        unique
+    }
+
+    // 读取目录中的 AppImage，使用其中嵌入的 .desktop 文件，读取失败时只使用文件名
+    pub fn add_appimages(&mut self, dirs: &[PathBuf]) {
+        for dir in dirs {
+            if !dir.is_dir() {
+                continue;
+            }
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.warnings
+                        .push(format!("Failed to read {}: {}", dir.display(), e));
+                    continue;
+                }
+            };
+            let mut files: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension.eq_ignore_ascii_case("appimage"))
+                        && path.is_file()
+                })
+                .collect();
+            files.sort();
+            for file in files {
+                // 同一个目录配置了多次
+                if self.apps.iter().any(|app| app.path.as_ref() == Some(&file)) {
+                    continue;
+                }
+                let file_name = file
+                    .file_stem()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut app = match appimage::desktop_entry(&file) {
+                    Ok(entry) => App {
+                        // 图标在 AppImage 中，由图标服务读取
+                        icon: Some(file.clone()),
+                        ..App::from_entry(entry)
+                    },
+                    Err(e) => {
+                        self.warnings
+                            .push(format!("Failed to read {}: {}", file.display(), e));
+                        App::default()
+                    }
+                };
+                // 文件名中一般带有版本号，如 `Obsidian-1.5.3`
+                match app.name.as_ref() {
+                    Some(name) if name != &file_name && !app.names.contains(&file_name) => {
+                        app.names.push(file_name)
+                    }
+                    Some(_) => {}
+                    None => app.name = Some(file_name),
+                }
+                app.path = Some(file);
+                app.source = Some("AppImage".to_string());
+                self.apps.push(app);
+            }
+        }
+    }
+
+    fn traverse_dir(dir_path: &Path, files: &mut Vec<PathBuf>, warnings: &mut Vec<String>) {
+        let entries = match fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warnings.push(format!("Failed to read {}: {}", dir_path.display(), e));
+                return;
+            }
+        };
+        for entry in entries {
+            let file_path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    warnings.push(format!("Failed to read {}: {}", dir_path.display(), e));
+                    continue;
+                }
+            };
+            if file_path.is_dir() {
+                Self::traverse_dir(&file_path, files, warnings)
+            } else if file_path
+                .extension()
+                .is_some_and(|extension| extension == "desktop")
+            {
+                files.push(file_path)
+            }
+        }
+    }
+}
@@ -0,0 +1,622 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// .desktop 文件中 [Desktop Entry] 组的内容，按 freedesktop 的 Desktop Entry 规范解析
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    // Type，应用为 Application
+    pub kind: String,
+    // 按当前语言取的 Name
+    pub name: Option<String>,
+    // 未翻译的 Name
+    pub default_name: Option<String>,
+    // 当前语言和未翻译的 GenericName，如 `Web Browser`
+    pub generic_name: Vec<String>,
+    // 按当前语言取的 Comment
+    pub comment: Option<String>,
+    // 当前语言和未翻译的 Keywords
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    // 图标名或绝对路径
+    pub icon: Option<String>,
+    // 启动时的工作目录
+    pub path: Option<PathBuf>,
+    // 需要在终端中运行
+    pub terminal: bool,
+    pub no_display: bool,
+    pub hidden: bool,
+    pub only_show_in: Vec<String>,
+    pub not_show_in: Vec<String>,
+    // AppImage 中的 X-AppImage-Version
+    pub appimage_version: Option<String>,
+}
+
+// 当前语言，如 `zh_CN.UTF-8` 去掉编码后为 `zh_CN`
+fn locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| value != "C" && value != "POSIX")
+}
+
+// 本地化的键按 lang_COUNTRY@MODIFIER、lang_COUNTRY、lang@MODIFIER、lang 的顺序匹配，越小越优先
+fn locale_rank(key_locale: &str, locale: &str) -> Option<usize> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or(locale);
+    let lang = locale.split('_').next().unwrap_or(locale);
+    let country = locale.split_once('_').map(|(_, country)| country);
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{}@{}", lang, modifier));
+    }
+    candidates.push(lang.to_string());
+    candidates
+        .iter()
+        .position(|candidate| candidate == key_locale)
+}
+
+// 值中的转义：\s \n \t \r \\，其它转义原样保留，留给 Exec 的引号规则处理
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// 以分号分隔的列表，`\;` 为分号本身
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => item.push(';'),
+                Some(other) => {
+                    item.push('\\');
+                    item.push(other);
+                }
+                None => item.push('\\'),
+            },
+            ';' => items.push(unescape(&std::mem::take(&mut item))),
+            _ => item.push(c),
+        }
+    }
+    if !item.is_empty() {
+        items.push(unescape(&item));
+    }
+    items
+}
+
+// 在 PATH 中查找可执行文件，用于 TryExec
+fn find_program(program: &str) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.is_absolute() {
+        return program.is_file().then(|| program.to_path_buf());
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    })
+}
+
+// file:// 开头的地址转成本地路径，%f 和 %F 只接受本地文件
+fn local_path(file: &str) -> String {
+    let Some(path) = file.strip_prefix("file://") else {
+        return file.to_string();
+    };
+    // 去掉主机名部分
+    let path = &path[path.find('/').unwrap_or(path.len())..];
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// 按 Exec 的引号规则拆分参数：双引号内 \" \` \$ \\ 为转义
+pub fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    // 当前参数是否已经开始，空的引号也是一个参数
+    let mut started = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' => quoted = false,
+                '\\' => match chars.next() {
+                    Some(next @ ('"' | '`' | '$' | '\\')) => arg.push(next),
+                    Some(next) => {
+                        arg.push('\\');
+                        arg.push(next);
+                    }
+                    None => return Err(format!("Invalid Exec `{}`", exec)),
+                },
+                _ => arg.push(c),
+            }
+            continue;
+        }
+        match c {
+            ' ' | '\t' | '\n' => {
+                if started {
+                    args.push(std::mem::take(&mut arg));
+                    started = false;
+                }
+            }
+            '"' => {
+                quoted = true;
+                started = true;
+            }
+            // 规范要求保留字符放在引号中，这里宽松处理引号外的转义
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    arg.push(next);
+                }
+                started = true;
+            }
+            _ => {
+                arg.push(c);
+                started = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(format!("Unterminated quote in Exec `{}`", exec));
+    }
+    if started {
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+impl DesktopEntry {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse_current(&content)
+    }
+
+    // 按当前语言解析，如 AppImage 中嵌入的 .desktop 文件
+    pub fn parse_current(content: &str) -> Result<Self, String> {
+        Self::parse(content, locale().as_deref())
+    }
+
+    pub fn parse(content: &str, locale: Option<&str>) -> Result<Self, String> {
+        // 未翻译的值和匹配当前语言的翻译（匹配程度, 值）
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        let mut translated: HashMap<&str, (usize, &str)> = HashMap::new();
+        let mut in_entry = false;
+        let mut found = false;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                found |= in_entry;
+                continue;
+            }
+            if !in_entry {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+                Some((key, key_locale)) => {
+                    let rank = locale.and_then(|locale| locale_rank(key_locale, locale));
+                    if let Some(rank) = rank {
+                        if translated.get(key).is_none_or(|(best, _)| rank < *best) {
+                            translated.insert(key, (rank, value));
+                        }
+                    }
+                }
+                None => {
+                    values.insert(key, value);
+                }
+            }
+        }
+        if !found {
+            return Err("No [Desktop Entry] group".to_string());
+        }
+
+        let value = |key: &str| values.get(key).map(|value| unescape(value));
+        let flag = |key: &str| values.get(key) == Some(&"true");
+        // 有当前语言的翻译时使用翻译
+        let localized = |key: &str| {
+            translated
+                .get(key)
+                .map(|(_, value)| *value)
+                .or(values.get(key).copied())
+        };
+        // 当前语言和未翻译的值都参与搜索
+        let both = |key: &str| {
+            let mut items = Vec::new();
+            for value in [localized(key), values.get(key).copied()]
+                .into_iter()
+                .flatten()
+            {
+                for item in split_list(value) {
+                    if !item.is_empty() && !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+            }
+            items
+        };
+        Ok(DesktopEntry {
+            kind: value("Type").unwrap_or_default(),
+            name: localized("Name").map(unescape),
+            default_name: value("Name"),
+            generic_name: both("GenericName"),
+            comment: localized("Comment")
+                .map(unescape)
+                .filter(|comment| !comment.is_empty()),
+            keywords: both("Keywords"),
+            categories: values
+                .get("Categories")
+                .map(|value| split_list(value))
+                .unwrap_or_default(),
+            exec: value("Exec"),
+            try_exec: value("TryExec"),
+            icon: value("Icon").filter(|icon| !icon.is_empty()),
+            path: value("Path")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+            terminal: flag("Terminal"),
+            no_display: flag("NoDisplay"),
+            hidden: flag("Hidden"),
+            only_show_in: values
+                .get("OnlyShowIn")
+                .map(|value| split_list(value))
+                .unwrap_or_default(),
+            not_show_in: values
+                .get("NotShowIn")
+                .map(|value| split_list(value))
+                .unwrap_or_default(),
+            appimage_version: value("X-AppImage-Version").filter(|version| !version.is_empty()),
+        })
+    }
+
+    // 是否在应用列表中显示，desktops 为 XDG_CURRENT_DESKTOP 中的桌面环境
+    pub fn visible(&self, desktops: &[String]) -> bool {
+        if self.kind != "Application" || self.hidden || self.no_display || self.exec.is_none() {
+            return false;
+        }
+        let current = |list: &[String]| list.iter().any(|item| desktops.contains(item));
+        if !self.only_show_in.is_empty() && !current(&self.only_show_in) {
+            return false;
+        }
+        if current(&self.not_show_in) {
+            return false;
+        }
+        // TryExec 指向的程序不存在时说明应用已经卸载
+        match self.try_exec.as_deref() {
+            Some(program) if !program.is_empty() => find_program(program).is_some(),
+            _ => true,
+        }
+    }
+
+    // 展开 Exec 中的域代码，返回要执行的命令行
+    // 只有 %f 或 %u 时每个文件启动一次，没有文件参数的域代码时不传文件
+    // args 追加在每条命令行的最后
+    pub fn command_lines(
+        &self,
+        file: &Path,
+        files: &[String],
+        args: &[String],
+    ) -> Result<Vec<Vec<String>>, String> {
+        let exec = self
+            .exec
+            .as_deref()
+            .ok_or(format!("{} has no Exec", file.display()))?;
+        let tokens = split_exec(exec)?;
+        let has_code = |codes: [&str; 2]| {
+            tokens
+                .iter()
+                .any(|token| codes.iter().any(|code| token.contains(code)))
+        };
+        let single = !has_code(["%F", "%U"]) && has_code(["%f", "%u"]);
+        let groups: Vec<&[String]> = if single && files.len() > 1 {
+            files.chunks(1).collect()
+        } else {
+            vec![files]
+        };
+        let mut lines = Vec::new();
+        for files in groups {
+            let mut line = Vec::new();
+            for token in tokens.iter() {
+                match token.as_str() {
+                    "%F" => line.extend(files.iter().map(|file| local_path(file))),
+                    "%U" => line.extend(files.iter().cloned()),
+                    "%i" => {
+                        if let Some(icon) = self.icon.as_ref() {
+                            line.push("--icon".to_string());
+                            line.push(icon.clone());
+                        }
+                    }
+                    _ => {
+                        if let Some(arg) = self.expand(token, file, files) {
+                            line.push(arg);
+                        }
+                    }
+                }
+            }
+            if line.is_empty() {
+                return Err(format!("Invalid Exec `{}`", exec));
+            }
+            line.extend(args.iter().cloned());
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
+    // 展开参数中的单个域代码，只有域代码且展开后为空时去掉这个参数
+    fn expand(&self, token: &str, file: &Path, files: &[String]) -> Option<String> {
+        let mut arg = String::new();
+        let mut has_code = false;
+        let mut chars = token.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                arg.push(c);
+                continue;
+            }
+            let Some(code) = chars.next() else {
+                arg.push('%');
+                break;
+            };
+            if code == '%' {
+                arg.push('%');
+                continue;
+            }
+            has_code = true;
+            match code {
+                'f' | 'F' => arg.push_str(
+                    &files
+                        .first()
+                        .map(|file| local_path(file))
+                        .unwrap_or_default(),
+                ),
+                'u' | 'U' => arg.push_str(files.first().map(String::as_str).unwrap_or_default()),
+                'i' => arg.push_str(self.icon.as_deref().unwrap_or_default()),
+                'c' => arg.push_str(self.name.as_deref().unwrap_or_default()),
+                'k' => arg.push_str(&file.to_string_lossy()),
+                // %d %D %n %N %v %m 已经废弃，和未知的域代码一起去掉
+                _ => {}
+            }
+        }
+        (!has_code || !arg.is_empty()).then_some(arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(exec: &str) -> DesktopEntry {
+        DesktopEntry::parse(
+            &format!(
+                "[Desktop Entry]\nType=Application\nName=Viewer\nIcon=viewer\nExec={}\n",
+                exec
+            ),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn lines(exec: &str, files: &[&str]) -> Vec<Vec<String>> {
+        let files: Vec<String> = files.iter().map(|file| file.to_string()).collect();
+        entry(exec)
+            .command_lines(
+                Path::new("/usr/share/applications/viewer.desktop"),
+                &files,
+                &[],
+            )
+            .unwrap()
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(
+            split_exec(r#"app  "a b" "q\"x" "\$HOME" "c\\d" "\`x\`" "" plain\ space"#).unwrap(),
+            strings(&[
+                "app",
+                "a b",
+                "q\"x",
+                "$HOME",
+                "c\\d",
+                "`x`",
+                "",
+                "plain space"
+            ])
+        );
+        // 引号内其它的反斜杠原样保留
+        assert_eq!(split_exec(r#""a\nb""#).unwrap(), strings(&["a\\nb"]));
+        assert_eq!(split_exec("a\tb\n").unwrap(), strings(&["a", "b"]));
+        assert!(split_exec(r#"app "open"#).is_err());
+        assert!(split_exec(r#"app "end\"#).is_err());
+    }
+
+    #[test]
+    fn unescapes_exec_value() {
+        // 先按字符串值的规则去掉一层转义，再按 Exec 的引号规则拆分
+        let entry = entry(r#"sh -c "echo \\"hi\\"""#);
+        assert_eq!(entry.exec.as_deref(), Some(r#"sh -c "echo \"hi\"""#));
+        assert_eq!(
+            split_exec(entry.exec.as_deref().unwrap()).unwrap(),
+            strings(&["sh", "-c", "echo \"hi\""])
+        );
+    }
+
+    #[test]
+    fn expands_single_file_per_command() {
+        assert_eq!(
+            lines("viewer %f", &["file:///tmp/a%20b.png", "/tmp/c.png"]),
+            vec![
+                strings(&["viewer", "/tmp/a b.png"]),
+                strings(&["viewer", "/tmp/c.png"])
+            ]
+        );
+        assert_eq!(
+            lines("viewer --url=%u", &["https://example.com/x", "file:///y"]),
+            vec![
+                strings(&["viewer", "--url=https://example.com/x"]),
+                strings(&["viewer", "--url=file:///y"])
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_file_lists() {
+        assert_eq!(
+            lines("viewer %F", &["file://host/tmp/a.png", "/tmp/b.png"]),
+            vec![strings(&["viewer", "/tmp/a.png", "/tmp/b.png"])]
+        );
+        assert_eq!(
+            lines("viewer %U", &["file:///tmp/a.png", "https://example.com"]),
+            vec![strings(&[
+                "viewer",
+                "file:///tmp/a.png",
+                "https://example.com"
+            ])]
+        );
+        // 没有文件时去掉只有域代码的参数
+        assert_eq!(lines("viewer %F", &[]), vec![strings(&["viewer"])]);
+        assert_eq!(lines("viewer %f", &[]), vec![strings(&["viewer"])]);
+    }
+
+    #[test]
+    fn expands_icon_name_and_location() {
+        assert_eq!(
+            lines("viewer %i --title=%c %k", &[]),
+            vec![strings(&[
+                "viewer",
+                "--icon",
+                "viewer",
+                "--title=Viewer",
+                "/usr/share/applications/viewer.desktop"
+            ])]
+        );
+        // 没有图标时 %i 不产生参数
+        let entry = DesktopEntry::parse("[Desktop Entry]\nExec=viewer %i\n", None).unwrap();
+        assert_eq!(
+            entry
+                .command_lines(Path::new("viewer.desktop"), &[], &[])
+                .unwrap(),
+            vec![strings(&["viewer"])]
+        );
+    }
+
+    #[test]
+    fn keeps_literal_percent() {
+        assert_eq!(
+            lines("printf 100%% %%f", &[]),
+            vec![strings(&["printf", "100%", "%f"])]
+        );
+    }
+
+    #[test]
+    fn drops_deprecated_codes() {
+        assert_eq!(
+            lines("viewer %d %D %n %N %v %m --dir=%d %f", &["/tmp/a"]),
+            vec![strings(&["viewer", "--dir=", "/tmp/a"])]
+        );
+    }
+
+    #[test]
+    fn ignores_files_without_file_codes() {
+        let files = strings(&["/tmp/a", "/tmp/b"]);
+        let args = strings(&["--new-window"]);
+        assert_eq!(
+            entry("viewer")
+                .command_lines(Path::new("viewer.desktop"), &files, &args)
+                .unwrap(),
+            vec![strings(&["viewer", "--new-window"])]
+        );
+    }
+
+    #[test]
+    fn rejects_empty_command() {
+        let entry = entry("%f");
+        assert!(entry
+            .command_lines(Path::new("viewer.desktop"), &[], &[])
+            .is_err());
+    }
+
+    #[test]
+    fn parses_terminal_entry() {
+        let entry = DesktopEntry::parse(
+            "[Desktop Entry]\nType=Application\nName=Top\nExec=htop\nTerminal=true\n",
+            None,
+        )
+        .unwrap();
+        assert!(entry.terminal);
+        // 命令行本身不变，由 launch 交给终端运行
+        assert_eq!(
+            entry
+                .command_lines(Path::new("htop.desktop"), &[], &[])
+                .unwrap(),
+            vec![strings(&["htop"])]
+        );
+        assert!(
+            !DesktopEntry::parse("[Desktop Entry]\nTerminal=false\n", None)
+                .unwrap()
+                .terminal
+        );
+    }
+}
@@ -0,0 +1,11 @@
+mod screenshot;
+pub use screenshot::Screenshot;
+
+mod apps;
+pub use apps::{App, Installed};
+
+pub mod appimage;
+pub mod desktop;
+mod squashfs;
+#[cfg(test)]
+pub mod xvfb;
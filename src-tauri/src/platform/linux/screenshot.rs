@@ -1,14 +1,17 @@
 use image::{ImageBuffer, Rgba};
 use std::{io::ErrorKind::WouldBlock, thread, time::Duration};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Screenshot {
-    data: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    pub data: ImageBuffer<Rgba<u8>, Vec<u8>>,
 }
 
 use scrap::{Capturer, Display};
 
+#[allow(dead_code)]
 impl Screenshot {
+    // 直接返回截取的图片
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, String> {
         let display = Display::primary().map_err(|e| e.to_string())?;
         let (w, h) = (display.width(), display.height());
@@ -25,7 +28,7 @@ impl Screenshot {
                     let img =
                         ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(w as u32, h as u32, buffer)
                             .ok_or("Failed to create image buffer.")?;
-                    Self { data: img.clone() };
+                    return Ok(img);
                 }
                 Err(error) => {
                     if error.kind() == WouldBlock {
@@ -0,0 +1,552 @@
+// 只读的 squashfs 4.0 解析，用于读取 AppImage 中的 .desktop 文件和图标
+// 支持 gzip 和 zstd 压缩，AppImage 一般使用这两种
+
+use std::{fs::File, io::Read, os::unix::fs::FileExt};
+
+const MAGIC: &[u8] = b"hsqs";
+const SUPERBLOCK_SIZE: usize = 96;
+// 压缩方式
+const GZIP: u16 = 1;
+const ZSTD: u16 = 6;
+// 元数据块解压后最大为 8K
+const METADATA_SIZE: usize = 8192;
+// 数据块大小中表示未压缩的位
+const UNCOMPRESSED_DATA: u32 = 1 << 24;
+// 元数据块头中表示未压缩的位
+const UNCOMPRESSED_METADATA: u16 = 1 << 15;
+const NO_FRAGMENT: u32 = 0xffff_ffff;
+// 每个元数据块中的分片表项数
+const FRAGMENTS_PER_BLOCK: u32 = 512;
+const MAX_SYMLINKS: usize = 16;
+// 一个文件最多的数据块数，按最小的 4K 块为 1G
+const MAX_BLOCKS: usize = 256 * 1024;
+// 目录内容的最大长度，超过的目录不是 AppImage 中会有的
+const MAX_DIRECTORY_SIZE: u32 = 4 * 1024 * 1024;
+
+// inode 类型
+const BASIC_DIR: u16 = 1;
+const BASIC_FILE: u16 = 2;
+const BASIC_SYMLINK: u16 = 3;
+const EXTENDED_DIR: u16 = 8;
+const EXTENDED_FILE: u16 = 9;
+const EXTENDED_SYMLINK: u16 = 10;
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn invalid() -> String {
+    "Invalid squashfs".to_string()
+}
+
+// 位置都来自镜像中的数据，损坏的镜像可能溢出
+fn add(position: u64, offset: u64) -> Result<u64, String> {
+    position.checked_add(offset).ok_or_else(invalid)
+}
+
+enum Inode {
+    Dir {
+        // 目录内容在目录表中的位置，size 包含 3 字节的额外长度
+        block: u32,
+        offset: u16,
+        size: u32,
+    },
+    File {
+        blocks_start: u64,
+        size: u64,
+        fragment: u32,
+        fragment_offset: u32,
+        block_sizes: Vec<u32>,
+    },
+    Symlink(String),
+    Other,
+}
+
+pub struct SquashFs {
+    file: File,
+    // squashfs 在文件中的起始位置，表的位置都相对于这里
+    offset: u64,
+    compression: u16,
+    block_size: u32,
+    root: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+// 按 `/` 分开路径，处理 `.` 和 `..`
+fn normalize(path: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part.to_string()),
+        }
+    }
+    parts
+}
+
+impl SquashFs {
+    pub fn open(file: File, offset: u64) -> Result<Self, String> {
+        let mut superblock = [0u8; SUPERBLOCK_SIZE];
+        file.read_exact_at(&mut superblock, offset)
+            .map_err(|e| e.to_string())?;
+        if &superblock[..4] != MAGIC {
+            return Err("Not a squashfs".to_string());
+        }
+        let field16 = |at| u16_at(&superblock, at).unwrap();
+        let field32 = |at| u32_at(&superblock, at).unwrap();
+        let field64 = |at| u64_at(&superblock, at).unwrap();
+        if field16(28) != 4 {
+            return Err(format!("Unsupported squashfs version {}", field16(28)));
+        }
+        let block_size = field32(12);
+        if !(4096..=1024 * 1024).contains(&block_size) {
+            return Err(invalid());
+        }
+        Ok(SquashFs {
+            file,
+            offset,
+            compression: field16(20),
+            block_size,
+            root: field64(32),
+            inode_table: field64(64),
+            directory_table: field64(72),
+            fragment_table: field64(80),
+        })
+    }
+
+    fn read_at(&self, position: u64, len: usize) -> Result<Vec<u8>, String> {
+        let mut data = vec![0u8; len];
+        self.file
+            .read_exact_at(&mut data, add(self.offset, position)?)
+            .map_err(|e| e.to_string())?;
+        Ok(data)
+    }
+
+    fn decompress(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+        let mut output = Vec::new();
+        match self.compression {
+            GZIP => flate2::read::ZlibDecoder::new(data)
+                .take(limit as u64 + 1)
+                .read_to_end(&mut output)
+                .map_err(|e| e.to_string())?,
+            ZSTD => zstd::stream::Decoder::new(data)
+                .map_err(|e| e.to_string())?
+                .take(limit as u64 + 1)
+                .read_to_end(&mut output)
+                .map_err(|e| e.to_string())?,
+            compression => return Err(format!("Unsupported squashfs compression {}", compression)),
+        };
+        if output.len() > limit {
+            return Err(invalid());
+        }
+        Ok(output)
+    }
+
+    // 读取一个元数据块，返回解压后的内容和下一个块的位置
+    fn metadata_block(&self, position: u64) -> Result<(Vec<u8>, u64), String> {
+        let header = self.read_at(position, 2)?;
+        let header = u16::from_le_bytes([header[0], header[1]]);
+        let size = (header & !UNCOMPRESSED_METADATA) as usize;
+        if size == 0 || size > METADATA_SIZE {
+            return Err(invalid());
+        }
+        let data = self.read_at(add(position, 2)?, size)?;
+        let data = if header & UNCOMPRESSED_METADATA != 0 {
+            data
+        } else {
+            self.decompress(&data, METADATA_SIZE)?
+        };
+        Ok((data, add(position, 2 + size as u64)?))
+    }
+
+    // 从 position 处的元数据块的 offset 开始读取 len 字节，可以跨越多个块
+    fn metadata(&self, position: u64, offset: usize, len: usize) -> Result<Vec<u8>, String> {
+        let mut position = position;
+        let mut skip = offset;
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let (block, next) = self.metadata_block(position)?;
+            if skip >= block.len() {
+                skip -= block.len();
+            } else {
+                data.extend_from_slice(&block[skip..]);
+                skip = 0;
+            }
+            position = next;
+        }
+        data.truncate(len);
+        Ok(data)
+    }
+
+    // inode 引用的高位为元数据块相对于 inode 表的位置，低 16 位为块内的偏移
+    fn inode(&self, reference: u64) -> Result<Inode, String> {
+        let position = add(self.inode_table, reference >> 16)?;
+        let offset = (reference & 0xffff) as usize;
+        let read = |len| self.metadata(position, offset, len);
+        // 16 字节的公共头之后是各类型的内容
+        let header = read(16)?;
+        let kind = u16_at(&header, 0).ok_or_else(invalid)?;
+        let inode = match kind {
+            BASIC_DIR => {
+                let data = read(32)?;
+                Inode::Dir {
+                    block: u32_at(&data, 16).ok_or_else(invalid)?,
+                    size: u16_at(&data, 24).ok_or_else(invalid)? as u32,
+                    offset: u16_at(&data, 26).ok_or_else(invalid)?,
+                }
+            }
+            EXTENDED_DIR => {
+                let data = read(40)?;
+                Inode::Dir {
+                    size: u32_at(&data, 20).ok_or_else(invalid)?,
+                    block: u32_at(&data, 24).ok_or_else(invalid)?,
+                    offset: u16_at(&data, 34).ok_or_else(invalid)?,
+                }
+            }
+            BASIC_FILE | EXTENDED_FILE => {
+                let (fixed, blocks_start, size, fragment, fragment_offset) = if kind == BASIC_FILE {
+                    let data = read(32)?;
+                    (
+                        32,
+                        u32_at(&data, 16).ok_or_else(invalid)? as u64,
+                        u32_at(&data, 28).ok_or_else(invalid)? as u64,
+                        u32_at(&data, 20).ok_or_else(invalid)?,
+                        u32_at(&data, 24).ok_or_else(invalid)?,
+                    )
+                } else {
+                    let data = read(56)?;
+                    (
+                        56,
+                        u64_at(&data, 16).ok_or_else(invalid)?,
+                        u64_at(&data, 24).ok_or_else(invalid)?,
+                        u32_at(&data, 44).ok_or_else(invalid)?,
+                        u32_at(&data, 48).ok_or_else(invalid)?,
+                    )
+                };
+                // 有分片时最后不满一块的部分在分片中
+                let block_size = self.block_size as u64;
+                let count = if fragment == NO_FRAGMENT {
+                    size.div_ceil(block_size)
+                } else {
+                    size / block_size
+                } as usize;
+                // 损坏的文件大小会导致分配过多内存
+                if count > MAX_BLOCKS {
+                    return Err(invalid());
+                }
+                let data = read(fixed + count * 4)?;
+                let block_sizes = (0..count)
+                    .map(|i| u32_at(&data, fixed + i * 4).ok_or_else(invalid))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Inode::File {
+                    blocks_start,
+                    size,
+                    fragment,
+                    fragment_offset,
+                    block_sizes,
+                }
+            }
+            BASIC_SYMLINK | EXTENDED_SYMLINK => {
+                let data = read(24)?;
+                let len = u32_at(&data, 20).ok_or_else(invalid)? as usize;
+                if len > 4096 {
+                    return Err(invalid());
+                }
+                let data = read(24 + len)?;
+                Inode::Symlink(String::from_utf8_lossy(&data[24..]).to_string())
+            }
+            _ => Inode::Other,
+        };
+        Ok(inode)
+    }
+
+    // 目录中的文件名和 inode 引用
+    fn entries(&self, block: u32, offset: u16, size: u32) -> Result<Vec<(String, u64)>, String> {
+        if size > MAX_DIRECTORY_SIZE {
+            return Err(invalid());
+        }
+        // 目录大小比实际内容多 3 字节
+        let len = (size as usize).saturating_sub(3);
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let data = self.metadata(
+            add(self.directory_table, block as u64)?,
+            offset as usize,
+            len,
+        )?;
+        let mut entries = Vec::new();
+        let mut position = 0;
+        while position + 12 <= data.len() {
+            let count = u32_at(&data, position).ok_or_else(invalid)? as usize + 1;
+            let start = u32_at(&data, position + 4).ok_or_else(invalid)? as u64;
+            position += 12;
+            for _ in 0..count {
+                let inode_offset = u16_at(&data, position).ok_or_else(invalid)? as u64;
+                let name_size = u16_at(&data, position + 6).ok_or_else(invalid)? as usize + 1;
+                let name = data
+                    .get(position + 8..position + 8 + name_size)
+                    .ok_or_else(invalid)?;
+                entries.push((
+                    String::from_utf8_lossy(name).to_string(),
+                    (start << 16) | inode_offset,
+                ));
+                position += 8 + name_size;
+            }
+        }
+        Ok(entries)
+    }
+
+    // 根目录中的文件名
+    pub fn root_entries(&self) -> Result<Vec<String>, String> {
+        match self.inode(self.root)? {
+            Inode::Dir {
+                block,
+                offset,
+                size,
+            } => Ok(self
+                .entries(block, offset, size)?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()),
+            _ => Err(invalid()),
+        }
+    }
+
+    // 按路径查找，跟随镜像内的符号链接，绝对路径的链接从镜像的根目录开始
+    fn lookup(&self, path: &str) -> Result<Option<Inode>, String> {
+        let mut parts = normalize(path);
+        for _ in 0..MAX_SYMLINKS {
+            let mut inode = self.inode(self.root)?;
+            let mut link = None;
+            for (i, name) in parts.iter().enumerate() {
+                let Inode::Dir {
+                    block,
+                    offset,
+                    size,
+                } = inode
+                else {
+                    return Ok(None);
+                };
+                let Some((_, reference)) = self
+                    .entries(block, offset, size)?
+                    .into_iter()
+                    .find(|(entry, _)| entry == name)
+                else {
+                    return Ok(None);
+                };
+                inode = self.inode(reference)?;
+                if let Inode::Symlink(target) = &inode {
+                    let base = if target.starts_with('/') {
+                        String::new()
+                    } else {
+                        parts[..i].join("/")
+                    };
+                    link = Some(format!("{}/{}/{}", base, target, parts[i + 1..].join("/")));
+                    break;
+                }
+            }
+            match link {
+                Some(link) => parts = normalize(&link),
+                None => return Ok(Some(inode)),
+            }
+        }
+        Err("Too many levels of symbolic links".to_string())
+    }
+
+    // 读取文件内容，文件不存在时返回 None，超过 limit 时返回错误
+    pub fn read(&self, path: &str, limit: u64) -> Result<Option<Vec<u8>>, String> {
+        let Some(Inode::File {
+            blocks_start,
+            size,
+            fragment,
+            fragment_offset,
+            block_sizes,
+        }) = self.lookup(path)?
+        else {
+            return Ok(None);
+        };
+        if size > limit {
+            return Err(format!("{} is too large", path));
+        }
+        let block_size = self.block_size as usize;
+        let mut data = Vec::with_capacity(size as usize);
+        let mut position = blocks_start;
+        for block in block_sizes {
+            let on_disk = (block & !UNCOMPRESSED_DATA) as usize;
+            // 大小为 0 的是稀疏块，内容全为 0
+            if on_disk == 0 {
+                let len = block_size.min((size as usize).saturating_sub(data.len()));
+                data.resize(data.len() + len, 0);
+                continue;
+            }
+            // 压缩后比原来大时按未压缩保存，所以不会超过块大小
+            if on_disk > block_size {
+                return Err(invalid());
+            }
+            let raw = self.read_at(position, on_disk)?;
+            position = add(position, on_disk as u64)?;
+            if block & UNCOMPRESSED_DATA != 0 {
+                data.extend_from_slice(&raw);
+            } else {
+                data.extend_from_slice(&self.decompress(&raw, block_size)?);
+            }
+        }
+        if fragment != NO_FRAGMENT {
+            // 分片表是指向元数据块的数组，每个块中有 512 个 16 字节的表项
+            let pointer = self.read_at(
+                add(
+                    self.fragment_table,
+                    (fragment / FRAGMENTS_PER_BLOCK) as u64 * 8,
+                )?,
+                8,
+            )?;
+            let entry = self.metadata(
+                u64_at(&pointer, 0).ok_or_else(invalid)?,
+                (fragment % FRAGMENTS_PER_BLOCK) as usize * 16,
+                16,
+            )?;
+            let start = u64_at(&entry, 0).ok_or_else(invalid)?;
+            let fragment_size = u32_at(&entry, 8).ok_or_else(invalid)?;
+            let on_disk = (fragment_size & !UNCOMPRESSED_DATA) as usize;
+            if on_disk > block_size {
+                return Err(invalid());
+            }
+            let raw = self.read_at(start, on_disk)?;
+            let block = if fragment_size & UNCOMPRESSED_DATA != 0 {
+                raw
+            } else {
+                self.decompress(&raw, block_size)?
+            };
+            let tail = (size as usize).saturating_sub(data.len());
+            let begin = fragment_offset as usize;
+            data.extend_from_slice(block.get(begin..begin + tail).ok_or_else(invalid)?);
+        }
+        data.truncate(size as usize);
+        Ok(Some(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // 测试用的镜像由 tests/fixtures/appimage/gen.py 生成，squashfs 从 192 字节开始
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/appimage")
+            .join(name)
+    }
+
+    fn open(name: &str) -> SquashFs {
+        SquashFs::open(File::open(fixture(name)).unwrap(), 192).unwrap()
+    }
+
+    fn icon() -> Vec<u8> {
+        let mut icon = (0..=255u8).collect::<Vec<_>>().repeat(40);
+        icon.extend_from_slice(b"TAIL");
+        icon
+    }
+
+    #[test]
+    fn lists_root() {
+        let mut root = open("Firefox.AppImage").root_entries().unwrap();
+        root.sort();
+        assert_eq!(
+            root,
+            [
+                ".DirIcon",
+                "AppRun",
+                "firefox.desktop",
+                "firefox.png",
+                "sparse",
+                "usr"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_files() {
+        for name in ["Firefox.AppImage", "Raw.AppImage"] {
+            let image = open(name);
+            // 目录内容跨越多个元数据块
+            for i in [0usize, 1, 450, 899] {
+                let data = image
+                    .read(&format!("usr/many/f{:04}.txt", i), 1 << 20)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(data, format!("file {}\n", i).repeat(i % 7 + 1).into_bytes());
+            }
+            assert_eq!(
+                image
+                    .read("usr/share/icons/hicolor/firefox.png", 1 << 20)
+                    .unwrap(),
+                Some(icon())
+            );
+            let sparse = image.read("sparse", 1 << 20).unwrap().unwrap();
+            assert_eq!(sparse.len(), 8195);
+            assert!(sparse[..8192].iter().all(|b| *b == 0));
+            assert_eq!(&sparse[8192..], b"end");
+        }
+    }
+
+    #[test]
+    fn follows_symlinks() {
+        let image = open("Firefox.AppImage");
+        assert_eq!(image.read("AppRun", 100).unwrap().unwrap(), b"#!/bin/sh\n");
+        // 相对链接中有 `..`，绝对链接从镜像的根目录开始
+        assert_eq!(image.read("firefox.png", 1 << 20).unwrap(), Some(icon()));
+        assert_eq!(image.read(".DirIcon", 1 << 20).unwrap(), Some(icon()));
+    }
+
+    #[test]
+    fn handles_missing_and_large_files() {
+        let image = open("Firefox.AppImage");
+        assert!(image.read("usr/many/nope", 100).unwrap().is_none());
+        assert!(image.read("firefox.desktop/x", 100).unwrap().is_none());
+        assert!(image.read("usr", 100).unwrap().is_none());
+        assert!(image
+            .read("usr/share/icons/hicolor/firefox.png", 100)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_corrupted_images() {
+        let image = open("BadBlock.AppImage");
+        assert_eq!(image.read("icon.png", 1 << 20).unwrap_err(), invalid());
+        assert_eq!(
+            open("BadDir.AppImage").root_entries().unwrap_err(),
+            invalid()
+        );
+        assert!(SquashFs::open(File::open(fixture("Firefox.AppImage")).unwrap(), 0).is_err());
+    }
+
+    // 镜像中的位置过大时返回错误，不会溢出
+    #[test]
+    fn rejects_overflowing_positions() {
+        let mut image = open("Firefox.AppImage");
+        image.inode_table = u64::MAX - 1;
+        image.root = 2 << 16;
+        assert_eq!(image.root_entries().unwrap_err(), invalid());
+        let mut image = open("Firefox.AppImage");
+        image.offset = u64::MAX;
+        assert_eq!(image.read("AppRun", 100).unwrap_err(), invalid());
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    env,
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+// 测试用的 X 服务器，可以再启动一个窗口管理器，结束时一起关闭
+pub struct Xvfb {
+    server: Child,
+    wm: Option<Child>,
+    pub display: String,
+}
+
+impl Xvfb {
+    pub fn start(number: u32) -> Self {
+        let display = format!(":{}", number);
+        let server = Command::new("Xvfb")
+            .arg(&display)
+            .args(["-screen", "0", "640x480x24", "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Xvfb is required");
+        // 先包起来，启动失败 panic 时也会结束进程
+        let xvfb = Xvfb {
+            server,
+            wm: None,
+            display,
+        };
+        xvfb.wait(|| x11rb::connect(Some(&xvfb.display)).is_ok());
+        xvfb
+    }
+
+    // 启动 TOOLBOX_TEST_WM 指定的窗口管理器，默认为 openbox，ready 返回 true 后才算启动完成
+    pub fn start_wm(&mut self, ready: impl FnMut() -> bool) {
+        let wm = env::var("TOOLBOX_TEST_WM").unwrap_or_else(|_| "openbox".to_string());
+        self.wm = Some(
+            Command::new(&wm)
+                .env("DISPLAY", &self.display)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap_or_else(|e| panic!("Failed to start {}: {}", wm, e)),
+        );
+        self.wait(ready);
+    }
+
+    pub fn wait(&self, mut ready: impl FnMut() -> bool) {
+        for _ in 0..100 {
+            if ready() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("Timed out waiting on {}", self.display);
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        for child in self.wm.iter_mut().chain([&mut self.server]) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
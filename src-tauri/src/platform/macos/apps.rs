@@ -1,6 +1,9 @@
 use std::{
+    collections::HashMap,
     fs::{self},
+    io::Cursor,
     path::{Path, PathBuf},
+    process::Command,
 };
 
 use serde::{Deserialize, Serialize};
@@ -8,19 +11,45 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Installed {
     pub apps: Vec<App>,
+    // 读取失败的目录或应用
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 impl Default for Installed {
     fn default() -> Self {
-        Installed { apps: Vec::new() }
+        Installed {
+            apps: Vec::new(),
+            warnings: Vec::new(),
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct App {
     pub name: Option<String>,
     pub path: Option<PathBuf>,
     pub icon: Option<PathBuf>,
+    // 版本号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // bundle id，如 `com.apple.Safari`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // 应用的说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    // 额外参与搜索的关键词
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    // 应用的其它名称，如未翻译的名称，name 为当前语言的名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+    // 安装来源，Linux 下用于区分 Flatpak、Snap 等，这里为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl Default for App {
@@ -29,28 +58,50 @@ impl Default for App {
             name: None,
             path: None,
             icon: None,
+            version: None,
+            id: None,
+            description: None,
+            categories: Vec::new(),
+            keywords: Vec::new(),
+            names: Vec::new(),
+            source: None,
         }
     }
 }
 
-impl App {
-    pub fn new(name: Option<String>, path: Option<PathBuf>, icon: Option<PathBuf>) -> Self {
-        App { name, path, icon }
-    }
-}
-
 use plist;
-use std::{fs::File, io::BufReader};
 
 impl Installed {
+    // 应用程序安装目录，这些目录变化时需要重新获取
+    pub fn dirs() -> Vec<PathBuf> {
+        let mut dirs = vec![PathBuf::from("/Applications")];
+        if let Some(home) = std::env::var_os("HOME") {
+            dirs.push(Path::new(&home).join("Applications"));
+        }
+        dirs
+    }
+
     pub fn new() -> Self {
         let mut apps: Vec<App> = Vec::new();
+        let mut warnings: Vec<String> = Vec::new();
+        let languages = Self::languages();
 
-        // 应用程序安装目录
-        let applications_dir = Path::new("/Applications");
-
-        // 获取应用程序列表
-        if let Ok(entries) = fs::read_dir(applications_dir) {
+        for applications_dir in Self::dirs() {
+            if !applications_dir.is_dir() {
+                continue;
+            }
+            // 获取应用程序列表
+            let entries = match fs::read_dir(&applications_dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warnings.push(format!(
+                        "Failed to read {}: {}",
+                        applications_dir.display(),
+                        e
+                    ));
+                    continue;
+                }
+            };
             for entry in entries.filter_map(Result::ok) {
                 let app_path = entry.path();
 
@@ -66,56 +117,224 @@ impl Installed {
                     let info_plist_path = app_path.join("Contents/Info.plist");
 
                     // 解析 Info.plist 文件
-                    if let Ok(plist_data) = Installed::read_plist(&info_plist_path) {
-                        // 应用程序名称和版本
-                        let _app_version = plist_data
+                    let plist_data = match plist::Value::from_file(&info_plist_path) {
+                        Ok(plist_data) => plist_data,
+                        Err(e) => {
+                            warnings.push(format!(
+                                "Failed to read {}: {}",
+                                info_plist_path.display(),
+                                e
+                            ));
+                            continue;
+                        }
+                    };
+
+                    // 应用程序可执行文件路径
+                    let executable_path = app_path.join("Contents/MacOS").join(
+                        plist_data
                             .as_dictionary()
-                            .and_then(|dict| dict.get("CFBundleShortVersionString"))
+                            .and_then(|dict| dict.get("CFBundleExecutable"))
+                            .and_then(|v| v.as_string())
+                            .unwrap_or(""),
+                    );
+
+                    // 应用程序图标路径
+                    // 图标文件名可以省略扩展名，由图标服务补上
+                    let icon_file = plist_data
+                        .as_dictionary()
+                        .and_then(|dict| dict.get("CFBundleIconFile"))
+                        .and_then(|v| v.as_string())
+                        .unwrap_or("AppIcon.icns");
+                    let icon_path = app_path.join("Contents/Resources").join(icon_file);
+                    let dict = plist_data.as_dictionary();
+                    let string = |key: &str| {
+                        dict.and_then(|dict| dict.get(key))
                             .and_then(|v| v.as_string())
-                            .unwrap_or("Unknown");
-
-                        // 应用程序可执行文件路径
-                        let executable_path = app_path.join("Contents/MacOS").join(
-                            plist_data
-                                .as_dictionary()
-                                .and_then(|dict| dict.get("CFBundleExecutable"))
-                                .and_then(|v| v.as_string())
-                                .unwrap_or(""),
-                        );
-
-                        // 应用程序图标路径
-                        let icon_path = app_path.join("Contents/Resources/AppIcon.icns");
-                        let name = app_name.to_string();
-                        // 输出应用程序信息
-                        apps.push(App::new(Some(name), Some(executable_path), Some(icon_path)));
+                            .map(str::to_string)
+                            .filter(|value| !value.is_empty())
+                    };
+                    // 本地化的名称作为显示名称，文件名和 bundle 中的名称作为其它名称参与搜索
+                    let name = Self::localized_name(&app_path, &languages)
+                        .unwrap_or_else(|| app_name.to_string());
+                    let mut names: Vec<String> = Vec::new();
+                    for other in std::iter::once(app_name.to_string())
+                        .chain(string("CFBundleDisplayName"))
+                        .chain(string("CFBundleName"))
+                    {
+                        if other != name && !names.contains(&other) {
+                            names.push(other);
+                        }
                     }
+                    // 如 `public.app-category.developer-tools`
+                    let categories = string("LSApplicationCategoryType")
+                        .map(|category| {
+                            category
+                                .trim_start_matches("public.app-category.")
+                                .to_string()
+                        })
+                        .into_iter()
+                        .collect();
+                    apps.push(App {
+                        name: Some(name),
+                        path: Some(executable_path),
+                        icon: Some(icon_path),
+                        version: string("CFBundleShortVersionString")
+                            .or_else(|| string("CFBundleVersion")),
+                        id: string("CFBundleIdentifier"),
+                        description: string("CFBundleGetInfoString"),
+                        categories,
+                        keywords: Vec::new(),
+                        names,
+                        source: None,
+                    });
                 }
             }
-        } else {
-            eprintln!("Failed to read applications directory.");
         }
 
-        Installed { apps }
+        Installed { apps, warnings }
     }
 
-    // 读取并解析 plist 文件
-    fn read_plist(path: &Path) -> Result<plist::Value, plist::Error> {
-        let plist_path = Path::new(path);
-        // 打开文件并创建一个读取器
-        let file = File::open(&plist_path).expect("Failed to open plist file");
-        let reader = BufReader::new(file);
-        // 使用 plist crate 解析 plist 文件
-        match plist::Value::from_reader(reader) {
-            Ok(plist::Value::Dictionary(dict)) => {
-                // 在这里处理字典类型的 plist 数据
-                return Ok(plist::Value::Dictionary(dict));
+    // 系统首选语言对应的 .lproj 目录名，按优先级排列，如 `zh-Hans-CN` 对应 `zh-Hans-CN`、`zh_CN`、`zh-Hans`、`zh`
+    fn languages() -> Vec<String> {
+        let mut languages: Vec<String> = Command::new("defaults")
+            .args(["read", "-g", "AppleLanguages"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| {
+                // 输出格式为 `(\n    "zh-Hans-CN",\n    en\n)`
+                String::from_utf8_lossy(&output.stdout)
+                    .split(|c: char| c == ',' || c == '(' || c == ')' || c.is_whitespace())
+                    .map(|language| language.trim_matches('"').to_string())
+                    .filter(|language| !language.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if languages.is_empty() {
+            if let Ok(lang) = std::env::var("LANG") {
+                let lang = lang.split('.').next().unwrap_or_default();
+                if !lang.is_empty() && lang != "C" && lang != "POSIX" {
+                    languages.push(lang.to_string());
+                }
+            }
+        }
+        let mut dirs = Vec::new();
+        for language in languages {
+            let parts: Vec<&str> = language.split(['-', '_']).collect();
+            let mut candidates = vec![language.clone()];
+            if parts.len() > 1 {
+                candidates.push(format!("{}_{}", parts[0], parts[parts.len() - 1]));
             }
-            Ok(other) => {
-                return Ok(other);
+            for len in (1..parts.len()).rev() {
+                candidates.push(parts[..len].join("-"));
             }
-            Err(e) => {
-                return Err(e);
+            for candidate in candidates {
+                if !dirs.contains(&candidate) {
+                    dirs.push(candidate);
+                }
+            }
+        }
+        dirs
+    }
+
+    // 从 `<语言>.lproj/InfoPlist.strings` 中读取本地化的应用名称
+    fn localized_name(app_path: &Path, languages: &[String]) -> Option<String> {
+        let resources = app_path.join("Contents/Resources");
+        languages.iter().find_map(|language| {
+            let strings = resources.join(format!("{}.lproj/InfoPlist.strings", language));
+            let data = fs::read(strings).ok()?;
+            let values = Self::parse_strings(&data)?;
+            ["CFBundleDisplayName", "CFBundleName"]
+                .iter()
+                .find_map(|key| values.get(*key).filter(|value| !value.is_empty()).cloned())
+        })
+    }
+
+    // .strings 文件可能是二进制或 XML 格式的 plist，也可能是 UTF-8 或 UTF-16 的 `"key" = "value";` 文本
+    fn parse_strings(data: &[u8]) -> Option<HashMap<String, String>> {
+        if let Ok(plist::Value::Dictionary(dict)) = plist::Value::from_reader(Cursor::new(data)) {
+            return Some(
+                dict.into_iter()
+                    .filter_map(|(key, value)| Some((key, value.into_string()?)))
+                    .collect(),
+            );
+        }
+        let text = match data {
+            [0xFF, 0xFE, rest @ ..] => String::from_utf16_lossy(
+                &rest
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            ),
+            [0xFE, 0xFF, rest @ ..] => String::from_utf16_lossy(
+                &rest
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => String::from_utf8_lossy(data)
+                .trim_start_matches('\u{feff}')
+                .to_string(),
+        };
+        let mut values = HashMap::new();
+        let mut chars = text.chars().peekable();
+        let mut tokens: Vec<String> = Vec::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    let mut token = String::new();
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' => match chars.next() {
+                                Some('n') => token.push('\n'),
+                                Some('t') => token.push('\t'),
+                                Some(c) => token.push(c),
+                                None => break,
+                            },
+                            c => token.push(c),
+                        }
+                    }
+                    tokens.push(token);
+                }
+                // 注释
+                '/' if chars.peek() == Some(&'*') => {
+                    let mut last = ' ';
+                    for c in chars.by_ref() {
+                        if last == '*' && c == '/' {
+                            break;
+                        }
+                        last = c;
+                    }
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                ';' => {
+                    if let [key, value] = tokens.as_slice() {
+                        values.insert(key.clone(), value.clone());
+                    }
+                    tokens.clear();
+                }
+                c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                    // 不带引号的 key
+                    let mut token = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                            break;
+                        }
+                        token.push(c);
+                        chars.next();
+                    }
+                    tokens.push(token);
+                }
+                _ => {}
             }
         }
+        Some(values)
     }
 }
@@ -1,16 +1,24 @@
 #[cfg(target_os = "linux")]
-#[path = "linux.rs"]
+#[path = "linux/mod.rs"]
+#[allow(clippy::module_inception)]
 pub mod platform;
 
 #[cfg(target_os = "windows")]
 #[path = "windows/mod.rs"]
+#[allow(clippy::module_inception)]
 pub mod platform;
 
 #[cfg(target_os = "macos")]
 #[path = "macos/mod.rs"]
+#[allow(clippy::module_inception)]
 pub mod platform;
 
 #[allow(unused)]
 pub use platform::{
     PreviewFile, App, Installed, Screenshot, init_preview_file,
 };
+
+#[cfg(all(test, target_os = "linux"))]
+pub use platform::xvfb;
+#[cfg(target_os = "linux")]
+pub use platform::{appimage, desktop};
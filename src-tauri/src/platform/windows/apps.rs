@@ -5,18 +5,14 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use std::ffi::OsString;
-use std::os::windows::ffi::{OsStrExt, OsStringExt};
-use windows::core::{Interface, GUID, HRESULT, PCWSTR};
-use windows::Win32::Foundation::{MAX_PATH, S_OK};
-use windows::Win32::System::Com::{
-    CoCreateInstance, CoInitializeEx, CoUninitialize, IPersistFile, CLSCTX_INPROC_SERVER,
-    COINIT_MULTITHREADED, STGM_READ,
+use std::ffi::{c_void, OsString};
+use windows::core::HSTRING;
+use windows::Win32::Storage::FileSystem::{
+    GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW, VS_FIXEDFILEINFO,
 };
-use windows::Win32::UI::Shell::IShellLinkW;
 
-// 手动定义 CLSID_ShellLink
-const CLSID_SHELL_LINK: GUID = GUID::from_u128(0x00021401_0000_0000_C000_000000000046);
+use crate::lnk::ShellLink;
+use crate::utils::expand_env;
 
 // 定义一个结构体来存储快捷方式信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +20,7 @@ pub struct ShortcutInfo {
     pub target_path: Option<PathBuf>,
     pub description: Option<OsString>,
     pub working_directory: Option<OsString>,
+    pub arguments: Option<OsString>,
     pub icon_location: Option<OsString>,
     pub icon_index: i32,
     pub name: Option<String>,
@@ -43,6 +40,10 @@ impl ShortcutInfo {
         self.working_directory.as_ref()
     }
 
+    pub fn arguments(&self) -> Option<&OsString> {
+        self.arguments.as_ref()
+    }
+
     pub fn icon_location(&self) -> Option<&OsString> {
         self.icon_location.as_ref()
     }
@@ -56,211 +57,160 @@ impl ShortcutInfo {
     }
 }
 
-// pub fn get_shortcut_info(lnk_path: &str) -> Option<ShortcutInfo> {
-//     unsafe {
-//         // 初始化COM库
-//         let hr: HRESULT = CoInitializeEx(None, COINIT_MULTITHREADED);
-//         if hr != S_OK {
-//             eprintln!(
-//                 "Failed to initialize COM library with HRESULT: 0x{:X}",
-//                 hr.0
-//             );
-//             CoUninitialize(); // 添加此行以确保在错误时释放COM库
-//             return None;
-//         }
-
-//         // 创建IShellLinkW对象
-//         let shell_link: Result<IShellLinkW, _> =
-//             CoCreateInstance(&CLSID_SHELL_LINK, None, CLSCTX_INPROC_SERVER);
-
-//         if let Ok(shell_link) = shell_link {
-//             // 获取IPersistFile接口
-//             let persist_file: IPersistFile = shell_link.cast().unwrap();
-//             let lnk_path = OsString::from(lnk_path);
-//             let lnk_path_wide: Vec<u16> = lnk_path.encode_wide().chain(Some(0)).collect();
-
-//             // 检查.lnk文件路径
-//             if !Path::new(&lnk_path).exists() {
-//                 eprintln!(
-//                     "The .lnk file does not exist at the specified path: {:?}",
-//                     lnk_path
-//                 );
-//                 CoUninitialize();
-//                 return None;
-//             }
-
-//             // 加载.lnk文件
-//             let hr = persist_file.Load(PCWSTR(lnk_path_wide.as_ptr()), STGM_READ);
-
-//             if hr.is_ok() {
-//                 let mut shortcut_info = ShortcutInfo {
-//                     target_path: None,
-//                     description: None,
-//                     working_directory: None,
-//                     icon_location: None,
-//                     icon_index: 0,
-//                 };
-
-//                 // 获取目标路径
-//                 let mut target_path: [u16; MAX_PATH as usize] = [0; MAX_PATH as usize];
-//                 let mut find_data: windows::Win32::Storage::FileSystem::WIN32_FIND_DATAW =
-//                     std::mem::zeroed();
-//                 let hr = shell_link.GetPath(&mut target_path, &mut find_data, 0);
-//                 if hr.is_ok() {
-//                     let len = target_path
-//                         .iter()
-//                         .position(|&c| c == 0)
-//                         .unwrap_or(target_path.len());
-//                     let target_path_osstring = OsString::from_wide(&target_path[..len]);
-//                     shortcut_info.target_path = Some(PathBuf::from(target_path_osstring));
-//                 }
-
-//                 // 获取描述信息
-//                 let mut description: [u16; 512] = [0; 512];
-//                 let hr = shell_link.GetDescription(&mut description);
-//                 if hr.is_ok() {
-//                     let len = description
-//                         .iter()
-//                         .position(|&c| c == 0)
-//                         .unwrap_or(description.len());
-//                     let description_osstring = OsString::from_wide(&description[..len]);
-//                     shortcut_info.description = Some(description_osstring);
-//                 }
-
-//                 // 获取工作目录
-//                 let mut working_directory: [u16; MAX_PATH as usize] = [0; MAX_PATH as usize];
-//                 let hr = shell_link.GetWorkingDirectory(&mut working_directory);
-//                 if hr.is_ok() {
-//                     let len = working_directory
-//                         .iter()
-//                         .position(|&c| c == 0)
-//                         .unwrap_or(working_directory.len());
-//                     let working_directory_osstring = OsString::from_wide(&working_directory[..len]);
-//                     shortcut_info.working_directory = Some(working_directory_osstring);
-//                 }
-
-//                 // 获取图标位置
-//                 let mut icon_location: [u16; MAX_PATH as usize] = [0; MAX_PATH as usize];
-//                 let mut icon_index: i32 = 0;
-//                 let hr = shell_link.GetIconLocation(&mut icon_location, &mut icon_index);
-//                 if hr.is_ok() {
-//                     let len = icon_location
-//                         .iter()
-//                         .position(|&c| c == 0)
-//                         .unwrap_or(icon_location.len());
-//                     let icon_location_osstring = OsString::from_wide(&icon_location[..len]);
-//                     shortcut_info.icon_location = Some(icon_location_osstring);
-//                     shortcut_info.icon_index = icon_index;
-//                 }
+impl ShortcutInfo {
+    // 解析快捷方式文件，路径中的环境变量在这里展开
+    pub fn read(lnk_path: &Path) -> Result<Self, String> {
+        let link = ShellLink::read(lnk_path)?;
+        let expand = |text: &String| OsString::from(expand_env(text));
+        Ok(ShortcutInfo {
+            target_path: link
+                .target(lnk_path)
+                .map(|target| PathBuf::from(expand_env(&target))),
+            description: link.name.as_ref().map(OsString::from),
+            working_directory: link.working_dir.as_ref().map(expand),
+            arguments: link.arguments.as_ref().map(OsString::from),
+            icon_location: link.icon().as_ref().map(expand),
+            icon_index: link.icon_index,
+            name: lnk_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string()),
+        })
+    }
+}
 
-//                 CoUninitialize();
-//                 return Some(shortcut_info);
-//             }
-//         }
+// 读取失败的快捷方式记录到 warnings 中，不影响其它快捷方式
+fn get_shortcut_info(lnk_paths: &[PathBuf], warnings: &mut Vec<String>) -> Vec<ShortcutInfo> {
+    lnk_paths
+        .iter()
+        .filter_map(|lnk_path| match ShortcutInfo::read(lnk_path) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                warnings.push(format!("Failed to read {}: {}", lnk_path.display(), e));
+                None
+            }
+        })
+        .collect()
+}
 
-//         // 释放COM库
-//         CoUninitialize();
-//     }
-//     None
-// }
+// 程序版本资源中的信息
+#[derive(Debug, Default)]
+struct VersionInfo {
+    version: Option<String>,
+    description: Option<String>,
+    product: Option<String>,
+}
 
-fn get_shortcut_info(lnk_paths: &Vec<PathBuf>) -> Vec<ShortcutInfo> {
+// 读取 exe 的版本资源，没有版本资源的程序各项为空
+fn version_info(path: &Path) -> VersionInfo {
+    let mut info = VersionInfo::default();
+    let file = HSTRING::from(path.as_os_str());
     unsafe {
-        // 初始化COM库
-        let hr: HRESULT = CoInitializeEx(None, COINIT_MULTITHREADED);
-        if hr != S_OK {
-            eprintln!(
-                "Failed to initialize COM library with HRESULT: 0x{:X}",
-                hr.0
-            );
-            // 释放COM库
-            CoUninitialize();
-            get_shortcut_info(lnk_paths);
+        let size = GetFileVersionInfoSizeW(&file, None);
+        if size == 0 {
+            return info;
         }
-        let mut results: Vec<ShortcutInfo> = Vec::new();
-        // 创建IShellLinkW对象
-        let shell_link: Result<IShellLinkW, _> =
-            CoCreateInstance(&CLSID_SHELL_LINK, None, CLSCTX_INPROC_SERVER).map_err(|_| {
-                return results.clone();
-            });
-
-        let shell_link = shell_link.unwrap();
-
-        for lnk_path in lnk_paths.iter() {
-            // 检查.lnk文件路径
-            if !lnk_path.exists() {
-                eprintln!(
-                    "The .lnk file does not exist at the specified path: {:?}",
-                    lnk_path
-                );
-                continue;
-            }
-
-            // 获取IPersistFile接口
-            let persist_file: IPersistFile = shell_link.cast().unwrap();
-            let lnk_path_wide: Vec<u16> = OsString::from(lnk_path)
-                .encode_wide()
-                .chain(Some(0))
-                .collect();
-
-            // 加载.lnk文件
-            let hr = persist_file.Load(PCWSTR(lnk_path_wide.as_ptr()), STGM_READ);
-            if hr.is_ok() {
-                let name: Option<String> = lnk_path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.to_string());
-                let mut shortcut_info = ShortcutInfo {
-                    name,
-                    target_path: None,
-                    description: None,
-                    working_directory: None,
-                    icon_location: None,
-                    icon_index: 0,
-                };
-
-                // 获取目标路径
-                let mut target_path: [u16; MAX_PATH as usize] = [0; MAX_PATH as usize];
-                let mut find_data: windows::Win32::Storage::FileSystem::WIN32_FIND_DATAW =
-                    std::mem::zeroed();
-                let hr = shell_link.GetPath(&mut target_path, &mut find_data, 0);
-                if hr.is_ok() {
-                    let len = target_path
-                        .iter()
-                        .position(|&c| c == 0)
-                        .unwrap_or(target_path.len());
-                    let target_path_osstring = OsString::from_wide(&target_path[..len]);
-                    shortcut_info.target_path = Some(PathBuf::from(target_path_osstring));
-                }
-
-                // ... 省略其他获取信息的代码 ...
-
-                results.push(shortcut_info);
+        let mut data = vec![0u8; size as usize];
+        if GetFileVersionInfoW(&file, 0, size, data.as_mut_ptr() as *mut c_void).is_err() {
+            return info;
+        }
+        // 返回的指针指向 data 内部，len 对字符串为字符数
+        let query = |block: &str| -> Option<(*const c_void, usize)> {
+            let mut buffer: *mut c_void = std::ptr::null_mut();
+            let mut len: u32 = 0;
+            let found = VerQueryValueW(
+                data.as_ptr() as *const c_void,
+                &HSTRING::from(block),
+                &mut buffer,
+                &mut len,
+            );
+            (found.as_bool() && !buffer.is_null() && len > 0)
+                .then_some((buffer as *const c_void, len as usize))
+        };
+        if let Some((buffer, len)) = query("\\") {
+            if len >= std::mem::size_of::<VS_FIXEDFILEINFO>() {
+                let fixed = &*(buffer as *const VS_FIXEDFILEINFO);
+                info.version = Some(format!(
+                    "{}.{}.{}.{}",
+                    fixed.dwProductVersionMS >> 16,
+                    fixed.dwProductVersionMS & 0xffff,
+                    fixed.dwProductVersionLS >> 16,
+                    fixed.dwProductVersionLS & 0xffff
+                ));
             }
         }
-
-        // 释放COM库
-        CoUninitialize();
-        results
+        // 字符串按第一个语言和代码页保存，没有时按美式英语、Unicode 查找
+        let translation = query("\\VarFileInfo\\Translation")
+            .filter(|(_, len)| *len >= 4)
+            .map(|(buffer, _)| {
+                let pair = std::slice::from_raw_parts(buffer as *const u16, 2);
+                format!("{:04x}{:04x}", pair[0], pair[1])
+            })
+            .unwrap_or("040904b0".to_string());
+        let string = |name: &str| {
+            query(&format!("\\StringFileInfo\\{}\\{}", translation, name))
+                .map(|(buffer, len)| {
+                    let units = std::slice::from_raw_parts(buffer as *const u16, len);
+                    String::from_utf16_lossy(units)
+                        .trim_end_matches('\0')
+                        .trim()
+                        .to_string()
+                })
+                .filter(|value| !value.is_empty())
+        };
+        info.description = string("FileDescription");
+        info.product = string("ProductName");
     }
+    info
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Installed {
     pub apps: Vec<App>,
+    // 读取失败的目录或快捷方式
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 impl Default for Installed {
     fn default() -> Self {
-        Installed { apps: Vec::new() }
+        Installed {
+            apps: Vec::new(),
+            warnings: Vec::new(),
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct App {
     pub name: Option<String>,
     pub path: Option<PathBuf>,
     pub icon: Option<PathBuf>,
+    // 版本号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // 快捷方式没有应用 id，为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // 应用的说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    // 额外参与搜索的关键词
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    // 应用的其它名称，如未翻译的名称，name 为当前语言的名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+    // 安装来源，Linux 下用于区分 Flatpak、Snap 等，这里为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    // 快捷方式中的参数和起始位置，启动时使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<PathBuf>,
 }
 
 impl Default for App {
@@ -269,6 +219,15 @@ impl Default for App {
             name: None,
             path: None,
             icon: None,
+            version: None,
+            id: None,
+            description: None,
+            categories: Vec::new(),
+            keywords: Vec::new(),
+            names: Vec::new(),
+            source: None,
+            arguments: None,
+            working_directory: None,
         }
     }
 }
@@ -277,64 +236,104 @@ impl Default for App {
 impl Installed {
     pub fn new() -> Self {
         let mut installed = Self::default();
-        installed.apps = Self::get_apps();
+        installed.apps = Self::get_apps(&mut installed.warnings);
         installed
     }
 
-    fn traverse_dir(dir_path: &Path, lnks: &mut Vec<PathBuf>) {
-        if let Ok(entries) = fs::read_dir(dir_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let file_path = entry.path();
-                    if file_path.is_dir() {
-                        // 如果是目录，则递归调用
-                        Self::traverse_dir(&file_path, lnks)
-                    } else {
-                        // 检查文件扩展名
-                        if let Some(extension) = file_path.extension() {
-                            if extension == "lnk" {
-                                // 处理 .lnk 文件
-                                lnks.push(file_path.clone())
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            let entry = fs::read_link(dir_path).expect("Failed to read link");
-            let file_path = entry.as_path();
-            // 检查文件扩展名
-            if let Some(extension) = file_path.extension() {
-                if extension == "lnk" {
-                    // 处理 .lnk 文件
+    // 应用所在的目录，这些目录变化时需要重新获取
+    pub fn dirs() -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            dirs.push(Path::new(&appdata).join("Microsoft\\Windows\\Start Menu\\Programs"));
+        }
+        let program_data =
+            std::env::var_os("ProgramData").unwrap_or_else(|| OsString::from("C:\\ProgramData"));
+        dirs.push(Path::new(&program_data).join("Microsoft\\Windows\\Start Menu\\Programs"));
+        dirs
+    }
 
-                    lnks.push(entry.clone())
+    fn traverse_dir(dir_path: &Path, lnks: &mut Vec<PathBuf>, warnings: &mut Vec<String>) {
+        let entries = match fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warnings.push(format!("Failed to read {}: {}", dir_path.display(), e));
+                return;
+            }
+        };
+        for entry in entries {
+            let file_path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    warnings.push(format!("Failed to read {}: {}", dir_path.display(), e));
+                    continue;
                 }
+            };
+            if file_path.is_dir() {
+                // 如果是目录，则递归调用
+                Self::traverse_dir(&file_path, lnks, warnings)
+            } else if file_path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("lnk"))
+            {
+                // 处理 .lnk 文件
+                lnks.push(file_path)
             }
         }
     }
-    fn get_apps() -> Vec<App> {
+
+    fn get_apps(warnings: &mut Vec<String>) -> Vec<App> {
         let mut apps = Vec::new();
-        let lnks = Self::get_lnks();
-        let shortcut_info_list = get_shortcut_info(&lnks);
+        let mut lnks: Vec<PathBuf> = Vec::new();
+        for dir in Self::dirs() {
+            if dir.is_dir() {
+                Self::traverse_dir(&dir, &mut lnks, warnings);
+            }
+        }
+        let shortcut_info_list = get_shortcut_info(&lnks, warnings);
         for info in shortcut_info_list {
+            let version = info
+                .target_path()
+                .map(|target| version_info(target))
+                .unwrap_or_default();
+            let name = info.name().cloned();
             apps.push(App {
-                name: info.name().cloned(),
+                // 产品名和快捷方式的名称不同时也参与搜索
+                names: version
+                    .product
+                    .iter()
+                    .filter(|product| name.as_ref() != Some(*product))
+                    .cloned()
+                    .collect(),
+                name,
                 path: info.target_path().cloned(),
-                icon: info.icon_location().map(|os_str| PathBuf::from(os_str)),
+                // 图标为 `路径,下标` 的形式，没有单独设置时使用程序本身的图标
+                icon: info
+                    .icon_location()
+                    .map(|location| {
+                        PathBuf::from(format!(
+                            "{},{}",
+                            location.to_string_lossy(),
+                            info.icon_index()
+                        ))
+                    })
+                    .or_else(|| info.target_path().cloned()),
+                version: version.version,
+                id: None,
+                // 快捷方式的备注，没有时使用程序的文件说明
+                description: info
+                    .description()
+                    .map(|description| description.to_string_lossy().to_string())
+                    .filter(|description| !description.is_empty())
+                    .or(version.description),
+                categories: Vec::new(),
+                keywords: Vec::new(),
+                source: None,
+                arguments: info
+                    .arguments()
+                    .map(|arguments| arguments.to_string_lossy().to_string()),
+                working_directory: info.working_directory().map(PathBuf::from),
             })
         }
         return apps;
     }
-    fn get_lnks() -> Vec<PathBuf> {
-        let appdata = std::env::var("APPDATA").expect("Failed to get APPDATA environment variable");
-        let start_menu_path = Path::new(&appdata).join("Microsoft\\Windows\\Start Menu\\Programs");
-        let mut lnks: Vec<PathBuf> = Vec::new();
-        Self::traverse_dir(&start_menu_path, &mut lnks);
-
-        let common_start_menu_path =
-            Path::new("C:\\ProgramData\\Microsoft\\Windows\\Start Menu\\Programs");
-        Self::traverse_dir(&common_start_menu_path, &mut lnks);
-        return lnks;
-    }
 }
@@ -0,0 +1,338 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+use tauri::{command, AppHandle, Emitter, EventTarget, Manager, Runtime, State, Webview};
+
+use super::discovery;
+
+// 投递给订阅者的事件名
+pub const BUS_EVENT: &str = "toolbox://bus";
+// 二进制/脚本插件在 stdout 中发布消息时使用的行前缀，后面跟一个 `{"topic": "...", "payload": ...}` JSON
+pub const PUBLISH_FRAME: &str = "::toolbox-publish::";
+
+// 插件间的消息总线，按 webview 记录订阅的主题
+#[derive(Default)]
+pub struct EventBus {
+    subscriptions: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl EventBus {
+    fn subscribe(&self, label: &str, topic: String) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .entry(label.to_string())
+            .or_default()
+            .insert(topic);
+    }
+
+    // topic 为空时取消该 webview 的全部订阅
+    fn unsubscribe(&self, label: &str, topic: Option<&str>) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        match topic {
+            Some(topic) => {
+                if let Some(patterns) = subscriptions.get_mut(label) {
+                    patterns.remove(topic);
+                }
+            }
+            None => {
+                subscriptions.remove(label);
+            }
+        }
+    }
+
+    // 订阅了该主题的 webview
+    fn subscribers(&self, topic: &str) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, patterns)| patterns.iter().any(|p| topic_matches(p, topic)))
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+
+    // 插件窗口为 `toolbox-plugin-{id}-window`，其中的 webview 为 `toolbox-plugin-{id}-webview`
+    fn remove_window(&self, window_label: &str) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.remove(window_label);
+        if let Some(prefix) = window_label.strip_suffix("-window") {
+            subscriptions.remove(&format!("{}-webview", prefix));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BusMessage {
+    pub topic: String,
+    // 发布者的插件 id，应用自身发布时为空
+    pub from: Option<String>,
+    pub payload: Value,
+}
+
+// 主题匹配，支持 `*` 和 `image.*` 这样的前缀通配
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => topic.starts_with(prefix),
+        None => pattern == topic,
+    }
+}
+
+fn is_valid_topic(topic: &str) -> bool {
+    !topic.is_empty()
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '*'))
+}
+
+// 从 webview 的 label 中解析插件 id，不是插件的 webview 返回 None
+fn plugin_of_label(label: &str) -> Option<&str> {
+    label
+        .strip_prefix("toolbox-plugin-")
+        .and_then(|rest| rest.strip_suffix("-webview"))
+}
+
+#[derive(Clone, Copy)]
+enum Access {
+    Publish,
+    Subscribe,
+}
+
+// 检查插件在 config.json 的 `topics` 中是否声明了该主题
+fn is_allowed(config: Option<&Value>, topic: &str, access: Access) -> bool {
+    let key = match access {
+        Access::Publish => "publish",
+        Access::Subscribe => "subscribe",
+    };
+    let patterns = match config
+        .and_then(|config| config.get("topics"))
+        .and_then(|topics| topics.get(key))
+        .and_then(|patterns| patterns.as_array())
+    {
+        Some(patterns) => patterns,
+        None => return false,
+    };
+    patterns
+        .iter()
+        .filter_map(|pattern| pattern.as_str())
+        .any(|pattern| topic_matches(pattern, topic))
+}
+
+// 插件的 config.json，应用自身或插件不存在时为空
+fn config_of<R: Runtime>(app: &AppHandle<R>, from: Option<&str>) -> Option<Value> {
+    let id = from?;
+    discovery::discover(app)
+        .get(id)
+        .map(|plugin| plugin.config.clone())
+}
+
+// 检查 from 是否可以访问该主题，应用自身（from 为空）不受限制，config 为 from 的 config.json
+fn check(
+    from: Option<&str>,
+    config: Option<&Value>,
+    topic: &str,
+    access: Access,
+) -> Result<(), String> {
+    if !is_valid_topic(topic) {
+        return Err(format!("Invalid topic `{}`", topic));
+    }
+    match from {
+        Some(id) if !is_allowed(config, topic, access) => {
+            let action = match access {
+                Access::Publish => "publish to",
+                Access::Subscribe => "subscribe to",
+            };
+            Err(format!(
+                "Plugin `{}` is not allowed to {} `{}`",
+                id, action, topic
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+// 发布消息，投递给所有订阅了该主题的 webview
+pub fn publish<R: Runtime>(
+    app: &AppHandle<R>,
+    from: Option<&str>,
+    topic: &str,
+    payload: Value,
+) -> Result<usize, String> {
+    check(from, config_of(app, from).as_ref(), topic, Access::Publish)?;
+    let targets = app.state::<EventBus>().subscribers(topic);
+    let message = BusMessage {
+        topic: topic.to_string(),
+        from: from.map(|id| id.to_string()),
+        payload,
+    };
+    for label in targets.iter() {
+        let target = EventTarget::Webview {
+            label: label.clone(),
+        };
+        if let Err(e) = app.emit_to(target, BUS_EVENT, message.clone()) {
+            eprintln!("Failed to deliver `{}` to {}: {}", topic, label, e);
+        }
+    }
+    Ok(targets.len())
+}
+
+// 从插件的 stdout 中取出发布帧并发布，返回去掉发布帧之后的输出
+pub fn publish_frames<R: Runtime>(app: &AppHandle<R>, plugin_id: &str, stdout: &str) -> String {
+    if !stdout.contains(PUBLISH_FRAME) {
+        return stdout.to_string();
+    }
+    let mut rest: Vec<&str> = Vec::new();
+    for line in stdout.lines() {
+        let frame = match line.strip_prefix(PUBLISH_FRAME) {
+            Some(frame) => frame,
+            None => {
+                rest.push(line);
+                continue;
+            }
+        };
+        let result = serde_json::from_str::<Value>(frame)
+            .map_err(|e| e.to_string())
+            .and_then(|message| {
+                let topic = message
+                    .get("topic")
+                    .and_then(|topic| topic.as_str())
+                    .ok_or("Missing topic".to_string())?;
+                let payload = message.get("payload").cloned().unwrap_or(Value::Null);
+                publish(app, Some(plugin_id), topic, payload)
+            });
+        if let Err(e) = result {
+            eprintln!("Plugin `{}` failed to publish: {}", plugin_id, e);
+        }
+    }
+    rest.join("\n")
+}
+
+#[command]
+pub fn bus_publish(
+    app: AppHandle,
+    webview: Webview,
+    topic: String,
+    payload: Value,
+) -> Result<usize, String> {
+    publish(&app, plugin_of_label(webview.label()), &topic, payload)
+}
+
+#[command]
+pub fn bus_subscribe(
+    app: AppHandle,
+    webview: Webview,
+    bus: State<'_, EventBus>,
+    topic: String,
+) -> Result<(), String> {
+    let label = webview.label();
+    let from = plugin_of_label(label);
+    check(
+        from,
+        config_of(&app, from).as_ref(),
+        &topic,
+        Access::Subscribe,
+    )?;
+    bus.subscribe(label, topic);
+    Ok(())
+}
+
+#[command]
+pub fn bus_unsubscribe(webview: Webview, bus: State<'_, EventBus>, topic: Option<String>) {
+    bus.unsubscribe(webview.label(), topic.as_deref());
+}
+
+// 窗口关闭时清理其中 webview 的订阅
+pub fn remove_window<R: Runtime>(app: &AppHandle<R>, window_label: &str) {
+    if let Some(bus) = app.try_state::<EventBus>() {
+        bus.remove_window(window_label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const WEBVIEW: &str = "toolbox-plugin-notes-webview";
+
+    // 插件 config.json 中的 `topics`
+    fn config() -> Value {
+        json!({
+            "id": "notes",
+            "topics": {
+                "publish": ["notes.saved", "image.*"],
+                "subscribe": ["clipboard.*", "theme"]
+            }
+        })
+    }
+
+    #[test]
+    fn checks_declared_topics() {
+        let config = config();
+        let check = |topic, access| check(Some("notes"), Some(&config), topic, access);
+        assert!(check("notes.saved", Access::Publish).is_ok());
+        assert!(check("image.resized", Access::Publish).is_ok());
+        assert_eq!(
+            check("notes.deleted", Access::Publish).unwrap_err(),
+            "Plugin `notes` is not allowed to publish to `notes.deleted`"
+        );
+        assert!(check("clipboard.text", Access::Subscribe).is_ok());
+        assert!(check("theme", Access::Subscribe).is_ok());
+        // 可以发布的主题不代表可以订阅
+        assert_eq!(
+            check("notes.saved", Access::Subscribe).unwrap_err(),
+            "Plugin `notes` is not allowed to subscribe to `notes.saved`"
+        );
+        assert_eq!(
+            check("notes saved", Access::Publish).unwrap_err(),
+            "Invalid topic `notes saved`"
+        );
+    }
+
+    #[test]
+    fn denies_plugins_without_topics() {
+        let bare = json!({ "id": "notes" });
+        assert!(check(Some("notes"), Some(&bare), "theme", Access::Subscribe).is_err());
+        let invalid = json!({ "topics": { "publish": "theme" } });
+        assert!(check(Some("notes"), Some(&invalid), "theme", Access::Publish).is_err());
+        // 插件不存在时没有配置
+        assert!(check(Some("gone"), None, "theme", Access::Publish).is_err());
+        // 应用自身不受限制
+        assert!(check(None, None, "theme", Access::Publish).is_ok());
+    }
+
+    #[test]
+    fn delivers_to_matching_subscribers() {
+        let bus = EventBus::default();
+        bus.subscribe(WEBVIEW, "clipboard.*".to_string());
+        bus.subscribe("search", "clipboard.text".to_string());
+        bus.subscribe("search", "theme".to_string());
+        let mut subscribers = bus.subscribers("clipboard.text");
+        subscribers.sort();
+        assert_eq!(subscribers, ["search", WEBVIEW]);
+        assert_eq!(bus.subscribers("clipboard.image"), [WEBVIEW]);
+        assert!(bus.subscribers("notes.saved").is_empty());
+
+        bus.unsubscribe("search", Some("clipboard.text"));
+        assert_eq!(bus.subscribers("clipboard.text"), [WEBVIEW]);
+        assert_eq!(bus.subscribers("theme"), ["search"]);
+        bus.unsubscribe("search", None);
+        assert!(bus.subscribers("theme").is_empty());
+    }
+
+    #[test]
+    fn removes_window_subscriptions() {
+        let bus = EventBus::default();
+        bus.subscribe(WEBVIEW, "theme".to_string());
+        bus.subscribe("toolbox-plugin-calc-webview", "theme".to_string());
+        bus.subscribe("search", "theme".to_string());
+        bus.remove_window("toolbox-plugin-notes-window");
+        bus.remove_window("search");
+        assert_eq!(bus.subscribers("theme"), ["toolbox-plugin-calc-webview"]);
+        assert_eq!(plugin_of_label(WEBVIEW), Some("notes"));
+        assert_eq!(plugin_of_label("search"), None);
+    }
+}
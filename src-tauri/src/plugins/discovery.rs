@@ -0,0 +1,419 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use tauri::{command, path::BaseDirectory, AppHandle, Manager, Runtime};
+
+use super::limits::ResourceLimits;
+use crate::utils::store;
+
+// store 中保存用户插件目录的键
+pub const USER_PLUGINS_DIR_KEY: &str = "plugins_dir";
+// 环境变量：覆盖系统级插件目录
+pub const SYSTEM_PLUGINS_DIR_ENV: &str = "VTOOLS_SYSTEM_PLUGINS_DIR";
+// 环境变量：开发中的插件目录，可以用系统路径分隔符指定多个
+pub const DEV_PLUGINS_DIR_ENV: &str = "VTOOLS_DEV_PLUGINS_DIR";
+
+// 插件的来源，顺序即优先级，后面的会覆盖前面的同名插件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginSource {
+    Bundled,
+    System,
+    User,
+    Dev,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginRoot {
+    pub source: PluginSource,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredPlugin {
+    pub id: String,
+    pub source: PluginSource,
+    // 插件所在的目录
+    pub dir: PathBuf,
+    // 插件的 config.json 内容
+    pub config: Value,
+}
+
+// 同一个 id 出现在多个目录中，只有 active 生效
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicatePlugin {
+    pub id: String,
+    pub active: PathBuf,
+    pub shadowed: Vec<PathBuf>,
+}
+
+// 无法加载的插件目录
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidPlugin {
+    pub dir: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Discovery {
+    pub roots: Vec<PluginRoot>,
+    pub plugins: Vec<DiscoveredPlugin>,
+    pub duplicates: Vec<DuplicatePlugin>,
+    pub invalid: Vec<InvalidPlugin>,
+}
+
+impl Discovery {
+    // 按优先级扫描所有插件目录
+    pub fn scan(roots: Vec<PluginRoot>) -> Self {
+        let mut found: Vec<DiscoveredPlugin> = Vec::new();
+        let mut invalid: Vec<InvalidPlugin> = Vec::new();
+
+        for root in roots.iter() {
+            let entries = match fs::read_dir(&root.path) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            let mut dirs: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect();
+            // 保证同一目录下的扫描顺序稳定
+            dirs.sort();
+            for dir in dirs {
+                match read_manifest(&dir) {
+                    Ok((id, config)) => found.push(DiscoveredPlugin {
+                        id,
+                        source: root.source,
+                        dir,
+                        config,
+                    }),
+                    Err(reason) => invalid.push(InvalidPlugin { dir, reason }),
+                }
+            }
+        }
+
+        // 后扫描到的覆盖先扫描到的
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut plugins: Vec<DiscoveredPlugin> = Vec::new();
+        let mut shadowed: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for plugin in found {
+            match index.get(&plugin.id) {
+                Some(&i) => {
+                    let old = std::mem::replace(&mut plugins[i], plugin);
+                    shadowed.entry(old.id).or_default().push(old.dir);
+                }
+                None => {
+                    index.insert(plugin.id.clone(), plugins.len());
+                    plugins.push(plugin);
+                }
+            }
+        }
+
+        let mut duplicates: Vec<DuplicatePlugin> = shadowed
+            .into_iter()
+            .map(|(id, shadowed)| DuplicatePlugin {
+                active: plugins[index[&id]].dir.clone(),
+                id,
+                shadowed,
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.id.cmp(&b.id));
+        for duplicate in duplicates.iter() {
+            eprintln!(
+                "Duplicate plugin id `{}`: using {:?}, ignoring {:?}",
+                duplicate.id, duplicate.active, duplicate.shadowed
+            );
+        }
+
+        Discovery {
+            roots,
+            plugins,
+            duplicates,
+            invalid,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&DiscoveredPlugin> {
+        self.plugins.iter().find(|plugin| plugin.id == id)
+    }
+}
+
+// 读取插件目录下的 config.json，返回插件 id 和配置
+fn read_manifest(dir: &Path) -> Result<(String, Value), String> {
+    let config_path = dir.join("config.json");
+    let content = fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+    let config: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    if !config.is_object() {
+        return Err("config.json is not an object".to_string());
+    }
+    // 没有声明 id 的插件使用目录名
+    let id = match config.get("id").and_then(|id| id.as_str()) {
+        Some(id) => id.to_string(),
+        None => dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    if !is_valid_id(&id) {
+        return Err(format!("invalid plugin id `{}`", id));
+    }
+    // 资源限制写错时不能悄悄按默认值执行
+    ResourceLimits::from_config(&config)?;
+    Ok((id, config))
+}
+
+// id 只支持英文、数字、_、- 且不能数字开头
+pub fn is_valid_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '-' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// 系统级插件目录，由运维统一下发
+fn system_plugins_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var(SYSTEM_PLUGINS_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(target_os = "windows") {
+        env::var("ProgramData")
+            .ok()
+            .map(|dir| PathBuf::from(dir).join("vtools").join("plugins"))
+    } else if cfg!(target_os = "macos") {
+        Some(PathBuf::from("/Library/Application Support/vtools/plugins"))
+    } else {
+        Some(PathBuf::from("/usr/share/vtools/plugins"))
+    }
+}
+
+// 用户插件目录，未配置时为 `$HOME/.vtools/plugins`
+pub fn user_plugins_dir<R: Runtime>(app: &AppHandle<R>) -> Option<PathBuf> {
+    if let Some(dir) = store::get(app, USER_PLUGINS_DIR_KEY) {
+        if let Some(dir) = dir.as_str().filter(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    app.path()
+        .home_dir()
+        .ok()
+        .map(|home| home.join(".vtools").join("plugins"))
+}
+
+// 开发中的插件目录
+fn dev_plugins_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Some(paths) = env::var_os(DEV_PLUGINS_DIR_ENV) {
+        dirs.extend(env::split_paths(&paths));
+    }
+    if cfg!(debug_assertions) {
+        if let Some(exe_dir) = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        {
+            dirs.push(exe_dir.join("plugins"));
+        }
+    }
+    dirs
+}
+
+// 按优先级返回所有插件根目录：内置、系统、用户、开发
+pub fn plugin_roots<R: Runtime>(app: &AppHandle<R>) -> Vec<PluginRoot> {
+    let mut roots: Vec<PluginRoot> = Vec::new();
+    if let Ok(path) = app.path().resolve("plugins", BaseDirectory::Resource) {
+        roots.push(PluginRoot {
+            source: PluginSource::Bundled,
+            path,
+        });
+    }
+    if let Some(path) = system_plugins_dir() {
+        roots.push(PluginRoot {
+            source: PluginSource::System,
+            path,
+        });
+    }
+    if let Some(path) = user_plugins_dir(app) {
+        roots.push(PluginRoot {
+            source: PluginSource::User,
+            path,
+        });
+    }
+    for path in dev_plugins_dirs() {
+        roots.push(PluginRoot {
+            source: PluginSource::Dev,
+            path,
+        });
+    }
+    // 同一个目录只扫描一次，保留优先级最高的那个
+    let mut unique: Vec<PluginRoot> = Vec::new();
+    for root in roots.into_iter().rev() {
+        if !unique.iter().any(|r| r.path == root.path) {
+            unique.push(root);
+        }
+    }
+    unique.reverse();
+    unique
+}
+
+// 扫描所有插件目录
+pub fn discover<R: Runtime>(app: &AppHandle<R>) -> Discovery {
+    Discovery::scan(plugin_roots(app))
+}
+
+// 获取所有生效的插件以及重复/无效的插件
+#[command]
+pub fn get_plugins(app: AppHandle) -> Discovery {
+    discover(&app)
+}
+
+// 获取插件所在的目录
+#[command]
+pub fn get_plugin_dir(app: AppHandle, id: String) -> Result<PathBuf, String> {
+    discover(&app)
+        .get(&id)
+        .map(|plugin| plugin.dir.clone())
+        .ok_or(format!("Plugin `{}` not found", id))
+}
+
+// 设置用户插件目录，传空值时恢复默认目录
+#[command]
+pub fn set_user_plugins_dir(
+    app: AppHandle,
+    dir: Option<String>,
+) -> Result<Option<PathBuf>, String> {
+    let value = dir.filter(|dir| !dir.is_empty()).map(|dir| json!(dir));
+    store::set(&app, USER_PLUGINS_DIR_KEY, value)?;
+    crate::search::invalidate(&app);
+    Ok(user_plugins_dir(&app))
+}
+
+// 获取当前生效的用户插件目录
+#[command]
+pub fn get_user_plugins_dir(app: AppHandle) -> Option<PathBuf> {
+    user_plugins_dir(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                env::temp_dir().join(format!("toolbox-discovery-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        // 在 root 下创建插件目录并写入 config.json
+        fn plugin(&self, root: &str, dir: &str, config: &str) -> PathBuf {
+            let dir = self.0.join(root).join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("config.json"), config).unwrap();
+            dir
+        }
+
+        fn root(&self, source: PluginSource, name: &str) -> PluginRoot {
+            PluginRoot {
+                source,
+                path: self.0.join(name),
+            }
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn later_roots_take_precedence() {
+        let temp = TempDir::new("precedence");
+        temp.plugin("bundled", "calc", r#"{ "id": "calc", "version": "1" }"#);
+        temp.plugin("bundled", "notes", r#"{ "id": "notes" }"#);
+        temp.plugin(
+            "system",
+            "calc-system",
+            r#"{ "id": "calc", "version": "2" }"#,
+        );
+        let user = temp.plugin("user", "calc", r#"{ "id": "calc", "version": "3" }"#);
+        let discovery = Discovery::scan(vec![
+            temp.root(PluginSource::Bundled, "bundled"),
+            temp.root(PluginSource::System, "system"),
+            temp.root(PluginSource::User, "user"),
+            temp.root(PluginSource::Dev, "missing"),
+        ]);
+
+        let ids: Vec<&str> = discovery.plugins.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["calc", "notes"]);
+        let calc = discovery.get("calc").unwrap();
+        assert_eq!(calc.source, PluginSource::User);
+        assert_eq!(calc.dir, user);
+        assert_eq!(calc.config["version"], "3");
+        assert_eq!(
+            discovery.get("notes").unwrap().source,
+            PluginSource::Bundled
+        );
+        assert!(discovery.invalid.is_empty());
+    }
+
+    #[test]
+    fn reports_duplicate_ids() {
+        let temp = TempDir::new("duplicates");
+        // 同一目录下 id 相同时按目录名排序，后面的生效
+        let first = temp.plugin("user", "a-calc", r#"{ "id": "calc" }"#);
+        let second = temp.plugin("user", "b-calc", r#"{ "id": "calc" }"#);
+        let dev = temp.plugin("dev", "calc", "{}");
+        let discovery = Discovery::scan(vec![
+            temp.root(PluginSource::User, "user"),
+            temp.root(PluginSource::Dev, "dev"),
+        ]);
+
+        assert_eq!(discovery.plugins.len(), 1);
+        assert_eq!(discovery.duplicates.len(), 1);
+        let duplicate = &discovery.duplicates[0];
+        assert_eq!(duplicate.id, "calc");
+        assert_eq!(duplicate.active, dev);
+        assert_eq!(duplicate.shadowed, [first, second]);
+    }
+
+    #[test]
+    fn collects_invalid_plugins() {
+        let temp = TempDir::new("invalid");
+        temp.plugin("user", "ok", "{}");
+        let bad_id = temp.plugin("user", "bad-id", r#"{ "id": "1calc" }"#);
+        let not_object = temp.plugin("user", "array", "[]");
+        let broken = temp.plugin("user", "broken", "{");
+        let limits = temp.plugin("user", "limits", r#"{ "limits": { "cpuSeconds": -1 } }"#);
+        let missing = temp.0.join("user").join("missing");
+        fs::create_dir_all(&missing).unwrap();
+        let discovery = Discovery::scan(vec![temp.root(PluginSource::User, "user")]);
+
+        assert_eq!(discovery.plugins.len(), 1);
+        assert_eq!(discovery.plugins[0].id, "ok");
+        let invalid: Vec<&PathBuf> = discovery.invalid.iter().map(|p| &p.dir).collect();
+        assert_eq!(invalid, [&not_object, &bad_id, &broken, &limits, &missing]);
+        assert!(discovery.invalid[3].reason.starts_with("invalid limits:"));
+    }
+
+    #[test]
+    fn validates_ids() {
+        assert!(is_valid_id("calc"));
+        assert!(is_valid_id("_my-plugin2"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("2calc"));
+        assert!(!is_valid_id("calc.plugin"));
+        assert!(!is_valid_id("计算器"));
+    }
+}
@@ -0,0 +1,595 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Runtime};
+
+use crate::plugins::discovery;
+
+// 插件进程的资源限制，在插件 config.json 的 `limits` 字段中声明，0 表示不限制
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResourceLimits {
+    // 最大 CPU 时间（秒）
+    pub cpu_seconds: u64,
+    // 最大地址空间（MB）
+    pub memory_mb: u64,
+    // 最多打开的文件数
+    pub open_files: u64,
+    // 最长运行时间（秒）
+    pub timeout_seconds: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            cpu_seconds: 30,
+            memory_mb: 1024,
+            open_files: 256,
+            timeout_seconds: 60,
+        }
+    }
+}
+
+impl ResourceLimits {
+    // 从插件配置中读取限制，未声明的项使用默认值
+    pub fn from_config(config: &Value) -> Result<Self, String> {
+        match config.get("limits") {
+            Some(limits) => {
+                serde_json::from_value(limits.clone()).map_err(|e| format!("invalid limits: {}", e))
+            }
+            None => Ok(ResourceLimits::default()),
+        }
+    }
+
+    // 超出限制时的错误信息
+    fn message(&self, exceeded: LimitExceeded) -> String {
+        format!(
+            "Plugin killed: {} limit exceeded ({})",
+            exceeded,
+            self.describe(exceeded)
+        )
+    }
+
+    fn describe(&self, exceeded: LimitExceeded) -> String {
+        match exceeded {
+            LimitExceeded::CpuTime => format!("{}s", self.cpu_seconds),
+            LimitExceeded::Memory => format!("{}MB", self.memory_mb),
+            LimitExceeded::OpenFiles => format!("{} files", self.open_files),
+            LimitExceeded::Timeout => format!("{}s", self.timeout_seconds),
+        }
+    }
+}
+
+// 获取插件的资源限制，不是插件（如启动本机应用）时不做限制
+pub fn for_plugin<R: Runtime>(
+    app: &AppHandle<R>,
+    plugin_id: Option<&str>,
+) -> Result<Option<ResourceLimits>, String> {
+    let Some(plugin_id) = plugin_id else {
+        return Ok(None);
+    };
+    let limits = match discovery::discover(app).get(plugin_id) {
+        Some(plugin) => ResourceLimits::from_config(&plugin.config)?,
+        None => ResourceLimits::default(),
+    };
+    Ok(Some(limits))
+}
+
+// 导致插件被终止的限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LimitExceeded {
+    CpuTime,
+    Memory,
+    OpenFiles,
+    Timeout,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LimitExceeded::CpuTime => "cpu time",
+            LimitExceeded::Memory => "memory",
+            LimitExceeded::OpenFiles => "open files",
+            LimitExceeded::Timeout => "wall-clock timeout",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
+pub struct LimitedOutput {
+    pub output: Output,
+    pub exceeded: Option<LimitExceeded>,
+    // 被调用方主动结束
+    pub cancelled: bool,
+    // 从启动到结束的耗时
+    pub duration: Duration,
+}
+
+impl LimitedOutput {
+    // 转换为命令的返回值，成功时返回 stdout，失败时返回 stderr 或超出的限制
+    pub fn into_result(self, limits: Option<&ResourceLimits>) -> Result<String, String> {
+        if let (Some(exceeded), Some(limits)) = (self.exceeded, limits) {
+            return Err(limits.message(exceeded));
+        }
+        if self.output.status.success() {
+            Ok(String::from_utf8_lossy(&self.output.stdout).to_string())
+        } else {
+            Err(String::from_utf8_lossy(&self.output.stderr).to_string())
+        }
+    }
+}
+
+// 在子进程 exec 之前设置 rlimit
+#[cfg(target_os = "linux")]
+fn apply_rlimits(command: &mut Command, limits: &ResourceLimits) {
+    use std::os::unix::process::CommandExt;
+
+    let limits = *limits;
+    unsafe {
+        command.pre_exec(move || {
+            let set = |resource, value: u64| -> io::Result<()> {
+                if value == 0 {
+                    return Ok(());
+                }
+                let rlim = libc::rlimit {
+                    rlim_cur: value as libc::rlim_t,
+                    rlim_max: value as libc::rlim_t,
+                };
+                if libc::setrlimit(resource, &rlim) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            };
+            // 软限制先发送 SIGXCPU，硬限制多给一秒后 SIGKILL
+            if limits.cpu_seconds > 0 {
+                let rlim = libc::rlimit {
+                    rlim_cur: limits.cpu_seconds as libc::rlim_t,
+                    rlim_max: (limits.cpu_seconds + 1) as libc::rlim_t,
+                };
+                if libc::setrlimit(libc::RLIMIT_CPU, &rlim) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            set(
+                libc::RLIMIT_AS,
+                limits.memory_mb.saturating_mul(1024 * 1024),
+            )?;
+            set(libc::RLIMIT_NOFILE, limits.open_files)?;
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_rlimits(_command: &mut Command, _limits: &ResourceLimits) {}
+
+// 超时后结束插件进程，Linux 下连同它启动的子进程一起结束
+fn kill(child: &mut Child) {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+// 进程的资源用量：CPU 时间来自 wait4 返回的 rusage，地址空间和打开文件数是运行中采样到的峰值
+#[derive(Debug, Clone, Copy, Default)]
+struct Usage {
+    cpu: Duration,
+    address_space: u64,
+    open_files: u64,
+}
+
+impl Usage {
+    // 从 /proc 读取进程当前的 VmPeak 和打开的文件数
+    #[cfg(target_os = "linux")]
+    fn sample(&mut self, pid: u32) {
+        if let Ok(status) = std::fs::read_to_string(format!("/proc/{}/status", pid)) {
+            let peak = status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmPeak:"))
+                .and_then(|value| {
+                    value
+                        .trim()
+                        .trim_end_matches("kB")
+                        .trim()
+                        .parse::<u64>()
+                        .ok()
+                });
+            if let Some(peak) = peak {
+                self.address_space = self.address_space.max(peak * 1024);
+            }
+        }
+        if let Ok(entries) = std::fs::read_dir(format!("/proc/{}/fd", pid)) {
+            self.open_files = self.open_files.max(entries.count() as u64);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sample(&mut self, _pid: u32) {}
+}
+
+// 用 wait4 回收子进程并取得 rusage，flags 含 WNOHANG 时进程未结束返回 None
+// 回收后不能再对 child 调用 wait
+#[cfg(target_os = "linux")]
+fn wait4(child: &mut Child, usage: &mut Usage, flags: i32) -> io::Result<Option<ExitStatus>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        let pid =
+            unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, flags, &mut rusage) };
+        match pid {
+            0 => return Ok(None),
+            -1 => {
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+            _ => break,
+        }
+    }
+    let time = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    };
+    usage.cpu = time(rusage.ru_utime) + time(rusage.ru_stime);
+    Ok(Some(ExitStatus::from_raw(status)))
+}
+
+#[cfg(target_os = "linux")]
+fn try_wait(child: &mut Child, usage: &mut Usage) -> io::Result<Option<ExitStatus>> {
+    wait4(child, usage, libc::WNOHANG)
+}
+
+#[cfg(target_os = "linux")]
+fn wait(child: &mut Child, usage: &mut Usage) -> io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = wait4(child, usage, 0)? {
+            return Ok(status);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn try_wait(child: &mut Child, _usage: &mut Usage) -> io::Result<Option<ExitStatus>> {
+    child.try_wait()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn wait(child: &mut Child, _usage: &mut Usage) -> io::Result<ExitStatus> {
+    child.wait()
+}
+
+// 分配失败时地址空间一般已接近上限，达到上限的 90% 即认为超出
+const MEMORY_THRESHOLD_PERCENT: u64 = 90;
+
+// 根据退出状态和资源用量推断是否因为 rlimit 被终止，不依赖插件自己输出的内容
+fn detect_exceeded(
+    status: &ExitStatus,
+    usage: &Usage,
+    limits: &ResourceLimits,
+) -> Option<LimitExceeded> {
+    if status.success() {
+        return None;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        // 到达软限制时收到 SIGXCPU；忽略它的进程在硬限制时被 SIGKILL，
+        // 其它原因的 SIGKILL 只有 CPU 时间确实用满时才算
+        let cpu_exceeded = match status.signal() {
+            Some(libc::SIGXCPU) => true,
+            Some(libc::SIGKILL) => usage.cpu >= Duration::from_secs(limits.cpu_seconds),
+            _ => false,
+        };
+        if limits.cpu_seconds > 0 && cpu_exceeded {
+            return Some(LimitExceeded::CpuTime);
+        }
+    }
+    // 打开的文件数达到上限后再打开会失败（EMFILE）
+    if limits.open_files > 0 && usage.open_files >= limits.open_files {
+        return Some(LimitExceeded::OpenFiles);
+    }
+    let memory = limits.memory_mb.saturating_mul(1024 * 1024);
+    if limits.memory_mb > 0 && usage.address_space >= memory / 100 * MEMORY_THRESHOLD_PERCENT {
+        return Some(LimitExceeded::Memory);
+    }
+    None
+}
+
+// exec 时就因为限制失败（如地址空间不足以加载程序），根据 errno 判断
+fn spawn_exceeded(error: &io::Error) -> Option<LimitExceeded> {
+    #[cfg(target_os = "linux")]
+    match error.raw_os_error() {
+        Some(libc::ENOMEM) => return Some(LimitExceeded::Memory),
+        Some(libc::EMFILE) => return Some(LimitExceeded::OpenFiles),
+        _ => {}
+    }
+    let _ = error;
+    None
+}
+
+// 输出来自哪个管道
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+type OutputHandler = Arc<dyn Fn(Stream, &str) + Send + Sync>;
+
+// 读取管道的全部内容，有 handler 时每读到一行回调一次
+fn read_pipe<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    stream: Stream,
+    handler: Option<OutputHandler>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let pipe = match pipe {
+            Some(pipe) => pipe,
+            None => return buf,
+        };
+        match handler {
+            Some(handler) => {
+                let mut reader = BufReader::new(pipe);
+                loop {
+                    let start = buf.len();
+                    match reader.read_until(b'\n', &mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => handler(stream, &String::from_utf8_lossy(&buf[start..])),
+                    }
+                }
+            }
+            None => {
+                let mut pipe = pipe;
+                let _ = pipe.read_to_end(&mut buf);
+            }
+        }
+        buf
+    })
+}
+
+// 执行命令并等待结束，limits 为 None 时不做任何限制
+pub fn run_limited(command: Command, limits: Option<&ResourceLimits>) -> io::Result<LimitedOutput> {
+    run(command, limits, None, None)
+}
+
+// 执行命令并逐行回调输出，cancel 被置为 true 时结束进程
+pub fn run_streaming<F>(
+    command: Command,
+    limits: Option<&ResourceLimits>,
+    cancel: &AtomicBool,
+    handler: F,
+) -> io::Result<LimitedOutput>
+where
+    F: Fn(Stream, &str) + Send + Sync + 'static,
+{
+    run(command, limits, Some(cancel), Some(Arc::new(handler)))
+}
+
+fn run(
+    mut command: Command,
+    limits: Option<&ResourceLimits>,
+    cancel: Option<&AtomicBool>,
+    handler: Option<OutputHandler>,
+) -> io::Result<LimitedOutput> {
+    if let Some(limits) = limits {
+        apply_rlimits(&mut command, limits);
+        // 放到单独的进程组中，超时时可以一起结束
+        #[cfg(target_os = "linux")]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    }
+    let started = Instant::now();
+    let spawned = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match (spawned, limits) {
+        (Ok(child), _) => child,
+        (Err(e), Some(limits)) => {
+            return Err(match spawn_exceeded(&e) {
+                Some(exceeded) => io::Error::new(e.kind(), limits.message(exceeded)),
+                None => e,
+            })
+        }
+        (Err(e), None) => return Err(e),
+    };
+    let stdout = read_pipe(child.stdout.take(), Stream::Stdout, handler.clone());
+    let stderr = read_pipe(child.stderr.take(), Stream::Stderr, handler);
+
+    let deadline = limits
+        .filter(|limits| limits.timeout_seconds > 0)
+        .map(|limits| Instant::now() + Duration::from_secs(limits.timeout_seconds));
+    let mut timed_out = false;
+    let mut cancelled = false;
+    let mut usage = Usage::default();
+    let status = loop {
+        if limits.is_some() {
+            usage.sample(child.id());
+        }
+        if let Some(status) = try_wait(&mut child, &mut usage)? {
+            break status;
+        }
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::SeqCst)) {
+            kill(&mut child);
+            cancelled = true;
+            break wait(&mut child, &mut usage)?;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            kill(&mut child);
+            timed_out = true;
+            break wait(&mut child, &mut usage)?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+    let exceeded = if timed_out {
+        Some(LimitExceeded::Timeout)
+    } else if cancelled {
+        None
+    } else {
+        limits.and_then(|limits| detect_exceeded(&output.status, &usage, limits))
+    };
+    Ok(LimitedOutput {
+        output,
+        exceeded,
+        cancelled,
+        duration: started.elapsed(),
+    })
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    fn limits() -> ResourceLimits {
+        ResourceLimits {
+            cpu_seconds: 1,
+            memory_mb: 100,
+            open_files: 32,
+            timeout_seconds: 10,
+        }
+    }
+
+    fn signaled(signal: i32) -> ExitStatus {
+        ExitStatus::from_raw(signal)
+    }
+
+    fn exited(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code << 8)
+    }
+
+    fn sh(script: &str) -> LimitedOutput {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        run_limited(command, Some(&limits())).unwrap()
+    }
+
+    #[test]
+    fn reads_limits_from_config() {
+        let limits = ResourceLimits::from_config(&serde_json::json!({
+            "limits": { "cpuSeconds": 5, "openFiles": 0 }
+        }))
+        .unwrap();
+        assert_eq!(limits.cpu_seconds, 5);
+        assert_eq!(limits.open_files, 0);
+        assert_eq!(limits.memory_mb, ResourceLimits::default().memory_mb);
+        assert!(ResourceLimits::from_config(&serde_json::json!({})).is_ok());
+        let error =
+            ResourceLimits::from_config(&serde_json::json!({ "limits": { "cpuSeconds": "5" } }))
+                .unwrap_err();
+        assert!(error.starts_with("invalid limits:"), "{}", error);
+    }
+
+    #[test]
+    fn attributes_cpu_time_by_signal_and_usage() {
+        let limits = limits();
+        let idle = Usage::default();
+        let busy = Usage {
+            cpu: Duration::from_secs(2),
+            ..Usage::default()
+        };
+        let detect = |status, usage| detect_exceeded(&status, usage, &limits);
+        assert_eq!(
+            detect(signaled(libc::SIGXCPU), &idle),
+            Some(LimitExceeded::CpuTime)
+        );
+        assert_eq!(
+            detect(signaled(libc::SIGKILL), &busy),
+            Some(LimitExceeded::CpuTime)
+        );
+        assert_eq!(detect(signaled(libc::SIGKILL), &idle), None);
+        assert_eq!(detect(signaled(libc::SIGSEGV), &busy), None);
+        let unlimited = ResourceLimits {
+            cpu_seconds: 0,
+            ..limits
+        };
+        assert_eq!(
+            detect_exceeded(&signaled(libc::SIGXCPU), &idle, &unlimited),
+            None
+        );
+    }
+
+    #[test]
+    fn attributes_memory_and_open_files_by_usage() {
+        let limits = limits();
+        let files = Usage {
+            open_files: 32,
+            ..Usage::default()
+        };
+        let memory = Usage {
+            address_space: 95 * 1024 * 1024,
+            ..Usage::default()
+        };
+        let small = Usage {
+            open_files: 31,
+            address_space: 50 * 1024 * 1024,
+            ..Usage::default()
+        };
+        assert_eq!(
+            detect_exceeded(&exited(1), &files, &limits),
+            Some(LimitExceeded::OpenFiles)
+        );
+        assert_eq!(
+            detect_exceeded(&exited(1), &memory, &limits),
+            Some(LimitExceeded::Memory)
+        );
+        assert_eq!(detect_exceeded(&exited(1), &small, &limits), None);
+        // 正常退出时不管用量多少都不算超出
+        assert_eq!(detect_exceeded(&exited(0), &files, &limits), None);
+    }
+
+    #[test]
+    fn ignores_error_messages_and_foreign_kills() {
+        let output = sh("echo 'Too many open files: cannot allocate memory' >&2; exit 1");
+        assert_eq!(output.exceeded, None);
+        assert!(output.into_result(Some(&limits())).is_err());
+        assert_eq!(sh("kill -9 $$").exceeded, None);
+    }
+
+    #[test]
+    fn kills_on_cpu_time() {
+        let output = sh("while :; do :; done");
+        assert_eq!(output.exceeded, Some(LimitExceeded::CpuTime));
+        assert_eq!(
+            output.into_result(Some(&limits())).unwrap_err(),
+            "Plugin killed: cpu time limit exceeded (1s)"
+        );
+    }
+
+    #[test]
+    fn times_out() {
+        let limits = ResourceLimits {
+            timeout_seconds: 1,
+            ..limits()
+        };
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let output = run_limited(command, Some(&limits)).unwrap();
+        assert_eq!(output.exceeded, Some(LimitExceeded::Timeout));
+        assert!(output.duration < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use super::{discovery, limits::LimitedOutput};
+
+// 单个日志文件的最大大小，超过后轮转
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+// 每个插件保留的历史日志文件数
+const MAX_LOG_FILES: usize = 3;
+// 单次调用中 stdout/stderr 最多保留的字节数
+const MAX_OUTPUT_SIZE: usize = 64 * 1024;
+// get_plugin_logs 默认返回的条数
+const DEFAULT_LIMIT: usize = 100;
+
+// 同一时间只允许一个线程写日志，避免轮转时互相覆盖
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+// 插件的一次调用记录，每行一条 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginLogEntry {
+    // 毫秒时间戳
+    pub timestamp: i64,
+    pub program: String,
+    pub args: Vec<String>,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    // 因超出资源限制被终止时记录是哪一项
+    pub exceeded: Option<String>,
+    pub duration_ms: u64,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl PluginLogEntry {
+    pub fn new(program: &str, args: &[String], output: &LimitedOutput) -> Self {
+        PluginLogEntry {
+            timestamp: chrono::Local::now().timestamp_millis(),
+            program: program.to_string(),
+            args: args.to_vec(),
+            exit_code: output.output.status.code(),
+            success: output.output.status.success() && output.exceeded.is_none(),
+            exceeded: output.exceeded.map(|exceeded| exceeded.to_string()),
+            duration_ms: output.duration.as_millis() as u64,
+            stdout: truncate(&output.output.stdout),
+            stderr: truncate(&output.output.stderr),
+        }
+    }
+
+    // 没有子进程的调用（如动态库）
+    pub fn from_result(
+        program: &str,
+        args: &[String],
+        result: &Result<String, String>,
+        duration: Duration,
+    ) -> Self {
+        let (stdout, stderr) = match result {
+            Ok(stdout) => (stdout.as_bytes(), &b""[..]),
+            Err(stderr) => (&b""[..], stderr.as_bytes()),
+        };
+        PluginLogEntry {
+            timestamp: chrono::Local::now().timestamp_millis(),
+            program: program.to_string(),
+            args: args.to_vec(),
+            exit_code: None,
+            success: result.is_ok(),
+            exceeded: None,
+            duration_ms: duration.as_millis() as u64,
+            stdout: truncate(stdout),
+            stderr: truncate(stderr),
+        }
+    }
+}
+
+fn truncate(data: &[u8]) -> String {
+    if data.len() <= MAX_OUTPUT_SIZE {
+        return String::from_utf8_lossy(data).to_string();
+    }
+    format!(
+        "{}\n... ({} bytes truncated)",
+        String::from_utf8_lossy(&data[..MAX_OUTPUT_SIZE]),
+        data.len() - MAX_OUTPUT_SIZE
+    )
+}
+
+// 插件日志目录
+fn logs_dir<R: Runtime>(app: &AppHandle<R>) -> io::Result<PathBuf> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(io::Error::other)?
+        .join("plugins");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// 插件的日志文件，index 为 0 时是当前文件，其余为轮转后的文件
+fn log_file(dir: &Path, id: &str, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{}.log", id))
+    } else {
+        dir.join(format!("{}.log.{}", id, index))
+    }
+}
+
+fn rotate(dir: &Path, id: &str) -> io::Result<()> {
+    let current = log_file(dir, id, 0);
+    match fs::metadata(&current) {
+        Ok(metadata) if metadata.len() >= MAX_LOG_SIZE => {}
+        _ => return Ok(()),
+    }
+    let _ = fs::remove_file(log_file(dir, id, MAX_LOG_FILES));
+    for index in (0..MAX_LOG_FILES).rev() {
+        let from = log_file(dir, id, index);
+        if from.exists() {
+            fs::rename(&from, log_file(dir, id, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+// 追加到当前日志文件，文件过大时先轮转
+fn append(dir: &Path, id: &str, entry: &PluginLogEntry) -> io::Result<()> {
+    let _guard = LOG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    rotate(dir, id)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file(dir, id, 0))?;
+    let line = serde_json::to_string(entry)?;
+    writeln!(file, "{}", line)
+}
+
+// 写入一条插件日志
+pub fn record<R: Runtime>(app: &AppHandle<R>, id: &str, entry: &PluginLogEntry) {
+    if !discovery::is_valid_id(id) {
+        return;
+    }
+    if let Err(e) = logs_dir(app).and_then(|dir| append(&dir, id, entry)) {
+        eprintln!("Failed to write log of plugin `{}`: {}", id, e);
+    }
+}
+
+// 读取插件日志，since 为毫秒时间戳，返回按时间排序的最后 limit 条
+#[command]
+pub fn get_plugin_logs(
+    app: AppHandle,
+    id: String,
+    since: Option<i64>,
+    limit: Option<usize>,
+) -> Result<Vec<PluginLogEntry>, String> {
+    if !discovery::is_valid_id(&id) {
+        return Err(format!("invalid plugin id `{}`", id));
+    }
+    let dir = logs_dir(&app).map_err(|e| e.to_string())?;
+    Ok(read(
+        &dir,
+        &id,
+        since.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_LIMIT),
+    ))
+}
+
+// 从最旧的文件开始读，跳过无法解析的行
+fn read(dir: &Path, id: &str, since: i64, limit: usize) -> Vec<PluginLogEntry> {
+    let mut entries: Vec<PluginLogEntry> = Vec::new();
+    for index in (0..=MAX_LOG_FILES).rev() {
+        let file = match fs::File::open(log_file(dir, id, index)) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if let Ok(entry) = serde_json::from_str::<PluginLogEntry>(&line) {
+                if entry.timestamp >= since {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+    let skip = entries.len().saturating_sub(limit);
+    entries.split_off(skip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("toolbox-logs-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn entry(timestamp: i64) -> PluginLogEntry {
+        PluginLogEntry {
+            timestamp,
+            program: "main.py".to_string(),
+            args: Vec::new(),
+            exit_code: Some(0),
+            success: true,
+            exceeded: None,
+            duration_ms: 1,
+            stdout: String::new(),
+            stderr: String::new(),
+        }
+    }
+
+    fn timestamps(entries: &[PluginLogEntry]) -> Vec<i64> {
+        entries.iter().map(|entry| entry.timestamp).collect()
+    }
+
+    #[test]
+    fn rotates_large_files() {
+        let temp = TempDir::new("rotate");
+        let (dir, id) = (temp.0.as_path(), "calc");
+        append(dir, id, &entry(1)).unwrap();
+        // 没有超过大小时继续写入同一个文件
+        append(dir, id, &entry(2)).unwrap();
+        assert!(!log_file(dir, id, 1).exists());
+
+        // 每次把当前文件撑到上限，下一次写入时轮转
+        for timestamp in 3..8 {
+            let file = OpenOptions::new()
+                .append(true)
+                .open(log_file(dir, id, 0))
+                .unwrap();
+            file.set_len(MAX_LOG_SIZE).unwrap();
+            append(dir, id, &entry(timestamp)).unwrap();
+        }
+        assert_eq!(fs::read_dir(dir).unwrap().count(), MAX_LOG_FILES + 1);
+        assert!(!log_file(dir, id, MAX_LOG_FILES + 1).exists());
+        let current = fs::read_to_string(log_file(dir, id, 0)).unwrap();
+        assert_eq!(current.lines().count(), 1);
+        // 最旧的两个文件被删除，填充的内容不是日志，读取时跳过
+        assert_eq!(timestamps(&read(dir, id, 0, 100)), [4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn filters_by_since_and_limit() {
+        let temp = TempDir::new("filter");
+        let (dir, id) = (temp.0.as_path(), "calc");
+        for timestamp in 1..=3 {
+            append(dir, id, &entry(timestamp)).unwrap();
+        }
+        // 轮转后的文件在前
+        fs::rename(log_file(dir, id, 0), log_file(dir, id, 1)).unwrap();
+        for timestamp in 4..=6 {
+            append(dir, id, &entry(timestamp)).unwrap();
+        }
+        fs::write(log_file(dir, id, 2), "not json\n").unwrap();
+
+        assert_eq!(timestamps(&read(dir, id, 0, 100)), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(timestamps(&read(dir, id, 3, 100)), [3, 4, 5, 6]);
+        assert_eq!(timestamps(&read(dir, id, 0, 2)), [5, 6]);
+        assert_eq!(timestamps(&read(dir, id, 2, 3)), [4, 5, 6]);
+        assert!(read(dir, id, 7, 100).is_empty());
+        assert!(read(dir, id, 0, 0).is_empty());
+        assert!(read(dir, "other", 0, 100).is_empty());
+    }
+}
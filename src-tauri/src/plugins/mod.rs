@@ -1,4 +1,12 @@
+pub mod bus;
+pub mod discovery;
+pub mod limits;
+pub mod logs;
+#[allow(clippy::module_inception)]
 pub mod plugins;
 
 // pub use plugins::Tools;
+pub use bus::{bus_publish, bus_subscribe, bus_unsubscribe, EventBus};
+pub use discovery::{get_plugin_dir, get_plugins, get_user_plugins_dir, set_user_plugins_dir};
+pub use logs::get_plugin_logs;
 pub use plugins::{run_node_script, run_php_script, run_python_script};
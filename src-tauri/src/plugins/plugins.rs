@@ -2,54 +2,92 @@ use md5;
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::Path;
-use std::process::{Command, Output};
-use tauri::command;
+use std::process::Command;
+use std::time::Instant;
+use tauri::{command, AppHandle};
+
+use super::bus;
+use super::limits;
+use super::logs::{self, PluginLogEntry};
+
+// 执行插件进程：按插件声明的资源限制执行，并把输出写入插件日志
+// plugin_id 为空时（如启动本机应用）不做限制也不记录日志
+pub fn run_plugin_process(
+    app: &AppHandle,
+    plugin_id: Option<&str>,
+    program: &str,
+    args: Vec<String>,
+) -> Result<String, String> {
+    let limits = limits::for_plugin(app, plugin_id)?;
+    let mut command = Command::new(program);
+    command.args(&args);
+    let started = Instant::now();
+    let output = match limits::run_limited(command, limits.as_ref()) {
+        Ok(output) => output,
+        Err(e) => {
+            let result = Err(format!("Failed to start process: {}", e));
+            if let Some(id) = plugin_id {
+                let entry = PluginLogEntry::from_result(program, &args, &result, started.elapsed());
+                logs::record(app, id, &entry);
+            }
+            return result;
+        }
+    };
+    if let Some(id) = plugin_id {
+        logs::record(app, id, &PluginLogEntry::new(program, &args, &output));
+    }
+    let result = output.into_result(limits.as_ref());
+    // 插件可以在 stdout 中输出发布帧向消息总线发布消息
+    match plugin_id {
+        Some(id) => result.map(|stdout| bus::publish_frames(app, id, &stdout)),
+        None => result,
+    }
+}
 
 #[command]
-pub fn run_node_script(script: String, args: Vec<String>) -> Result<String, String> {
+pub fn run_node_script(
+    app: AppHandle,
+    script: String,
+    args: Vec<String>,
+    plugin_id: Option<String>,
+) -> Result<String, String> {
     let mut full_args = vec![script];
     full_args.extend(args);
 
-    let output: Output = Command::new("node")
-        .args(&full_args)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        Ok(stdout)
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        Err(stderr)
-    }
+    run_plugin_process(&app, plugin_id.as_deref(), "node", full_args)
 }
 
 #[command]
-pub fn run_php_script(script: String, args: Vec<String>) -> Result<String, String> {
-    run_script("php", script, args)
+pub fn run_php_script(
+    app: AppHandle,
+    script: String,
+    args: Vec<String>,
+    plugin_id: Option<String>,
+) -> Result<String, String> {
+    run_script(&app, "php", script, args, plugin_id)
 }
 
 #[command]
-pub fn run_python_script(script: String, args: Vec<String>) -> Result<String, String> {
-    run_script("python", script, args)
+pub fn run_python_script(
+    app: AppHandle,
+    script: String,
+    args: Vec<String>,
+    plugin_id: Option<String>,
+) -> Result<String, String> {
+    run_script(&app, "python", script, args, plugin_id)
 }
 
-fn run_script(command: &str, script: String, args: Vec<String>) -> Result<String, String> {
+fn run_script(
+    app: &AppHandle,
+    command: &str,
+    script: String,
+    args: Vec<String>,
+    plugin_id: Option<String>,
+) -> Result<String, String> {
     let mut full_args = vec!["-c".to_string(), script];
     full_args.extend(args);
     println!("Running script: {} {:?}", command, full_args);
-    let output: Output = Command::new(command)
-        .args(&full_args)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        Ok(stdout)
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        Err(stderr)
-    }
+    run_plugin_process(app, plugin_id.as_deref(), command, full_args)
 }
 
 const HEADER: &[u8; 4] = b"PLUG"; // 自定义文件头
@@ -118,7 +156,7 @@ impl Tools {
         let calculated_checksum = format!("{:x}", hasher);
 
         // 比较计算的校验和与存储的校验和
-        Ok(&calculated_checksum.as_bytes() == &stored_checksum)
+        Ok(calculated_checksum.as_bytes() == stored_checksum)
     }
 
     pub fn install(plugin_path: &str, install_dir: &str) -> io::Result<()> {
@@ -0,0 +1,737 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+use tauri::{command, AppHandle, Manager, Runtime};
+use tauri_plugin_cli::Matches;
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::plugins::discovery::{self, PluginSource};
+use crate::snippets::{self, Snippet};
+use crate::utils::{capability, list_file, store};
+use crate::{apps, clipboard, search, shell};
+
+// 备份文件格式版本，格式不兼容时递增
+const PROFILE_FORMAT: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const STORE_FILE: &str = "store.json";
+const SNIPPETS_FILE: &str = "snippets.json";
+const PLUGINS_DIR: &str = "plugins";
+const PLUGIN_DATA_DIR: &str = "plugin-data";
+// 备份中可以包含的 store 键，其它的与本机相关（如版本号、进程号、插件目录），不需要迁移
+const KNOWN_STORE_KEYS: [&str; 7] = [
+    apps::CONFIG_KEY,
+    clipboard::CONFIG_KEY,
+    search::aliases::CONFIG_KEY,
+    search::bookmarks::CONFIG_KEY,
+    search::content::CONFIG_KEY,
+    search::files::CONFIG_KEY,
+    shell::CONFIG_KEY,
+];
+
+// 导入时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    // 保留本机已有的插件和设置，同名的使用备份中的
+    #[default]
+    Merge,
+    // 删除本机的用户插件和设置，完全使用备份中的
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfilePlugin {
+    pub id: String,
+    pub version: Option<String>,
+}
+
+// 备份文件中的 manifest.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileManifest {
+    pub format: u32,
+    pub app_version: String,
+    pub created_at: String,
+    pub plugins: Vec<ProfilePlugin>,
+    pub store_keys: Vec<String>,
+}
+
+// 插件私有数据的目录
+pub fn plugin_data_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(PLUGIN_DATA_DIR))
+        .map_err(|e| e.to_string())
+}
+
+fn user_plugins_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    discovery::user_plugins_dir(app).ok_or("Failed to resolve user plugins directory".to_string())
+}
+
+// 把目录中的文件写入压缩包的 prefix 下
+fn add_dir<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    src: &Path,
+    prefix: &str,
+) -> Result<(), String> {
+    let options = SimpleFileOptions::default();
+    for entry in WalkDir::new(src).min_depth(1) {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let relative = entry
+            .path()
+            .strip_prefix(src)
+            .map_err(|e| e.to_string())?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .join("/");
+        let name = format!("{}/{}", prefix, relative);
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options)
+                .map_err(|e| e.to_string())?;
+        } else if entry.file_type().is_file() {
+            zip.start_file(name, options).map_err(|e| e.to_string())?;
+            let mut file = File::open(entry.path()).map_err(|e| e.to_string())?;
+            io::copy(&mut file, zip).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// 导出用户插件、插件数据、片段和 store 到一个 zip 文件
+pub fn export_to<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Result<ProfileManifest, String> {
+    let data_dir = plugin_data_dir(app)?;
+    let plugins: Vec<_> = discovery::discover(app)
+        .plugins
+        .into_iter()
+        .filter(|plugin| plugin.source == PluginSource::User)
+        .collect();
+
+    let mut settings = Map::new();
+    for (key, value) in store::open(app)?.entries() {
+        if KNOWN_STORE_KEYS.contains(&key.as_str()) {
+            settings.insert(key, value);
+        }
+    }
+
+    let manifest = ProfileManifest {
+        format: PROFILE_FORMAT,
+        app_version: app.package_info().version.to_string(),
+        created_at: chrono::Local::now().to_rfc3339(),
+        plugins: plugins
+            .iter()
+            .map(|plugin| ProfilePlugin {
+                id: plugin.id.clone(),
+                version: plugin
+                    .config
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string()),
+            })
+            .collect(),
+        store_keys: settings.keys().cloned().collect(),
+    };
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(MANIFEST_FILE, options)
+        .map_err(|e| e.to_string())?;
+    let content = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.write_all(&content).map_err(|e| e.to_string())?;
+
+    zip.start_file(STORE_FILE, options)
+        .map_err(|e| e.to_string())?;
+    let content = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+    zip.write_all(&content).map_err(|e| e.to_string())?;
+
+    let snippets_file = snippets::snippets_file(app)?;
+    if snippets_file.is_file() {
+        zip.start_file(SNIPPETS_FILE, options)
+            .map_err(|e| e.to_string())?;
+        let mut file = File::open(&snippets_file).map_err(|e| e.to_string())?;
+        io::copy(&mut file, &mut zip).map_err(|e| e.to_string())?;
+    }
+
+    for plugin in plugins.iter() {
+        add_dir(
+            &mut zip,
+            &plugin.dir,
+            &format!("{}/{}", PLUGINS_DIR, plugin.id),
+        )?;
+        let plugin_data = data_dir.join(&plugin.id);
+        if plugin_data.is_dir() {
+            add_dir(
+                &mut zip,
+                &plugin_data,
+                &format!("{}/{}", PLUGIN_DATA_DIR, plugin.id),
+            )?;
+        }
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(manifest)
+}
+
+// 备份中的一个文件要解压到的位置
+enum Target {
+    Manifest,
+    Store,
+    Snippets,
+    Plugin(String, PathBuf),
+    PluginData(String, PathBuf),
+}
+
+// 校验压缩包中的路径，拒绝绝对路径和 `..`
+fn classify(name: &Path) -> Result<Target, String> {
+    let parts: Vec<String> = name
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => Ok(part.to_string_lossy().to_string()),
+            _ => Err(format!("Invalid path in profile: {:?}", name)),
+        })
+        .collect::<Result<_, _>>()?;
+    match parts.as_slice() {
+        [file] if file == MANIFEST_FILE => Ok(Target::Manifest),
+        [file] if file == STORE_FILE => Ok(Target::Store),
+        [file] if file == SNIPPETS_FILE => Ok(Target::Snippets),
+        [dir, id, rest @ ..] if dir == PLUGINS_DIR || dir == PLUGIN_DATA_DIR => {
+            if !discovery::is_valid_id(id) {
+                return Err(format!("Invalid plugin id in profile: `{}`", id));
+            }
+            let rest: PathBuf = rest.iter().collect();
+            if dir == PLUGINS_DIR {
+                Ok(Target::Plugin(id.clone(), rest))
+            } else {
+                Ok(Target::PluginData(id.clone(), rest))
+            }
+        }
+        _ => Err(format!("Unexpected file in profile: {:?}", name)),
+    }
+}
+
+fn read_entry<R: Read>(entry: &mut R) -> Result<Vec<u8>, String> {
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+    Ok(content)
+}
+
+// 备份中的内容，插件在 extract 时再解压
+#[derive(Debug)]
+struct Profile {
+    manifest: ProfileManifest,
+    settings: Map<String, Value>,
+    snippets: Option<Vec<Snippet>>,
+}
+
+// 在修改任何文件之前检查整个压缩包
+fn validate(archive: &mut ZipArchive<File>) -> Result<Profile, String> {
+    let mut manifest: Option<ProfileManifest> = None;
+    let mut settings: Option<Map<String, Value>> = None;
+    let mut snippets: Option<Vec<Snippet>> = None;
+    let mut configs: HashMap<String, Value> = HashMap::new();
+    let mut plugin_ids: HashSet<String> = HashSet::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        let name = entry
+            .enclosed_name()
+            .ok_or(format!("Invalid path in profile: {}", entry.name()))?;
+        match classify(&name)? {
+            Target::Manifest => {
+                let content = read_entry(&mut entry)?;
+                manifest = Some(
+                    serde_json::from_slice(&content)
+                        .map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?,
+                );
+            }
+            Target::Store => {
+                let content = read_entry(&mut entry)?;
+                settings = Some(
+                    serde_json::from_slice(&content)
+                        .map_err(|e| format!("Invalid {}: {}", STORE_FILE, e))?,
+                );
+            }
+            Target::Snippets => {
+                let content = read_entry(&mut entry)?;
+                let parsed = list_file::parse_json(&content, snippets::FILE_KEY)
+                    .and_then(|parsed| snippets::merge(Vec::new(), parsed))
+                    .map_err(|e| format!("Invalid {}: {}", SNIPPETS_FILE, e))?;
+                snippets = Some(parsed);
+            }
+            Target::Plugin(id, rest) => {
+                if rest == Path::new("config.json") {
+                    let content = read_entry(&mut entry)?;
+                    let config: Value = serde_json::from_slice(&content)
+                        .map_err(|e| format!("Invalid config.json of plugin `{}`: {}", id, e))?;
+                    configs.insert(id.clone(), config);
+                }
+                plugin_ids.insert(id);
+            }
+            Target::PluginData(_, _) => {}
+        }
+    }
+
+    let manifest = manifest.ok_or(format!("Missing {} in profile", MANIFEST_FILE))?;
+    if manifest.format != PROFILE_FORMAT {
+        return Err(format!(
+            "Unsupported profile format {}, expected {}",
+            manifest.format, PROFILE_FORMAT
+        ));
+    }
+    let settings = settings.ok_or(format!("Missing {} in profile", STORE_FILE))?;
+    let keys: HashSet<&String> = settings.keys().collect();
+    if keys != manifest.store_keys.iter().collect() {
+        return Err("Settings in profile do not match its manifest".to_string());
+    }
+    if let Some(key) = keys
+        .into_iter()
+        .find(|key| !KNOWN_STORE_KEYS.contains(&key.as_str()))
+    {
+        return Err(format!("Unknown setting `{}` in profile", key));
+    }
+
+    let declared: HashSet<String> = manifest.plugins.iter().map(|p| p.id.clone()).collect();
+    if declared != plugin_ids {
+        return Err("Plugins in profile do not match its manifest".to_string());
+    }
+    for id in plugin_ids.iter() {
+        let config = configs
+            .get(id)
+            .ok_or(format!("Plugin `{}` in profile has no config.json", id))?;
+        match config.get("id").and_then(|v| v.as_str()) {
+            Some(config_id) if config_id != id => {
+                return Err(format!(
+                    "Plugin `{}` in profile declares a different id `{}`",
+                    id, config_id
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(Profile {
+        manifest,
+        settings,
+        snippets,
+    })
+}
+
+// 与 dir 同级的目录，保证可以直接 rename 到 dir 下
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+// 把压缩包中的插件和插件数据解压到 plugins 和 data 两个目录下
+fn extract(archive: &mut ZipArchive<File>, plugins: &Path, data: &Path) -> Result<(), String> {
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        let name = match entry.enclosed_name() {
+            Some(name) => name,
+            None => continue,
+        };
+        let dest = match classify(&name)? {
+            Target::Plugin(id, rest) => plugins.join(id).join(rest),
+            Target::PluginData(id, rest) => data.join(id).join(rest),
+            _ => continue,
+        };
+        if entry.is_dir() {
+            fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut out = File::create(&dest).map_err(|e| e.to_string())?;
+        io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// 一次替换：target 原有的内容移到 backup，再把 staged 移到 target，staged 不存在时只删除
+struct Swap {
+    target: PathBuf,
+    staged: PathBuf,
+    backup: PathBuf,
+}
+
+// 先移走所有原有的目录再移入新的，某一步失败时还原已经完成的移动
+fn apply(swaps: &[Swap]) -> Result<(), String> {
+    let mut backed_up = Vec::new();
+    let mut installed = Vec::new();
+    let result = (|| -> io::Result<()> {
+        for swap in swaps.iter().filter(|swap| swap.target.exists()) {
+            fs::rename(&swap.target, &swap.backup)?;
+            backed_up.push(swap);
+        }
+        for swap in swaps.iter().filter(|swap| swap.staged.exists()) {
+            fs::rename(&swap.staged, &swap.target)?;
+            installed.push(swap);
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        for swap in installed {
+            let _ = fs::remove_dir_all(&swap.target);
+        }
+        for swap in backed_up {
+            let _ = fs::rename(&swap.backup, &swap.target);
+        }
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+// 从 zip 文件中恢复用户插件、插件数据、片段和 store
+// 先全部解压到临时目录，成功后再替换已有的目录，失败时不改动本机的插件
+pub fn import_from<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    mode: ImportMode,
+) -> Result<ProfileManifest, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let Profile {
+        manifest,
+        settings,
+        snippets,
+    } = validate(&mut archive)?;
+
+    // 合并后的片段也要在修改文件之前算好，本机的片段文件无效时不导入
+    let snippets = match (snippets, mode) {
+        (Some(imported), ImportMode::Merge) => {
+            Some(snippets::merge(snippets::load(app)?, imported)?)
+        }
+        (None, ImportMode::Merge) => None,
+        (imported, ImportMode::Replace) => Some(imported.unwrap_or_default()),
+    };
+
+    let roots = [user_plugins_dir(app)?, plugin_data_dir(app)?];
+    let staging = roots.clone().map(|root| sibling(&root, ".import"));
+    let backup = roots.clone().map(|root| sibling(&root, ".backup"));
+    for dir in staging.iter().chain(backup.iter()) {
+        if dir.exists() {
+            fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+    }
+    for dir in roots.iter().chain(staging.iter()).chain(backup.iter()) {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let result = extract(&mut archive, &staging[0], &staging[1]).and_then(|_| {
+        let mut swaps = Vec::new();
+        for i in 0..roots.len() {
+            // 备份中的插件替换本机的同名插件和数据，replace 时本机的其它插件也一起删除
+            let mut names: Vec<OsString> = manifest
+                .plugins
+                .iter()
+                .map(|plugin| plugin.id.clone().into())
+                .collect();
+            if mode == ImportMode::Replace {
+                for entry in fs::read_dir(&roots[i])
+                    .map_err(|e| e.to_string())?
+                    .flatten()
+                {
+                    if !names.contains(&entry.file_name()) {
+                        names.push(entry.file_name());
+                    }
+                }
+            }
+            swaps.extend(names.iter().map(|name| Swap {
+                target: roots[i].join(name),
+                staged: staging[i].join(name),
+                backup: backup[i].join(name),
+            }));
+        }
+        apply(&swaps)
+    });
+    for dir in staging.iter().chain(backup.iter()) {
+        let _ = fs::remove_dir_all(dir);
+    }
+    result?;
+
+    let store = store::open(app)?;
+    for key in KNOWN_STORE_KEYS {
+        match settings.get(key) {
+            Some(value) => store.set(key, value.clone()),
+            None if mode == ImportMode::Replace => {
+                store.delete(key);
+            }
+            None => {}
+        }
+    }
+    store.save().map_err(|e| e.to_string())?;
+
+    if let Some(snippets) = snippets {
+        list_file::write(
+            &snippets::snippets_file(app)?,
+            snippets::FILE_KEY,
+            &snippets,
+        )?;
+    }
+
+    // 重新生成并加载插件的权限文件
+    capability::generate(app).map_err(|e| e.to_string())?;
+    capability::add(app);
+    crate::search::invalidate(app);
+
+    Ok(manifest)
+}
+
+// 导入后让已经运行的服务重新读取配置，命令行导入时应用随即退出，不需要
+fn reload<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    apps::reload(app);
+    shell::init(app);
+    search::aliases::init(app);
+    search::bookmarks::init(app);
+    search::files::reload(app);
+    search::content::reload(app);
+    snippets::init(app);
+    clipboard::reload(app)
+}
+
+// 导出配置
+#[command]
+pub fn export_profile(app: AppHandle, path: String) -> Result<ProfileManifest, String> {
+    export_to(&app, Path::new(&path))
+}
+
+// 导入配置，mode 为 `merge` 或 `replace`，默认 `merge`
+#[command]
+pub fn import_profile(
+    app: AppHandle,
+    path: String,
+    mode: Option<ImportMode>,
+) -> Result<ProfileManifest, String> {
+    let manifest = import_from(&app, Path::new(&path), mode.unwrap_or_default())?;
+    reload(&app)?;
+    Ok(manifest)
+}
+
+// 处理命令行中的导入/导出参数，返回 Some 时表示已处理，应用应当退出
+pub fn run_cli<R: Runtime>(app: &AppHandle<R>, matches: &Matches) -> Option<i32> {
+    let value = |name: &str| {
+        matches
+            .args
+            .get(name)
+            .and_then(|arg| arg.value.as_str())
+            .map(|value| value.to_string())
+    };
+    let result = if let Some(path) = value("export-profile") {
+        export_to(app, Path::new(&path))
+    } else if let Some(path) = value("import-profile") {
+        let mode = match value("import-mode").as_deref() {
+            Some("replace") => ImportMode::Replace,
+            _ => ImportMode::Merge,
+        };
+        import_from(app, Path::new(&path), mode)
+    } else {
+        return None;
+    };
+    match result {
+        Ok(manifest) => {
+            println!(
+                "Profile done: {} plugin(s), {} setting(s)",
+                manifest.plugins.len(),
+                manifest.store_keys.len()
+            );
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("Profile failed: {}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "toolbox-profile-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write(&self, path: &str, content: &str) -> PathBuf {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            path
+        }
+
+        fn read(&self, path: &str) -> Option<String> {
+            fs::read_to_string(self.0.join(path)).ok()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // 生成一个备份文件，files 为压缩包中的路径和内容
+    fn profile(temp: &TempDir, store_keys: &[&str], files: &[(&str, &str)]) -> ZipArchive<File> {
+        let path = temp.0.join("profile.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let manifest = ProfileManifest {
+            format: PROFILE_FORMAT,
+            app_version: "1.0.0".to_string(),
+            created_at: String::new(),
+            plugins: vec![ProfilePlugin {
+                id: "calc".to_string(),
+                version: None,
+            }],
+            store_keys: store_keys.iter().map(|key| key.to_string()).collect(),
+        };
+        let manifest = serde_json::to_string(&manifest).unwrap();
+        for (name, content) in [(MANIFEST_FILE, manifest.as_str())].iter().chain(files) {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        ZipArchive::new(File::open(path).unwrap()).unwrap()
+    }
+
+    const PLUGIN: (&str, &str) = ("plugins/calc/config.json", r#"{ "id": "calc" }"#);
+
+    #[test]
+    fn validates_store_keys() {
+        let temp = TempDir::new("store-keys");
+        let store = r#"{ "shell": {}, "apps": {} }"#;
+        let mut archive = profile(&temp, &["shell", "apps"], &[PLUGIN, (STORE_FILE, store)]);
+        let parsed = validate(&mut archive).unwrap();
+        assert_eq!(parsed.manifest.plugins[0].id, "calc");
+        assert_eq!(parsed.settings.len(), 2);
+        assert!(parsed.snippets.is_none());
+
+        // 插件目录与本机相关，不能导入
+        let store = r#"{ "shell": {}, "plugins_dir": "" }"#;
+        let mut archive = profile(
+            &temp,
+            &["shell", "plugins_dir"],
+            &[PLUGIN, (STORE_FILE, store)],
+        );
+        assert_eq!(
+            validate(&mut archive).unwrap_err(),
+            "Unknown setting `plugins_dir` in profile"
+        );
+
+        let store = r#"{ "shell": {}, "version": "0.1.0" }"#;
+        let mut archive = profile(&temp, &["shell", "version"], &[PLUGIN, (STORE_FILE, store)]);
+        assert_eq!(
+            validate(&mut archive).unwrap_err(),
+            "Unknown setting `version` in profile"
+        );
+
+        let store = r#"{ "shell": {}, "apps": {} }"#;
+        let mut archive = profile(&temp, &["shell"], &[PLUGIN, (STORE_FILE, store)]);
+        assert_eq!(
+            validate(&mut archive).unwrap_err(),
+            "Settings in profile do not match its manifest"
+        );
+    }
+
+    #[test]
+    fn validates_snippets() {
+        let temp = TempDir::new("snippets");
+        let snippets = r#"{ "snippets": [{ "keyword": "sig", "body": "Thanks" }] }"#;
+        let files = [PLUGIN, (STORE_FILE, "{}"), (SNIPPETS_FILE, snippets)];
+        let mut archive = profile(&temp, &[], &files);
+        let snippets = validate(&mut archive).unwrap().snippets.unwrap();
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].keyword, "sig");
+        assert!(!snippets[0].id.is_empty());
+
+        let snippets = r#"{ "snippets": [{ "keyword": "", "body": "Thanks" }] }"#;
+        let files = [PLUGIN, (STORE_FILE, "{}"), (SNIPPETS_FILE, snippets)];
+        let mut archive = profile(&temp, &[], &files);
+        assert!(validate(&mut archive).is_err());
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        let temp = TempDir::new("paths");
+        let files = [PLUGIN, (STORE_FILE, "{}"), ("plugins/1calc/index.js", "")];
+        let mut archive = profile(&temp, &[], &files);
+        assert!(validate(&mut archive).is_err());
+        let files = [PLUGIN, (STORE_FILE, "{}"), ("other/file", "")];
+        let mut archive = profile(&temp, &[], &files);
+        assert!(validate(&mut archive).is_err());
+    }
+
+    #[test]
+    fn swaps_directories() {
+        let temp = TempDir::new("swap");
+        temp.write("root/calc/index.js", "old");
+        temp.write("root/notes/index.js", "old");
+        temp.write("staging/calc/index.js", "new");
+        fs::create_dir_all(temp.0.join("backup")).unwrap();
+        let swap = |name: &str| Swap {
+            target: temp.0.join("root").join(name),
+            staged: temp.0.join("staging").join(name),
+            backup: temp.0.join("backup").join(name),
+        };
+        apply(&[swap("calc"), swap("notes")]).unwrap();
+        assert_eq!(temp.read("root/calc/index.js").as_deref(), Some("new"));
+        // 备份中没有的插件被删除
+        assert!(!temp.0.join("root/notes").exists());
+    }
+
+    #[test]
+    fn restores_directories_on_failure() {
+        let temp = TempDir::new("restore");
+        temp.write("root/calc/index.js", "old calc");
+        temp.write("root/notes/index.js", "old notes");
+        temp.write("staging/calc/index.js", "new");
+        fs::create_dir_all(temp.0.join("backup")).unwrap();
+        let swaps = [
+            Swap {
+                target: temp.0.join("root/calc"),
+                staged: temp.0.join("staging/calc"),
+                backup: temp.0.join("backup/calc"),
+            },
+            // 备份目录不存在，移动失败
+            Swap {
+                target: temp.0.join("root/notes"),
+                staged: temp.0.join("staging/notes"),
+                backup: temp.0.join("missing/notes"),
+            },
+        ];
+        assert!(apply(&swaps).is_err());
+        assert_eq!(temp.read("root/calc/index.js").as_deref(), Some("old calc"));
+        assert_eq!(
+            temp.read("root/notes/index.js").as_deref(),
+            Some("old notes")
+        );
+        assert_eq!(temp.read("staging/calc/index.js").as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn names_siblings() {
+        assert_eq!(
+            sibling(Path::new("/home/user/.vtools/plugins"), ".import"),
+            Path::new("/home/user/.vtools/plugins.import")
+        );
+    }
+}
@@ -0,0 +1,810 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, RwLock},
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use super::index::{ResultKind, SearchResult};
+use super::{query, SearchEngine};
+use crate::apps;
+use crate::clipboard::{self, ClipboardService};
+use crate::plugins::discovery::{self, PluginSource};
+
+mod plugin;
+
+pub use plugin::PluginAction;
+
+// 动作需要用户输入的值的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputKind {
+    Text,
+    Directory,
+    File,
+}
+
+// 执行动作前需要用户输入的值，执行时按 name 传入
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionInput {
+    pub name: String,
+    pub label: String,
+    pub kind: InputKind,
+}
+
+// 搜索结果上可执行的一个动作
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Action {
+    pub id: String,
+    pub label: String,
+    pub inputs: Vec<ActionInput>,
+    // 不为空时需要用户确认后才执行
+    pub confirm: Option<String>,
+    // 为 false 时只显示为按钮，点击结果时不执行
+    pub clickable: bool,
+}
+
+impl Action {
+    pub fn new(id: &str, label: &str) -> Self {
+        Action {
+            id: id.to_string(),
+            label: label.to_string(),
+            inputs: Vec::new(),
+            confirm: None,
+            clickable: true,
+        }
+    }
+
+    pub fn input(mut self, name: &str, label: &str, kind: InputKind) -> Self {
+        self.inputs.push(ActionInput {
+            name: name.to_string(),
+            label: label.to_string(),
+            kind,
+        });
+        self
+    }
+
+    pub fn confirm(mut self, message: Option<String>) -> Self {
+        self.confirm = message;
+        self
+    }
+
+    pub fn button(mut self) -> Self {
+        self.clickable = false;
+        self
+    }
+}
+
+// 动作执行完后前端要做的事
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ActionOutcome {
+    // 保持搜索窗口不变
+    Done,
+    // 隐藏搜索窗口并清空搜索
+    Hide,
+    // 重新搜索，用于删除、固定等修改了结果的动作
+    Refresh,
+    // 打开模块插件，由前端创建插件窗口
+    OpenPlugin { config: Value },
+    // 命令已在后台执行，result 为显示输出的结果，输出通过 shell-output 事件发送
+    Shell { result: SearchResult },
+    // 插件动作的输出，显示在结果列表的最前面
+    PluginOutput { result: SearchResult },
+}
+
+// 最近一次搜索的结果和插件声明的动作
+#[derive(Default)]
+pub struct ActionService {
+    results: Mutex<HashMap<String, SearchResult>>,
+    // 第一次使用时从插件目录读取
+    plugin_actions: RwLock<Option<Vec<PluginAction>>>,
+}
+
+impl ActionService {
+    fn plugin_actions<R: Runtime>(&self, app: &AppHandle<R>) -> Vec<PluginAction> {
+        if let Some(actions) = self.plugin_actions.read().unwrap().as_ref() {
+            return actions.clone();
+        }
+        let actions = PluginAction::load(app);
+        *self.plugin_actions.write().unwrap() = Some(actions.clone());
+        actions
+    }
+
+    // 给结果加上可执行的动作，并记住这些结果供 execute_action 使用
+    pub fn attach<R: Runtime>(&self, app: &AppHandle<R>, results: &mut [SearchResult]) {
+        let plugin_actions = self.plugin_actions(app);
+        for result in results.iter_mut() {
+            result.labels = labels_for(result);
+            result.actions = actions_for(result, &plugin_actions);
+        }
+        let mut remembered = self.results.lock().unwrap();
+        remembered.clear();
+        remembered.extend(
+            results
+                .iter()
+                .map(|result| (result.id.clone(), result.clone())),
+        );
+    }
+
+    // 插件的输出不是搜索出来的，单独加入
+    fn remember(&self, result: &SearchResult) {
+        self.results
+            .lock()
+            .unwrap()
+            .insert(result.id.clone(), result.clone());
+    }
+
+    fn get(&self, id: &str) -> Result<SearchResult, String> {
+        self.results
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or(format!("Result `{}` not found", id))
+    }
+
+    // 插件目录变化后重新读取
+    pub fn invalidate(&self) {
+        *self.plugin_actions.write().unwrap() = None;
+    }
+}
+
+fn text<'a>(result: &'a SearchResult, key: &str) -> Result<&'a str, String> {
+    result.data[key]
+        .as_str()
+        .ok_or(format!("Result `{}` has no {}", result.id, key))
+}
+
+// 文件所在的目录，目录则为自身
+fn dir_of(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_dir() {
+        return path.to_path_buf();
+    }
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+// 文件类结果共有的动作
+fn path_actions(open_with: Action) -> Vec<Action> {
+    vec![
+        Action::new("open", "打开"),
+        open_with,
+        Action::new("reveal", "打开所在目录"),
+        Action::new("copy_path", "复制路径"),
+        Action::new("open_terminal", "在终端中打开"),
+    ]
+}
+
+// 各类型结果内置的动作
+fn builtin(result: &SearchResult) -> Vec<Action> {
+    let data = &result.data;
+    let choose_program =
+        || Action::new("open_with", "选择程序打开").input("program", "程序", InputKind::File);
+    match result.kind {
+        ResultKind::Calculator => vec![Action::new("copy_value", "复制结果")],
+        ResultKind::Snippet => {
+            let mut copy = Action::new("copy_value", "复制");
+            for name in data["inputs"].as_array().into_iter().flatten() {
+                if let Some(name) = name.as_str() {
+                    copy = copy.input(name, name, InputKind::Text);
+                }
+            }
+            vec![copy]
+        }
+        ResultKind::InternalPlugin => vec![Action::new("open", "打开")],
+        ResultKind::Plugin => {
+            let mut actions = vec![Action::new("open", "打开")];
+            if data["source"] == json!(PluginSource::User) {
+                actions.push(
+                    Action::new("uninstall", "卸载")
+                        .confirm(Some(format!("确定卸载插件 {}？", result.title))),
+                );
+            }
+            actions
+        }
+        ResultKind::App => {
+            let mut actions = vec![if data.get("window").is_some() {
+                Action::new("open", "切换到窗口")
+            } else {
+                Action::new("open", "打开")
+            }];
+            if data["path"].is_string() {
+                actions.push(Action::new("run_args", "带参数运行").input(
+                    "args",
+                    "参数",
+                    InputKind::Text,
+                ));
+                actions.push(Action::new("reveal", "打开所在目录"));
+                actions.push(Action::new("copy_path", "复制路径"));
+            }
+            actions
+        }
+        ResultKind::Window => vec![Action::new("open", "切换到窗口")],
+        ResultKind::Recent => path_actions(match data["app"].as_str() {
+            Some(app) => Action::new("open_with", &format!("用 {} 打开", app)),
+            None => choose_program(),
+        }),
+        ResultKind::Bookmark => vec![
+            Action::new("open", "打开网址"),
+            Action::new("copy_value", "复制网址"),
+        ],
+        ResultKind::File | ResultKind::Content => path_actions(choose_program()),
+        ResultKind::Clipboard => vec![
+            Action::new("paste", "粘贴"),
+            Action::new("copy_value", "复制"),
+            if data["pinned"].as_bool().unwrap_or(false) {
+                Action::new("unpin", "取消固定")
+            } else {
+                Action::new("pin", "固定")
+            },
+            Action::new("delete", "删除"),
+        ],
+        ResultKind::Command => {
+            let confirm = data["dangerous"]
+                .as_str()
+                .map(|pattern| format!("命令匹配确认规则 {}，确定执行？", pattern));
+            vec![
+                Action::new("run", "执行").confirm(confirm.clone()),
+                Action::new("open_terminal", "在终端中执行").confirm(confirm.clone()),
+                Action::new("run_in_dir", "选择目录执行")
+                    .input("dir", "工作目录", InputKind::Directory)
+                    .confirm(confirm),
+            ]
+        }
+        ResultKind::PluginOutput => vec![Action::new("copy_value", "复制结果")],
+        ResultKind::ShellOutput => vec![Action::new("cancel", "停止").button()],
+    }
+}
+
+// 结果的状态，由前端显示在标题后面
+fn labels_for(result: &SearchResult) -> Vec<String> {
+    let data = &result.data;
+    match result.kind {
+        ResultKind::Clipboard if data["pinned"].as_bool().unwrap_or(false) => {
+            vec!["已固定".to_string()]
+        }
+        ResultKind::App if data.get("window").is_some() => vec!["运行中".to_string()],
+        ResultKind::Command => data["dangerous"]
+            .as_str()
+            .map(|pattern| format!("需要确认：匹配规则 {}", pattern))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn actions_for(result: &SearchResult, plugin_actions: &[PluginAction]) -> Vec<Action> {
+    let mut actions = builtin(result);
+    actions.extend(
+        plugin_actions
+            .iter()
+            .filter(|action| action.applies(result.kind))
+            .map(PluginAction::to_action),
+    );
+    actions
+}
+
+// 启动应用，不等待它退出
+fn spawn(program: &str, args: &[String]) -> Result<(), String> {
+    Command::new(program)
+        .args(args)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start {}: {}", program, e))
+}
+
+// 删除用户安装的插件
+fn uninstall<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<(), String> {
+    let discovery = discovery::discover(app);
+    let plugin = discovery
+        .get(id)
+        .ok_or(format!("Plugin `{}` not found", id))?;
+    if plugin.source != PluginSource::User {
+        return Err(format!("Plugin `{}` is not installed by user", id));
+    }
+    fs::remove_dir_all(&plugin.dir).map_err(|e| e.to_string())?;
+    super::invalidate(app);
+    Ok(())
+}
+
+async fn execute(
+    app: &AppHandle,
+    result: &SearchResult,
+    action_id: &str,
+    inputs: &HashMap<String, String>,
+    confirmed: bool,
+) -> Result<ActionOutcome, String> {
+    let input = |name: &str| inputs.get(name).map(|value| value.trim().to_string());
+    match (result.kind, action_id) {
+        (ResultKind::Plugin | ResultKind::InternalPlugin, "open") => {
+            Ok(ActionOutcome::OpenPlugin {
+                config: result.data.clone(),
+            })
+        }
+        (ResultKind::Plugin, "uninstall") => {
+            uninstall(app, text(result, "id")?)?;
+            Ok(ActionOutcome::Refresh)
+        }
+        (ResultKind::App, "open") => {
+            // 已经在运行的应用切换到它的窗口
+            match result.data["window"].as_u64() {
+                Some(window) => super::switcher::activate_window(window as u32)?,
+                None => apps::launch_app(app.clone(), result.id.clone(), None, None).await?,
+            }
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::App, "run_args") => {
+            // 和 shell 一样分词，带空格的参数可以加引号
+            let (tokens, error) = query::tokenize(&input("args").unwrap_or_default());
+            if let Some(error) = error {
+                return Err(error.message);
+            }
+            let args: Vec<String> = tokens.into_iter().map(|token| token.text).collect();
+            apps::launch_app(app.clone(), result.id.clone(), None, Some(args)).await?;
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::Window, "open") => {
+            let id = result.data["id"]
+                .as_u64()
+                .ok_or(format!("Result `{}` has no id", result.id))?;
+            super::switcher::activate_window(id as u32)?;
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::Recent, "open") => {
+            let path = text(result, "path")?.to_string();
+            super::recent::open_recent(app.state(), path, None)?;
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::File | ResultKind::Content, "open") => {
+            super::files::open_path(text(result, "path")?.to_string())?;
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::Bookmark, "open") => {
+            super::bookmarks::open_url(app.clone(), text(result, "url")?.to_string())?;
+            Ok(ActionOutcome::Hide)
+        }
+        (_, "open_with") => {
+            let path = text(result, "path")?.to_string();
+            match input("program").filter(|program| !program.is_empty()) {
+                Some(program) => spawn(&program, &[path])?,
+                None => super::recent::open_recent(app.state(), path, Some(true))?,
+            }
+            Ok(ActionOutcome::Hide)
+        }
+        (_, "reveal") => {
+            super::files::reveal_path(text(result, "path")?.to_string())?;
+            Ok(ActionOutcome::Hide)
+        }
+        (_, "copy_path") => {
+            app.state::<ClipboardService>()
+                .set_text(text(result, "path")?)?;
+            Ok(ActionOutcome::Done)
+        }
+        (ResultKind::Calculator, "copy_value") => {
+            app.state::<ClipboardService>()
+                .set_text(text(result, "value")?)?;
+            super::calc::add_calc_history(
+                app.clone(),
+                text(result, "expression")?.to_string(),
+                text(result, "display")?.to_string(),
+            )
+            .await?;
+            Ok(ActionOutcome::Done)
+        }
+        (ResultKind::Snippet, "copy_value") => {
+            crate::snippets::copy_snippet(
+                app.clone(),
+                app.state(),
+                app.state(),
+                text(result, "id")?.to_string(),
+                Some(inputs.clone()),
+            )?;
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::Bookmark, "copy_value") => {
+            app.state::<ClipboardService>()
+                .set_text(text(result, "url")?)?;
+            Ok(ActionOutcome::Done)
+        }
+        (ResultKind::PluginOutput, "copy_value") => {
+            app.state::<ClipboardService>()
+                .set_text(text(result, "output")?)?;
+            Ok(ActionOutcome::Done)
+        }
+        (ResultKind::Clipboard, "paste") => {
+            // 先隐藏搜索窗口，让焦点回到原来的应用
+            if let Some(window) = app.get_webview_window("search") {
+                window.hide().map_err(|e| e.to_string())?;
+            }
+            let id = text(result, "id")?.to_string();
+            clipboard::paste_clipboard_entry(app.clone(), app.state(), id).await?;
+            Ok(ActionOutcome::Hide)
+        }
+        (ResultKind::Clipboard, "copy_value") => {
+            clipboard::copy_clipboard_entry(
+                app.clone(),
+                app.state(),
+                text(result, "id")?.to_string(),
+            )?;
+            Ok(ActionOutcome::Refresh)
+        }
+        (ResultKind::Clipboard, "pin" | "unpin") => {
+            let id = text(result, "id")?.to_string();
+            clipboard::pin_clipboard_entry(app.clone(), app.state(), id, action_id == "pin")?;
+            Ok(ActionOutcome::Refresh)
+        }
+        (ResultKind::Clipboard, "delete") => {
+            clipboard::delete_clipboard_entry(
+                app.clone(),
+                app.state(),
+                text(result, "id")?.to_string(),
+            )?;
+            Ok(ActionOutcome::Refresh)
+        }
+        (ResultKind::Command, "run" | "run_in_dir") => {
+            let command = text(result, "command")?.to_string();
+            let cwd = match action_id {
+                "run_in_dir" => input("dir"),
+                _ => result.data["cwd"].as_str().map(str::to_string),
+            };
+            let id = crate::shell::run_shell_command(
+                app.clone(),
+                app.state(),
+                command.clone(),
+                cwd,
+                Some(confirmed),
+            )?;
+            let result = shell_result(&app.state::<ActionService>(), id, &command);
+            Ok(ActionOutcome::Shell { result })
+        }
+        (ResultKind::ShellOutput, "cancel") => {
+            let id = result.data["runId"]
+                .as_u64()
+                .ok_or(format!("Result `{}` has no runId", result.id))?;
+            crate::shell::cancel_shell_command(app.state(), id);
+            Ok(ActionOutcome::Done)
+        }
+        (ResultKind::Command, "open_terminal") => {
+            crate::shell::open_in_terminal(
+                app.clone(),
+                app.state(),
+                text(result, "command")?.to_string(),
+                result.data["cwd"].as_str().map(str::to_string),
+                Some(confirmed),
+            )?;
+            Ok(ActionOutcome::Hide)
+        }
+        (_, "open_terminal") => {
+            crate::shell::open_terminal_at(app, &dir_of(text(result, "path")?))?;
+            Ok(ActionOutcome::Hide)
+        }
+        _ => Err(format!(
+            "Action `{}` is not supported for result `{}`",
+            action_id, result.id
+        )),
+    }
+}
+
+// 启动类的动作记录到启动历史，用于结果排序
+fn records_launch(kind: ResultKind) -> bool {
+    matches!(
+        kind,
+        ResultKind::Snippet
+            | ResultKind::InternalPlugin
+            | ResultKind::Plugin
+            | ResultKind::App
+            | ResultKind::Recent
+            | ResultKind::Bookmark
+            | ResultKind::File
+            | ResultKind::Content
+    )
+}
+
+// 查找结果上的动作，需要确认但没有确认或缺少输入时不执行
+fn find_action<'a>(
+    result: &'a SearchResult,
+    action_id: &str,
+    inputs: &HashMap<String, String>,
+    confirmed: bool,
+) -> Result<&'a Action, String> {
+    let action = result
+        .actions
+        .iter()
+        .find(|action| action.id == action_id)
+        .ok_or(format!(
+            "Action `{}` not found for result `{}`",
+            action_id, result.id
+        ))?;
+    if action.confirm.is_some() && !confirmed {
+        return Err(format!("Action `{}` requires confirmation", action_id));
+    }
+    for input in action.inputs.iter() {
+        if !inputs.contains_key(&input.name) {
+            return Err(format!("Missing input `{}`", input.name));
+        }
+    }
+    Ok(action)
+}
+
+// 执行搜索结果上的动作，result_id 为最近一次搜索返回的结果
+// inputs 为动作声明的输入，需要确认的动作要传 confirmed
+#[command]
+pub async fn execute_action(
+    app: AppHandle,
+    actions: State<'_, ActionService>,
+    result_id: String,
+    action_id: String,
+    inputs: Option<HashMap<String, String>>,
+    confirmed: Option<bool>,
+    query: Option<String>,
+) -> Result<ActionOutcome, String> {
+    let result = actions.get(&result_id)?;
+    let confirmed = confirmed.unwrap_or(false);
+    let inputs = inputs.unwrap_or_default();
+    let action = find_action(&result, &action_id, &inputs, confirmed)?;
+
+    let plugin_action = actions
+        .plugin_actions(&app)
+        .into_iter()
+        .find(|plugin_action| plugin_action.action_id() == action_id);
+    let outcome = match plugin_action {
+        Some(plugin_action) => {
+            let (handle, target) = (app.clone(), result.clone());
+            let plugin_id = plugin_action.plugin_id.clone();
+            let output =
+                tauri::async_runtime::spawn_blocking(move || plugin_action.run(&handle, &target))
+                    .await
+                    .map_err(|e| e.to_string())??;
+            if output.trim().is_empty() {
+                ActionOutcome::Done
+            } else {
+                let result = output_result(&app, &actions, &plugin_id, &action.label, &output);
+                ActionOutcome::PluginOutput { result }
+            }
+        }
+        None => execute(&app, &result, &action_id, &inputs, confirmed).await?,
+    };
+
+    // 窗口、命令等每次都不同，不记录
+    if records_launch(result.kind) && result.actions.first().map(|a| &a.id) == Some(&action_id) {
+        app.state::<SearchEngine>().with_history(&app, |history| {
+            history.record(&result.id, query.as_deref());
+            history.save(&app)
+        })?;
+    }
+    Ok(outcome)
+}
+
+// 插件的输出作为一个结果，可以复制或交给其它插件的动作处理
+fn output_result(
+    app: &AppHandle,
+    actions: &ActionService,
+    plugin_id: &str,
+    title: &str,
+    output: &str,
+) -> SearchResult {
+    let output = output.trim_end();
+    let mut result = SearchResult {
+        id: format!("output:{}", plugin_id),
+        kind: ResultKind::PluginOutput,
+        title: title.to_string(),
+        subtitle: Some(output.to_string()),
+        icon: None,
+        score: 0.0,
+        highlights: Vec::new(),
+        data: json!({ "pluginId": plugin_id, "output": output }),
+        labels: Vec::new(),
+        actions: Vec::new(),
+    };
+    result.actions = actions_for(&result, &actions.plugin_actions(app));
+    actions.remember(&result);
+    result
+}
+
+// 正在执行的命令，输出由前端根据 shell-output 事件追加到 subtitle
+fn shell_result(actions: &ActionService, id: u64, command: &str) -> SearchResult {
+    let mut result = SearchResult {
+        id: format!("shell-output:{}", id),
+        kind: ResultKind::ShellOutput,
+        title: command.to_string(),
+        subtitle: None,
+        icon: None,
+        score: 0.0,
+        highlights: Vec::new(),
+        data: json!({ "runId": id, "command": command }),
+        labels: Vec::new(),
+        actions: Vec::new(),
+    };
+    result.actions = builtin(&result);
+    actions.remember(&result);
+    result
+}
+
+// 二进制/脚本插件执行后调用，把输出转为带动作的结果
+#[command]
+pub fn plugin_output(
+    app: AppHandle,
+    actions: State<'_, ActionService>,
+    plugin_id: String,
+    name: String,
+    output: String,
+) -> SearchResult {
+    output_result(&app, &actions, &plugin_id, &name, &output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(kind: ResultKind, data: Value) -> SearchResult {
+        SearchResult {
+            id: "result".to_string(),
+            kind,
+            title: "Notes".to_string(),
+            subtitle: None,
+            icon: None,
+            score: 0.0,
+            highlights: Vec::new(),
+            data,
+            labels: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    fn ids(actions: &[Action]) -> Vec<&str> {
+        actions.iter().map(|action| action.id.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_builtin_actions() {
+        let path_ids = ["open", "open_with", "reveal", "copy_path", "open_terminal"];
+        let cases = [
+            (ResultKind::Calculator, json!({}), vec!["copy_value"]),
+            (ResultKind::Snippet, json!({}), vec!["copy_value"]),
+            (ResultKind::InternalPlugin, json!({}), vec!["open"]),
+            (
+                ResultKind::Plugin,
+                json!({ "source": "builtin" }),
+                vec!["open"],
+            ),
+            (
+                ResultKind::Plugin,
+                json!({ "source": "user" }),
+                vec!["open", "uninstall"],
+            ),
+            (ResultKind::App, json!({}), vec!["open"]),
+            (
+                ResultKind::App,
+                json!({ "path": "/usr/bin/notes" }),
+                vec!["open", "run_args", "reveal", "copy_path"],
+            ),
+            (ResultKind::Window, json!({}), vec!["open"]),
+            (ResultKind::Recent, json!({}), path_ids.to_vec()),
+            (ResultKind::File, json!({}), path_ids.to_vec()),
+            (ResultKind::Content, json!({}), path_ids.to_vec()),
+            (ResultKind::Bookmark, json!({}), vec!["open", "copy_value"]),
+            (
+                ResultKind::Clipboard,
+                json!({}),
+                vec!["paste", "copy_value", "pin", "delete"],
+            ),
+            (
+                ResultKind::Clipboard,
+                json!({ "pinned": true }),
+                vec!["paste", "copy_value", "unpin", "delete"],
+            ),
+            (
+                ResultKind::Command,
+                json!({}),
+                vec!["run", "open_terminal", "run_in_dir"],
+            ),
+            (ResultKind::PluginOutput, json!({}), vec!["copy_value"]),
+            (ResultKind::ShellOutput, json!({}), vec!["cancel"]),
+        ];
+        for (kind, data, expected) in cases {
+            assert_eq!(
+                ids(&builtin(&result(kind, data.clone()))),
+                expected,
+                "{:?} {}",
+                kind,
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn describes_builtin_actions() {
+        let snippet = builtin(&result(
+            ResultKind::Snippet,
+            json!({ "inputs": ["Name", "Date"] }),
+        ));
+        let names: Vec<&str> = snippet[0]
+            .inputs
+            .iter()
+            .map(|input| input.name.as_str())
+            .collect();
+        assert_eq!(names, ["Name", "Date"]);
+
+        let running = builtin(&result(ResultKind::App, json!({ "window": 1 })));
+        assert_eq!(running[0].label, "切换到窗口");
+        let recent = builtin(&result(ResultKind::Recent, json!({ "app": "Editor" })));
+        assert_eq!(recent[1].label, "用 Editor 打开");
+        assert!(recent[1].inputs.is_empty());
+        let file = builtin(&result(ResultKind::File, json!({})));
+        assert_eq!(file[1].inputs[0].kind, InputKind::File);
+
+        let plugin = builtin(&result(ResultKind::Plugin, json!({ "source": "user" })));
+        assert_eq!(plugin[1].confirm.as_deref(), Some("确定卸载插件 Notes？"));
+        let command = builtin(&result(ResultKind::Command, json!({ "dangerous": "rm *" })));
+        assert!(command.iter().all(|action| action.confirm.is_some()));
+        let command = builtin(&result(ResultKind::Command, json!({})));
+        assert!(command.iter().all(|action| action.confirm.is_none()));
+        assert_eq!(command[2].inputs[0].kind, InputKind::Directory);
+
+        let output = builtin(&result(ResultKind::ShellOutput, json!({})));
+        assert!(!output[0].clickable);
+    }
+
+    #[test]
+    fn labels_results() {
+        let labels = |kind, data| labels_for(&result(kind, data));
+        assert_eq!(
+            labels(ResultKind::Clipboard, json!({ "pinned": true })),
+            ["已固定"]
+        );
+        assert!(labels(ResultKind::Clipboard, json!({ "pinned": false })).is_empty());
+        assert_eq!(labels(ResultKind::App, json!({ "window": 1 })), ["运行中"]);
+        assert!(labels(ResultKind::App, json!({})).is_empty());
+        assert_eq!(
+            labels(ResultKind::Command, json!({ "dangerous": "rm *" })),
+            ["需要确认：匹配规则 rm *"]
+        );
+        assert!(labels(ResultKind::Command, json!({})).is_empty());
+        assert!(labels(ResultKind::File, json!({ "pinned": true })).is_empty());
+    }
+
+    #[test]
+    fn appends_plugin_actions() {
+        let plugin_actions = [
+            PluginAction::with_kinds(&["file"]),
+            PluginAction::with_kinds(&["bookmark"]),
+        ];
+        let file = actions_for(&result(ResultKind::File, json!({})), &plugin_actions);
+        assert_eq!(file.len(), 6);
+        assert_eq!(file[5].id, "plugin:upload:send");
+        let window = actions_for(&result(ResultKind::Window, json!({})), &plugin_actions);
+        assert_eq!(ids(&window), ["open"]);
+    }
+
+    #[test]
+    fn rejects_unconfirmed_actions_and_missing_inputs() {
+        let mut command = result(ResultKind::Command, json!({ "dangerous": "rm *" }));
+        command.actions = builtin(&command);
+        let no_inputs = HashMap::new();
+        assert_eq!(
+            find_action(&command, "run", &no_inputs, false).unwrap_err(),
+            "Action `run` requires confirmation"
+        );
+        assert_eq!(
+            find_action(&command, "run", &no_inputs, true).unwrap().id,
+            "run"
+        );
+        assert_eq!(
+            find_action(&command, "run_in_dir", &no_inputs, true).unwrap_err(),
+            "Missing input `dir`"
+        );
+        let inputs = HashMap::from([("dir".to_string(), "/tmp".to_string())]);
+        assert!(find_action(&command, "run_in_dir", &inputs, true).is_ok());
+        assert_eq!(
+            find_action(&command, "paste", &inputs, true).unwrap_err(),
+            "Action `paste` not found for result `result`"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Runtime};
+
+use super::Action;
+use crate::plugins::discovery;
+use crate::plugins::plugins::run_plugin_process;
+use crate::search::index::{ResultKind, SearchResult};
+
+// 插件在 config.json 的 `actions` 中声明的动作，如
+// `{ "id": "upload", "label": "上传", "kinds": ["file"], "exec": "upload.sh", "args": ["{path}"] }`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Declared {
+    id: String,
+    label: String,
+    // 适用的结果类型，为空时适用于所有结果
+    #[serde(default)]
+    kinds: Vec<String>,
+    // 插件目录下的程序或 PATH 中的命令
+    exec: String,
+    // 可以使用 {path}、{url}、{value}、{title} 占位符
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+// 占位符的值
+fn placeholder<'a>(name: &str, result: &'a SearchResult) -> Option<&'a str> {
+    let data = &result.data;
+    match name {
+        "path" | "url" => Some(data[name].as_str().unwrap_or_default()),
+        "value" => Some(
+            ["output", "value", "url", "path", "command", "text"]
+                .iter()
+                .find_map(|key| data[*key].as_str())
+                .unwrap_or(&result.title),
+        ),
+        "title" => Some(&result.title),
+        _ => None,
+    }
+}
+
+// 一次替换参数中的所有占位符，替换进来的值中的 `{...}` 不会再被替换，未知的占位符原样保留
+fn expand(arg: &str, result: &SearchResult) -> String {
+    let mut expanded = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest
+            .find('}')
+            .and_then(|end| Some((end, placeholder(&rest[1..end], result)?)));
+        match value {
+            Some((end, value)) => {
+                expanded.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginAction {
+    pub plugin_id: String,
+    dir: PathBuf,
+    declared: Declared,
+}
+
+impl PluginAction {
+    // 读取所有插件声明的动作，格式错误的动作被忽略
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Vec<PluginAction> {
+        let mut actions = Vec::new();
+        for plugin in discovery::discover(app).plugins {
+            let declared = match plugin.config.get("actions") {
+                Some(declared) => declared.clone(),
+                None => continue,
+            };
+            match serde_json::from_value::<Vec<Declared>>(declared) {
+                Ok(declared) => actions.extend(declared.into_iter().map(|declared| PluginAction {
+                    plugin_id: plugin.id.clone(),
+                    dir: plugin.dir.clone(),
+                    declared,
+                })),
+                Err(e) => eprintln!("Invalid actions in plugin {}: {}", plugin.id, e),
+            }
+        }
+        actions
+    }
+
+    // 与内置动作区分，格式为 `plugin:<插件 id>:<动作 id>`
+    pub fn action_id(&self) -> String {
+        format!("plugin:{}:{}", self.plugin_id, self.declared.id)
+    }
+
+    pub fn applies(&self, kind: ResultKind) -> bool {
+        let name = serde_json::to_value(kind).ok();
+        self.declared.kinds.is_empty()
+            || self
+                .declared
+                .kinds
+                .iter()
+                .any(|k| name.as_ref().and_then(|name| name.as_str()) == Some(k))
+    }
+
+    pub fn to_action(&self) -> Action {
+        Action::new(&self.action_id(), &self.declared.label)
+    }
+
+    // 按插件的资源限制执行，返回 stdout
+    pub fn run(&self, app: &AppHandle, result: &SearchResult) -> Result<String, String> {
+        let args = self
+            .declared
+            .args
+            .iter()
+            .map(|arg| expand(arg, result))
+            .collect();
+        let local = self.dir.join(&self.declared.exec);
+        let program = if local.is_file() {
+            local.to_string_lossy().to_string()
+        } else {
+            self.declared.exec.clone()
+        };
+        run_plugin_process(app, Some(&self.plugin_id), &program, args)
+    }
+}
+
+// 测试用的动作，id 为 `plugin:upload:send`
+#[cfg(test)]
+impl PluginAction {
+    pub(super) fn with_kinds(kinds: &[&str]) -> Self {
+        PluginAction {
+            plugin_id: "upload".to_string(),
+            dir: PathBuf::new(),
+            declared: Declared {
+                id: "send".to_string(),
+                label: "上传".to_string(),
+                kinds: kinds.iter().map(|kind| kind.to_string()).collect(),
+                exec: "upload.sh".to_string(),
+                args: Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(kind: ResultKind, data: serde_json::Value) -> SearchResult {
+        SearchResult {
+            id: "result".to_string(),
+            kind,
+            title: "Report".to_string(),
+            subtitle: None,
+            icon: None,
+            score: 0.0,
+            highlights: Vec::new(),
+            data,
+            labels: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn applies_to_declared_kinds() {
+        let file = PluginAction::with_kinds(&["file", "recent"]);
+        assert!(file.applies(ResultKind::File));
+        assert!(file.applies(ResultKind::Recent));
+        assert!(!file.applies(ResultKind::Bookmark));
+        assert!(!PluginAction::with_kinds(&["File"]).applies(ResultKind::File));
+        assert!(PluginAction::with_kinds(&[]).applies(ResultKind::Clipboard));
+        assert_eq!(file.action_id(), "plugin:upload:send");
+        assert_eq!(file.to_action().label, "上传");
+    }
+
+    #[test]
+    fn expands_placeholders_once() {
+        let file = result(ResultKind::File, json!({ "path": "/tmp/{title}" }));
+        assert_eq!(expand("--file={path}", &file), "--file=/tmp/{title}");
+        assert_eq!(expand("{value}:{title}", &file), "/tmp/{title}:Report");
+        assert_eq!(expand("{url}", &file), "");
+        assert_eq!(expand("{other} {title", &file), "{other} {title");
+        assert_eq!(expand("{{title}}", &file), "{Report}");
+
+        let output = result(ResultKind::PluginOutput, json!({ "output": "42" }));
+        assert_eq!(expand("{value}", &output), "42");
+        let window = result(ResultKind::Window, json!({}));
+        assert_eq!(expand("{value}", &window), "Report");
+    }
+}
@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use std::{path::Path, sync::RwLock};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use super::index::{SearchIndex, SearchResult};
+use crate::plugins::discovery;
+use crate::utils::{list_file, store};
+
+// 别名在 store 中的键
+pub const CONFIG_KEY: &str = "aliases";
+// 输入与别名完全相同时加的分数，排在计算结果和片段之前
+const ALIAS_SCORE: f64 = 8000.0;
+
+// 别名指向的应用或插件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AliasTarget {
+    App {
+        path: String,
+    },
+    // 二进制/脚本插件执行时 args 放在输入的参数之前
+    Plugin {
+        id: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl AliasTarget {
+    // 对应的搜索结果 id
+    fn result_id(&self) -> String {
+        match self {
+            AliasTarget::App { path } => format!("app:{}", path),
+            AliasTarget::Plugin { id, .. } => format!("plugin:{}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alias {
+    #[serde(default)]
+    pub id: String,
+    // 在搜索框中输入的关键词，不区分大小写
+    pub keyword: String,
+    pub target: AliasTarget,
+}
+
+// 导入导出时文件中的键，TOML 中为 `[[aliases]]` 数组
+const FILE_KEY: &str = "aliases";
+
+#[derive(Default)]
+pub struct AliasStore {
+    aliases: RwLock<Vec<Alias>>,
+}
+
+impl AliasStore {
+    pub fn list(&self) -> Vec<Alias> {
+        self.aliases.read().unwrap().clone()
+    }
+
+    fn save<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let value = serde_json::to_value(self.list()).map_err(|e| e.to_string())?;
+        store::set(app, CONFIG_KEY, Some(value))
+    }
+
+    fn upsert(&self, alias: Alias) -> Result<Alias, String> {
+        upsert(&mut self.aliases.write().unwrap(), alias)
+    }
+
+    // 输入与别名完全相同时把指向的结果排到最前面，结果中没有时从索引中取出
+    pub fn boost(&self, query: &str, results: &mut Vec<SearchResult>, index: &SearchIndex) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        let aliases = self.aliases.read().unwrap();
+        for alias in aliases
+            .iter()
+            .filter(|alias| alias.keyword.eq_ignore_ascii_case(query))
+        {
+            let id = alias.target.result_id();
+            match results.iter_mut().find(|result| result.id == id) {
+                Some(result) => result.score += ALIAS_SCORE,
+                None => {
+                    if let Some(mut result) = index.get(&id) {
+                        result.score = ALIAS_SCORE;
+                        results.push(result);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 新增或按 id 更新，关键词不能重复
+fn upsert(aliases: &mut Vec<Alias>, mut alias: Alias) -> Result<Alias, String> {
+    alias.keyword = alias.keyword.trim().to_string();
+    if alias.keyword.is_empty() {
+        return Err("Alias keyword is required".to_string());
+    }
+    if alias.keyword.contains(char::is_whitespace) {
+        return Err("Alias keyword must not contain spaces".to_string());
+    }
+    match &alias.target {
+        AliasTarget::App { path } if path.trim().is_empty() => {
+            return Err("Alias app path is required".to_string())
+        }
+        AliasTarget::Plugin { id, .. } if !discovery::is_valid_id(id) => {
+            return Err(format!("Invalid plugin id `{}`", id))
+        }
+        _ => {}
+    }
+    if alias.id.is_empty() {
+        alias.id = uuid::Uuid::new_v4().to_string();
+    }
+    if aliases
+        .iter()
+        .any(|a| a.id != alias.id && a.keyword.eq_ignore_ascii_case(&alias.keyword))
+    {
+        return Err(format!("Alias `{}` is already used", alias.keyword));
+    }
+    match aliases.iter_mut().find(|a| a.id == alias.id) {
+        Some(existing) => *existing = alias.clone(),
+        None => aliases.push(alias.clone()),
+    }
+    Ok(alias)
+}
+
+// 启动时从 store 读取
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let aliases = store::get(app, CONFIG_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    *app.state::<AliasStore>().aliases.write().unwrap() = aliases;
+}
+
+#[command]
+pub fn get_aliases(aliases: State<'_, AliasStore>) -> Vec<Alias> {
+    aliases.list()
+}
+
+#[command]
+pub fn save_alias(
+    app: AppHandle,
+    aliases: State<'_, AliasStore>,
+    alias: Alias,
+) -> Result<Alias, String> {
+    let alias = aliases.upsert(alias)?;
+    aliases.save(&app)?;
+    Ok(alias)
+}
+
+#[command]
+pub fn delete_alias(
+    app: AppHandle,
+    aliases: State<'_, AliasStore>,
+    id: String,
+) -> Result<(), String> {
+    aliases
+        .aliases
+        .write()
+        .unwrap()
+        .retain(|alias| alias.id != id);
+    aliases.save(&app)
+}
+
+// 把导入的别名合并到 current 中，有一个无效时全部不导入
+fn merge(mut current: Vec<Alias>, imported: Vec<Alias>) -> Result<Vec<Alias>, String> {
+    for mut alias in imported {
+        alias.id = current
+            .iter()
+            .find(|a| a.keyword.eq_ignore_ascii_case(alias.keyword.trim()))
+            .map(|a| a.id.clone())
+            .unwrap_or_default();
+        upsert(&mut current, alias)?;
+    }
+    Ok(current)
+}
+
+// 从 JSON 或 TOML 文件导入，关键词相同的别名会被覆盖，返回导入的数量
+#[command]
+pub fn import_aliases(
+    app: AppHandle,
+    aliases: State<'_, AliasStore>,
+    path: String,
+) -> Result<usize, String> {
+    let imported: Vec<Alias> = list_file::read(Path::new(&path), FILE_KEY)?;
+    let count = imported.len();
+    {
+        let mut current = aliases.aliases.write().unwrap();
+        *current = merge(current.clone(), imported)?;
+    }
+    aliases.save(&app)?;
+    Ok(count)
+}
+
+// 按扩展名导出为 JSON 或 TOML
+#[command]
+pub fn export_aliases(aliases: State<'_, AliasStore>, path: String) -> Result<(), String> {
+    list_file::write(Path::new(&path), FILE_KEY, &aliases.list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(keyword: &str, path: &str) -> Alias {
+        Alias {
+            id: String::new(),
+            keyword: keyword.to_string(),
+            target: AliasTarget::App {
+                path: path.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn merges_imported_aliases() {
+        let mut current = Vec::new();
+        let ff = upsert(&mut current, alias("ff", "/usr/bin/firefox")).unwrap();
+        let merged = merge(
+            current,
+            vec![
+                alias(" FF ", "/usr/bin/librewolf"),
+                alias("t", "/usr/bin/kitty"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].id, ff.id);
+        assert_eq!(merged[0].keyword, "FF");
+        assert_eq!(
+            merged[0].target,
+            AliasTarget::App {
+                path: "/usr/bin/librewolf".to_string()
+            }
+        );
+        assert!(!merged[1].id.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_imports() {
+        let imported = vec![
+            alias("ff", "/usr/bin/firefox"),
+            alias("bad keyword", "/bin/sh"),
+        ];
+        assert_eq!(
+            merge(Vec::new(), imported).unwrap_err(),
+            "Alias keyword must not contain spaces"
+        );
+        let plugin = Alias {
+            target: AliasTarget::Plugin {
+                id: "1calc".to_string(),
+                args: Vec::new(),
+            },
+            ..alias("calc", "")
+        };
+        assert!(merge(Vec::new(), vec![plugin]).is_err());
+        assert!(merge(Vec::new(), vec![alias("empty", " ")]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use rusqlite::Connection;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{Bookmark, Profile, ProfileKind};
+
+// 各 Chromium 系浏览器的用户数据目录，相对于配置目录
+#[cfg(target_os = "linux")]
+const BROWSERS: [(&str, &str); 7] = [
+    ("Chrome", "google-chrome"),
+    ("Chrome Beta", "google-chrome-beta"),
+    ("Chromium", "chromium"),
+    ("Brave", "BraveSoftware/Brave-Browser"),
+    ("Edge", "microsoft-edge"),
+    ("Vivaldi", "vivaldi"),
+    ("Opera", "opera"),
+];
+
+#[cfg(target_os = "macos")]
+const BROWSERS: [(&str, &str); 7] = [
+    ("Chrome", "Google/Chrome"),
+    ("Chrome Beta", "Google/Chrome Beta"),
+    ("Chromium", "Chromium"),
+    ("Brave", "BraveSoftware/Brave-Browser"),
+    ("Edge", "Microsoft Edge"),
+    ("Vivaldi", "Vivaldi"),
+    ("Opera", "com.operasoftware.Opera"),
+];
+
+#[cfg(target_os = "windows")]
+const BROWSERS: [(&str, &str); 7] = [
+    ("Chrome", "Google\\Chrome\\User Data"),
+    ("Chrome Beta", "Google\\Chrome Beta\\User Data"),
+    ("Chromium", "Chromium\\User Data"),
+    ("Brave", "BraveSoftware\\Brave-Browser\\User Data"),
+    ("Edge", "Microsoft\\Edge\\User Data"),
+    ("Vivaldi", "Vivaldi\\User Data"),
+    ("Opera", "Opera Software\\Opera Stable"),
+];
+
+pub const BOOKMARKS_FILE: &str = "Bookmarks";
+pub const HISTORY_FILE: &str = "History";
+
+// 配置目录：Linux 为 ~/.config，macOS 为 ~/Library/Application Support，Windows 为 %LOCALAPPDATA%
+fn config_dir(home: &Path) -> PathBuf {
+    #[cfg(target_os = "linux")]
+    {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or(home.join(".config"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        home.join("Library").join("Application Support")
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("LOCALAPPDATA")
+            .map(PathBuf::from)
+            .unwrap_or(home.join("AppData").join("Local"))
+    }
+}
+
+// 用户数据目录下的 `Default`、`Profile 1` 等，包含书签或历史文件的才算
+pub fn profiles(home: &Path) -> Vec<Profile> {
+    let base = config_dir(home);
+    let mut profiles = Vec::new();
+    for (browser, dir) in BROWSERS {
+        let root = base.join(dir);
+        let entries = match fs::read_dir(&root) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !dir.join(BOOKMARKS_FILE).exists() && !dir.join(HISTORY_FILE).exists() {
+                continue;
+            }
+            profiles.push(Profile {
+                browser: browser.to_string(),
+                name: entry.file_name().to_string_lossy().to_string(),
+                dir,
+                kind: ProfileKind::Chromium,
+            });
+        }
+    }
+    profiles
+}
+
+// 读取 `Bookmarks` JSON 文件
+pub fn bookmarks(profile: &Profile) -> Result<Vec<Bookmark>, String> {
+    let path = profile.dir.join(BOOKMARKS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let json: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let mut result = Vec::new();
+    if let Some(roots) = json.get("roots").and_then(|roots| roots.as_object()) {
+        for root in roots.values() {
+            collect(profile, root, None, &mut result);
+        }
+    }
+    Ok(result)
+}
+
+// 递归遍历书签树，folder 为所在文件夹的路径
+fn collect(profile: &Profile, node: &Value, folder: Option<&str>, result: &mut Vec<Bookmark>) {
+    let name = node
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    match node.get("type").and_then(|v| v.as_str()) {
+        Some("url") => {
+            if let Some(url) = node.get("url").and_then(|v| v.as_str()) {
+                result.push(Bookmark::new(
+                    profile,
+                    name,
+                    url,
+                    folder.map(str::to_string),
+                ));
+            }
+        }
+        Some("folder") => {
+            let path = match folder {
+                Some(folder) => format!("{}/{}", folder, name),
+                None => name.to_string(),
+            };
+            if let Some(children) = node.get("children").and_then(|v| v.as_array()) {
+                for child in children {
+                    collect(profile, child, Some(&path), result);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// 读取 `History` 数据库中访问最多的网址
+pub fn history(
+    profile: &Profile,
+    conn: &Connection,
+    limit: usize,
+) -> Result<Vec<Bookmark>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT url, title, visit_count FROM urls
+             WHERE hidden = 0 AND visit_count > 0
+             ORDER BY visit_count DESC, last_visit_time DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .flatten()
+        .map(|(url, title, visits)| {
+            let mut item = Bookmark::new(profile, &title.unwrap_or_default(), &url, None);
+            item.history = true;
+            item.visits = visits.max(0) as u32;
+            item
+        })
+        .collect())
+}
@@ -0,0 +1,137 @@
+use rusqlite::Connection;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{Bookmark, Profile, ProfileKind};
+
+pub const PLACES_FILE: &str = "places.sqlite";
+
+// Firefox 的配置目录，包括 Snap 和 Flatpak 安装的版本
+fn roots(home: &Path) -> Vec<(&'static str, PathBuf)> {
+    #[cfg(target_os = "linux")]
+    {
+        vec![
+            ("Firefox", home.join(".mozilla/firefox")),
+            ("Firefox", home.join("snap/firefox/common/.mozilla/firefox")),
+            (
+                "Firefox",
+                home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"),
+            ),
+            ("LibreWolf", home.join(".librewolf")),
+        ]
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let support = home.join("Library").join("Application Support");
+        vec![
+            ("Firefox", support.join("Firefox").join("Profiles")),
+            ("LibreWolf", support.join("librewolf").join("Profiles")),
+        ]
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let appdata = std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .unwrap_or(home.join("AppData").join("Roaming"));
+        vec![
+            (
+                "Firefox",
+                appdata.join("Mozilla").join("Firefox").join("Profiles"),
+            ),
+            ("LibreWolf", appdata.join("librewolf").join("Profiles")),
+        ]
+    }
+}
+
+// 配置目录下包含 places.sqlite 的子目录，如 `xxxxxxxx.default-release`
+pub fn profiles(home: &Path) -> Vec<Profile> {
+    let mut profiles = Vec::new();
+    for (browser, root) in roots(home) {
+        let entries = match fs::read_dir(&root) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !dir.join(PLACES_FILE).exists() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            // 目录名为 `随机前缀.名称`
+            let name = name
+                .split_once('.')
+                .map(|(_, name)| name.to_string())
+                .unwrap_or(name);
+            profiles.push(Profile {
+                browser: browser.to_string(),
+                name,
+                dir,
+                kind: ProfileKind::Firefox,
+            });
+        }
+    }
+    profiles
+}
+
+// 书签及其所在文件夹，跳过 `place:` 开头的智能书签
+pub fn bookmarks(profile: &Profile, conn: &Connection) -> Result<Vec<Bookmark>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT b.title, p.url, f.title FROM moz_bookmarks b
+             JOIN moz_places p ON b.fk = p.id
+             LEFT JOIN moz_bookmarks f ON b.parent = f.id
+             WHERE b.type = 1 AND p.url NOT LIKE 'place:%'",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .flatten()
+        .map(|(title, url, folder)| {
+            let folder = folder.filter(|folder| !folder.is_empty());
+            Bookmark::new(profile, &title.unwrap_or_default(), &url, folder)
+        })
+        .collect())
+}
+
+// 按 frecency 取常用的历史记录
+pub fn history(
+    profile: &Profile,
+    conn: &Connection,
+    limit: usize,
+) -> Result<Vec<Bookmark>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT url, title, visit_count FROM moz_places
+             WHERE hidden = 0 AND visit_count > 0 AND url NOT LIKE 'place:%'
+             ORDER BY frecency DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .flatten()
+        .map(|(url, title, visits)| {
+            let mut item = Bookmark::new(profile, &title.unwrap_or_default(), &url, None);
+            item.history = true;
+            item.visits = visits.max(0) as u32;
+            item
+        })
+        .collect())
+}
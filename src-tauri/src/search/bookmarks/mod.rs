@@ -0,0 +1,362 @@
+use notify::RecommendedWatcher;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+use tauri_plugin_shell::ShellExt;
+
+use super::index::{ResultKind, SearchIndex, SearchItem, SearchResult};
+use super::watcher;
+use crate::utils::store;
+
+mod chromium;
+mod firefox;
+
+// 配置在 store 中的键
+pub const CONFIG_KEY: &str = "bookmarks";
+// 数据库快照目录，位于 app_cache_dir 中
+const SNAPSHOT_DIR: &str = "bookmarks";
+// 浏览器使用时文件变化频繁，两次重新读取的最小间隔
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+// 历史记录的分数打折，排在书签后面
+const HISTORY_WEIGHT: f64 = 0.7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BookmarkConfig {
+    pub enabled: bool,
+    // 是否同时搜索浏览历史
+    pub include_history: bool,
+    // 每个配置文件最多读取的历史记录数
+    pub history_limit: usize,
+}
+
+impl Default for BookmarkConfig {
+    fn default() -> Self {
+        BookmarkConfig {
+            enabled: true,
+            include_history: false,
+            history_limit: 1000,
+        }
+    }
+}
+
+impl BookmarkConfig {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        store::get(app, CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProfileKind {
+    Chromium,
+    Firefox,
+}
+
+// 浏览器的一个用户配置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub browser: String,
+    pub name: String,
+    pub dir: PathBuf,
+    pub kind: ProfileKind,
+}
+
+// 一条书签或历史记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    pub folder: Option<String>,
+    pub browser: String,
+    pub profile: String,
+    pub history: bool,
+    pub visits: u32,
+}
+
+impl Bookmark {
+    fn new(profile: &Profile, title: &str, url: &str, folder: Option<String>) -> Self {
+        Bookmark {
+            title: title.trim().to_string(),
+            url: url.to_string(),
+            folder,
+            browser: profile.browser.clone(),
+            profile: profile.name.clone(),
+            history: false,
+            visits: 0,
+        }
+    }
+
+    fn to_item(&self) -> SearchItem {
+        let title = if self.title.is_empty() {
+            self.url.clone()
+        } else {
+            self.title.clone()
+        };
+        // 去掉协议头，便于按域名搜索
+        let address = self
+            .url
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(&self.url);
+        SearchItem::new(
+            format!("bookmark:{}", self.url),
+            ResultKind::Bookmark,
+            title,
+            json!(self),
+        )
+        .subtitle(Some(self.url.clone()))
+        .keyword(address, 0.6)
+        .keyword(self.folder.as_deref().unwrap_or_default(), 0.4)
+    }
+}
+
+// 复制一份数据库再以只读方式打开，避免与正在运行的浏览器争用锁
+// WAL 文件一起复制，否则会缺少最近的修改
+fn snapshot(db: &Path, dir: &Path) -> Result<Connection, String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let name = format!("{:x}.sqlite", md5::compute(db.to_string_lossy().as_bytes()));
+    let copy = dir.join(name);
+    fs::copy(db, &copy).map_err(|e| e.to_string())?;
+    for suffix in ["-wal", "-shm"] {
+        let source = PathBuf::from(format!("{}{}", db.to_string_lossy(), suffix));
+        let target = PathBuf::from(format!("{}{}", copy.to_string_lossy(), suffix));
+        if suffix == "-wal" && source.exists() {
+            fs::copy(&source, &target).map_err(|e| e.to_string())?;
+        } else {
+            let _ = fs::remove_file(&target);
+        }
+    }
+    Connection::open_with_flags(
+        &copy,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| e.to_string())
+}
+
+// 读取一个配置文件的书签和历史
+fn read_profile(
+    profile: &Profile,
+    config: &BookmarkConfig,
+    snapshot_dir: &Path,
+) -> Result<Vec<Bookmark>, String> {
+    let limit = config.history_limit;
+    match profile.kind {
+        ProfileKind::Chromium => {
+            let mut items = chromium::bookmarks(profile)?;
+            let history = profile.dir.join(chromium::HISTORY_FILE);
+            if config.include_history && history.exists() {
+                let conn = snapshot(&history, snapshot_dir)?;
+                items.extend(chromium::history(profile, &conn, limit)?);
+            }
+            Ok(items)
+        }
+        ProfileKind::Firefox => {
+            let conn = snapshot(&profile.dir.join(firefox::PLACES_FILE), snapshot_dir)?;
+            let mut items = firefox::bookmarks(profile, &conn)?;
+            if config.include_history {
+                items.extend(firefox::history(profile, &conn, limit)?);
+            }
+            Ok(items)
+        }
+    }
+}
+
+pub fn profiles<R: Runtime>(app: &AppHandle<R>) -> Vec<Profile> {
+    let home = match app.path().home_dir() {
+        Ok(home) => home,
+        Err(_) => return Vec::new(),
+    };
+    let mut profiles = chromium::profiles(&home);
+    profiles.extend(firefox::profiles(&home));
+    profiles
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkStatus {
+    pub enabled: bool,
+    pub profiles: Vec<Profile>,
+    pub bookmarks: usize,
+    pub history: usize,
+}
+
+// 浏览器书签服务
+#[derive(Default)]
+pub struct BookmarkSearch {
+    index: RwLock<SearchIndex>,
+    config: RwLock<BookmarkConfig>,
+    status: RwLock<BookmarkStatus>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    // 每次重新监听加一，旧的监听回调发现代数变化后退出
+    generation: AtomicU64,
+}
+
+impl BookmarkSearch {
+    pub fn status(&self) -> BookmarkStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        if !self.config.read().unwrap().enabled {
+            return Vec::new();
+        }
+        let mut results = self.index.read().unwrap().search(query, limit);
+        for result in results.iter_mut() {
+            if result.data["history"].as_bool() == Some(true) {
+                result.score *= HISTORY_WEIGHT;
+            }
+        }
+        results
+    }
+
+    // 重新读取所有配置文件，同一网址只保留一条，书签优先
+    fn reload<R: Runtime>(&self, app: &AppHandle<R>, profiles: &[Profile]) {
+        let config = self.config.read().unwrap().clone();
+        let snapshot_dir = match app.path().app_cache_dir() {
+            Ok(dir) => dir.join(SNAPSHOT_DIR),
+            Err(_) => return,
+        };
+        let mut all: Vec<Bookmark> = Vec::new();
+        for profile in profiles {
+            match read_profile(profile, &config, &snapshot_dir) {
+                Ok(items) => all.extend(items),
+                Err(e) => eprintln!(
+                    "Failed to read {} profile {}: {}",
+                    profile.browser, profile.name, e
+                ),
+            }
+        }
+        all.sort_by_key(|item| item.history);
+        let mut seen: HashSet<String> = HashSet::new();
+        all.retain(|item| seen.insert(item.url.clone()));
+        let history = all.iter().filter(|item| item.history).count();
+        let items: Vec<SearchItem> = all.iter().map(Bookmark::to_item).collect();
+        *self.index.write().unwrap() = SearchIndex::new(items);
+        *self.status.write().unwrap() = BookmarkStatus {
+            enabled: config.enabled,
+            profiles: profiles.to_vec(),
+            bookmarks: all.len() - history,
+            history,
+        };
+    }
+}
+
+// 启动时调用：在后台读取书签并监听浏览器配置目录
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    *app.state::<BookmarkSearch>().config.write().unwrap() = BookmarkConfig::load(app);
+    let app = app.clone();
+    thread::spawn(move || refresh(&app));
+}
+
+// 重新发现配置文件、读取书签并重新监听
+fn refresh<R: Runtime>(app: &AppHandle<R>) {
+    let bookmarks = app.state::<BookmarkSearch>();
+    let generation = bookmarks.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    *bookmarks.watcher.lock().unwrap() = None;
+    if !bookmarks.config.read().unwrap().enabled {
+        *bookmarks.index.write().unwrap() = SearchIndex::default();
+        *bookmarks.status.write().unwrap() = BookmarkStatus::default();
+        return;
+    }
+    let profiles = profiles(app);
+    bookmarks.reload(app, &profiles);
+    if let Err(e) = watch(app, profiles, generation) {
+        eprintln!("Failed to watch browser profiles: {}", e);
+    }
+}
+
+// 书签或历史文件变化时重新读取，浏览器频繁写入时合并处理
+fn watch<R: Runtime>(
+    app: &AppHandle<R>,
+    profiles: Vec<Profile>,
+    generation: u64,
+) -> Result<(), String> {
+    let dirs: Vec<PathBuf> = profiles.iter().map(|profile| profile.dir.clone()).collect();
+    let files = [
+        chromium::BOOKMARKS_FILE,
+        chromium::HISTORY_FILE,
+        firefox::PLACES_FILE,
+        "places.sqlite-wal",
+    ];
+    let watcher = {
+        let app = app.clone();
+        let mut dirty = false;
+        let mut last_reload = Instant::now();
+        watcher::watch(&dirs, move |changes| {
+            let bookmarks = app.state::<BookmarkSearch>();
+            if bookmarks.generation.load(Ordering::SeqCst) != generation {
+                return false;
+            }
+            dirty |= changes.rescan
+                || changes.paths.iter().any(|path| {
+                    path.file_name()
+                        .is_some_and(|name| files.iter().any(|file| name == *file))
+                });
+            if dirty && last_reload.elapsed() >= RELOAD_INTERVAL {
+                bookmarks.reload(&app, &profiles);
+                last_reload = Instant::now();
+                dirty = false;
+            }
+            true
+        })?
+    };
+    *app.state::<BookmarkSearch>().watcher.lock().unwrap() = Some(watcher);
+    Ok(())
+}
+
+#[command]
+pub fn get_bookmark_status(bookmarks: State<'_, BookmarkSearch>) -> BookmarkStatus {
+    bookmarks.status()
+}
+
+#[command]
+pub fn get_bookmark_config(bookmarks: State<'_, BookmarkSearch>) -> BookmarkConfig {
+    bookmarks.config.read().unwrap().clone()
+}
+
+// 保存配置并重新读取
+#[command]
+pub async fn set_bookmark_config(
+    app: AppHandle,
+    bookmarks: State<'_, BookmarkSearch>,
+    config: BookmarkConfig,
+) -> Result<BookmarkStatus, String> {
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store::set(&app, CONFIG_KEY, Some(value))?;
+    *bookmarks.config.write().unwrap() = config;
+    refresh(&app);
+    Ok(bookmarks.status())
+}
+
+#[command]
+pub async fn refresh_bookmarks(
+    app: AppHandle,
+    bookmarks: State<'_, BookmarkSearch>,
+) -> Result<BookmarkStatus, String> {
+    refresh(&app);
+    Ok(bookmarks.status())
+}
+
+// 用默认浏览器打开网址
+#[command]
+pub fn open_url(app: AppHandle, url: String) -> Result<(), String> {
+    #[allow(deprecated)]
+    app.shell().open(url, None).map_err(|e| e.to_string())
+}
@@ -0,0 +1,739 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Num, Signed, ToPrimitive};
+
+use super::number::Number;
+use super::units::{self, Unit};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // 第二项为进制
+    Number(String, u32),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 16] = [
+    "**", "<<", ">>", "+", "-", "*", "/", "^", "(", ")", ",", "!", "%", "&", "|", "~",
+];
+
+// 转换目标前的关键字
+const CONVERT_KEYWORDS: [&str; 4] = ["to", "in", "as", "="];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            // 十六、八、二进制
+            let radix = match (c, chars.get(i + 1)) {
+                ('0', Some('x' | 'X')) => 16,
+                ('0', Some('o' | 'O')) => 8,
+                ('0', Some('b' | 'B')) => 2,
+                _ => 10,
+            };
+            if radix != 10 {
+                let start = i + 2;
+                let mut end = start;
+                while end < chars.len() && (chars[end].is_digit(radix) || chars[end] == '_') {
+                    end += 1;
+                }
+                if end == start {
+                    return Err("Invalid number".to_string());
+                }
+                tokens.push(Token::Number(chars[start..end].iter().collect(), radix));
+                i = end;
+                continue;
+            }
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            // 科学计数法，`2e` 后面不是数字时 e 为常数
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    while j < chars.len() && chars[j].is_ascii_digit() {
+                        j += 1;
+                    }
+                    i = j;
+                }
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect(), 10));
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' || c == '°' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+        // 全角和常见的数学符号
+        let c = match c {
+            '×' => '*',
+            '÷' => '/',
+            '（' => '(',
+            '）' => ')',
+            '，' => ',',
+            '＝' => '=',
+            c => c,
+        };
+        if c == '=' {
+            tokens.push(Token::Ident("=".to_string()));
+            i += 1;
+            continue;
+        }
+        let rest: String = std::iter::once(c)
+            .chain(chars[i + 1..].iter().take(1).copied())
+            .collect();
+        match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            Some(op) => {
+                tokens.push(Token::Op(op));
+                i += op.chars().count();
+            }
+            None => return Err(format!("Unexpected character `{}`", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+// 计算过程中的值，可以带单位；percent 为 true 时表示百分数
+#[derive(Debug, Clone)]
+pub struct Value {
+    pub number: Number,
+    pub unit: Option<&'static Unit>,
+    percent: bool,
+}
+
+impl Value {
+    fn plain(number: Number) -> Self {
+        Value {
+            number,
+            unit: None,
+            percent: false,
+        }
+    }
+
+    // 百分数参与乘除等运算时按除以 100 处理
+    fn resolved(self) -> Number {
+        if self.percent {
+            self.number
+                .div(&Number::int(100))
+                .unwrap_or(Number::Float(f64::NAN))
+        } else {
+            self.number
+        }
+    }
+
+    fn require_plain(self, what: &str) -> Result<Number, String> {
+        if self.unit.is_some() {
+            return Err(format!("{} does not support units", what));
+        }
+        Ok(self.resolved())
+    }
+
+    fn integer(self, what: &str) -> Result<BigInt, String> {
+        self.require_plain(what)?
+            .to_integer()
+            .ok_or(format!("{} requires integers", what))
+    }
+}
+
+// 转换目标：单位或进制
+#[derive(Debug, Clone, Copy)]
+pub enum Target {
+    Unit(&'static Unit),
+    Radix(u32),
+}
+
+fn radix_of(name: &str) -> Option<u32> {
+    match name.to_lowercase().as_str() {
+        "hex" | "hexadecimal" | "十六进制" => Some(16),
+        "oct" | "octal" | "八进制" => Some(8),
+        "bin" | "binary" | "二进制" => Some(2),
+        "dec" | "decimal" | "十进制" => Some(10),
+        _ => None,
+    }
+}
+
+fn constant(name: &str) -> Option<Number> {
+    match name {
+        "pi" | "π" | "PI" => Some(Number::Float(std::f64::consts::PI)),
+        "e" => Some(Number::Float(std::f64::consts::E)),
+        "tau" | "τ" => Some(Number::Float(std::f64::consts::TAU)),
+        "phi" | "φ" => Some(Number::Float((1.0 + 5f64.sqrt()) / 2.0)),
+        _ => None,
+    }
+}
+
+// 有理数的平方根，分子分母都是完全平方数时精确计算
+fn sqrt(number: &Number) -> Result<Number, String> {
+    if let Number::Exact(value) = number {
+        if value.is_negative() {
+            return Err("Square root of a negative number".to_string());
+        }
+        let (n, d) = (value.numer().sqrt(), value.denom().sqrt());
+        if &(&n * &n) == value.numer() && &(&d * &d) == value.denom() {
+            return Ok(Number::Exact(BigRational::new(n, d)));
+        }
+    }
+    Ok(Number::Float(number.to_f64().sqrt()))
+}
+
+fn call(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    let arity = |n: usize| -> Result<(), String> {
+        if args.len() != n {
+            Err(format!("{} expects {} argument(s)", name, n))
+        } else {
+            Ok(())
+        }
+    };
+    let float = |f: fn(f64) -> f64, args: Vec<Value>| -> Result<Value, String> {
+        let x = args.into_iter().next().unwrap().require_plain(name)?;
+        Ok(Value::plain(Number::Float(f(x.to_f64()))))
+    };
+    match name {
+        "sqrt" => {
+            arity(1)?;
+            let x = args.into_iter().next().unwrap().require_plain(name)?;
+            Ok(Value::plain(sqrt(&x)?))
+        }
+        "abs" | "floor" | "ceil" | "round" | "trunc" => {
+            arity(1)?;
+            // 取整和绝对值保留单位
+            let value = args.into_iter().next().unwrap();
+            let number = match &value.number {
+                Number::Exact(x) => Number::Exact(match name {
+                    "abs" => x.abs(),
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    "round" => x.round(),
+                    _ => x.trunc(),
+                }),
+                Number::Float(x) => Number::Float(match name {
+                    "abs" => x.abs(),
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    "round" => x.round(),
+                    _ => x.trunc(),
+                }),
+            };
+            Ok(Value { number, ..value })
+        }
+        "sin" => arity(1).and_then(|_| float(f64::sin, args)),
+        "cos" => arity(1).and_then(|_| float(f64::cos, args)),
+        "tan" => arity(1).and_then(|_| float(f64::tan, args)),
+        "asin" => arity(1).and_then(|_| float(f64::asin, args)),
+        "acos" => arity(1).and_then(|_| float(f64::acos, args)),
+        "atan" => arity(1).and_then(|_| float(f64::atan, args)),
+        "sinh" => arity(1).and_then(|_| float(f64::sinh, args)),
+        "cosh" => arity(1).and_then(|_| float(f64::cosh, args)),
+        "tanh" => arity(1).and_then(|_| float(f64::tanh, args)),
+        "exp" => arity(1).and_then(|_| float(f64::exp, args)),
+        "ln" => arity(1).and_then(|_| float(f64::ln, args)),
+        "log2" => arity(1).and_then(|_| float(f64::log2, args)),
+        "log10" => arity(1).and_then(|_| float(f64::log10, args)),
+        "deg" => arity(1).and_then(|_| float(f64::to_degrees, args)),
+        "rad" => arity(1).and_then(|_| float(f64::to_radians, args)),
+        "log" => match args.len() {
+            1 => float(f64::log10, args),
+            2 => {
+                let mut args = args.into_iter();
+                let x = args.next().unwrap().require_plain(name)?.to_f64();
+                let base = args.next().unwrap().require_plain(name)?.to_f64();
+                Ok(Value::plain(Number::Float(x.log(base))))
+            }
+            _ => Err("log expects 1 or 2 arguments".to_string()),
+        },
+        "min" | "max" => {
+            if args.is_empty() {
+                return Err(format!("{} expects at least 1 argument", name));
+            }
+            let mut numbers: Vec<Number> = Vec::new();
+            for arg in args {
+                numbers.push(arg.require_plain(name)?);
+            }
+            let mut best = numbers[0].clone();
+            for n in numbers.into_iter().skip(1) {
+                let better = if name == "min" {
+                    n.to_f64() < best.to_f64()
+                } else {
+                    n.to_f64() > best.to_f64()
+                };
+                if better {
+                    best = n;
+                }
+            }
+            Ok(Value::plain(best))
+        }
+        "gcd" | "lcm" => {
+            arity(2)?;
+            let mut args = args.into_iter();
+            let a = args.next().unwrap().integer(name)?;
+            let b = args.next().unwrap().integer(name)?;
+            let result = if name == "gcd" { a.gcd(&b) } else { a.lcm(&b) };
+            Ok(Value::plain(Number::from_bigint(result)))
+        }
+        _ => Err(format!("Unknown function `{}`", name)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if self.peek() == Some(&Token::Op(static_op(op))) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_ident(&mut self, name: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == name) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_keyword(token: Option<&Token>) -> bool {
+        matches!(token, Some(Token::Ident(ident))
+            if CONVERT_KEYWORDS.contains(&ident.as_str()) || ident == "mod" || ident == "xor")
+    }
+
+    // 下一个记号是否可以开始一个新的操作数，用于隐式乘法和区分取模与百分号
+    fn starts_operand(&self) -> bool {
+        match self.peek() {
+            Some(Token::Number(..)) => true,
+            Some(Token::Op("(")) => true,
+            Some(Token::Ident(_)) => !Self::is_keyword(self.peek()),
+            _ => false,
+        }
+    }
+
+    // 表达式后面可选的 `to 单位`、`in hex`
+    fn conversion(&mut self) -> Result<(Value, Option<Target>), String> {
+        let value = self.bit_or()?;
+        if self.pos >= self.tokens.len() {
+            return Ok((value, None));
+        }
+        let keyword = match self.next() {
+            Some(Token::Ident(ident)) if CONVERT_KEYWORDS.contains(&ident.as_str()) => ident,
+            _ => return Err("Unexpected token".to_string()),
+        };
+        let target = match self.next() {
+            Some(Token::Ident(name)) => match radix_of(&name) {
+                Some(radix) => Target::Radix(radix),
+                None => Target::Unit(units::find(&name).ok_or(format!("Unknown unit `{}`", name))?),
+            },
+            // `=` 后面可以为空，如 `1+2=`
+            None if keyword == "=" => return Ok((value, None)),
+            _ => return Err("Expected a unit after conversion".to_string()),
+        };
+        if self.pos < self.tokens.len() {
+            return Err("Unexpected token".to_string());
+        }
+        Ok((value, Some(target)))
+    }
+
+    fn bit_or(&mut self) -> Result<Value, String> {
+        let mut left = self.bit_xor()?;
+        while self.eat_op("|") {
+            let right = self.bit_xor()?;
+            let result = left.integer("|")? | right.integer("|")?;
+            left = Value::plain(Number::from_bigint(result));
+        }
+        Ok(left)
+    }
+
+    fn bit_xor(&mut self) -> Result<Value, String> {
+        let mut left = self.bit_and()?;
+        while self.eat_ident("xor") {
+            let right = self.bit_and()?;
+            let result = left.integer("xor")? ^ right.integer("xor")?;
+            left = Value::plain(Number::from_bigint(result));
+        }
+        Ok(left)
+    }
+
+    fn bit_and(&mut self) -> Result<Value, String> {
+        let mut left = self.shift()?;
+        while self.eat_op("&") {
+            let right = self.shift()?;
+            let result = left.integer("&")? & right.integer("&")?;
+            left = Value::plain(Number::from_bigint(result));
+        }
+        Ok(left)
+    }
+
+    fn shift(&mut self) -> Result<Value, String> {
+        let mut left = self.additive()?;
+        loop {
+            let op = if self.eat_op("<<") {
+                "<<"
+            } else if self.eat_op(">>") {
+                ">>"
+            } else {
+                break;
+            };
+            let right = self.additive()?;
+            let value = left.integer(op)?;
+            let amount = right
+                .integer(op)?
+                .to_usize()
+                .filter(|n| *n <= 65536)
+                .ok_or("Invalid shift amount".to_string())?;
+            let result = if op == "<<" {
+                value << amount
+            } else {
+                value >> amount
+            };
+            left = Value::plain(Number::from_bigint(result));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Value, String> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let subtract = if self.eat_op("+") {
+                false
+            } else if self.eat_op("-") {
+                true
+            } else {
+                break;
+            };
+            let right = self.multiplicative()?;
+            left = add(left, right, subtract)?;
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Value, String> {
+        let mut left = self.unary()?;
+        loop {
+            if self.eat_op("*") {
+                let right = self.unary()?;
+                left = mul(left, right)?;
+            } else if self.eat_op("/") {
+                let right = self.unary()?;
+                left = div(left, right)?;
+            } else if self.eat_ident("mod")
+                || (self.peek() == Some(&Token::Op("%")) && {
+                    // 后面跟着操作数时 % 是取模
+                    self.pos += 1;
+                    let operand = self.starts_operand();
+                    if !operand {
+                        self.pos -= 1;
+                    }
+                    operand
+                })
+            {
+                let right = self.unary()?;
+                let unit = left.unit;
+                let number = left.resolved().rem(&right.require_plain("mod")?)?;
+                left = Value {
+                    number,
+                    unit,
+                    percent: false,
+                };
+            } else if self.starts_operand() {
+                // 隐式乘法：2pi、3(4+5)
+                let right = self.unary()?;
+                left = mul(left, right)?;
+            } else {
+                break;
+            }
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Value, String> {
+        if self.eat_op("-") {
+            let value = self.unary()?;
+            return Ok(Value {
+                number: value.number.neg(),
+                ..value
+            });
+        }
+        if self.eat_op("+") {
+            return self.unary();
+        }
+        if self.eat_op("~") {
+            let value = self.unary()?.integer("~")?;
+            return Ok(Value::plain(Number::from_bigint(!value)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Value, String> {
+        let base = self.postfix()?;
+        if self.eat_op("^") || self.eat_op("**") {
+            // 右结合，指数可以带负号
+            let exponent = self.unary()?.require_plain("^")?;
+            let number = base.require_plain("^")?.pow(&exponent)?;
+            return Ok(Value::plain(number));
+        }
+        Ok(base)
+    }
+
+    fn postfix(&mut self) -> Result<Value, String> {
+        let mut value = self.primary()?;
+        loop {
+            if self.eat_op("!") {
+                value = Value::plain(value.require_plain("!")?.factorial()?);
+            } else if self.peek() == Some(&Token::Op("%")) && {
+                self.pos += 1;
+                let operand = self.starts_operand();
+                self.pos -= 1;
+                !operand
+            } {
+                self.pos += 1;
+                value.percent = true;
+            } else if let Some(unit) = self.unit_after_value(&value) {
+                self.pos += 1;
+                value.unit = Some(unit);
+            } else {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
+    // 数值后面紧跟的单位，如 `10 km`
+    fn unit_after_value(&self, value: &Value) -> Option<&'static Unit> {
+        if value.unit.is_some() || value.percent {
+            return None;
+        }
+        let name = match self.peek() {
+            Some(Token::Ident(name)) => name,
+            _ => return None,
+        };
+        // 函数调用
+        if self.peek_at(1) == Some(&Token::Op("(")) {
+            return None;
+        }
+        // `in` 后面是单位或进制时是转换关键字，否则是英寸
+        if CONVERT_KEYWORDS.contains(&name.as_str()) {
+            let followed_by_target = match self.peek_at(1) {
+                Some(Token::Ident(next)) => units::find(next).is_some() || radix_of(next).is_some(),
+                _ => false,
+            };
+            if name != "in" || followed_by_target {
+                return None;
+            }
+        }
+        units::find(name)
+    }
+
+    fn primary(&mut self) -> Result<Value, String> {
+        match self.next() {
+            Some(Token::Number(text, 10)) => Number::parse_decimal(&text)
+                .map(Value::plain)
+                .ok_or("Invalid number".to_string()),
+            Some(Token::Number(text, radix)) => {
+                BigInt::from_str_radix(&text.replace('_', ""), radix)
+                    .map(|n| Value::plain(Number::from_bigint(n)))
+                    .map_err(|e| e.to_string())
+            }
+            Some(Token::Op("(")) => {
+                let value = self.bit_or()?;
+                if !self.eat_op(")") {
+                    return Err("Missing `)`".to_string());
+                }
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.eat_op("(") {
+                    let mut args = Vec::new();
+                    if !self.eat_op(")") {
+                        loop {
+                            args.push(self.bit_or()?);
+                            if self.eat_op(")") {
+                                break;
+                            }
+                            if !self.eat_op(",") {
+                                return Err("Expected `,` or `)`".to_string());
+                            }
+                        }
+                    }
+                    return call(&name.to_lowercase(), args);
+                }
+                constant(&name)
+                    .map(Value::plain)
+                    .ok_or(format!("Unknown name `{}`", name))
+            }
+            _ => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn static_op(op: &str) -> &'static str {
+    OPERATORS.iter().find(|o| **o == op).copied().unwrap_or("")
+}
+
+// 加减：带单位时换算为左边的单位；右边是百分数时按左边的百分比计算，如 200 + 10%
+fn add(left: Value, right: Value, subtract: bool) -> Result<Value, String> {
+    if right.percent && !left.percent {
+        let ratio = right.resolved();
+        let delta = left.number.mul(&ratio);
+        let number = if subtract {
+            left.number.sub(&delta)
+        } else {
+            left.number.add(&delta)
+        };
+        return Ok(Value { number, ..left });
+    }
+    let percent = left.percent && right.percent;
+    let (unit, right_number) = match (left.unit, right.unit) {
+        (Some(a), Some(b)) => {
+            if a.dimension == units::Dimension::Temperature && !std::ptr::eq(a, b) {
+                return Err("Cannot add different temperature units".to_string());
+            }
+            (Some(a), b.convert(&right.number, a)?)
+        }
+        (a, b) => (a.or(b), right.number.clone()),
+    };
+    let (left_number, right_number) = if percent {
+        (left.number, right_number)
+    } else {
+        (
+            Value {
+                number: left.number,
+                unit: None,
+                percent: left.percent,
+            }
+            .resolved(),
+            Value {
+                number: right_number,
+                unit: None,
+                percent: right.percent,
+            }
+            .resolved(),
+        )
+    };
+    let number = if subtract {
+        left_number.sub(&right_number)
+    } else {
+        left_number.add(&right_number)
+    };
+    Ok(Value {
+        number,
+        unit,
+        percent,
+    })
+}
+
+fn mul(left: Value, right: Value) -> Result<Value, String> {
+    let unit = match (left.unit, right.unit) {
+        (Some(_), Some(_)) => return Err("Cannot multiply two quantities with units".to_string()),
+        (a, b) => a.or(b),
+    };
+    Ok(Value {
+        number: left.resolved().mul(&right.resolved()),
+        unit,
+        percent: false,
+    })
+}
+
+fn div(left: Value, right: Value) -> Result<Value, String> {
+    match (left.unit, right.unit) {
+        // 同一维度的两个量相除得到比值
+        (Some(a), Some(b)) => {
+            let right = b.convert(&right.number, a)?;
+            Ok(Value::plain(left.number.div(&right)?))
+        }
+        (None, Some(_)) => Err("Cannot divide by a quantity with a unit".to_string()),
+        (unit, None) => Ok(Value {
+            number: left.resolved().div(&right.resolved())?,
+            unit,
+            percent: false,
+        }),
+    }
+}
+
+// 计算结果
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub value: Value,
+    pub target: Option<Target>,
+    // 只有一个数字或名称，不算一次计算
+    pub trivial: bool,
+}
+
+pub fn evaluate(input: &str) -> Result<Evaluation, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("Empty expression".to_string());
+    }
+    let trivial = tokens.len() == 1 && matches!(&tokens[0], Token::Number(_, 10) | Token::Ident(_));
+    let mut parser = Parser { tokens, pos: 0 };
+    let (mut value, target) = parser.conversion()?;
+    if let Some(Target::Unit(to)) = target {
+        let from = value
+            .unit
+            .ok_or(format!("Cannot convert a plain number to {}", to.name()))?;
+        value = Value {
+            number: from.convert(&value.number, to)?,
+            unit: Some(to),
+            percent: false,
+        };
+    }
+    if value.percent && value.unit.is_none() {
+        // 单独的百分数显示为小数，如 15% = 0.15
+        value = Value::plain(value.resolved());
+    }
+    Ok(Evaluation {
+        value,
+        target,
+        trivial,
+    })
+}
+
+// 整数按进制格式化，负数带负号
+pub fn format_radix(value: &BigInt, radix: u32) -> String {
+    let prefix = match radix {
+        16 => "0x",
+        8 => "0o",
+        2 => "0b",
+        _ => "",
+    };
+    let sign = if value.is_negative() { "-" } else { "" };
+    format!("{}{}{}", sign, prefix, value.abs().to_str_radix(radix))
+}
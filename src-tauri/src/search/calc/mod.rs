@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fs, path::PathBuf};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use super::index::{ResultKind, SearchResult};
+
+mod expr;
+mod number;
+mod units;
+
+use expr::{format_radix, Target};
+
+// 以 `=` 开头时总是按表达式计算
+pub const QUERY_PREFIX: &str = "=";
+// 计算结果排在最前面
+const RESULT_SCORE: f64 = 5000.0;
+// 计算历史文件，位于 app_data_dir 中
+const HISTORY_FILE: &str = "calc-history.json";
+const MAX_HISTORY: usize = 50;
+
+// 整数结果的各进制表示
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bases {
+    pub dec: String,
+    pub hex: String,
+    pub oct: String,
+    pub bin: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcResult {
+    pub expression: String,
+    // 数值部分，复制时使用
+    pub value: String,
+    pub unit: Option<String>,
+    // 显示的结果，带单位或进制前缀
+    pub display: String,
+    pub bases: Option<Bases>,
+}
+
+pub fn evaluate(expression: &str) -> Result<CalcResult, String> {
+    let evaluation = expr::evaluate(expression)?;
+    let number = &evaluation.value.number;
+    let integer = number.to_integer();
+    let bases = integer.as_ref().map(|n| Bases {
+        dec: n.to_string(),
+        hex: format_radix(n, 16),
+        oct: format_radix(n, 8),
+        bin: format_radix(n, 2),
+    });
+    let value = match (evaluation.target, integer.as_ref()) {
+        (Some(Target::Radix(radix)), Some(n)) => format_radix(n, radix),
+        (Some(Target::Radix(_)), None) => {
+            return Err("Only integers can be converted to another base".to_string())
+        }
+        _ => number.format(),
+    };
+    let unit = evaluation.value.unit.map(|unit| unit.name().to_string());
+    let display = match unit.as_ref() {
+        Some(unit) => format!("{} {}", value, unit),
+        None => value.clone(),
+    };
+    Ok(CalcResult {
+        expression: expression.trim().to_string(),
+        value,
+        unit,
+        display,
+        bases,
+    })
+}
+
+// 作为搜索结果的计算，只有一个数字或名称时不显示
+pub fn search(query: &str) -> Option<SearchResult> {
+    let (expression, forced) = match query.trim_start().strip_prefix(QUERY_PREFIX) {
+        Some(expression) => (expression, true),
+        None => (query, false),
+    };
+    if expression.trim().is_empty() {
+        return None;
+    }
+    if !forced && expr::evaluate(expression).ok()?.trivial {
+        return None;
+    }
+    let result = evaluate(expression).ok()?;
+    Some(SearchResult {
+        id: format!("calc:{}", result.expression),
+        kind: ResultKind::Calculator,
+        title: result.display.clone(),
+        subtitle: Some(format!("{} =", result.expression)),
+        icon: None,
+        score: RESULT_SCORE,
+        highlights: Vec::new(),
+        data: json!(result),
+        labels: Vec::new(),
+        actions: Vec::new(),
+    })
+}
+
+// 一条计算历史
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcHistoryItem {
+    pub expression: String,
+    pub result: String,
+    pub time: i64,
+}
+
+fn history_file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(HISTORY_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn load_history<R: Runtime>(app: &AppHandle<R>) -> Vec<CalcHistoryItem> {
+    history_file(app)
+        .ok()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_history<R: Runtime>(app: &AppHandle<R>, items: &[CalcHistoryItem]) -> Result<(), String> {
+    let file = history_file(app)?;
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string(items).map_err(|e| e.to_string())?;
+    fs::write(file, content).map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn calculate(expression: String) -> Result<CalcResult, String> {
+    evaluate(&expression)
+}
+
+// 最近的计算，最新的在前
+#[command]
+pub async fn get_calc_history(app: AppHandle) -> Result<Vec<CalcHistoryItem>, String> {
+    Ok(load_history(&app))
+}
+
+// 复制计算结果时记录，相同的表达式只保留最新一条
+#[command]
+pub async fn add_calc_history(
+    app: AppHandle,
+    expression: String,
+    result: String,
+) -> Result<(), String> {
+    let expression = expression.trim().to_string();
+    let mut items = load_history(&app);
+    items.retain(|item| item.expression != expression);
+    items.insert(
+        0,
+        CalcHistoryItem {
+            expression,
+            result,
+            time: chrono::Local::now().timestamp_millis(),
+        },
+    );
+    items.truncate(MAX_HISTORY);
+    save_history(&app, &items)
+}
+
+#[command]
+pub async fn clear_calc_history(app: AppHandle) -> Result<(), String> {
+    save_history(&app, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(expression: &str) -> String {
+        evaluate(expression).unwrap().display
+    }
+
+    #[test]
+    fn evaluates_arithmetic() {
+        assert_eq!(display("1 + 2 * 3"), "7");
+        assert_eq!(display("(1 + 2) * 3"), "9");
+        assert_eq!(display("2 ^ 3 ^ 2"), "512");
+        assert_eq!(display("2 ** 10"), "1024");
+        assert_eq!(display("-2 ^ 2"), "-4");
+        assert_eq!(display("10 / 4"), "2.5");
+        assert_eq!(display("7 % 3"), "1");
+        assert_eq!(display("5!"), "120");
+        assert_eq!(display("3 × 4 ÷ 2"), "6");
+        assert_eq!(display("（1 + 1）"), "2");
+        assert_eq!(display("0.1 + 0.2"), "0.3");
+    }
+
+    #[test]
+    fn evaluates_functions_and_constants() {
+        assert_eq!(display("sqrt(16)"), "4");
+        assert_eq!(display("sqrt(2)"), "1.414213562373");
+        assert_eq!(display("max(1, 5, 3)"), "5");
+        assert_eq!(display("gcd(12, 18)"), "6");
+        assert_eq!(display("round(2.5)"), "3");
+        assert_eq!(display("2 * pi"), "6.28318530718");
+        assert!(evaluate("foo(1)").is_err());
+        assert!(evaluate("sqrt(-1)").is_err());
+    }
+
+    #[test]
+    fn evaluates_bitwise_and_radix() {
+        assert_eq!(display("0xff & 0b1010"), "10");
+        assert_eq!(display("1 << 4 | 1"), "17");
+        assert_eq!(display("255 to hex"), "0xff");
+        assert_eq!(display("0o17 in dec"), "15");
+        let result = evaluate("10").unwrap();
+        assert_eq!(result.bases.unwrap().bin, "0b1010");
+        assert!(evaluate("1.5 to hex").is_err());
+    }
+
+    #[test]
+    fn converts_units() {
+        assert_eq!(display("1 km to m"), "1000 m");
+        assert_eq!(display("100 °C to °F"), "212 °F");
+        assert_eq!(display("1 GiB in MiB"), "1024 MiB");
+        assert_eq!(display("90 min + 1 h to h"), "2.5 h");
+        assert!(evaluate("1 kg to m").is_err());
+        assert!(evaluate("5 to m").is_err());
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(evaluate("").is_err());
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("1 $ 2").is_err());
+    }
+
+    // 结果过大时改用浮点数，不会一直计算下去
+    #[test]
+    fn limits_huge_powers() {
+        assert_eq!(display("(9^99999)^99999"), "∞");
+        assert_eq!(display("2^100000").len(), 30103);
+    }
+
+    #[test]
+    fn hides_trivial_search_results() {
+        assert!(search("42").is_none());
+        assert!(search("pi").is_none());
+        assert_eq!(search("=42").unwrap().title, "42");
+        assert_eq!(search("1+1").unwrap().title, "2");
+        assert!(search("hello world").is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+
+// 小数最多显示的位数
+const PRECISION: usize = 20;
+// 精确计算时允许的最大指数，避免生成过大的数字
+const MAX_EXACT_EXPONENT: u32 = 100_000;
+// 精确计算乘方时结果的最大位数（二进制），超过时改用浮点数
+const MAX_EXACT_BITS: u64 = 1 << 19;
+
+// 能精确表示的数用有理数计算，三角函数等无法精确计算的结果用浮点数
+#[derive(Debug, Clone)]
+pub enum Number {
+    Exact(BigRational),
+    Float(f64),
+}
+
+impl Number {
+    pub fn int(value: i64) -> Self {
+        Number::Exact(BigRational::from_integer(BigInt::from(value)))
+    }
+
+    pub fn from_bigint(value: BigInt) -> Self {
+        Number::Exact(BigRational::from_integer(value))
+    }
+
+    // 解析十进制数，如 `12`、`3.14`、`1e-3`
+    pub fn parse_decimal(text: &str) -> Option<Self> {
+        let text = text.replace('_', "");
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(pos) => (&text[..pos], text[pos + 1..].parse::<i32>().ok()?),
+            None => (text.as_str(), 0),
+        };
+        let (int_part, frac_part) = match mantissa.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, frac_part),
+            None => (mantissa, ""),
+        };
+        let digits = format!("{}{}", int_part, frac_part);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let numerator: BigInt = digits.parse().ok()?;
+        let scale = exponent - frac_part.len() as i32;
+        if scale.unsigned_abs() > MAX_EXACT_EXPONENT {
+            return None;
+        }
+        let factor = BigInt::from(10).pow(scale.unsigned_abs());
+        let value = if scale >= 0 {
+            BigRational::from_integer(numerator * factor)
+        } else {
+            BigRational::new(numerator, factor)
+        };
+        Some(Number::Exact(value))
+    }
+
+    // 解析 `5/9` 或十进制数，用于单位换算系数
+    pub fn parse_ratio(text: &str) -> Self {
+        match text.split_once('/') {
+            Some((a, b)) => {
+                let a = Number::parse_decimal(a).unwrap_or(Number::int(0));
+                let b = Number::parse_decimal(b).unwrap_or(Number::int(1));
+                a.div(&b).unwrap_or(Number::int(0))
+            }
+            None => Number::parse_decimal(text).unwrap_or(Number::int(0)),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Exact(value) => value.to_f64().unwrap_or(f64::NAN),
+            Number::Float(value) => *value,
+        }
+    }
+
+    // 整数时返回对应的大整数
+    pub fn to_integer(&self) -> Option<BigInt> {
+        match self {
+            Number::Exact(value) if value.is_integer() => Some(value.to_integer()),
+            Number::Float(value) if value.fract() == 0.0 => BigInt::from_f64(*value),
+            _ => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Exact(value) => value.is_zero(),
+            Number::Float(value) => *value == 0.0,
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a + b),
+            _ => Number::Float(self.to_f64() + other.to_f64()),
+        }
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a - b),
+            _ => Number::Float(self.to_f64() - other.to_f64()),
+        }
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a * b),
+            _ => Number::Float(self.to_f64() * other.to_f64()),
+        }
+    }
+
+    pub fn div(&self, other: &Number) -> Result<Number, String> {
+        if other.is_zero() {
+            return Err("Division by zero".to_string());
+        }
+        Ok(match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a / b),
+            _ => Number::Float(self.to_f64() / other.to_f64()),
+        })
+    }
+
+    pub fn rem(&self, other: &Number) -> Result<Number, String> {
+        if other.is_zero() {
+            return Err("Division by zero".to_string());
+        }
+        Ok(match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a % b),
+            _ => Number::Float(self.to_f64() % other.to_f64()),
+        })
+    }
+
+    pub fn neg(&self) -> Number {
+        match self {
+            Number::Exact(value) => Number::Exact(-value),
+            Number::Float(value) => Number::Float(-value),
+        }
+    }
+
+    // 整数指数且结果不太大时精确计算，其余用浮点数
+    // 结果的位数按底数分子分母中较大的位数乘以指数估算
+    pub fn pow(&self, exponent: &Number) -> Result<Number, String> {
+        if let (Number::Exact(base), Some(exp)) = (self, exponent.to_integer()) {
+            if let Some(exp) = exp.to_i64() {
+                let bits = base.numer().bits().max(base.denom().bits());
+                let small = bits.saturating_mul(exp.unsigned_abs()) <= MAX_EXACT_BITS;
+                if exp.unsigned_abs() <= MAX_EXACT_EXPONENT as u64 && small {
+                    if exp < 0 && base.is_zero() {
+                        return Err("Division by zero".to_string());
+                    }
+                    let value = base.pow(exp.unsigned_abs() as i32);
+                    return Ok(Number::Exact(if exp < 0 { value.recip() } else { value }));
+                }
+            }
+        }
+        Ok(Number::Float(self.to_f64().powf(exponent.to_f64())))
+    }
+
+    // 阶乘，只支持非负整数
+    pub fn factorial(&self) -> Result<Number, String> {
+        let n = self
+            .to_integer()
+            .and_then(|n| n.to_u32())
+            .filter(|n| *n <= 10_000)
+            .ok_or("Factorial requires a non-negative integer up to 10000".to_string())?;
+        let mut result = BigInt::one();
+        for i in 2..=n {
+            result *= i;
+        }
+        Ok(Number::from_bigint(result))
+    }
+
+    pub fn format(&self) -> String {
+        match self {
+            Number::Exact(value) => format_rational(value),
+            Number::Float(value) => format_float(*value),
+        }
+    }
+}
+
+fn format_rational(value: &BigRational) -> String {
+    if value.is_integer() {
+        return value.to_integer().to_string();
+    }
+    let negative = value.is_negative();
+    let value = value.abs();
+    let (int_part, mut rem) = value.numer().div_rem(value.denom());
+    let mut digits = String::new();
+    for _ in 0..PRECISION {
+        if rem.is_zero() {
+            break;
+        }
+        rem *= 10;
+        let (digit, r) = rem.div_rem(value.denom());
+        digits.push_str(&digit.to_string());
+        rem = r;
+    }
+    // 超出精度的部分四舍五入
+    let mut int_part = int_part;
+    if !rem.is_zero() && rem * 2 >= *value.denom() {
+        let mut chars: Vec<u8> = digits.into_bytes();
+        let mut carry = true;
+        for c in chars.iter_mut().rev() {
+            if !carry {
+                break;
+            }
+            if *c == b'9' {
+                *c = b'0';
+            } else {
+                *c += 1;
+                carry = false;
+            }
+        }
+        if carry {
+            int_part += 1;
+        }
+        digits = String::from_utf8(chars).unwrap_or_default();
+    }
+    let digits = digits.trim_end_matches('0');
+    let sign = if negative { "-" } else { "" };
+    if digits.is_empty() {
+        format!("{}{}", sign, int_part)
+    } else {
+        format!("{}{}.{}", sign, int_part, digits)
+    }
+}
+
+fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "∞" } else { "-∞" }.to_string();
+    }
+    let abs = value.abs();
+    if abs != 0.0 && !(1e-9..1e21).contains(&abs) {
+        return format!("{:e}", value);
+    }
+    // 去掉浮点误差，如 0.30000000000000004
+    let text = format!("{:.12}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str) -> Number {
+        Number::parse_decimal(text).unwrap()
+    }
+
+    #[test]
+    fn parses_decimals() {
+        assert_eq!(number("12").format(), "12");
+        assert_eq!(number("3.14").format(), "3.14");
+        assert_eq!(number("1_000").format(), "1000");
+        assert_eq!(number("1e-3").format(), "0.001");
+        assert_eq!(number("2.5E2").format(), "250");
+        assert!(Number::parse_decimal("1e999999").is_none());
+        assert!(Number::parse_decimal("1.2.3").is_none());
+        assert!(Number::parse_decimal("").is_none());
+        assert_eq!(
+            Number::parse_ratio("5/9").format(),
+            "0.55555555555555555556"
+        );
+    }
+
+    #[test]
+    fn keeps_exact_arithmetic() {
+        let sum = number("0.1").add(&number("0.2"));
+        assert_eq!(sum.format(), "0.3");
+        assert_eq!(
+            Number::int(1).div(&Number::int(3)).unwrap().format(),
+            "0.33333333333333333333"
+        );
+        assert_eq!(
+            Number::int(2).div(&Number::int(3)).unwrap().format(),
+            "0.66666666666666666667"
+        );
+        assert_eq!(Number::int(-7).rem(&Number::int(3)).unwrap().format(), "-1");
+        assert!(Number::int(1).div(&Number::int(0)).is_err());
+        assert!(Number::int(1).rem(&Number::int(0)).is_err());
+    }
+
+    #[test]
+    fn raises_to_integer_powers_exactly() {
+        assert_eq!(
+            Number::int(2).pow(&Number::int(64)).unwrap().format(),
+            "18446744073709551616"
+        );
+        assert_eq!(
+            Number::int(2).pow(&Number::int(-2)).unwrap().format(),
+            "0.25"
+        );
+        assert!(Number::int(0).pow(&Number::int(-1)).is_err());
+        assert!(matches!(
+            Number::int(2).pow(&Number::int(100_000)).unwrap(),
+            Number::Exact(_)
+        ));
+        assert!(matches!(
+            number("0.5").pow(&number("0.5")).unwrap(),
+            Number::Float(_)
+        ));
+    }
+
+    // 指数不大但结果过大时不精确计算，如 (9^99999)^99999
+    #[test]
+    fn falls_back_to_float_for_huge_powers() {
+        let base = Number::int(9).pow(&Number::int(99_999)).unwrap();
+        assert!(matches!(base, Number::Exact(_)));
+        let result = base.pow(&Number::int(99_999)).unwrap();
+        assert!(matches!(result, Number::Float(_)));
+        assert_eq!(result.format(), "∞");
+        let small = number("0.001").pow(&Number::int(99_999)).unwrap();
+        assert!(matches!(small, Number::Float(_)));
+    }
+
+    #[test]
+    fn limits_factorial() {
+        assert_eq!(
+            Number::int(20).factorial().unwrap().format(),
+            "2432902008176640000"
+        );
+        assert!(Number::int(-1).factorial().is_err());
+        assert!(number("1.5").factorial().is_err());
+        assert!(Number::int(10_001).factorial().is_err());
+    }
+
+    #[test]
+    fn formats_floats() {
+        assert_eq!(Number::Float(0.1 + 0.2).format(), "0.3");
+        assert_eq!(Number::Float(-0.0).format(), "0");
+        assert_eq!(Number::Float(1e21).format(), "1e21");
+        assert_eq!(Number::Float(f64::NAN).format(), "NaN");
+        assert_eq!(Number::Float(f64::NEG_INFINITY).format(), "-∞");
+    }
+}
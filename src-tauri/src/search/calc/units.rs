@@ -0,0 +1,145 @@
+use super::number::Number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Mass,
+    Data,
+    Temperature,
+    Time,
+}
+
+// 单位，换算为基本单位：base = (value + offset) * scale
+// 长度为米，质量为克，数据为字节，温度为开尔文，时间为秒
+#[derive(Debug)]
+pub struct Unit {
+    // 第一个为显示名称
+    pub names: &'static [&'static str],
+    pub dimension: Dimension,
+    scale: &'static str,
+    offset: &'static str,
+}
+
+impl Unit {
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+
+    pub fn to_base(&self, value: &Number) -> Number {
+        value
+            .add(&Number::parse_ratio(self.offset))
+            .mul(&Number::parse_ratio(self.scale))
+    }
+
+    pub fn to_unit(&self, value: &Number) -> Number {
+        value
+            .div(&Number::parse_ratio(self.scale))
+            .unwrap_or(Number::Float(f64::NAN))
+            .sub(&Number::parse_ratio(self.offset))
+    }
+
+    // 同一维度的单位之间换算
+    pub fn convert(&self, value: &Number, to: &Unit) -> Result<Number, String> {
+        if self.dimension != to.dimension {
+            return Err(format!("Cannot convert {} to {}", self.name(), to.name()));
+        }
+        Ok(to.to_unit(&self.to_base(value)))
+    }
+}
+
+macro_rules! unit {
+    ($dimension:ident, $scale:expr, [$($name:expr),+]) => {
+        Unit { names: &[$($name),+], dimension: Dimension::$dimension, scale: $scale, offset: "0" }
+    };
+    ($dimension:ident, $scale:expr, $offset:expr, [$($name:expr),+]) => {
+        Unit { names: &[$($name),+], dimension: Dimension::$dimension, scale: $scale, offset: $offset }
+    };
+}
+
+pub static UNITS: &[Unit] = &[
+    // 长度
+    unit!(
+        Length,
+        "1",
+        ["m", "meter", "meters", "metre", "metres", "米"]
+    ),
+    unit!(
+        Length,
+        "1000",
+        ["km", "kilometer", "kilometers", "公里", "千米"]
+    ),
+    unit!(Length, "0.01", ["cm", "centimeter", "centimeters", "厘米"]),
+    unit!(Length, "0.001", ["mm", "millimeter", "millimeters", "毫米"]),
+    unit!(
+        Length,
+        "0.000001",
+        ["um", "μm", "micrometer", "micrometers"]
+    ),
+    unit!(Length, "0.000000001", ["nm", "nanometer", "nanometers"]),
+    unit!(Length, "1609.344", ["mi", "mile", "miles", "英里"]),
+    unit!(Length, "0.9144", ["yd", "yard", "yards", "码"]),
+    unit!(Length, "0.3048", ["ft", "foot", "feet", "英尺"]),
+    unit!(Length, "0.0254", ["in", "inch", "inches", "英寸"]),
+    unit!(Length, "1852", ["nmi", "nautical_mile", "海里"]),
+    // 质量
+    unit!(Mass, "1", ["g", "gram", "grams", "克"]),
+    unit!(Mass, "0.001", ["mg", "milligram", "milligrams", "毫克"]),
+    unit!(
+        Mass,
+        "1000",
+        ["kg", "kilogram", "kilograms", "公斤", "千克"]
+    ),
+    unit!(Mass, "1000000", ["t", "tonne", "tonnes", "ton", "吨"]),
+    unit!(Mass, "500", ["斤"]),
+    unit!(Mass, "453.59237", ["lb", "lbs", "pound", "pounds", "磅"]),
+    unit!(Mass, "28.349523125", ["oz", "ounce", "ounces", "盎司"]),
+    // 数据大小
+    unit!(Data, "1/8", ["bit", "bits", "b"]),
+    unit!(Data, "1", ["B", "byte", "bytes", "字节"]),
+    unit!(Data, "125", ["kbit", "Kbit"]),
+    unit!(Data, "125000", ["Mbit"]),
+    unit!(Data, "125000000", ["Gbit"]),
+    unit!(Data, "1000", ["KB", "kB"]),
+    unit!(Data, "1000000", ["MB"]),
+    unit!(Data, "1000000000", ["GB"]),
+    unit!(Data, "1000000000000", ["TB"]),
+    unit!(Data, "1000000000000000", ["PB"]),
+    unit!(Data, "1024", ["KiB"]),
+    unit!(Data, "1048576", ["MiB"]),
+    unit!(Data, "1073741824", ["GiB"]),
+    unit!(Data, "1099511627776", ["TiB"]),
+    unit!(Data, "1125899906842624", ["PiB"]),
+    // 温度
+    unit!(Temperature, "1", ["K", "kelvin"]),
+    unit!(Temperature, "1", "273.15", ["°C", "C", "celsius", "摄氏度"]),
+    unit!(
+        Temperature,
+        "5/9",
+        "459.67",
+        ["°F", "F", "fahrenheit", "华氏度"]
+    ),
+    // 时间
+    unit!(Time, "1", ["s", "sec", "secs", "second", "seconds", "秒"]),
+    unit!(Time, "0.001", ["ms", "millisecond", "milliseconds", "毫秒"]),
+    unit!(Time, "60", ["min", "mins", "minute", "minutes", "分钟"]),
+    unit!(Time, "3600", ["h", "hr", "hrs", "hour", "hours", "小时"]),
+    unit!(Time, "86400", ["d", "day", "days", "天"]),
+    unit!(Time, "604800", ["wk", "week", "weeks", "周"]),
+    unit!(Time, "31557600", ["yr", "year", "years", "年"]),
+];
+
+// 按名称查找单位，先区分大小写，没有时再不区分大小写（只有一个单位匹配时）
+pub fn find(name: &str) -> Option<&'static Unit> {
+    if let Some(unit) = UNITS.iter().find(|unit| unit.names.contains(&name)) {
+        return Some(unit);
+    }
+    let mut matches = UNITS.iter().filter(|unit| {
+        unit.names
+            .iter()
+            .any(|alias| alias.eq_ignore_ascii_case(name))
+    });
+    match (matches.next(), matches.next()) {
+        (Some(unit), None) => Some(unit),
+        _ => None,
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
+};
+
+use super::text::{read_text, tokenize};
+use crate::search::files::index::{modified_millis, read_bytes, read_u32, write_bytes};
+
+// 索引文件的标识和版本，格式变化时增加版本号
+const MAGIC: &[u8; 8] = b"VTCIDX01";
+
+// 一个建了索引的文件
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: i64,
+    deleted: bool,
+}
+
+// 倒排索引：词 -> 包含该词的文件 id（升序）
+#[derive(Debug, Default)]
+pub struct ContentIndex {
+    docs: Vec<Document>,
+    ids: HashMap<PathBuf, u32>,
+    postings: BTreeMap<Box<str>, Vec<u32>>,
+    deleted: usize,
+}
+
+impl ContentIndex {
+    pub fn len(&self) -> usize {
+        self.docs.len() - self.deleted
+    }
+
+    pub fn doc(&self, id: u32) -> Option<&Document> {
+        self.docs.get(id as usize).filter(|doc| !doc.deleted)
+    }
+
+    // 文件的大小和修改时间与索引中一致时不需要重新建索引
+    pub fn is_fresh(&self, path: &Path, size: u64, modified: i64) -> bool {
+        self.ids
+            .get(path)
+            .and_then(|id| self.doc(*id))
+            .is_some_and(|doc| doc.size == size && doc.modified == modified)
+    }
+
+    // 为一个文件建索引，已有的旧索引先删除；不是文本文件时只删除
+    pub fn index_file(&mut self, path: &Path, max_size: u64) {
+        let text = read_text(path, max_size);
+        self.insert(path, text.as_deref());
+    }
+
+    // 用已读取的内容建索引，text 为 None 时只删除
+    pub fn insert(&mut self, path: &Path, text: Option<&str>) {
+        self.remove_file(path);
+        let (metadata, text) = match (fs::metadata(path), text) {
+            (Ok(metadata), Some(text)) => (metadata, text),
+            _ => return,
+        };
+        let id = self.docs.len() as u32;
+        self.docs.push(Document {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified: modified_millis(&metadata),
+            deleted: false,
+        });
+        self.ids.insert(path.to_path_buf(), id);
+        let tokens: HashSet<String> = tokenize(text).into_iter().collect();
+        for token in tokens {
+            self.postings.entry(token.into()).or_default().push(id);
+        }
+    }
+
+    // 删除一个文件，是目录时删除其下的所有文件
+    pub fn remove(&mut self, path: &Path) {
+        if self.remove_file(path) {
+            return;
+        }
+        let children: Vec<PathBuf> = self
+            .ids
+            .keys()
+            .filter(|p| p.starts_with(path))
+            .cloned()
+            .collect();
+        for child in children {
+            self.remove_file(&child);
+        }
+    }
+
+    // 只删除路径完全相同的文件，返回是否在索引中
+    // 倒排表中的 id 留到整理时再清理
+    fn remove_file(&mut self, path: &Path) -> bool {
+        let Some(id) = self.ids.remove(path) else {
+            return false;
+        };
+        self.docs[id as usize].deleted = true;
+        self.deleted += 1;
+        true
+    }
+
+    // 索引中所有文件的路径
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.ids.keys().cloned().collect()
+    }
+
+    // 删除的文件超过四分之一时整理
+    pub fn compact_if_needed(&mut self) {
+        if self.deleted * 4 <= self.docs.len() {
+            return;
+        }
+        let mut mapping: Vec<Option<u32>> = vec![None; self.docs.len()];
+        let mut docs: Vec<Document> = Vec::with_capacity(self.len());
+        for (old, doc) in self.docs.drain(..).enumerate() {
+            if doc.deleted {
+                continue;
+            }
+            mapping[old] = Some(docs.len() as u32);
+            docs.push(doc);
+        }
+        for ids in self.postings.values_mut() {
+            *ids = ids.iter().filter_map(|id| mapping[*id as usize]).collect();
+        }
+        self.postings.retain(|_, ids| !ids.is_empty());
+        self.ids = docs
+            .iter()
+            .enumerate()
+            .map(|(id, doc)| (doc.path.clone(), id as u32))
+            .collect();
+        self.docs = docs;
+        self.deleted = 0;
+    }
+
+    // 包含 token 的文件，prefix 为 true 时包含以 token 开头的词
+    fn lookup(&self, token: &str, prefix: bool) -> Vec<u32> {
+        if !prefix {
+            return self.postings.get(token).cloned().unwrap_or_default();
+        }
+        let mut ids: Vec<u32> = self
+            .postings
+            .range::<str, _>((Bound::Included(token), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(token))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    // 可能包含查询内容的文件，按修改时间从新到旧
+    // 最后一个词按前缀匹配，便于边输入边搜索
+    pub fn candidates(&self, query: &str) -> Vec<u32> {
+        let tokens = tokenize(query);
+        let mut result: Option<Vec<u32>> = None;
+        for (i, token) in tokens.iter().enumerate() {
+            let ids = self.lookup(token, i + 1 == tokens.len());
+            result = Some(match result {
+                Some(current) => intersect(&current, &ids),
+                None => ids,
+            });
+            if result.as_ref().is_some_and(|ids| ids.is_empty()) {
+                break;
+            }
+        }
+        // 查询内容都是单个字母数字时没有可用的词，检查所有文件
+        let mut ids: Vec<u32> = result.unwrap_or_else(|| (0..self.docs.len() as u32).collect());
+        ids.retain(|id| !self.docs[*id as usize].deleted);
+        ids.sort_by_key(|id| std::cmp::Reverse(self.docs[*id as usize].modified));
+        ids
+    }
+
+    pub fn save(&self, path: &Path, config: &str) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        writer.write_all(MAGIC)?;
+        write_bytes(&mut writer, config.as_bytes())?;
+
+        // 保存时跳过已删除的文件，id 需要重新映射
+        let mut mapping: Vec<Option<u32>> = vec![None; self.docs.len()];
+        let mut count: u32 = 0;
+        for (id, doc) in self.docs.iter().enumerate() {
+            if !doc.deleted {
+                mapping[id] = Some(count);
+                count += 1;
+            }
+        }
+        writer.write_all(&count.to_le_bytes())?;
+        for doc in self.docs.iter().filter(|doc| !doc.deleted) {
+            write_bytes(&mut writer, doc.path.to_string_lossy().as_bytes())?;
+            writer.write_all(&doc.size.to_le_bytes())?;
+            writer.write_all(&doc.modified.to_le_bytes())?;
+        }
+        writer.write_all(&(self.postings.len() as u32).to_le_bytes())?;
+        for (token, ids) in self.postings.iter() {
+            let ids: Vec<u32> = ids.iter().filter_map(|id| mapping[*id as usize]).collect();
+            write_bytes(&mut writer, token.as_bytes())?;
+            writer.write_all(&(ids.len() as u32).to_le_bytes())?;
+            for id in ids {
+                writer.write_all(&id.to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(temp, path)
+    }
+
+    // 读取索引文件，配置不一致时返回 None
+    pub fn load(path: &Path, config: &str) -> io::Result<Option<Self>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_bytes(&mut reader)? != config.as_bytes() {
+            return Ok(None);
+        }
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid content index");
+        let mut index = ContentIndex::default();
+        let count = read_u32(&mut reader)?;
+        for id in 0..count {
+            let path =
+                PathBuf::from(String::from_utf8_lossy(&read_bytes(&mut reader)?).to_string());
+            let mut size = [0u8; 8];
+            reader.read_exact(&mut size)?;
+            let mut modified = [0u8; 8];
+            reader.read_exact(&mut modified)?;
+            index.ids.insert(path.clone(), id);
+            index.docs.push(Document {
+                path,
+                size: u64::from_le_bytes(size),
+                modified: i64::from_le_bytes(modified),
+                deleted: false,
+            });
+        }
+        let tokens = read_u32(&mut reader)?;
+        for _ in 0..tokens {
+            let token = String::from_utf8_lossy(&read_bytes(&mut reader)?).to_string();
+            let len = read_u32(&mut reader)?;
+            let mut ids: Vec<u32> = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let id = read_u32(&mut reader)?;
+                if id >= count {
+                    return Err(invalid());
+                }
+                ids.push(id);
+            }
+            index.postings.insert(token.into(), ids);
+        }
+        Ok(Some(index))
+    }
+}
+
+// 两个升序列表的交集
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                result.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    // 测试用的临时目录，结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "toolbox-content-index-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        // 写入文件并设置修改时间，age 越大越旧
+        fn write(&self, name: &str, text: &str, age: u64) -> PathBuf {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, text).unwrap();
+            let modified = SystemTime::now() - Duration::from_secs(age * 60);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn build(dir: &TempDir) -> ContentIndex {
+        let mut index = ContentIndex::default();
+        for (name, text, age) in [
+            ("notes.txt", "Meeting notes: budget review", 3),
+            ("docs/plan.md", "Project plan and budget", 1),
+            ("docs/中文.txt", "预算会议", 2),
+        ] {
+            let path = dir.write(name, text, age);
+            index.index_file(&path, 1024);
+        }
+        index
+    }
+
+    fn names(index: &ContentIndex, query: &str) -> Vec<String> {
+        index
+            .candidates(query)
+            .into_iter()
+            .map(|id| index.doc(id).unwrap().path.file_name().unwrap())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn finds_candidates() {
+        let dir = TempDir::new("candidates");
+        let index = build(&dir);
+        assert_eq!(index.len(), 3);
+        // 按修改时间从新到旧，最后一个词按前缀匹配
+        assert_eq!(names(&index, "budget"), ["plan.md", "notes.txt"]);
+        assert_eq!(names(&index, "BUD"), ["plan.md", "notes.txt"]);
+        assert_eq!(names(&index, "budget meet"), ["notes.txt"]);
+        assert!(names(&index, "meet budget").is_empty());
+        assert_eq!(names(&index, "会议"), ["中文.txt"]);
+        assert!(names(&index, "missing").is_empty());
+        // 没有可用的词时检查所有文件
+        assert_eq!(names(&index, "a b").len(), 3);
+    }
+
+    #[test]
+    fn removes_files_and_directories() {
+        let dir = TempDir::new("remove");
+        let mut index = build(&dir);
+        let notes = dir.0.join("notes.txt");
+        assert!(index.is_fresh(&notes, 28, index.doc(0).unwrap().modified));
+
+        // 重新建索引时替换旧的内容
+        let path = dir.write("notes.txt", "holiday", 0);
+        index.index_file(&path, 1024);
+        assert_eq!(index.len(), 3);
+        assert_eq!(names(&index, "holiday"), ["notes.txt"]);
+        assert_eq!(names(&index, "budget"), ["plan.md"]);
+        // 二进制文件只删除
+        fs::write(&path, b"\0\0").unwrap();
+        index.index_file(&path, 1024);
+        assert!(names(&index, "holiday").is_empty());
+
+        index.remove(&dir.0.join("docs"));
+        assert_eq!(index.len(), 0);
+        index.compact_if_needed();
+        assert!(index.paths().is_empty());
+        assert!(index.postings.is_empty());
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = TempDir::new("save");
+        let mut index = build(&dir);
+        index.remove(&dir.0.join("notes.txt"));
+        let file = dir.0.join("index.bin");
+        index.save(&file, "signature").unwrap();
+        let loaded = ContentIndex::load(&file, "signature").unwrap().unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.docs.len(), 2);
+        assert_eq!(names(&loaded, "budget"), ["plan.md"]);
+        assert_eq!(names(&loaded, "预算"), ["中文.txt"]);
+        let plan = dir.0.join("docs/plan.md");
+        let doc = &index.docs[index.ids[&plan] as usize];
+        assert!(loaded.is_fresh(&plan, doc.size, doc.modified));
+        // 配置变化后不使用旧的索引
+        assert!(ContentIndex::load(&file, "other").unwrap().is_none());
+    }
+
+    #[test]
+    fn intersects_sorted_ids() {
+        assert_eq!(intersect(&[1, 3, 5, 7], &[2, 3, 4, 7, 9]), [3, 7]);
+        assert!(intersect(&[1, 2], &[3, 4]).is_empty());
+        assert!(intersect(&[], &[1]).is_empty());
+    }
+}
@@ -0,0 +1,533 @@
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+use walkdir::WalkDir;
+
+use super::files::{FileIndexConfig, DEFAULT_IGNORE};
+use super::index::{ResultKind, SearchResult};
+use super::watcher;
+use crate::utils::store;
+
+pub mod index;
+pub mod text;
+
+pub use index::ContentIndex;
+
+// 使用 `in:` 前缀时搜索文件内容
+pub const QUERY_PREFIX: &str = "in:";
+
+// 索引配置在 store 中的键
+pub const CONFIG_KEY: &str = "content_index";
+// 索引文件，位于 app_cache_dir 中
+const INDEX_FILE: &str = "content-index.bin";
+// 两次写入索引文件的最小间隔
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// 一次搜索最多读取的文件数
+const MAX_VERIFY_FILES: usize = 500;
+// 每个文件最多返回的匹配行数，及每行最多显示的字符数
+const MAX_LINES_PER_FILE: usize = 3;
+const MAX_SNIPPET_LEN: usize = 160;
+
+// 默认只为常见的文本文件建索引
+const DEFAULT_EXTENSIONS: [&str; 24] = [
+    "txt", "md", "json", "toml", "yaml", "yml", "ini", "conf", "cfg", "xml", "csv", "log", "rs",
+    "js", "ts", "vue", "py", "php", "go", "java", "c", "h", "cpp", "sh",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContentIndexConfig {
+    pub enabled: bool,
+    pub roots: Vec<PathBuf>,
+    pub ignore: Vec<String>,
+    pub include_hidden: bool,
+    // 建索引的扩展名，为空时不限制
+    pub extensions: Vec<String>,
+    // 超过该大小（字节）的文件不建索引
+    pub max_file_size: u64,
+}
+
+impl Default for ContentIndexConfig {
+    fn default() -> Self {
+        ContentIndexConfig {
+            enabled: false,
+            roots: Vec::new(),
+            ignore: DEFAULT_IGNORE.iter().map(|p| p.to_string()).collect(),
+            include_hidden: false,
+            extensions: DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            max_file_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl ContentIndexConfig {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        store::get(app, CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    fn signature(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    // 忽略规则与文件索引相同
+    fn filter(&self) -> FileIndexConfig {
+        FileIndexConfig::new(self.roots.clone(), self.ignore.clone(), self.include_hidden)
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| self.extensions.contains(&ext))
+    }
+}
+
+// 一行匹配，highlights 为行内命中的字符范围 [start, end)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineMatch {
+    pub line: usize,
+    pub text: String,
+    pub highlights: Vec<(usize, usize)>,
+}
+
+// 找出包含查询词的行，所有词都出现在文件中时才返回
+fn match_lines(content: &str, words: &[String]) -> Option<Vec<LineMatch>> {
+    let lower = content.to_lowercase();
+    if !words.iter().all(|word| lower.contains(word.as_str())) {
+        return None;
+    }
+    let mut lines: Vec<(usize, LineMatch)> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let lower: Vec<char> = chars
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+        let mut highlights: Vec<(usize, usize)> = Vec::new();
+        let mut hits = 0;
+        for word in words.iter() {
+            let word: Vec<char> = word.chars().collect();
+            let mut found = false;
+            let mut start = 0;
+            while start + word.len() <= lower.len() {
+                if lower[start..start + word.len()] == word[..] {
+                    highlights.push((start, start + word.len()));
+                    found = true;
+                    start += word.len();
+                } else {
+                    start += 1;
+                }
+            }
+            if found {
+                hits += 1;
+            }
+        }
+        if hits == 0 {
+            continue;
+        }
+        highlights.sort_unstable();
+        // 行太长时只保留第一个命中附近的内容
+        let offset = highlights[0].0.saturating_sub(MAX_SNIPPET_LEN / 4);
+        let end = (offset + MAX_SNIPPET_LEN).min(chars.len());
+        let text: String = chars[offset..end].iter().collect();
+        let highlights = highlights
+            .into_iter()
+            .filter(|(s, e)| *s >= offset && *e <= end)
+            .map(|(s, e)| (s - offset, e - offset))
+            .collect();
+        lines.push((
+            hits,
+            LineMatch {
+                line: number + 1,
+                text,
+                highlights,
+            },
+        ));
+    }
+    // 命中词数多的行优先，其次按行号
+    lines.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.line.cmp(&b.1.line)));
+    Some(
+        lines
+            .into_iter()
+            .take(MAX_LINES_PER_FILE)
+            .map(|(_, line)| line)
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentIndexStatus {
+    pub enabled: bool,
+    pub building: bool,
+    pub files: usize,
+}
+
+// 文件内容索引服务
+#[derive(Default)]
+pub struct ContentSearch {
+    index: RwLock<ContentIndex>,
+    config: RwLock<ContentIndexConfig>,
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    building: AtomicBool,
+    generation: AtomicU64,
+}
+
+impl ContentSearch {
+    pub fn status(&self) -> ContentIndexStatus {
+        ContentIndexStatus {
+            enabled: self.config.read().unwrap().enabled,
+            building: self.building.load(Ordering::SeqCst),
+            files: self.index.read().unwrap().len(),
+        }
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let config = self.config.read().unwrap().clone();
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect();
+        if !config.enabled || words.is_empty() {
+            return Vec::new();
+        }
+        // 先在索引中取出候选文件，再读取文件确认并取出匹配的行
+        let candidates: Vec<(PathBuf, i64)> = {
+            let index = self.index.read().unwrap();
+            index
+                .candidates(query)
+                .into_iter()
+                .take(MAX_VERIFY_FILES)
+                .filter_map(|id| index.doc(id).map(|doc| (doc.path.clone(), doc.modified)))
+                .collect()
+        };
+        let mut results: Vec<SearchResult> = Vec::new();
+        for (path, modified) in candidates {
+            if results.len() >= limit {
+                break;
+            }
+            let content = match text::read_text(&path, config.max_file_size) {
+                Some(content) => content,
+                None => continue,
+            };
+            let lines = match match_lines(&content, &words) {
+                Some(lines) if !lines.is_empty() => lines,
+                _ => continue,
+            };
+            let path_str = path.to_string_lossy().to_string();
+            results.push(SearchResult {
+                id: format!("content:{}", path_str),
+                kind: ResultKind::Content,
+                title: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or(path_str.clone()),
+                subtitle: Some(format!("{}: {}", lines[0].line, lines[0].text.trim())),
+                icon: None,
+                // 候选文件已按修改时间排序，越靠前分越高
+                score: 500.0 - results.len() as f64,
+                highlights: Vec::new(),
+                data: json!({
+                    "path": path_str,
+                    "modified": modified,
+                    "matches": lines,
+                }),
+                labels: Vec::new(),
+                actions: Vec::new(),
+            });
+        }
+        results
+    }
+}
+
+fn index_file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(INDEX_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>) {
+    let content = app.state::<ContentSearch>();
+    let signature = content.config.read().unwrap().signature();
+    let result = index_file(app).and_then(|file| {
+        content
+            .index
+            .read()
+            .unwrap()
+            .save(&file, &signature)
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to save content index: {}", e);
+    }
+}
+
+// 遍历根目录，只为新增或修改过的文件重新建索引，并删除已不存在的文件
+fn sync(content: &ContentSearch, config: &ContentIndexConfig, generation: u64) {
+    let filter = config.filter();
+    let mut stale: HashSet<PathBuf> = content.index.read().unwrap().paths().into_iter().collect();
+    for root in config.roots.iter() {
+        let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+            entry.depth() == 0 || !filter.is_ignored(entry.path(), entry.metadata().ok().as_ref())
+        });
+        for entry in walker.filter_map(|entry| entry.ok()) {
+            if content.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if !entry.file_type().is_file() || !config.accepts(entry.path()) {
+                continue;
+            }
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if metadata.len() > config.max_file_size {
+                continue;
+            }
+            let path = entry.path();
+            stale.remove(path);
+            let modified = super::files::index::modified_millis(&metadata);
+            if content
+                .index
+                .read()
+                .unwrap()
+                .is_fresh(path, metadata.len(), modified)
+            {
+                continue;
+            }
+            // 读取文件在锁外进行，避免长时间阻塞搜索
+            let text = text::read_text(path, config.max_file_size);
+            content.index.write().unwrap().insert(path, text.as_deref());
+        }
+    }
+    let mut index = content.index.write().unwrap();
+    for path in stale {
+        index.remove(&path);
+    }
+    index.compact_if_needed();
+}
+
+// 处理一批文件变化
+fn apply_changes(index: &mut ContentIndex, config: &ContentIndexConfig, paths: &[PathBuf]) {
+    let filter = config.filter();
+    for path in paths {
+        if !path.exists() {
+            index.remove(path);
+            continue;
+        }
+        if filter.is_ignored(path, None) {
+            continue;
+        }
+        if path.is_dir() {
+            // 新建或移入的目录
+            let walker = WalkDir::new(path).into_iter().filter_entry(|entry| {
+                entry.depth() == 0
+                    || !filter.is_ignored(entry.path(), entry.metadata().ok().as_ref())
+            });
+            for entry in walker.filter_map(|entry| entry.ok()) {
+                if entry.file_type().is_file() && config.accepts(entry.path()) {
+                    index.index_file(entry.path(), config.max_file_size);
+                }
+            }
+        } else if config.accepts(path) {
+            index.index_file(path, config.max_file_size);
+        }
+    }
+    index.compact_if_needed();
+}
+
+// 启动时调用：读取上次的索引，在后台增量更新并监听变化
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let config = ContentIndexConfig::load(app);
+    let content = app.state::<ContentSearch>();
+    *content.config.write().unwrap() = config.clone();
+    if !config.enabled {
+        return;
+    }
+    if let Ok(file) = index_file(app) {
+        if let Ok(Some(index)) = ContentIndex::load(&file, &config.signature()) {
+            *content.index.write().unwrap() = index;
+        }
+    }
+    reindex(app);
+}
+
+// 配置在别处修改后（如导入备份）重新读取并同步索引
+pub fn reload<R: Runtime>(app: &AppHandle<R>) {
+    *app.state::<ContentSearch>().config.write().unwrap() = ContentIndexConfig::load(app);
+    reindex(app);
+}
+
+// 在后台同步索引，完成后开始监听文件变化
+pub fn reindex<R: Runtime>(app: &AppHandle<R>) {
+    let content = app.state::<ContentSearch>();
+    let generation = content.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    *content.watcher.lock().unwrap() = None;
+    let config = content.config.read().unwrap().clone();
+    if !config.enabled {
+        *content.index.write().unwrap() = ContentIndex::default();
+        return;
+    }
+    content.building.store(true, Ordering::SeqCst);
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let content = app.state::<ContentSearch>();
+        sync(&content, &config, generation);
+        if content.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        content.building.store(false, Ordering::SeqCst);
+        save(&app);
+        if let Err(e) = watch(&app, &config, generation) {
+            eprintln!("Failed to watch content index roots: {}", e);
+        }
+    });
+}
+
+// path 及其下没有被忽略的目录
+fn dirs(filter: &FileIndexConfig, path: &Path) -> Vec<PathBuf> {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !filter.is_ignored(entry.path(), entry.metadata().ok().as_ref())
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+// 监听根目录的变化，增量更新索引
+// 和文件索引一样，Linux 上只逐个监听没有被忽略的目录，避免 inotify 监听数量浪费在 node_modules 等目录上
+fn watch<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &ContentIndexConfig,
+    generation: u64,
+) -> Result<(), String> {
+    let filter = config.filter();
+    #[cfg(target_os = "linux")]
+    let (paths, mode) = (
+        config
+            .roots
+            .iter()
+            .flat_map(|root| dirs(&filter, root))
+            .collect::<Vec<PathBuf>>(),
+        RecursiveMode::NonRecursive,
+    );
+    #[cfg(not(target_os = "linux"))]
+    let (paths, mode) = (config.roots.clone(), RecursiveMode::Recursive);
+    let watcher = {
+        let app = app.clone();
+        let config = config.clone();
+        let mut watched: HashSet<PathBuf> = paths.iter().cloned().collect();
+        let mut last_save = Instant::now();
+        let mut dirty = false;
+        watcher::watch_with(&paths, mode, move |changes| {
+            let content = app.state::<ContentSearch>();
+            if content.generation.load(Ordering::SeqCst) != generation {
+                return false;
+            }
+            if changes.rescan {
+                reindex(&app);
+                return false;
+            }
+            if !changes.paths.is_empty() {
+                let paths: Vec<PathBuf> = changes.paths.into_iter().collect();
+                apply_changes(&mut content.index.write().unwrap(), &config, &paths);
+                // 新建或移入的目录及其下的目录需要加上监听
+                let mut added = Vec::new();
+                for path in paths.iter() {
+                    if mode == RecursiveMode::NonRecursive
+                        && path.is_dir()
+                        && !watched.contains(path)
+                        && !filter.is_ignored(path, None)
+                    {
+                        added.extend(dirs(&filter, path));
+                    }
+                }
+                added.retain(|dir| watched.insert(dir.clone()));
+                if let Some(watcher) = content.watcher.lock().unwrap().as_mut() {
+                    for dir in added {
+                        if let Err(e) = watcher.watch(&dir, mode) {
+                            eprintln!("Failed to watch {}: {}", dir.display(), e);
+                        }
+                    }
+                }
+                dirty = true;
+            }
+            if dirty && last_save.elapsed() >= SAVE_INTERVAL {
+                save(&app);
+                last_save = Instant::now();
+                dirty = false;
+            }
+            true
+        })?
+    };
+    // 监听期间配置已经变化时丢弃
+    let content = app.state::<ContentSearch>();
+    if content.generation.load(Ordering::SeqCst) == generation {
+        *content.watcher.lock().unwrap() = Some(watcher);
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn search_content(
+    content: State<'_, ContentSearch>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, String> {
+    Ok(content.search(&query, limit.unwrap_or(20)))
+}
+
+#[command]
+pub fn get_content_index_status(content: State<'_, ContentSearch>) -> ContentIndexStatus {
+    content.status()
+}
+
+#[command]
+pub fn get_content_index_config(app: AppHandle) -> ContentIndexConfig {
+    ContentIndexConfig::load(&app)
+}
+
+// 保存配置并重新同步索引
+#[command]
+pub fn set_content_index_config(
+    app: AppHandle,
+    content: State<'_, ContentSearch>,
+    config: ContentIndexConfig,
+) -> Result<ContentIndexStatus, String> {
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store::set(&app, CONFIG_KEY, Some(value))?;
+    *content.config.write().unwrap() = config;
+    reindex(&app);
+    Ok(content.status())
+}
+
+#[command]
+pub fn rebuild_content_index(
+    app: AppHandle,
+    content: State<'_, ContentSearch>,
+) -> ContentIndexStatus {
+    *content.index.write().unwrap() = ContentIndex::default();
+    reindex(&app);
+    content.status()
+}
@@ -0,0 +1,109 @@
+use encoding_rs::Encoding;
+use std::{fs, path::Path};
+
+// 检查是否为二进制文件时读取的字节数
+const SNIFF_SIZE: usize = 8192;
+// 过长的词不建索引
+const MAX_TOKEN_LEN: usize = 64;
+
+// 以 NUL 字节判断是否为二进制文件，UTF-16 文件有 BOM，在此之前已经处理
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_SIZE)].contains(&0)
+}
+
+// 识别编码并解码，二进制文件返回 None
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Some(text.into_owned());
+    }
+    if is_binary(bytes) {
+        return None;
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Some(text.to_string());
+    }
+    // 不是 UTF-8 时猜测编码，如 GBK、Shift_JIS
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _, _) = encoding.decode(bytes);
+    Some(text.into_owned())
+}
+
+// 读取文本文件，超过大小限制或是二进制文件时返回 None
+pub fn read_text(path: &Path, max_size: u64) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > max_size {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    decode(&bytes)
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // 日文假名
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xAC00..=0xD7AF // 韩文
+        | 0xF900..=0xFAFF
+        | 0x20000..=0x2FA1F)
+}
+
+// 拆分为小写的词：连续的字母数字为一个词，中日韩文字每个字单独为一个词
+// 单个的字母数字太常见，不建索引
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let flush = |current: &mut String, tokens: &mut Vec<String>| {
+        let len = current.chars().count();
+        if len > 1 && len <= MAX_TOKEN_LEN {
+            tokens.push(current.clone());
+        }
+        current.clear();
+    };
+    for c in text.chars() {
+        if is_cjk(c) {
+            flush(&mut current, &mut tokens);
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() || c == '_' {
+            current.extend(c.to_lowercase());
+        } else {
+            flush(&mut current, &mut tokens);
+        }
+    }
+    flush(&mut current, &mut tokens);
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_text() {
+        assert_eq!(
+            tokenize("Hello, World! a_b x 42 中文字"),
+            ["hello", "world", "a_b", "42", "中", "文", "字"]
+        );
+        // 汉字和字母相邻时也分开，单个字母数字和过长的词不建索引
+        assert_eq!(tokenize("abc中def"), ["abc", "中", "def"]);
+        let long = "x".repeat(MAX_TOKEN_LEN + 1);
+        assert_eq!(tokenize(&format!("{} ok I", long)), ["ok"]);
+        assert_eq!(tokenize("ÄÖÜ straße"), ["äöü", "straße"]);
+        assert!(tokenize(" - ! ").is_empty());
+    }
+
+    #[test]
+    fn decodes_text() {
+        assert_eq!(decode("你好".as_bytes()).as_deref(), Some("你好"));
+        assert_eq!(decode(b"\xff\xfeh\0i\0").as_deref(), Some("hi"));
+        assert_eq!(decode(b"a\0b"), None);
+        // GBK 编码的中文
+        assert_eq!(
+            decode(b"\xc4\xe3\xba\xc3\xa3\xac\xca\xc0\xbd\xe7").as_deref(),
+            Some("你好，世界")
+        );
+    }
+}
@@ -0,0 +1,599 @@
+use globset::{Glob, GlobMatcher};
+use std::{
+    collections::HashMap,
+    fs::{self, File, Metadata},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use walkdir::WalkDir;
+
+use super::FileIndexConfig;
+
+// 索引文件的标识和版本，格式变化时增加版本号
+const MAGIC: &[u8; 8] = b"VTFIDX01";
+// 根目录没有父节点
+const NO_PARENT: u32 = u32::MAX;
+
+const FLAG_DIR: u8 = 1;
+
+// 索引中的一个文件或目录
+// 只保存文件名和父节点，完整路径在需要时向上拼接
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    // 根目录为完整路径，其余为文件名
+    pub name: Box<str>,
+    // 小写的文件名，与 name 相同时为空
+    lower: Option<Box<str>>,
+    pub parent: u32,
+    pub is_dir: bool,
+    pub size: u64,
+    // 毫秒时间戳
+    pub modified: i64,
+    deleted: bool,
+}
+
+impl FileEntry {
+    fn new(name: &str, parent: u32, metadata: Option<&Metadata>) -> Self {
+        let lower = name.to_lowercase();
+        FileEntry {
+            name: name.into(),
+            lower: if lower == name {
+                None
+            } else {
+                Some(lower.into())
+            },
+            parent,
+            is_dir: metadata.is_none_or(|m| m.is_dir()),
+            size: metadata.filter(|m| m.is_file()).map_or(0, |m| m.len()),
+            modified: metadata.map_or(0, modified_millis),
+            deleted: false,
+        }
+    }
+
+    pub fn lower(&self) -> &str {
+        self.lower.as_deref().unwrap_or(&self.name)
+    }
+
+    fn update(&mut self, metadata: &Metadata) {
+        self.is_dir = metadata.is_dir();
+        self.size = if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        };
+        self.modified = modified_millis(metadata);
+    }
+}
+
+pub(crate) fn modified_millis(metadata: &Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+// 文件查询：带 `*`、`?`、`[` 的按 glob 匹配，其余按文件名的前缀和子串匹配
+pub enum FileQuery {
+    Text(String),
+    // 第二项为 true 时匹配完整路径，否则只匹配文件名
+    Glob(GlobMatcher, bool),
+}
+
+impl FileQuery {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let query = query.trim();
+        if query.contains(['*', '?', '[']) {
+            let full_path = query.contains(['/', '\\']);
+            let glob = Glob::new(&query.to_lowercase()).map_err(|e| e.to_string())?;
+            Ok(FileQuery::Glob(glob.compile_matcher(), full_path))
+        } else {
+            Ok(FileQuery::Text(query.to_lowercase()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FileMatch {
+    pub index: u32,
+    pub score: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct FileIndex {
+    entries: Vec<FileEntry>,
+    // 目录下的子节点，用于按路径查找
+    children: HashMap<u32, Vec<u32>>,
+    deleted: usize,
+}
+
+impl FileIndex {
+    // 遍历配置中的所有根目录建立索引
+    pub fn build(config: &FileIndexConfig) -> Self {
+        let mut index = FileIndex::default();
+        for root in config.roots.iter() {
+            if !root.is_dir() {
+                continue;
+            }
+            let metadata = fs::metadata(root).ok();
+            let id = index.push(FileEntry::new(
+                &root.to_string_lossy(),
+                NO_PARENT,
+                metadata.as_ref(),
+            ));
+            index.walk(id, root, config);
+        }
+        index
+    }
+
+    // 遍历 dir 下的内容，加到 id 节点下
+    fn walk(&mut self, id: u32, dir: &Path, config: &FileIndexConfig) {
+        // 每一层目录对应的节点，深度为下标
+        let mut stack: Vec<u32> = vec![id];
+        let walker = WalkDir::new(dir)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !config.is_ignored(entry.path(), entry.metadata().ok().as_ref())
+            });
+        for entry in walker.filter_map(|entry| entry.ok()) {
+            let depth = entry.depth();
+            stack.truncate(depth);
+            let parent = match stack.last() {
+                Some(parent) => *parent,
+                None => continue,
+            };
+            let metadata = entry.metadata().ok();
+            let name = entry.file_name().to_string_lossy();
+            let child = self.push(FileEntry::new(&name, parent, metadata.as_ref()));
+            if entry.file_type().is_dir() {
+                stack.push(child);
+            }
+        }
+    }
+
+    fn push(&mut self, entry: FileEntry) -> u32 {
+        let id = self.entries.len() as u32;
+        if entry.parent != NO_PARENT {
+            self.children.entry(entry.parent).or_default().push(id);
+        }
+        self.entries.push(entry);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.deleted
+    }
+
+    pub fn entry(&self, id: u32) -> Option<&FileEntry> {
+        self.entries.get(id as usize).filter(|entry| !entry.deleted)
+    }
+
+    // 拼接完整路径
+    pub fn path(&self, id: u32) -> PathBuf {
+        let mut names: Vec<&str> = Vec::new();
+        let mut current = id;
+        while let Some(entry) = self.entries.get(current as usize) {
+            names.push(&entry.name);
+            current = entry.parent;
+        }
+        names.iter().rev().collect()
+    }
+
+    // 按路径查找节点
+    pub fn find(&self, path: &Path) -> Option<u32> {
+        let (mut current, relative) = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.parent == NO_PARENT && !entry.deleted)
+            .find_map(|(id, entry)| {
+                path.strip_prefix(&*entry.name)
+                    .ok()
+                    .map(|rest| (id as u32, rest.to_path_buf()))
+            })?;
+        for component in relative.iter() {
+            let name = component.to_string_lossy();
+            current = *self
+                .children
+                .get(&current)?
+                .iter()
+                .find(|child| *self.entries[**child as usize].name == *name)?;
+        }
+        Some(current)
+    }
+
+    // 索引中 path 及其下的所有目录，path 为 None 时为所有根目录及其下的目录
+    pub fn dirs(&self, path: Option<&Path>) -> Vec<PathBuf> {
+        let mut pending: Vec<u32> = match path {
+            Some(path) => self.find(path).into_iter().collect(),
+            None => self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.parent == NO_PARENT && !entry.deleted)
+                .map(|(id, _)| id as u32)
+                .collect(),
+        };
+        let mut dirs = Vec::new();
+        while let Some(id) = pending.pop() {
+            if !self.entries[id as usize].is_dir {
+                continue;
+            }
+            dirs.push(self.path(id));
+            if let Some(children) = self.children.get(&id) {
+                pending.extend(children);
+            }
+        }
+        dirs
+    }
+
+    // 新增或更新一个路径，新增的目录会遍历其内容
+    pub fn upsert(&mut self, path: &Path, config: &FileIndexConfig) {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return self.remove(path),
+        };
+        if let Some(id) = self.find(path) {
+            let was_dir = self.entries[id as usize].is_dir;
+            self.entries[id as usize].update(&metadata);
+            if was_dir != metadata.is_dir() {
+                self.remove_children(id);
+                if metadata.is_dir() {
+                    self.walk(id, path, config);
+                }
+            }
+            return;
+        }
+        let (parent, name) = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => (parent, name.to_string_lossy()),
+            _ => return,
+        };
+        if config.is_ignored(path, Some(&metadata)) {
+            return;
+        }
+        // 父目录不在索引中（被忽略或不在根目录下）时不处理
+        let parent = match self.find(parent) {
+            Some(parent) => parent,
+            None => return,
+        };
+        let id = self.push(FileEntry::new(&name, parent, Some(&metadata)));
+        if metadata.is_dir() {
+            self.walk(id, path, config);
+        }
+    }
+
+    // 删除一个路径及其下的所有内容
+    pub fn remove(&mut self, path: &Path) {
+        let id = match self.find(path) {
+            Some(id) => id,
+            None => return,
+        };
+        let parent = self.entries[id as usize].parent;
+        if let Some(siblings) = self.children.get_mut(&parent) {
+            siblings.retain(|child| *child != id);
+        }
+        self.remove_children(id);
+        self.entries[id as usize].deleted = true;
+        self.deleted += 1;
+    }
+
+    fn remove_children(&mut self, id: u32) {
+        let mut pending = self.children.remove(&id).unwrap_or_default();
+        while let Some(child) = pending.pop() {
+            self.entries[child as usize].deleted = true;
+            self.deleted += 1;
+            if let Some(children) = self.children.remove(&child) {
+                pending.extend(children);
+            }
+        }
+    }
+
+    // 删除的节点超过四分之一时整理，去掉已删除的节点
+    pub fn compact_if_needed(&mut self) {
+        if self.deleted * 4 <= self.entries.len() {
+            return;
+        }
+        let mut mapping: Vec<u32> = vec![NO_PARENT; self.entries.len()];
+        let mut entries: Vec<FileEntry> = Vec::with_capacity(self.len());
+        for (old, entry) in self.entries.drain(..).enumerate() {
+            if entry.deleted {
+                continue;
+            }
+            mapping[old] = entries.len() as u32;
+            entries.push(entry);
+        }
+        let mut index = FileIndex::default();
+        for mut entry in entries {
+            if entry.parent != NO_PARENT {
+                entry.parent = mapping[entry.parent as usize];
+            }
+            index.push(entry);
+        }
+        *self = index;
+    }
+
+    // 查询，返回得分最高的 limit 项
+    pub fn query(&self, query: &FileQuery, limit: usize) -> Vec<FileMatch> {
+        let mut matches: Vec<FileMatch> = Vec::new();
+        for (id, entry) in self.entries.iter().enumerate() {
+            if entry.deleted || entry.parent == NO_PARENT {
+                continue;
+            }
+            let score = match query {
+                FileQuery::Text(text) => {
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let name = entry.lower();
+                    if name == text {
+                        300.0
+                    } else if name.starts_with(text.as_str()) {
+                        250.0
+                    } else if name.contains(text.as_str()) {
+                        200.0
+                    } else {
+                        continue;
+                    }
+                }
+                FileQuery::Glob(glob, false) => {
+                    if glob.is_match(entry.lower()) {
+                        200.0
+                    } else {
+                        continue;
+                    }
+                }
+                FileQuery::Glob(glob, true) => {
+                    let path = self.path(id as u32).to_string_lossy().to_lowercase();
+                    if glob.is_match(path) {
+                        200.0
+                    } else {
+                        continue;
+                    }
+                }
+            };
+            // 文件名越短越接近查询内容
+            let score = score - (entry.name.chars().count() as f64).min(50.0);
+            matches.push(FileMatch {
+                index: id as u32,
+                score,
+            });
+        }
+        let cmp = |a: &FileMatch, b: &FileMatch| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(
+                    self.entries[b.index as usize]
+                        .modified
+                        .cmp(&self.entries[a.index as usize].modified),
+                )
+        };
+        if matches.len() > limit && limit > 0 {
+            matches.select_nth_unstable_by(limit - 1, cmp);
+            matches.truncate(limit);
+        }
+        matches.sort_by(cmp);
+        matches.truncate(limit);
+        matches
+    }
+
+    // 保存为紧凑的二进制格式，config 用于下次读取时判断配置是否变化
+    pub fn save(&self, path: &Path, config: &str) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        writer.write_all(MAGIC)?;
+        write_bytes(&mut writer, config.as_bytes())?;
+
+        // 保存时跳过已删除的节点，父节点的下标需要重新映射
+        let mut mapping: Vec<u32> = vec![NO_PARENT; self.entries.len()];
+        let mut count: u32 = 0;
+        for (id, entry) in self.entries.iter().enumerate() {
+            if !entry.deleted {
+                mapping[id] = count;
+                count += 1;
+            }
+        }
+        writer.write_all(&count.to_le_bytes())?;
+        for entry in self.entries.iter().filter(|entry| !entry.deleted) {
+            let parent = if entry.parent == NO_PARENT {
+                NO_PARENT
+            } else {
+                mapping[entry.parent as usize]
+            };
+            writer.write_all(&parent.to_le_bytes())?;
+            writer.write_all(&[if entry.is_dir { FLAG_DIR } else { 0 }])?;
+            writer.write_all(&entry.size.to_le_bytes())?;
+            writer.write_all(&entry.modified.to_le_bytes())?;
+            write_bytes(&mut writer, entry.name.as_bytes())?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(temp, path)
+    }
+
+    // 读取索引文件，配置不一致时返回 None
+    pub fn load(path: &Path, config: &str) -> io::Result<Option<Self>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_bytes(&mut reader)? != config.as_bytes() {
+            return Ok(None);
+        }
+        let count = read_u32(&mut reader)?;
+        let mut index = FileIndex::default();
+        index.entries.reserve(count as usize);
+        for _ in 0..count {
+            let parent = read_u32(&mut reader)?;
+            let mut flags = [0u8; 1];
+            reader.read_exact(&mut flags)?;
+            let mut size = [0u8; 8];
+            reader.read_exact(&mut size)?;
+            let mut modified = [0u8; 8];
+            reader.read_exact(&mut modified)?;
+            let name = String::from_utf8_lossy(&read_bytes(&mut reader)?).to_string();
+            let mut entry = FileEntry::new(&name, parent, None);
+            entry.is_dir = flags[0] & FLAG_DIR != 0;
+            entry.size = u64::from_le_bytes(size);
+            entry.modified = i64::from_le_bytes(modified);
+            if parent != NO_PARENT && parent >= index.entries.len() as u32 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid file index",
+                ));
+            }
+            index.push(entry);
+        }
+        Ok(Some(index))
+    }
+}
+
+pub(crate) fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+pub(crate) fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_u32(reader)? as usize;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::files::DEFAULT_IGNORE;
+
+    // 测试用的临时目录，结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "toolbox-file-index-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            for path in [
+                "docs/report.pdf",
+                "docs/notes.txt",
+                "src/main.rs",
+                "node_modules/pkg/index.js",
+                ".cache/data.bin",
+            ] {
+                let path = dir.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, b"x").unwrap();
+            }
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn config(root: &Path) -> FileIndexConfig {
+        FileIndexConfig::new(
+            vec![root.to_path_buf()],
+            DEFAULT_IGNORE.iter().map(|p| p.to_string()).collect(),
+            false,
+        )
+    }
+
+    fn names(index: &FileIndex, query: &str) -> Vec<String> {
+        let query = FileQuery::parse(query).unwrap();
+        index
+            .query(&query, 10)
+            .into_iter()
+            .map(|m| index.entry(m.index).unwrap().name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn skips_ignored_and_hidden_paths() {
+        let dir = TempDir::new("ignore");
+        let index = FileIndex::build(&config(&dir.0));
+        assert_eq!(names(&index, "index.js"), Vec::<String>::new());
+        assert_eq!(names(&index, "data"), Vec::<String>::new());
+        assert_eq!(names(&index, "main"), ["main.rs"]);
+        // 根目录、docs、src 和 3 个文件
+        assert_eq!(index.len(), 6);
+
+        let mut dirs = index.dirs(None);
+        dirs.sort();
+        assert_eq!(dirs, [dir.0.clone(), dir.0.join("docs"), dir.0.join("src")]);
+        assert_eq!(index.dirs(Some(&dir.0.join("src"))), [dir.0.join("src")]);
+        assert!(index.dirs(Some(&dir.0.join("node_modules"))).is_empty());
+    }
+
+    #[test]
+    fn matches_names_and_globs() {
+        let dir = TempDir::new("query");
+        let index = FileIndex::build(&config(&dir.0));
+        assert_eq!(names(&index, "REPORT"), ["report.pdf"]);
+        assert_eq!(names(&index, "*.txt"), ["notes.txt"]);
+        assert_eq!(names(&index, "**/src/*"), ["main.rs"]);
+        assert_eq!(names(&index, "docs"), ["docs"]);
+        assert!(FileQuery::parse("[").is_err());
+    }
+
+    #[test]
+    fn applies_changes() {
+        let dir = TempDir::new("upsert");
+        let config = config(&dir.0);
+        let mut index = FileIndex::build(&config);
+
+        let nested = dir.0.join("new/deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("added.md"), b"x").unwrap();
+        index.upsert(&dir.0.join("new"), &config);
+        assert_eq!(names(&index, "added"), ["added.md"]);
+        assert_eq!(index.dirs(Some(&dir.0.join("new"))).len(), 2);
+
+        // 被忽略的目录中的变化不加入索引
+        fs::create_dir_all(dir.0.join("src/target")).unwrap();
+        index.upsert(&dir.0.join("src/target"), &config);
+        index.upsert(&dir.0.join("node_modules/pkg/index.js"), &config);
+        assert!(index.find(&dir.0.join("src/target")).is_none());
+        assert_eq!(names(&index, "index"), Vec::<String>::new());
+
+        fs::remove_dir_all(dir.0.join("docs")).unwrap();
+        index.upsert(&dir.0.join("docs"), &config);
+        assert_eq!(names(&index, "report"), Vec::<String>::new());
+        index.compact_if_needed();
+        assert_eq!(names(&index, "main"), ["main.rs"]);
+        assert_eq!(
+            index.path(index.find(&dir.0.join("src/main.rs")).unwrap()),
+            dir.0.join("src/main.rs")
+        );
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = TempDir::new("save");
+        let index = FileIndex::build(&config(&dir.0));
+        let file = dir.0.join("index.bin");
+        index.save(&file, "signature").unwrap();
+        let loaded = FileIndex::load(&file, "signature").unwrap().unwrap();
+        assert_eq!(loaded.len(), index.len());
+        assert_eq!(names(&loaded, "notes"), ["notes.txt"]);
+        // 配置变化后不使用旧的索引
+        assert!(FileIndex::load(&file, "other").unwrap().is_none());
+    }
+}
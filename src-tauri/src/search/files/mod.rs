@@ -0,0 +1,475 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{command, AppHandle, Manager, Runtime, State, Url};
+
+use super::index::{ResultKind, SearchResult};
+use super::watcher;
+use crate::utils::store;
+
+pub mod index;
+
+pub use index::{FileIndex, FileQuery};
+
+// 索引配置在 store 中的键
+pub const CONFIG_KEY: &str = "file_index";
+// 索引文件，位于 app_cache_dir 中
+const INDEX_FILE: &str = "file-index.bin";
+// 两次写入索引文件的最小间隔
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// 索引文件超过这个时间没有更新时启动后重新遍历，关闭期间的变化没有记录
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 默认忽略的目录
+pub const DEFAULT_IGNORE: [&str; 6] = [
+    "**/node_modules",
+    "**/.git",
+    "**/target",
+    "**/__pycache__",
+    "**/$RECYCLE.BIN",
+    "**/.Trash*",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FileIndexConfig {
+    pub enabled: bool,
+    pub roots: Vec<PathBuf>,
+    // glob 格式，匹配完整路径
+    pub ignore: Vec<String>,
+    // 是否包含隐藏文件
+    pub include_hidden: bool,
+    #[serde(skip)]
+    ignore_set: Option<GlobSet>,
+}
+
+impl Default for FileIndexConfig {
+    fn default() -> Self {
+        // 遍历整个用户目录开销较大，需要在设置中开启
+        FileIndexConfig {
+            enabled: false,
+            roots: Vec::new(),
+            ignore: DEFAULT_IGNORE.iter().map(|p| p.to_string()).collect(),
+            include_hidden: false,
+            ignore_set: None,
+        }
+    }
+}
+
+impl FileIndexConfig {
+    pub fn new(roots: Vec<PathBuf>, ignore: Vec<String>, include_hidden: bool) -> Self {
+        FileIndexConfig {
+            enabled: true,
+            roots,
+            ignore,
+            include_hidden,
+            ignore_set: None,
+        }
+        .compile()
+    }
+
+    // 读取配置，没有配置根目录时使用用户目录
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let mut config: FileIndexConfig = store::get(app, CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+        if config.roots.is_empty() {
+            if let Ok(home) = app.path().home_dir() {
+                config.roots.push(home);
+            }
+        }
+        config.compile()
+    }
+
+    fn compile(mut self) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in self.ignore.iter() {
+            match Glob::new(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => eprintln!("Invalid ignore pattern `{}`: {}", pattern, e),
+            }
+        }
+        self.ignore_set = builder.build().ok();
+        self
+    }
+
+    // 写入索引文件头，配置变化后旧的索引文件失效
+    fn signature(&self) -> String {
+        json!({
+            "roots": self.roots,
+            "ignore": self.ignore,
+            "includeHidden": self.include_hidden,
+        })
+        .to_string()
+    }
+
+    pub fn is_ignored(&self, path: &Path, metadata: Option<&Metadata>) -> bool {
+        if !self.include_hidden && is_hidden(path, metadata) {
+            return true;
+        }
+        self.ignore_set
+            .as_ref()
+            .is_some_and(|set| set.is_match(path))
+    }
+}
+
+fn is_hidden(path: &Path, _metadata: Option<&Metadata>) -> bool {
+    if path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    {
+        return true;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Some(metadata) = _metadata {
+            return metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+        }
+    }
+    false
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileIndexStatus {
+    pub enabled: bool,
+    pub building: bool,
+    pub entries: usize,
+    pub roots: Vec<PathBuf>,
+}
+
+// 文件索引服务
+#[derive(Default)]
+pub struct FileSearch {
+    index: RwLock<FileIndex>,
+    config: RwLock<FileIndexConfig>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    building: AtomicBool,
+    // 每次重建加一，旧的重建和监听线程发现代数变化后退出
+    generation: AtomicU64,
+}
+
+impl FileSearch {
+    pub fn status(&self) -> FileIndexStatus {
+        let config = self.config.read().unwrap();
+        FileIndexStatus {
+            enabled: config.enabled,
+            building: self.building.load(Ordering::SeqCst),
+            entries: self.index.read().unwrap().len(),
+            roots: config.roots.clone(),
+        }
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        if !self.config.read().unwrap().enabled || query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = FileQuery::parse(query)?;
+        let index = self.index.read().unwrap();
+        let results = index
+            .query(&query, limit)
+            .into_iter()
+            .filter_map(|m| {
+                let entry = index.entry(m.index)?;
+                let path = index.path(m.index);
+                let path_str = path.to_string_lossy().to_string();
+                Some(SearchResult {
+                    id: format!("file:{}", path_str),
+                    kind: ResultKind::File,
+                    title: entry.name.to_string(),
+                    subtitle: Some(path_str.clone()),
+                    icon: None,
+                    score: m.score,
+                    highlights: Vec::new(),
+                    data: json!({
+                        "path": path_str,
+                        "isDir": entry.is_dir,
+                        "size": entry.size,
+                        "modified": entry.modified,
+                    }),
+                    labels: Vec::new(),
+                    actions: Vec::new(),
+                })
+            })
+            .collect();
+        Ok(results)
+    }
+}
+
+fn index_file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(INDEX_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>) {
+    let files = app.state::<FileSearch>();
+    let signature = files.config.read().unwrap().signature();
+    let result = index_file(app).and_then(|file| {
+        files
+            .index
+            .read()
+            .unwrap()
+            .save(&file, &signature)
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to save file index: {}", e);
+    }
+}
+
+// 启动时调用：先读取上次的索引文件以便立即可用，没有索引文件或已经过期时在后台重新遍历
+// 之后开始监听变化
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let config = FileIndexConfig::load(app);
+    let files = app.state::<FileSearch>();
+    *files.config.write().unwrap() = config.clone();
+    if !config.enabled {
+        return;
+    }
+    let file = index_file(app).ok();
+    let loaded = file
+        .as_ref()
+        .and_then(|file| FileIndex::load(file, &config.signature()).ok().flatten());
+    let fresh = loaded.is_some() && file.as_deref().is_some_and(is_fresh);
+    if let Some(index) = loaded {
+        *files.index.write().unwrap() = index;
+    }
+    if fresh {
+        resume(app);
+    } else {
+        rebuild(app);
+    }
+}
+
+// 配置在别处修改后（如导入备份）重新读取并重建索引
+pub fn reload<R: Runtime>(app: &AppHandle<R>) {
+    *app.state::<FileSearch>().config.write().unwrap() = FileIndexConfig::load(app);
+    rebuild(app);
+}
+
+fn is_fresh(file: &Path) -> bool {
+    fs::metadata(file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < STALE_AFTER)
+}
+
+// 使用已有的索引，在后台开始监听变化
+fn resume<R: Runtime>(app: &AppHandle<R>) {
+    let files = app.state::<FileSearch>();
+    let generation = files.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let config = files.config.read().unwrap().clone();
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(e) = watch(&app, &config, generation) {
+            eprintln!("Failed to watch file index roots: {}", e);
+        }
+    });
+}
+
+// 在后台重新建立索引，完成后开始监听文件变化
+pub fn rebuild<R: Runtime>(app: &AppHandle<R>) {
+    let files = app.state::<FileSearch>();
+    let generation = files.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    // 停止旧的监听
+    *files.watcher.lock().unwrap() = None;
+    let config = files.config.read().unwrap().clone();
+    if !config.enabled {
+        *files.index.write().unwrap() = FileIndex::default();
+        return;
+    }
+    files.building.store(true, Ordering::SeqCst);
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let index = FileIndex::build(&config);
+        let files = app.state::<FileSearch>();
+        if files.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        *files.index.write().unwrap() = index;
+        files.building.store(false, Ordering::SeqCst);
+        save(&app);
+        if let Err(e) = watch(&app, &config, generation) {
+            eprintln!("Failed to watch file index roots: {}", e);
+        }
+    });
+}
+
+// 监听根目录的变化，增量更新索引
+// inotify 递归监听时会给被忽略的目录（如 node_modules）也加上监听，Linux 上按索引中的目录逐个监听
+// 其它平台的递归监听由系统实现，被忽略的目录中的变化在 upsert 中跳过
+fn watch<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &FileIndexConfig,
+    generation: u64,
+) -> Result<(), String> {
+    let files = app.state::<FileSearch>();
+    #[cfg(target_os = "linux")]
+    let (paths, mode) = (
+        files.index.read().unwrap().dirs(None),
+        RecursiveMode::NonRecursive,
+    );
+    #[cfg(not(target_os = "linux"))]
+    let (paths, mode) = (config.roots.clone(), RecursiveMode::Recursive);
+    let watcher = {
+        let app = app.clone();
+        let config = config.clone();
+        let mut last_save = Instant::now();
+        let mut dirty = false;
+        watcher::watch_with(&paths, mode, move |changes| {
+            let files = app.state::<FileSearch>();
+            if files.generation.load(Ordering::SeqCst) != generation {
+                return false;
+            }
+            if changes.rescan {
+                rebuild(&app);
+                return false;
+            }
+            if !changes.paths.is_empty() {
+                // 新建的目录及其下的目录需要加上监听
+                let mut added = Vec::new();
+                let mut index = files.index.write().unwrap();
+                for path in changes.paths.iter() {
+                    let known = index.find(path).is_some();
+                    index.upsert(path, &config);
+                    if mode == RecursiveMode::NonRecursive && !known {
+                        added.extend(index.dirs(Some(path)));
+                    }
+                }
+                index.compact_if_needed();
+                drop(index);
+                if let Some(watcher) = files.watcher.lock().unwrap().as_mut() {
+                    for dir in added {
+                        if let Err(e) = watcher.watch(&dir, mode) {
+                            eprintln!("Failed to watch {}: {}", dir.display(), e);
+                        }
+                    }
+                }
+                dirty = true;
+            }
+            if dirty && last_save.elapsed() >= SAVE_INTERVAL {
+                save(&app);
+                last_save = Instant::now();
+                dirty = false;
+            }
+            true
+        })?
+    };
+    // 监听期间配置已经变化时丢弃
+    if files.generation.load(Ordering::SeqCst) == generation {
+        *files.watcher.lock().unwrap() = Some(watcher);
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn search_files(
+    files: State<'_, FileSearch>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, String> {
+    files.search(&query, limit.unwrap_or(50))
+}
+
+#[command]
+pub fn get_file_index_status(files: State<'_, FileSearch>) -> FileIndexStatus {
+    files.status()
+}
+
+#[command]
+pub fn get_file_index_config(app: AppHandle) -> FileIndexConfig {
+    FileIndexConfig::load(&app)
+}
+
+// 保存配置并重新建立索引
+#[command]
+pub fn set_file_index_config(
+    app: AppHandle,
+    files: State<'_, FileSearch>,
+    config: FileIndexConfig,
+) -> Result<FileIndexStatus, String> {
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store::set(&app, CONFIG_KEY, Some(value))?;
+    *files.config.write().unwrap() = FileIndexConfig::load(&app);
+    rebuild(&app);
+    Ok(files.status())
+}
+
+#[command]
+pub fn rebuild_file_index(app: AppHandle, files: State<'_, FileSearch>) -> FileIndexStatus {
+    rebuild(&app);
+    files.status()
+}
+
+// 用系统默认的程序打开文件或目录
+#[command]
+pub fn open_path(path: String) -> Result<(), String> {
+    crate::apps::open(Path::new(&path))
+}
+
+// 在文件管理器中显示并选中文件
+#[command]
+pub fn reveal_path(path: String) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("explorer")
+            .arg(format!("/select,{}", path))
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open")
+            .args(["-R", &path])
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // 优先通过 FileManager1 接口选中文件，不支持时打开所在目录
+        let uri = Url::from_file_path(&path).map_err(|_| format!("Invalid path `{}`", path))?;
+        let status = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+                &format!("array:string:{}", uri),
+                "string:",
+            ])
+            .status();
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            _ => {
+                let parent = Path::new(&path)
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or(path);
+                open_path(parent)
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+use super::index::{SearchIndex, SearchResult};
+
+// 历史记录文件，位于 app_data_dir 中
+const HISTORY_FILE: &str = "search-history.json";
+// 每一项保留的最近启动时间数量
+const MAX_VISITS: usize = 10;
+// 最多记录的项数和查询词数，超过后丢弃最久未使用的
+const MAX_ITEMS: usize = 1000;
+const MAX_QUERIES: usize = 500;
+// 频率分和查询关联分最多能加的分数
+const MAX_FRECENCY_BONUS: f64 = 200.0;
+const MAX_QUERY_BONUS: f64 = 400.0;
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+// 一项的使用记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemHistory {
+    // 总启动次数
+    pub count: u32,
+    // 最近几次启动的毫秒时间戳
+    pub visits: Vec<i64>,
+}
+
+// 某个查询词选中某项的次数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryHistory {
+    pub last_used: i64,
+    pub picks: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct History {
+    pub items: HashMap<String, ItemHistory>,
+    pub queries: HashMap<String, QueryHistory>,
+}
+
+// 越近的启动权重越高
+fn visit_weight(now: i64, visit: i64) -> f64 {
+    let age = now - visit;
+    if age < 4 * DAY {
+        100.0
+    } else if age < 14 * DAY {
+        70.0
+    } else if age < 31 * DAY {
+        50.0
+    } else if age < 90 * DAY {
+        30.0
+    } else {
+        10.0
+    }
+}
+
+// 查询词统一为小写，多个空白合并为一个
+fn normalize(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+impl ItemHistory {
+    // 启动次数乘以最近几次启动的平均权重
+    pub fn frecency(&self, now: i64) -> f64 {
+        if self.visits.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.visits.iter().map(|v| visit_weight(now, *v)).sum();
+        self.count as f64 * total / self.visits.len() as f64
+    }
+}
+
+impl History {
+    pub fn file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+        app.path()
+            .app_data_dir()
+            .map(|dir| dir.join(HISTORY_FILE))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        Self::file(app)
+            .ok()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let file = Self::file(app)?;
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(file, content).map_err(|e| e.to_string())
+    }
+
+    // 记录一次启动，query 为启动时输入的内容
+    pub fn record(&mut self, id: &str, query: Option<&str>) {
+        let now = chrono::Local::now().timestamp_millis();
+        let item = self.items.entry(id.to_string()).or_default();
+        item.count += 1;
+        item.visits.push(now);
+        if item.visits.len() > MAX_VISITS {
+            item.visits.remove(0);
+        }
+
+        if let Some(query) = query.map(normalize).filter(|q| !q.is_empty()) {
+            let entry = self.queries.entry(query).or_default();
+            entry.last_used = now;
+            *entry.picks.entry(id.to_string()).or_default() += 1;
+        }
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        if self.items.len() > MAX_ITEMS {
+            let mut items: Vec<(String, i64)> = self
+                .items
+                .iter()
+                .map(|(id, item)| (id.clone(), item.visits.last().copied().unwrap_or(0)))
+                .collect();
+            items.sort_by_key(|(_, last)| *last);
+            for (id, _) in items.iter().take(self.items.len() - MAX_ITEMS) {
+                self.items.remove(id);
+            }
+        }
+        if self.queries.len() > MAX_QUERIES {
+            let mut queries: Vec<(String, i64)> = self
+                .queries
+                .iter()
+                .map(|(query, entry)| (query.clone(), entry.last_used))
+                .collect();
+            queries.sort_by_key(|(_, last)| *last);
+            for (query, _) in queries.iter().take(self.queries.len() - MAX_QUERIES) {
+                self.queries.remove(query);
+            }
+        }
+    }
+
+    // 查询词与各项的关联度，0 ~ 1
+    // 完全相同的查询词权重最高，之前输入过以当前内容开头的查询词（如输入到一半）次之
+    fn associations(&self, query: &str) -> HashMap<String, f64> {
+        let mut result: HashMap<String, f64> = HashMap::new();
+        // 空的查询词是所有查询词的前缀，不做关联
+        if query.is_empty() {
+            return result;
+        }
+        for (past, entry) in self.queries.iter() {
+            let factor = if past == query {
+                1.0
+            } else if past.starts_with(query) {
+                0.5
+            } else {
+                continue;
+            };
+            for (id, picks) in entry.picks.iter() {
+                let picks = *picks as f64;
+                let value = factor * picks / (picks + 1.0);
+                let current = result.entry(id.clone()).or_default();
+                if value > *current {
+                    *current = value;
+                }
+            }
+        }
+        result
+    }
+
+    // 根据历史记录调整搜索结果的分数，查询词关联的项即使没有匹配上也会加入结果
+    pub fn rank(&self, query: &str, results: &mut Vec<SearchResult>, index: &SearchIndex) {
+        let now = chrono::Local::now().timestamp_millis();
+        let query = normalize(query);
+        let associations = self.associations(&query);
+
+        for (id, _) in associations.iter() {
+            if results.iter().all(|result| &result.id != id) {
+                if let Some(result) = index.get(id) {
+                    results.push(result);
+                }
+            }
+        }
+
+        // 频率分按最常用的一项归一化
+        let max_frecency = self
+            .items
+            .values()
+            .map(|item| item.frecency(now))
+            .fold(0.0, f64::max);
+        for result in results.iter_mut() {
+            if max_frecency > 0.0 {
+                if let Some(item) = self.items.get(&result.id) {
+                    result.score += MAX_FRECENCY_BONUS * item.frecency(now) / max_frecency;
+                }
+            }
+            if let Some(association) = associations.get(&result.id) {
+                result.score += MAX_QUERY_BONUS * association;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn associates_past_queries() {
+        let mut history = History::default();
+        history.record("app:firefox", Some("Fire"));
+        history.record("app:firefox", Some("fire"));
+        history.record("app:files", Some("files"));
+        history.record("app:terminal", None);
+
+        let exact = history.associations("fire");
+        assert_eq!(exact.len(), 1);
+        assert!((exact["app:firefox"] - 2.0 / 3.0).abs() < 1e-9);
+        let prefix = history.associations("fi");
+        assert!((prefix["app:firefox"] - 1.0 / 3.0).abs() < 1e-9);
+        assert!((prefix["app:files"] - 0.25).abs() < 1e-9);
+        assert!(history.associations("").is_empty());
+        assert!(history.associations("term").is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{cmp::Ordering, path::PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+use super::actions::Action;
+use super::matcher::{tokenize, Term};
+use crate::plugins::discovery;
+
+// 内置插件的配置，与前端 `src/data/internal-plugins.ts` 保持一致
+const INTERNAL_PLUGINS: &str = include_str!("../../resources/config/internal-plugins.json");
+
+// 搜索结果的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResultKind {
+    Calculator,
+    Snippet,
+    App,
+    Plugin,
+    InternalPlugin,
+    Window,
+    Recent,
+    Bookmark,
+    File,
+    Content,
+    Clipboard,
+    Command,
+    PluginOutput,
+    // 正在执行的命令的输出
+    ShellOutput,
+}
+
+impl ResultKind {
+    // 分数相同时的排序，越小越靠前
+    fn priority(&self) -> u8 {
+        match self {
+            ResultKind::Calculator => 0,
+            ResultKind::Snippet => 1,
+            ResultKind::InternalPlugin => 2,
+            ResultKind::Plugin => 3,
+            ResultKind::App => 4,
+            ResultKind::Window => 5,
+            ResultKind::Recent => 6,
+            ResultKind::Bookmark => 7,
+            ResultKind::File => 8,
+            ResultKind::Content => 9,
+            ResultKind::Clipboard => 10,
+            ResultKind::Command => 11,
+            ResultKind::PluginOutput => 12,
+            ResultKind::ShellOutput => 13,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub id: String,
+    pub kind: ResultKind,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub icon: Option<PathBuf>,
+    pub score: f64,
+    // title 中命中的字符下标，用于高亮
+    pub highlights: Vec<usize>,
+    // 原始数据，应用为 App，插件为插件配置
+    pub data: Value,
+    // 显示在标题后面的状态，如已固定、运行中
+    pub labels: Vec<String>,
+    // 可执行的动作，第一个为点击时的默认动作
+    pub actions: Vec<Action>,
+}
+
+// 参与匹配的文本及其权重
+#[derive(Debug, Clone)]
+struct Field {
+    term: Term,
+    weight: f64,
+    is_title: bool,
+}
+
+// 索引中的一项
+#[derive(Debug, Clone)]
+pub struct SearchItem {
+    pub id: String,
+    pub kind: ResultKind,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub icon: Option<PathBuf>,
+    pub data: Value,
+    fields: Vec<Field>,
+}
+
+impl SearchItem {
+    pub fn new(id: String, kind: ResultKind, title: String, data: Value) -> Self {
+        let fields = vec![Field {
+            term: Term::new(&title),
+            weight: 1.0,
+            is_title: true,
+        }];
+        SearchItem {
+            id,
+            kind,
+            title,
+            subtitle: None,
+            icon: None,
+            data,
+            fields,
+        }
+    }
+
+    pub fn subtitle(mut self, subtitle: Option<String>) -> Self {
+        self.subtitle = subtitle;
+        self
+    }
+
+    pub fn icon(mut self, icon: Option<PathBuf>) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    // 额外参与匹配的文本，如关键词
+    pub fn keyword(mut self, text: &str, weight: f64) -> Self {
+        if !text.trim().is_empty() {
+            self.fields.push(Field {
+                term: Term::new(text),
+                weight,
+                is_title: false,
+            });
+        }
+        self
+    }
+
+    fn to_result(&self, score: f64, highlights: Vec<usize>) -> SearchResult {
+        SearchResult {
+            id: self.id.clone(),
+            kind: self.kind,
+            title: self.title.clone(),
+            subtitle: self.subtitle.clone(),
+            icon: self.icon.clone(),
+            score,
+            highlights,
+            data: self.data.clone(),
+            labels: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    // 所有关键词都要命中，分数为各关键词最好得分之和
+    fn matches(&self, tokens: &[Vec<char>]) -> Option<(f64, Vec<usize>)> {
+        let mut total = 0.0;
+        let mut highlights: Vec<usize> = Vec::new();
+        for token in tokens {
+            let mut best: Option<(f64, &Field, Vec<usize>)> = None;
+            for field in self.fields.iter() {
+                if let Some(m) = field.term.matches(token) {
+                    let score = m.score * field.weight;
+                    if best.as_ref().is_none_or(|(s, _, _)| score > *s) {
+                        best = Some((score, field, m.positions));
+                    }
+                }
+            }
+            let (score, field, positions) = best?;
+            total += score;
+            if field.is_title {
+                highlights.extend(positions);
+            }
+        }
+        highlights.sort_unstable();
+        highlights.dedup();
+        Some((total, highlights))
+    }
+}
+
+// 内存中的搜索索引
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    items: Vec<SearchItem>,
+}
+
+impl SearchIndex {
+    // 由现成的条目建立索引，供书签、最近文件等其它来源使用
+    pub fn new(items: Vec<SearchItem>) -> Self {
+        SearchIndex { items }
+    }
+
+    pub fn build<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let mut items: Vec<SearchItem> = Vec::new();
+
+        // 内置插件
+        if let Ok(Value::Array(plugins)) = serde_json::from_str::<Value>(INTERNAL_PLUGINS) {
+            for plugin in plugins {
+                if let Some(item) = plugin_item(&plugin, ResultKind::InternalPlugin) {
+                    items.push(item);
+                }
+            }
+        }
+
+        // 各插件目录中的插件，二进制/脚本插件通过前缀调用，不参与搜索
+        for plugin in discovery::discover(app).plugins {
+            if plugin.config.get("type").and_then(|v| v.as_str()) != Some("module") {
+                continue;
+            }
+            let mut config = plugin.config.clone();
+            config["id"] = json!(plugin.id);
+            // 只有用户安装的插件可以卸载
+            config["source"] = json!(plugin.source);
+            if let Some(item) = plugin_item(&config, ResultKind::Plugin) {
+                items.push(item);
+            }
+        }
+
+        // 本机安装的应用，使用后台扫描的缓存
+        let installed = app
+            .try_state::<crate::apps::AppCatalog>()
+            .map(|catalog| catalog.list())
+            .unwrap_or_default();
+        for app in installed {
+            let name = match app.name.clone() {
+                Some(name) if !name.is_empty() => name,
+                _ => continue,
+            };
+            let path = app.path.clone();
+            let id = format!(
+                "app:{}",
+                path.as_ref()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or(name.clone())
+            );
+            // 同一应用有多个安装时按来源区分，如 `Flatpak · /var/lib/flatpak/...`
+            let subtitle = path.map(|p| match app.source.as_ref() {
+                Some(source) => format!("{} · {}", source, p.to_string_lossy()),
+                None => p.to_string_lossy().to_string(),
+            });
+            let mut item = SearchItem::new(id, ResultKind::App, name, json!(app))
+                .subtitle(subtitle)
+                .icon(app.icon.clone());
+            // 未翻译的名称、关键词和分类等，如用 "browser" 找到 Firefox
+            for name in &app.names {
+                item = item.keyword(name, 0.9);
+            }
+            for keyword in &app.keywords {
+                item = item.keyword(keyword, 0.7);
+            }
+            for category in &app.categories {
+                item = item.keyword(category, 0.5);
+            }
+            if let Some(app_id) = &app.id {
+                item = item.keyword(app_id, 0.4);
+            }
+            if let Some(description) = &app.description {
+                item = item.keyword(description, 0.3);
+            }
+            if let Some(source) = &app.source {
+                item = item.keyword(source, 0.3);
+            }
+            items.push(item);
+        }
+
+        SearchIndex { items }
+    }
+
+    // 按 id 取出一项，分数为 0
+    pub fn get(&self, id: &str) -> Option<SearchResult> {
+        self.items
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| item.to_result(0.0, Vec::new()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let tokens = tokenize(query);
+        if tokens.is_empty() {
+            return Vec::new();
+        }
+        let mut results: Vec<SearchResult> = self
+            .items
+            .iter()
+            .filter_map(|item| {
+                item.matches(&tokens)
+                    .map(|(score, highlights)| item.to_result(score, highlights))
+            })
+            .collect();
+        sort_results(&mut results);
+        results.truncate(limit);
+        results
+    }
+}
+
+// 按分数从高到低排序，分数相同时按类型和标题长度
+pub fn sort_results(results: &mut [SearchResult]) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then(a.kind.priority().cmp(&b.kind.priority()))
+            .then(a.title.chars().count().cmp(&b.title.chars().count()))
+    });
+}
+
+// 由插件配置生成索引项
+fn plugin_item(config: &Value, kind: ResultKind) -> Option<SearchItem> {
+    let id = config.get("id")?.as_str()?.to_string();
+    let name = config
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or(&id)
+        .to_string();
+    let description = config
+        .get("description")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    let mut item = SearchItem::new(format!("plugin:{}", id), kind, name, config.clone())
+        .subtitle(description)
+        .keyword(&id, 0.6);
+    if let Some(keywords) = config.get("keywords").and_then(|v| v.as_array()) {
+        for keyword in keywords.iter().filter_map(|v| v.as_str()) {
+            item = item.keyword(keyword, 0.8);
+        }
+    }
+    Some(item)
+}
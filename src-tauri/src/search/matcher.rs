@@ -0,0 +1,370 @@
+use pinyin::ToPinyinMulti;
+
+// 各种匹配方式的基础分，越精确分越高
+const SCORE_EXACT: f64 = 1000.0;
+const SCORE_PREFIX: f64 = 800.0;
+const SCORE_SUBSTRING: f64 = 600.0;
+const SCORE_FUZZY: f64 = 300.0;
+// 拼音匹配相对于直接匹配的折扣
+const PINYIN_FACTOR: f64 = 0.9;
+
+// 多音字组合出的拼音读法最多保留的数量
+const MAX_READINGS: usize = 8;
+
+// 一种拼音读法
+#[derive(Debug, Clone, Default)]
+struct Reading {
+    // 全拼，汉字替换为拼音，其余字符为小写
+    pinyin: Vec<char>,
+    // 全拼中每个字符对应的原文字符下标
+    pinyin_index: Vec<usize>,
+    // 首字母，与原文字符一一对应
+    initials: Vec<char>,
+}
+
+// 预处理后的待匹配文本，包含小写形式和拼音
+#[derive(Debug, Clone)]
+pub struct Term {
+    // 原文的字符
+    chars: Vec<char>,
+    // 小写后的字符，与 chars 一一对应
+    lower: Vec<char>,
+    // 单词开头的字符，用于缩写匹配：vsc -> Visual Studio Code
+    acronym: Vec<char>,
+    acronym_index: Vec<usize>,
+    // 拼音读法，不包含汉字时为空
+    readings: Vec<Reading>,
+}
+
+// 一次匹配的结果，positions 为原文中命中的字符下标，用于高亮
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub score: f64,
+    pub positions: Vec<usize>,
+}
+
+// 汉字的所有拼音（不带声调，去重）
+fn syllables(c: char) -> Option<Vec<&'static str>> {
+    let multi = c.to_pinyin_multi()?;
+    let mut result: Vec<&'static str> = Vec::new();
+    for p in multi {
+        let plain = p.plain();
+        if !result.contains(&plain) {
+            result.push(plain);
+        }
+    }
+    Some(result)
+}
+
+impl Term {
+    pub fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let lower: Vec<char> = chars
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+
+        let mut acronym = Vec::new();
+        let mut acronym_index = Vec::new();
+        for index in 0..chars.len() {
+            if chars[index].is_alphanumeric() && is_word_start(&chars, index) {
+                acronym.push(lower[index]);
+                acronym_index.push(index);
+            }
+        }
+
+        let mut readings: Vec<Reading> = vec![Reading::default()];
+        let mut has_cjk = false;
+        for (index, l) in lower.iter().enumerate() {
+            let options = match syllables(chars[index]) {
+                Some(options) => {
+                    has_cjk = true;
+                    options
+                }
+                None => {
+                    for reading in readings.iter_mut() {
+                        reading.pinyin.push(*l);
+                        reading.pinyin_index.push(index);
+                        reading.initials.push(*l);
+                    }
+                    continue;
+                }
+            };
+            let mut next: Vec<Reading> = Vec::new();
+            for reading in readings.iter() {
+                for syllable in options.iter() {
+                    // 组合太多时丢掉后面的读法，都取第一个读音的读法总会保留
+                    if next.len() >= MAX_READINGS {
+                        break;
+                    }
+                    let mut reading = reading.clone();
+                    for pc in syllable.chars() {
+                        reading.pinyin.push(pc);
+                        reading.pinyin_index.push(index);
+                    }
+                    reading.initials.push(syllable.chars().next().unwrap_or(*l));
+                    next.push(reading);
+                }
+            }
+            readings = next;
+        }
+        if !has_cjk {
+            readings.clear();
+        }
+
+        Term {
+            chars,
+            lower,
+            acronym,
+            acronym_index,
+            readings,
+        }
+    }
+
+    // 用小写的查询词匹配，返回最好的一种匹配方式
+    pub fn matches(&self, query: &[char]) -> Option<Match> {
+        if query.is_empty() {
+            return None;
+        }
+        let mut best = match_chars(query, &self.lower, &self.chars);
+        // 缩写：vsc -> Visual Studio Code
+        if self.acronym.len() > 1 && self.acronym.starts_with(query) {
+            let m = Match {
+                score: SCORE_SUBSTRING + 150.0,
+                positions: self.acronym_index[..query.len()].to_vec(),
+            };
+            best = better(best, m);
+        }
+        for reading in self.readings.iter() {
+            // 首字母：wx -> 微信
+            if let Some(m) = match_chars(query, &reading.initials, &self.chars) {
+                best = better(best, scaled(m, PINYIN_FACTOR));
+            }
+            // 全拼：weixin -> 微信
+            if let Some(m) = match_chars(query, &reading.pinyin, &reading.pinyin) {
+                let mut positions: Vec<usize> = m
+                    .positions
+                    .iter()
+                    .map(|&i| reading.pinyin_index[i])
+                    .collect();
+                positions.dedup();
+                let m = Match {
+                    score: m.score,
+                    positions,
+                };
+                best = better(best, scaled(m, PINYIN_FACTOR));
+            }
+        }
+        best
+    }
+}
+
+fn scaled(m: Match, factor: f64) -> Match {
+    Match {
+        score: m.score * factor,
+        positions: m.positions,
+    }
+}
+
+fn better(a: Option<Match>, b: Match) -> Option<Match> {
+    match a {
+        Some(a) if a.score >= b.score => Some(a),
+        _ => Some(b),
+    }
+}
+
+// 是否是单词的开头，用于给模糊匹配加分
+fn is_word_start(original: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+    let prev = original[index - 1];
+    let cur = original[index];
+    !prev.is_alphanumeric() || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+// 依次尝试完全匹配、前缀、子串和模糊子序列匹配
+fn match_chars(query: &[char], target: &[char], original: &[char]) -> Option<Match> {
+    if query.len() > target.len() {
+        return None;
+    }
+    // 越短的目标越接近查询词，给一点加分
+    let ratio = query.len() as f64 / target.len() as f64;
+    if target == query {
+        return Some(Match {
+            score: SCORE_EXACT,
+            positions: (0..query.len()).collect(),
+        });
+    }
+    if target.starts_with(query) {
+        return Some(Match {
+            score: SCORE_PREFIX + 100.0 * ratio,
+            positions: (0..query.len()).collect(),
+        });
+    }
+    if let Some(start) = target.windows(query.len()).position(|w| w == query) {
+        let bonus = if is_word_start(original, start) {
+            100.0
+        } else {
+            0.0
+        };
+        return Some(Match {
+            score: SCORE_SUBSTRING + bonus + 50.0 * ratio,
+            positions: (start..start + query.len()).collect(),
+        });
+    }
+    fuzzy(query, target, original).map(|(score, positions)| Match {
+        score: SCORE_FUZZY + score + 50.0 * ratio,
+        positions,
+    })
+}
+
+// 模糊子序列匹配：从每个可能的起点贪心匹配，取得分最高的一次
+// 连续命中和命中单词开头加分，跳过的字符扣分
+pub fn fuzzy(query: &[char], target: &[char], original: &[char]) -> Option<(f64, Vec<usize>)> {
+    let mut best: Option<(f64, Vec<usize>)> = None;
+    for start in 0..target.len() {
+        if target[start] != query[0] {
+            continue;
+        }
+        let mut positions = vec![start];
+        let mut qi = 1;
+        let mut ti = start + 1;
+        while qi < query.len() && ti < target.len() {
+            if target[ti] == query[qi] {
+                positions.push(ti);
+                qi += 1;
+            }
+            ti += 1;
+        }
+        if qi < query.len() {
+            // 后面的起点只会更靠后，不可能再匹配上
+            break;
+        }
+        let mut score = 0.0;
+        for (i, &pos) in positions.iter().enumerate() {
+            score += 10.0;
+            if original.len() == target.len() && is_word_start(original, pos) {
+                score += 20.0;
+            }
+            if i > 0 {
+                let gap = pos - positions[i - 1] - 1;
+                if gap == 0 {
+                    score += 15.0;
+                } else {
+                    score -= (gap as f64).min(10.0);
+                }
+            }
+        }
+        score -= (start as f64).min(20.0);
+        if best.as_ref().is_none_or(|(s, _)| score > *s) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+// 把查询词拆分成小写的关键词
+pub fn tokenize(query: &str) -> Vec<Vec<char>> {
+    query
+        .split_whitespace()
+        .map(|word| word.to_lowercase().chars().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn matches(text: &str, q: &str) -> Option<Match> {
+        Term::new(text).matches(&query(q))
+    }
+
+    fn score(text: &str, q: &str) -> f64 {
+        matches(text, q).map_or(0.0, |m| m.score)
+    }
+
+    #[test]
+    fn ranks_match_kinds() {
+        assert_eq!(score("Code", "code"), SCORE_EXACT);
+        let prefix = score("Codex", "code");
+        let substring = score("VS Code", "code");
+        let inner = score("Unicode", "code");
+        let fuzzy = score("Calendar Desktop", "cde");
+        assert!(
+            SCORE_EXACT > prefix && prefix > substring,
+            "{} {}",
+            prefix,
+            substring
+        );
+        // 单词开头的子串比单词中间的分高
+        assert!(substring > inner && inner > fuzzy, "{} {}", inner, fuzzy);
+        assert!(fuzzy >= SCORE_FUZZY);
+        assert_eq!(matches("Code", "xyz"), None);
+        assert_eq!(matches("Code", ""), None);
+        assert_eq!(matches("ab", "abc"), None);
+    }
+
+    #[test]
+    fn returns_highlight_positions() {
+        assert_eq!(matches("VS Code", "code").unwrap().positions, [3, 4, 5, 6]);
+        // 缩写：vsc -> Visual Studio Code
+        let m = matches("Visual Studio Code", "vsc").unwrap();
+        assert_eq!(m.positions, [0, 7, 14]);
+        assert!(m.score > SCORE_SUBSTRING);
+        assert_eq!(matches("fileZilla", "fz").unwrap().positions, [0, 4]);
+    }
+
+    #[test]
+    fn prefers_contiguous_fuzzy_matches() {
+        let target = query("xaxbxcabc");
+        let (_, positions) = fuzzy(&query("abc"), &target, &target).unwrap();
+        assert_eq!(positions, [6, 7, 8]);
+        let target = query("xaxbxc");
+        assert_eq!(fuzzy(&query("abc"), &target, &target).unwrap().1, [1, 3, 5]);
+        assert_eq!(fuzzy(&query("abd"), &target, &target), None);
+    }
+
+    #[test]
+    fn matches_pinyin() {
+        // 首字母和全拼，高亮原文中的汉字
+        let initials = matches("微信", "wx").unwrap();
+        assert_eq!(initials.positions, [0, 1]);
+        let full = matches("微信", "weixin").unwrap();
+        assert_eq!(full.positions, [0, 1]);
+        assert_eq!(full.score, SCORE_EXACT * PINYIN_FACTOR);
+        assert_eq!(matches("腾讯QQ", "txqq").unwrap().positions, [0, 1, 2, 3]);
+        assert_eq!(matches("网易云音乐", "yinyue").unwrap().positions, [3, 4]);
+        // 直接匹配原文比拼音分高
+        assert!(score("微信", "微信") > score("微信", "weixin"));
+        assert_eq!(matches("微信", "qq"), None);
+        assert!(Term::new("Firefox").readings.is_empty());
+    }
+
+    #[test]
+    fn matches_every_reading() {
+        // 多音字的各种读音都能匹配
+        assert!(matches("重庆", "chongqing").is_some());
+        assert!(matches("重要", "zhongyao").is_some());
+        assert!(matches("重要", "cy").is_some());
+        // 读音组合的数量有上限
+        let term = Term::new("重重重重重重");
+        assert_eq!(term.readings.len(), MAX_READINGS);
+        // 都取第一个读音的读法总会保留
+        let first = syllables('重').unwrap()[0].repeat(6);
+        assert!(term.matches(&query(&first)).is_some());
+    }
+
+    #[test]
+    fn tokenizes_queries() {
+        assert_eq!(
+            tokenize("  VS  Code 微信 "),
+            [query("vs"), query("code"), query("微信")]
+        );
+        assert!(tokenize(" ").is_empty());
+    }
+}
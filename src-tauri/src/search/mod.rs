@@ -0,0 +1,297 @@
+use std::{
+    collections::HashSet,
+    fs,
+    sync::{Mutex, RwLock},
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use index::ResultKind;
+
+pub mod actions;
+pub mod aliases;
+pub mod bookmarks;
+pub mod calc;
+pub mod content;
+pub mod files;
+pub mod history;
+pub mod index;
+pub mod matcher;
+pub mod query;
+pub mod recent;
+pub mod switcher;
+pub mod watcher;
+
+pub use history::History;
+pub use index::{sort_results, SearchIndex, SearchResult};
+pub use query::{ParsedQuery, QueryMode};
+
+// search 默认返回的条数
+const DEFAULT_LIMIT: usize = 50;
+// 混入普通搜索结果的文件最多条数，查询内容太短时不搜索文件
+const MAX_FILE_RESULTS: usize = 20;
+const MIN_FILE_QUERY: usize = 2;
+// 混入普通搜索结果的最近文件最多条数
+const MAX_RECENT_RESULTS: usize = 10;
+// 混入普通搜索结果的窗口最多条数
+const MAX_WINDOW_RESULTS: usize = 10;
+// 混入普通搜索结果的书签最多条数
+const MAX_BOOKMARK_RESULTS: usize = 20;
+// 有扩展名条件时文件和最近文件多取的倍数
+const EXTENSION_FACTOR: usize = 5;
+
+// 搜索索引，第一次搜索时建立
+#[derive(Default)]
+pub struct SearchEngine {
+    index: RwLock<Option<SearchIndex>>,
+    // 启动历史，第一次使用时从文件读取
+    history: Mutex<Option<History>>,
+    // 通过前缀调用的插件，第一次解析查询时读取
+    commands: RwLock<Option<Vec<query::PluginCommand>>>,
+}
+
+impl SearchEngine {
+    pub fn parse(&self, app: &AppHandle, input: &str) -> ParsedQuery {
+        if self.commands.read().unwrap().is_none() {
+            *self.commands.write().unwrap() = Some(query::PluginCommand::load(app));
+        }
+        let commands = self.commands.read().unwrap();
+        let aliases = app
+            .try_state::<aliases::AliasStore>()
+            .map(|aliases| aliases.list())
+            .unwrap_or_default();
+        query::parse(input, commands.as_deref().unwrap_or_default(), &aliases)
+    }
+
+    pub fn search(&self, app: &AppHandle, input: &str, limit: usize) -> Vec<SearchResult> {
+        let parsed = self.parse(app, input);
+        let filters = &parsed.filters;
+        let query = parsed.text.as_str();
+        match parsed.mode {
+            // `in:` 前缀只搜索文件内容，其余搜索不受影响
+            QueryMode::Content => {
+                return match app.try_state::<content::ContentSearch>() {
+                    Some(content) => {
+                        let mut results = content.search(query, limit);
+                        results.retain(|result| filters.matches_extension(&result.data));
+                        results
+                    }
+                    None => Vec::new(),
+                };
+            }
+            // `cb:` 前缀只搜索剪贴板历史
+            QueryMode::Clipboard => {
+                return match app.try_state::<crate::clipboard::ClipboardService>() {
+                    Some(clipboard) => clipboard.search(query, limit),
+                    None => Vec::new(),
+                };
+            }
+            // `>` 前缀执行命令，结果为输入的命令和匹配的历史命令
+            QueryMode::Shell => {
+                return match app.try_state::<crate::shell::ShellService>() {
+                    Some(shell) => shell.search(query, limit),
+                    None => Vec::new(),
+                };
+            }
+            // `=` 前缀只计算表达式
+            QueryMode::Calculator => return calc::search(input).into_iter().collect(),
+            // 插件由前端按解析出的参数执行
+            QueryMode::Plugin => return Vec::new(),
+            QueryMode::Search => {}
+        }
+        if self.index.read().unwrap().is_none() {
+            self.refresh(app);
+        }
+        let index = self.index.read().unwrap();
+        let index = match index.as_ref() {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        let mut results = index.search(query, usize::MAX);
+        // 别名指向的应用和插件排在最前面
+        if let Some(aliases) = app.try_state::<aliases::AliasStore>() {
+            aliases.boost(query, &mut results, index);
+        }
+        // 不支持的平台或没有 X11 时没有窗口
+        let windows = if filters.wants(ResultKind::Window) || filters.wants(ResultKind::App) {
+            switcher::list().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        if filters.wants(ResultKind::Window) {
+            results.extend(switcher::search(
+                &windows,
+                query,
+                MAX_WINDOW_RESULTS.min(limit),
+            ));
+        }
+        switcher::annotate(&mut results, &windows);
+        if let Some(snippets) = app.try_state::<crate::snippets::SnippetStore>() {
+            if filters.wants(ResultKind::Snippet) {
+                results.extend(snippets.search(query));
+            }
+        }
+        if let Some(bookmarks) = app.try_state::<bookmarks::BookmarkSearch>() {
+            if filters.wants(ResultKind::Bookmark) {
+                results.extend(bookmarks.search(query, MAX_BOOKMARK_RESULTS.min(limit)));
+            }
+        }
+        // 只有扩展名条件时按扩展名列出文件
+        let by_extension = query.is_empty() && !filters.extensions.is_empty();
+        if query.chars().count() >= MIN_FILE_QUERY || by_extension {
+            // 有扩展名条件时多取一些，过滤后仍有足够的结果
+            let extra = if filters.extensions.is_empty() {
+                1
+            } else {
+                EXTENSION_FACTOR
+            };
+            let recent = match app.try_state::<recent::RecentDocuments>() {
+                Some(recent) if filters.wants(ResultKind::Recent) && !by_extension => {
+                    recent.search(app, query, MAX_RECENT_RESULTS.min(limit) * extra)
+                }
+                _ => Vec::new(),
+            };
+            // 已经作为最近文件出现的不再重复显示
+            let recent_paths: HashSet<String> = recent
+                .iter()
+                .filter_map(|result| result.data["path"].as_str().map(str::to_string))
+                .collect();
+            results.extend(recent);
+            let file_queries: Vec<String> = if by_extension {
+                filters
+                    .extensions
+                    .iter()
+                    .map(|ext| format!("*.{}", ext))
+                    .collect()
+            } else {
+                vec![query.to_string()]
+            };
+            match app.try_state::<files::FileSearch>() {
+                Some(files) if filters.wants(ResultKind::File) => {
+                    for file_query in file_queries {
+                        let found = files
+                            .search(&file_query, MAX_FILE_RESULTS.min(limit) * extra)
+                            .unwrap_or_default();
+                        results.extend(found.into_iter().filter(|result| {
+                            result.data["path"]
+                                .as_str()
+                                .is_none_or(|path| !recent_paths.contains(path))
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.with_history(app, |history| history.rank(query, &mut results, index));
+        if filters.wants(ResultKind::Calculator) {
+            results.extend(calc::search(query));
+        }
+        if !filters.is_empty() {
+            results.retain(|result| filters.matches(result.kind, &result.data));
+        }
+        sort_results(&mut results);
+        results.truncate(limit);
+        results
+    }
+
+    pub fn with_history<T>(&self, app: &AppHandle, f: impl FnOnce(&mut History) -> T) -> T {
+        let mut history = self.history.lock().unwrap();
+        f(history.get_or_insert_with(|| History::load(app)))
+    }
+
+    // 重新建立索引，返回索引中的条目数
+    pub fn refresh(&self, app: &AppHandle) -> usize {
+        let index = SearchIndex::build(app);
+        let len = index.len();
+        *self.index.write().unwrap() = Some(index);
+        len
+    }
+}
+
+// 插件目录等发生变化后清空索引，下次搜索时重新建立
+pub fn invalidate<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(engine) = app.try_state::<SearchEngine>() {
+        *engine.index.write().unwrap() = None;
+        *engine.commands.write().unwrap() = None;
+    }
+    if let Some(actions) = app.try_state::<actions::ActionService>() {
+        actions.invalidate();
+    }
+}
+
+// 返回的结果带有可执行的动作，通过 execute_action 执行
+#[command]
+pub async fn search(
+    app: AppHandle,
+    engine: State<'_, SearchEngine>,
+    actions: State<'_, actions::ActionService>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, String> {
+    let mut results = engine.search(&app, &query, limit.unwrap_or(DEFAULT_LIMIT));
+    actions.attach(&app, &mut results);
+    if let Some(icons) = app.try_state::<crate::icons::IconService>() {
+        icons.attach(&mut results);
+    }
+    Ok(results)
+}
+
+// 解析搜索框的输入，前端据此决定调用插件还是搜索，并显示输入中的错误
+#[command]
+pub async fn parse_query(
+    app: AppHandle,
+    engine: State<'_, SearchEngine>,
+    query: String,
+) -> Result<ParsedQuery, String> {
+    Ok(engine.parse(&app, &query))
+}
+
+// 安装/卸载插件或应用后调用，重建索引
+#[command]
+pub async fn refresh_search_index(
+    app: AppHandle,
+    engine: State<'_, SearchEngine>,
+    actions: State<'_, actions::ActionService>,
+) -> Result<usize, String> {
+    actions.invalidate();
+    *engine.commands.write().unwrap() = None;
+    Ok(engine.refresh(&app))
+}
+
+// 记录一次启动，用于结果排序
+#[command]
+pub async fn record_launch(
+    app: AppHandle,
+    engine: State<'_, SearchEngine>,
+    id: String,
+    query: Option<String>,
+) -> Result<(), String> {
+    engine.with_history(&app, |history| {
+        history.record(&id, query.as_deref());
+        history.save(&app)
+    })
+}
+
+// 清空启动历史
+#[command]
+pub async fn clear_search_history(
+    app: AppHandle,
+    engine: State<'_, SearchEngine>,
+) -> Result<(), String> {
+    engine.with_history(&app, |history| {
+        *history = History::default();
+        history.save(&app)
+    })
+}
+
+// 将启动历史导出为 JSON 文件
+#[command]
+pub async fn export_search_history(
+    app: AppHandle,
+    engine: State<'_, SearchEngine>,
+    path: String,
+) -> Result<(), String> {
+    let content = engine.with_history(&app, |history| {
+        serde_json::to_string_pretty(history).map_err(|e| e.to_string())
+    })?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
@@ -0,0 +1,733 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use tauri::{AppHandle, Runtime};
+
+use super::aliases::{Alias, AliasTarget};
+use super::index::ResultKind;
+use crate::plugins::discovery;
+
+// 以这些前缀开头时交给对应的搜索，前缀之后的内容原样传入
+const CONTENT_PREFIX: &str = super::content::QUERY_PREFIX;
+const CLIPBOARD_PREFIX: &str = crate::clipboard::QUERY_PREFIX;
+const SHELL_PREFIX: &str = crate::shell::QUERY_PREFIX;
+const CALCULATOR_PREFIX: &str = super::calc::QUERY_PREFIX;
+
+// 查询交给谁处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryMode {
+    Search,
+    Content,
+    Clipboard,
+    Shell,
+    Calculator,
+    Plugin,
+}
+
+// 按空白分开的一段输入
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    // 带引号或转义的内容不会被当作过滤条件或选项
+    pub quoted: bool,
+    // 在输入中的字符位置，用于提示错误
+    pub start: usize,
+}
+
+// 输入中的错误，position 为出错的字符位置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryError {
+    pub message: String,
+    pub position: Option<usize>,
+}
+
+impl QueryError {
+    fn new(message: String, position: Option<usize>) -> Self {
+        QueryError { message, position }
+    }
+}
+
+// 按 shell 的规则分词：单引号内原样保留，双引号内和引号外可以用 `\` 转义
+// 引号没有闭合时仍返回已读到的内容，同时返回错误
+pub fn tokenize(input: &str) -> (Vec<Token>, Option<QueryError>) {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut chars = input.chars().enumerate().peekable();
+    let mut error = None;
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            tokens.extend(current.take());
+            continue;
+        }
+        let token = current.get_or_insert_with(|| Token {
+            text: String::new(),
+            quoted: false,
+            start: i,
+        });
+        match c {
+            '\'' | '"' => {
+                token.quoted = true;
+                let mut closed = false;
+                while let Some((_, next)) = chars.next() {
+                    if next == c {
+                        closed = true;
+                        break;
+                    }
+                    // 双引号内只有 `"` 和 `\` 需要转义
+                    if c == '"' && next == '\\' {
+                        if let Some((_, escaped)) = chars.next_if(|(_, e)| *e == '"' || *e == '\\')
+                        {
+                            token.text.push(escaped);
+                            continue;
+                        }
+                    }
+                    token.text.push(next);
+                }
+                if !closed && error.is_none() {
+                    error = Some(QueryError::new(
+                        format!("Unterminated quote {}", c),
+                        Some(i),
+                    ));
+                }
+            }
+            '\\' => match chars.next() {
+                Some((_, escaped)) => {
+                    token.quoted = true;
+                    token.text.push(escaped);
+                }
+                None => token.text.push(c),
+            },
+            _ => token.text.push(c),
+        }
+    }
+    tokens.extend(current);
+    (tokens, error)
+}
+
+// 普通搜索中的过滤条件
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryFilters {
+    // `type:` 只显示这些类型的结果
+    pub kinds: Vec<ResultKind>,
+    // `ext:` 只显示这些扩展名的文件，小写且不带 `.`
+    pub extensions: Vec<String>,
+}
+
+impl QueryFilters {
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty() && self.extensions.is_empty()
+    }
+
+    // 是否需要搜索某类结果
+    pub fn wants(&self, kind: ResultKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    // 结果是否满足过滤条件，有扩展名条件时没有路径的结果被过滤掉
+    pub fn matches(&self, kind: ResultKind, data: &Value) -> bool {
+        self.wants(kind) && self.matches_extension(data)
+    }
+
+    pub fn matches_extension(&self, data: &Value) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        data["path"]
+            .as_str()
+            .and_then(|path| Path::new(path).extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| self.extensions.contains(&ext))
+    }
+
+    // 解析 `key:value` 形式的过滤条件，不是过滤条件时返回 None
+    fn apply(&mut self, token: &Token) -> Option<Result<(), QueryError>> {
+        if token.quoted {
+            return None;
+        }
+        let (key, value) = token.text.split_once(':')?;
+        let values = value
+            .split(',')
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty());
+        match key.to_lowercase().as_str() {
+            "type" => {
+                for value in values {
+                    match kinds_of(&value) {
+                        Some(kinds) => self.kinds.extend(kinds),
+                        None => {
+                            return Some(Err(QueryError::new(
+                                format!("Unknown type `{}`", value),
+                                Some(token.start),
+                            )))
+                        }
+                    }
+                }
+            }
+            "ext" => self
+                .extensions
+                .extend(values.map(|value| value.trim_start_matches('.').to_string())),
+            _ => return None,
+        }
+        Some(Ok(()))
+    }
+}
+
+// `type:` 的值对应的结果类型
+fn kinds_of(name: &str) -> Option<Vec<ResultKind>> {
+    let kinds = match name {
+        "app" | "apps" => vec![ResultKind::App],
+        "window" | "windows" => vec![ResultKind::Window],
+        "plugin" | "plugins" => vec![ResultKind::Plugin, ResultKind::InternalPlugin],
+        "file" | "files" => vec![ResultKind::File, ResultKind::Recent],
+        "recent" => vec![ResultKind::Recent],
+        "bookmark" | "bookmarks" => vec![ResultKind::Bookmark],
+        "snippet" | "snippets" => vec![ResultKind::Snippet],
+        "calc" | "calculator" => vec![ResultKind::Calculator],
+        _ => return None,
+    };
+    Some(kinds)
+}
+
+// 插件声明的参数类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArgumentType {
+    #[default]
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Enum,
+    // 已存在的文件或目录
+    Path,
+}
+
+// 插件在 config.json 的 `arguments` 中声明的参数
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArgumentSpec {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub kind: ArgumentType,
+    #[serde(default)]
+    pub required: bool,
+    // 为 true 时通过 `--name value` 传入，否则按位置传入
+    #[serde(default)]
+    pub flag: bool,
+    // flag 的短名，如 `n` 对应 `-n`
+    pub short: Option<String>,
+    // enum 类型可选的值
+    #[serde(default)]
+    pub values: Vec<String>,
+    // 最后一个位置参数可以接收剩余的所有参数
+    #[serde(default)]
+    pub variadic: bool,
+}
+
+impl ArgumentSpec {
+    fn convert(&self, text: &str, position: usize) -> Result<Value, QueryError> {
+        let invalid = |expected: &str| {
+            QueryError::new(
+                format!("`{}` expects {}, got `{}`", self.name, expected, text),
+                Some(position),
+            )
+        };
+        match self.kind {
+            ArgumentType::String => Ok(json!(text)),
+            ArgumentType::Number => text
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .map(|number| json!(number))
+                .ok_or(invalid("a number")),
+            ArgumentType::Integer => text
+                .parse::<i64>()
+                .map(|number| json!(number))
+                .map_err(|_| invalid("an integer")),
+            ArgumentType::Boolean => match text.to_lowercase().as_str() {
+                "true" | "yes" | "1" | "on" => Ok(json!(true)),
+                "false" | "no" | "0" | "off" => Ok(json!(false)),
+                _ => Err(invalid("true or false")),
+            },
+            ArgumentType::Enum => {
+                if self.values.iter().any(|value| value == text) {
+                    Ok(json!(text))
+                } else {
+                    Err(invalid(&format!("one of {}", self.values.join(", "))))
+                }
+            }
+            ArgumentType::Path => {
+                if Path::new(text).exists() {
+                    Ok(json!(text))
+                } else {
+                    Err(QueryError::new(
+                        format!("`{}` does not exist", text),
+                        Some(position),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+// 按插件声明的参数检查输入，返回参数名到值的映射
+pub fn validate(
+    specs: &[ArgumentSpec],
+    tokens: &[Token],
+) -> Result<Map<String, Value>, QueryError> {
+    let mut values = Map::new();
+    let positionals: Vec<&ArgumentSpec> = specs.iter().filter(|spec| !spec.flag).collect();
+    let mut position = 0;
+    let mut rest_only = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        i += 1;
+        // `--` 之后都是位置参数
+        if !token.quoted && !rest_only && token.text == "--" {
+            rest_only = true;
+            continue;
+        }
+        let flag_name = match (token.quoted || rest_only, token.text.strip_prefix("--")) {
+            (false, Some(name)) if !name.is_empty() => Some(name.to_string()),
+            _ => token
+                .text
+                .strip_prefix('-')
+                .filter(|name| !token.quoted && !rest_only && name.chars().count() == 1)
+                .filter(|name| name.parse::<f64>().is_err())
+                .map(str::to_string),
+        };
+        if let Some(flag_name) = flag_name {
+            let (name, inline) = match flag_name.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (flag_name, None),
+            };
+            let spec = specs
+                .iter()
+                .filter(|spec| spec.flag)
+                .find(|spec| spec.name == name || spec.short.as_deref() == Some(name.as_str()))
+                .ok_or(QueryError::new(
+                    format!("Unknown option `{}`", token.text),
+                    Some(token.start),
+                ))?;
+            let value = match (inline, spec.kind) {
+                (Some(value), _) => spec.convert(&value, token.start)?,
+                (None, ArgumentType::Boolean) => json!(true),
+                (None, _) => {
+                    let next = tokens.get(i).ok_or(QueryError::new(
+                        format!("Option `{}` requires a value", token.text),
+                        Some(token.start),
+                    ))?;
+                    i += 1;
+                    spec.convert(&next.text, next.start)?
+                }
+            };
+            values.insert(spec.name.clone(), value);
+            continue;
+        }
+        let spec = match positionals.get(position) {
+            Some(spec) => spec,
+            None => match positionals.last().filter(|spec| spec.variadic) {
+                Some(spec) => spec,
+                None => {
+                    return Err(QueryError::new(
+                        format!("Unexpected argument `{}`", token.text),
+                        Some(token.start),
+                    ))
+                }
+            },
+        };
+        let value = spec.convert(&token.text, token.start)?;
+        if spec.variadic {
+            match values.get_mut(&spec.name) {
+                Some(Value::Array(items)) => items.push(value),
+                _ => {
+                    values.insert(spec.name.clone(), json!([value]));
+                }
+            }
+        } else {
+            values.insert(spec.name.clone(), value);
+        }
+        position += 1;
+    }
+    if let Some(missing) = specs
+        .iter()
+        .find(|spec| spec.required && !values.contains_key(&spec.name))
+    {
+        let name = match missing.flag {
+            true => format!("--{}", missing.name),
+            false => missing.name.clone(),
+        };
+        return Err(QueryError::new(
+            format!("Missing argument `{}`", name),
+            None,
+        ));
+    }
+    Ok(values)
+}
+
+// 通过前缀调用的二进制/脚本插件
+#[derive(Debug, Clone)]
+pub struct PluginCommand {
+    pub id: String,
+    pub prefix: String,
+    // 没有声明时不检查参数
+    pub arguments: Option<Vec<ArgumentSpec>>,
+    // 参数声明有错误时，使用该插件会提示这个错误
+    pub error: Option<String>,
+}
+
+impl PluginCommand {
+    // 读取所有声明了 prefix 的插件
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Vec<PluginCommand> {
+        discovery::discover(app)
+            .plugins
+            .into_iter()
+            .filter(|plugin| {
+                matches!(
+                    plugin.config.get("type").and_then(|v| v.as_str()),
+                    Some("binary" | "script")
+                )
+            })
+            .filter_map(|plugin| {
+                let prefix = plugin.config.get("prefix")?.as_str()?.trim().to_string();
+                if prefix.is_empty() {
+                    return None;
+                }
+                let arguments = plugin
+                    .config
+                    .get("arguments")
+                    .cloned()
+                    .map(serde_json::from_value::<Vec<ArgumentSpec>>)
+                    .transpose();
+                let (arguments, error) = match arguments {
+                    Ok(arguments) => (arguments, None),
+                    Err(e) => (
+                        None,
+                        Some(format!("Invalid arguments in plugin {}: {}", plugin.id, e)),
+                    ),
+                };
+                Some(PluginCommand {
+                    id: plugin.id,
+                    prefix,
+                    arguments,
+                    error,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedQuery {
+    pub mode: QueryMode,
+    // 插件或内置搜索的前缀
+    pub prefix: Option<String>,
+    pub plugin_id: Option<String>,
+    // 去掉前缀和过滤条件后用于匹配的内容
+    pub text: String,
+    // 传给插件的参数，保留大小写和引号内的空白
+    pub args: Vec<String>,
+    pub filters: QueryFilters,
+    // 按插件声明转换后的参数
+    pub arguments: Map<String, Value>,
+    pub error: Option<QueryError>,
+}
+
+impl ParsedQuery {
+    fn new(mode: QueryMode, prefix: Option<&str>, text: &str) -> Self {
+        ParsedQuery {
+            mode,
+            prefix: prefix.map(str::to_string),
+            plugin_id: None,
+            text: text.trim().to_string(),
+            args: Vec::new(),
+            filters: QueryFilters::default(),
+            arguments: Map::new(),
+            error: None,
+        }
+    }
+}
+
+// 解析搜索框的输入
+pub fn parse(input: &str, commands: &[PluginCommand], aliases: &[Alias]) -> ParsedQuery {
+    let trimmed = input.trim_start();
+    // 命令和表达式原样保留，不分词
+    for (prefix, mode) in [
+        (CLIPBOARD_PREFIX, QueryMode::Clipboard),
+        (SHELL_PREFIX, QueryMode::Shell),
+        (CALCULATOR_PREFIX, QueryMode::Calculator),
+    ] {
+        if let Some(rest) = trimmed.strip_prefix(prefix) {
+            return ParsedQuery::new(mode, Some(prefix), rest);
+        }
+    }
+
+    let (tokens, error) = tokenize(input);
+    // 前缀后面至少有一个空白才调用插件，只输入前缀时按普通搜索
+    if let Some(first) = tokens.first().filter(|first| !first.quoted) {
+        let has_args = input.trim().chars().count() > first.text.chars().count();
+        if let (Some((command, preset)), true) =
+            (find_command(&first.text, commands, aliases), has_args)
+        {
+            // 别名预设的参数放在输入的参数之前
+            let rest: Vec<Token> = preset
+                .iter()
+                .map(|text| Token {
+                    text: text.clone(),
+                    quoted: false,
+                    start: first.start,
+                })
+                .chain(tokens[1..].iter().cloned())
+                .collect();
+            let mut parsed = ParsedQuery::new(QueryMode::Plugin, Some(&first.text), "");
+            parsed.text = join(&rest);
+            parsed.args = rest.iter().map(|token| token.text.clone()).collect();
+            parsed.plugin_id = Some(command.id.clone());
+            parsed.error = match (error, &command.error) {
+                (Some(e), _) => Some(e),
+                (None, Some(message)) => Some(QueryError::new(message.clone(), None)),
+                (None, None) => match &command.arguments {
+                    Some(specs) => validate(specs, &rest)
+                        .map(|arguments| parsed.arguments = arguments)
+                        .err(),
+                    None => None,
+                },
+            };
+            return parsed;
+        }
+    }
+
+    // `in:` 后面的内容搜索文件内容，可以和其它过滤条件一起使用
+    let (mode, rest) = match trimmed.strip_prefix(CONTENT_PREFIX) {
+        Some(rest) => (QueryMode::Content, rest),
+        None => (QueryMode::Search, input),
+    };
+    // 位置仍按完整输入计算
+    let offset = input.chars().count() - rest.chars().count();
+    let (mut tokens, error) = tokenize(rest);
+    // 搜索时引号没有闭合多半是名称中的撇号，按空白分开即可
+    if error.is_some() {
+        tokens = split(rest);
+    }
+    tokens.iter_mut().for_each(|token| token.start += offset);
+    let mut parsed = ParsedQuery::new(
+        mode,
+        (mode == QueryMode::Content).then_some(CONTENT_PREFIX),
+        "",
+    );
+    let mut words = Vec::new();
+    for token in tokens {
+        match parsed.filters.apply(&token) {
+            Some(Ok(())) => {}
+            Some(Err(e)) => {
+                parsed.error.get_or_insert(e);
+            }
+            None => words.push(token),
+        }
+    }
+    parsed.text = join(&words);
+    parsed
+}
+
+// 按插件前缀或指向插件的别名查找，返回别名预设的参数
+fn find_command<'a>(
+    word: &str,
+    commands: &'a [PluginCommand],
+    aliases: &'a [Alias],
+) -> Option<(&'a PluginCommand, &'a [String])> {
+    if let Some(command) = commands.iter().find(|command| command.prefix == word) {
+        return Some((command, &[]));
+    }
+    aliases
+        .iter()
+        .filter(|alias| alias.keyword.eq_ignore_ascii_case(word))
+        .find_map(|alias| match &alias.target {
+            AliasTarget::Plugin { id, args } => commands
+                .iter()
+                .find(|command| &command.id == id)
+                .map(|command| (command, args.as_slice())),
+            AliasTarget::App { .. } => None,
+        })
+}
+
+// 只按空白分开，不处理引号和转义
+fn split(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    for (i, c) in input.chars().enumerate() {
+        if c.is_whitespace() {
+            tokens.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| Token {
+                    text: String::new(),
+                    quoted: false,
+                    start: i,
+                })
+                .text
+                .push(c);
+        }
+    }
+    tokens.extend(current);
+    tokens
+}
+
+fn join(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|token| token.text.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|token| token.text.as_str()).collect()
+    }
+
+    fn spec(value: Value) -> ArgumentSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn command(id: &str, prefix: &str, arguments: Option<Vec<ArgumentSpec>>) -> PluginCommand {
+        PluginCommand {
+            id: id.to_string(),
+            prefix: prefix.to_string(),
+            arguments,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn tokenizes_quotes_and_escapes() {
+        let (tokens, error) = tokenize(r#"a  'b c' "d \"e\" \n" f\ g"#);
+        assert!(error.is_none());
+        assert_eq!(texts(&tokens), ["a", "b c", r#"d "e" \n"#, "f g"]);
+        assert_eq!(
+            tokens.iter().map(|token| token.quoted).collect::<Vec<_>>(),
+            [false, true, true, true]
+        );
+        assert_eq!(
+            tokens.iter().map(|token| token.start).collect::<Vec<_>>(),
+            [0, 3, 9, 22]
+        );
+
+        // 位置按字符计算，引号中间也可以连着其它内容
+        let (tokens, _) = tokenize("文件 x'y z'w");
+        assert_eq!(texts(&tokens), ["文件", "xy zw"]);
+        assert_eq!(tokens[1].start, 3);
+
+        let (tokens, error) = tokenize("a 'b c");
+        assert_eq!(texts(&tokens), ["a", "b c"]);
+        assert_eq!(error.unwrap().position, Some(2));
+        assert_eq!(texts(&tokenize(r"a\").0), [r"a\"]);
+        assert!(tokenize("   ").0.is_empty());
+    }
+
+    #[test]
+    fn parses_filters() {
+        let parsed = parse("Type:app,Files ext:.PDF,md report 'ext:x'", &[], &[]);
+        assert_eq!(parsed.mode, QueryMode::Search);
+        assert_eq!(parsed.text, "report ext:x");
+        assert_eq!(
+            parsed.filters.kinds,
+            [ResultKind::App, ResultKind::File, ResultKind::Recent]
+        );
+        assert_eq!(parsed.filters.extensions, ["pdf", "md"]);
+        assert!(parsed
+            .filters
+            .matches(ResultKind::File, &json!({ "path": "/a/b.Pdf" })));
+        assert!(!parsed
+            .filters
+            .matches(ResultKind::File, &json!({ "path": "/a/b.txt" })));
+        assert!(!parsed.filters.matches(ResultKind::App, &json!({})));
+        assert!(!parsed.filters.wants(ResultKind::Bookmark));
+
+        let parsed = parse("x type:nope", &[], &[]);
+        assert_eq!(parsed.error.unwrap().position, Some(2));
+        // 没有闭合的引号按空白分开
+        let parsed = parse("in: ext:rs don't", &[], &[]);
+        assert_eq!(parsed.mode, QueryMode::Content);
+        assert_eq!(parsed.text, "don't");
+        assert_eq!(parsed.filters.extensions, ["rs"]);
+        assert!(parsed.error.is_none());
+    }
+
+    #[test]
+    fn parses_prefixes() {
+        let parsed = parse("  > ls 'a  b'", &[], &[]);
+        assert_eq!(parsed.mode, QueryMode::Shell);
+        assert_eq!(parsed.text, "ls 'a  b'");
+        assert_eq!(parse("=1 + 2", &[], &[]).mode, QueryMode::Calculator);
+        assert_eq!(parse("cb: x", &[], &[]).mode, QueryMode::Clipboard);
+
+        let commands = [command("translate", "tr", None)];
+        let aliases = [Alias {
+            id: String::new(),
+            keyword: "EN".to_string(),
+            target: AliasTarget::Plugin {
+                id: "translate".to_string(),
+                args: vec!["--to".to_string(), "en".to_string()],
+            },
+        }];
+        let parsed = parse("tr 'Hello  World' x", &commands, &aliases);
+        assert_eq!(parsed.mode, QueryMode::Plugin);
+        assert_eq!(parsed.plugin_id.as_deref(), Some("translate"));
+        assert_eq!(parsed.args, ["Hello  World", "x"]);
+        let parsed = parse("en 你好", &commands, &aliases);
+        assert_eq!(parsed.prefix.as_deref(), Some("en"));
+        assert_eq!(parsed.args, ["--to", "en", "你好"]);
+        // 只输入前缀或者前缀带引号时按普通搜索
+        assert_eq!(parse("tr", &commands, &aliases).mode, QueryMode::Search);
+        assert_eq!(parse("'tr' x", &commands, &aliases).mode, QueryMode::Search);
+    }
+
+    #[test]
+    fn validates_arguments() {
+        let specs = [
+            spec(json!({ "name": "count", "type": "integer", "flag": true, "short": "n" })),
+            spec(json!({ "name": "verbose", "type": "boolean", "flag": true })),
+            spec(json!({ "name": "mode", "type": "enum", "values": ["a", "b"], "required": true })),
+            spec(json!({ "name": "rest", "type": "number", "variadic": true })),
+        ];
+        let check = |input: &str| validate(&specs, &tokenize(input).0);
+        let values = check("-n 3 b --verbose 1 -- -2.5").unwrap();
+        assert_eq!(
+            Value::Object(values),
+            json!({ "count": 3, "verbose": true, "mode": "b", "rest": [1.0, -2.5] })
+        );
+        let values = check("--count=4 --verbose=no a").unwrap();
+        assert_eq!(values["count"], json!(4));
+        assert_eq!(values["verbose"], json!(false));
+
+        let error = |input: &str| check(input).unwrap_err();
+        assert_eq!(
+            error("a -n x").message,
+            "`count` expects an integer, got `x`"
+        );
+        assert_eq!(error("a -n x").position, Some(5));
+        assert_eq!(error("c").message, "`mode` expects one of a, b, got `c`");
+        assert_eq!(error("a --size 1").message, "Unknown option `--size`");
+        assert_eq!(error("a -n").message, "Option `-n` requires a value");
+        assert_eq!(error("-n 1").message, "Missing argument `mode`");
+        // 带引号的内容不当作选项
+        assert_eq!(
+            error("'-n'").message,
+            "`mode` expects one of a, b, got `-n`"
+        );
+
+        let specs = [spec(json!({ "name": "word" }))];
+        assert_eq!(
+            validate(&specs, &tokenize("a b").0).unwrap_err().message,
+            "Unexpected argument `b`"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock},
+    time::SystemTime,
+};
+use tauri::{command, AppHandle, Manager, Runtime, State, Url};
+
+use super::files::open_path;
+use super::index::{sort_results, ResultKind, SearchIndex, SearchItem, SearchResult};
+#[cfg(target_os = "linux")]
+use crate::platform::desktop::DesktopEntry;
+
+mod vscode;
+mod xbel;
+
+// 超过这个天数的记录分数减半
+const RECENCY_DAYS: f64 = 7.0;
+
+// 最近使用记录的来源，新增来源时加一种 kind 和对应的子模块
+#[derive(Debug, Clone, Copy)]
+pub enum SourceKind {
+    Xbel,
+    // 参数为编辑器的启动命令
+    VsCode(&'static str),
+}
+
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub name: String,
+    pub file: PathBuf,
+    pub kind: SourceKind,
+}
+
+impl Source {
+    fn read(&self) -> Result<Vec<RecentItem>, String> {
+        match self.kind {
+            SourceKind::Xbel => xbel::read(self),
+            SourceKind::VsCode(command) => vscode::read(self, command),
+        }
+    }
+}
+
+fn sources(home: &Path) -> Vec<Source> {
+    let mut sources = xbel::sources(home);
+    sources.extend(vscode::sources(home));
+    sources
+}
+
+// 一条最近使用记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentItem {
+    pub path: PathBuf,
+    pub name: String,
+    pub mime: Option<String>,
+    // 记录这条使用记录的应用及其启动命令
+    pub app: Option<String>,
+    pub exec: Option<String>,
+    // 最后使用时间，Unix 时间戳（秒）
+    pub time: i64,
+    pub source: String,
+}
+
+impl RecentItem {
+    fn file_name(path: &Path) -> String {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(path.to_string_lossy().to_string())
+    }
+
+    fn to_item(&self) -> SearchItem {
+        let path = self.path.to_string_lossy().to_string();
+        SearchItem::new(
+            format!("recent:{}", path),
+            ResultKind::Recent,
+            self.name.clone(),
+            json!(self),
+        )
+        .subtitle(Some(path.clone()))
+        .keyword(&path, 0.3)
+    }
+}
+
+// `file:///home/a%20b.txt` 转为本地路径，主机名只接受空或 localhost，其它协议返回 None
+pub fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    let url = Url::parse(uri).ok()?;
+    if url.scheme() != "file" {
+        return None;
+    }
+    url.to_file_path().ok()
+}
+
+// 按最后使用时间打折，越久远分数越低
+fn recency_weight(time: i64, now: i64) -> f64 {
+    let days = (now - time).max(0) as f64 / 86400.0;
+    0.5 + 0.5 / (1.0 + days / RECENCY_DAYS)
+}
+
+// 最近使用的文件，每次搜索前检查来源文件的修改时间，变化时重新读取
+#[derive(Default)]
+pub struct RecentDocuments {
+    cache: Mutex<HashMap<PathBuf, (SystemTime, Vec<RecentItem>)>>,
+    index: RwLock<SearchIndex>,
+}
+
+impl RecentDocuments {
+    fn refresh<R: Runtime>(&self, app: &AppHandle<R>) {
+        let home = match app.path().home_dir() {
+            Ok(home) => home,
+            Err(_) => return,
+        };
+        let mut cache = self.cache.lock().unwrap();
+        let mut changed = false;
+        for source in sources(&home) {
+            let modified = match fs::metadata(&source.file).and_then(|meta| meta.modified()) {
+                Ok(modified) => modified,
+                Err(_) => {
+                    changed |= cache.remove(&source.file).is_some();
+                    continue;
+                }
+            };
+            if cache.get(&source.file).map(|(time, _)| *time) == Some(modified) {
+                continue;
+            }
+            // 读取失败时不记录修改时间，下次搜索时重试
+            match source.read() {
+                Ok(items) => {
+                    cache.insert(source.file.clone(), (modified, items));
+                    changed = true;
+                }
+                Err(e) => eprintln!("Failed to read {}: {}", source.file.display(), e),
+            }
+        }
+        if !changed {
+            return;
+        }
+        // 同一文件可能出现在多个来源中，保留最近的一条
+        let mut all: Vec<&RecentItem> = cache
+            .values()
+            .flat_map(|(_, items)| items.iter())
+            .filter(|item| item.path.exists())
+            .collect();
+        all.sort_by_key(|item| Reverse(item.time));
+        let mut seen: HashSet<&Path> = HashSet::new();
+        let items = all
+            .into_iter()
+            .filter(|item| seen.insert(&item.path))
+            .map(RecentItem::to_item)
+            .collect();
+        *self.index.write().unwrap() = SearchIndex::new(items);
+    }
+
+    pub fn search<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        query: &str,
+        limit: usize,
+    ) -> Vec<SearchResult> {
+        self.refresh(app);
+        let now = chrono::Local::now().timestamp();
+        let mut results: Vec<SearchResult> = self
+            .index
+            .read()
+            .unwrap()
+            .search(query, usize::MAX)
+            .into_iter()
+            // 读取之后被删除的文件
+            .filter(|result| {
+                result.data["path"]
+                    .as_str()
+                    .is_some_and(|path| Path::new(path).exists())
+            })
+            .collect();
+        for result in results.iter_mut() {
+            let time = result.data["time"].as_i64().unwrap_or_default();
+            result.score *= recency_weight(time, now);
+        }
+        sort_results(&mut results);
+        results.truncate(limit);
+        results
+    }
+
+    fn find(&self, path: &Path) -> Option<RecentItem> {
+        self.cache
+            .lock()
+            .unwrap()
+            .values()
+            .flat_map(|(_, items)| items.iter())
+            .filter(|item| item.path == path)
+            .max_by_key(|item| item.time)
+            .cloned()
+    }
+}
+
+// 用记录的启动命令打开，命令按桌面文件 Exec 的规则展开，`%f` `%u` 等替换为文件路径
+#[cfg(target_os = "linux")]
+fn open_with_exec(exec: &str, path: &str) -> Result<(), String> {
+    let entry = DesktopEntry {
+        exec: Some(exec.to_string()),
+        ..Default::default()
+    };
+    for line in entry.command_lines(Path::new(path), &[path.to_string()], &[])? {
+        let mut command = std::process::Command::new(&line[0]);
+        command.args(&line[1..]);
+        crate::apps::spawn_detached(command, &line[0])?;
+    }
+    Ok(())
+}
+
+// 其它平台的记录来自编辑器，不按桌面文件的规则启动，用默认应用打开
+#[cfg(not(target_os = "linux"))]
+fn open_with_exec(_exec: &str, path: &str) -> Result<(), String> {
+    open_path(path.to_string())
+}
+
+// 打开最近使用的文件，with_app 为 true 时用记录它的应用打开，否则用默认应用
+#[command]
+pub fn open_recent(
+    recent: State<'_, RecentDocuments>,
+    path: String,
+    with_app: Option<bool>,
+) -> Result<(), String> {
+    if with_app.unwrap_or(false) {
+        let exec = recent.find(Path::new(&path)).and_then(|item| item.exec);
+        if let Some(exec) = exec {
+            match open_with_exec(&exec, &path) {
+                Ok(()) => return Ok(()),
+                // 应用已卸载等情况下退回默认应用
+                Err(e) => eprintln!("Failed to run {}: {}", exec, e),
+            }
+        }
+    }
+    open_path(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn converts_file_uris() {
+        let path = file_uri_to_path;
+        assert_eq!(
+            path("file:///home/a%20b/%E7%AC%94%E8%AE%B0.txt"),
+            Some(PathBuf::from("/home/a b/笔记.txt"))
+        );
+        assert_eq!(
+            path("file://localhost/tmp/x"),
+            Some(PathBuf::from("/tmp/x"))
+        );
+        assert_eq!(path("file://server/tmp/x"), None);
+        assert_eq!(path("https://example.com/x"), None);
+        assert_eq!(path("/tmp/x"), None);
+    }
+}
@@ -0,0 +1,105 @@
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use super::{file_uri_to_path, RecentItem, Source, SourceKind};
+
+// VS Code 系编辑器的配置目录名和启动命令
+const EDITORS: [(&str, &str, &str); 4] = [
+    ("VS Code", "Code", "code"),
+    ("VS Code Insiders", "Code - Insiders", "code-insiders"),
+    ("VSCodium", "VSCodium", "codium"),
+    ("Code - OSS", "Code - OSS", "code-oss"),
+];
+
+// 最近打开的文件和文件夹保存在 state.vscdb 的这个键中
+const RECENT_KEY: &str = "history.recentlyOpenedPathsList";
+
+fn config_dir(home: &Path) -> PathBuf {
+    #[cfg(target_os = "linux")]
+    {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or(home.join(".config"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        home.join("Library").join("Application Support")
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("APPDATA")
+            .map(PathBuf::from)
+            .unwrap_or(home.join("AppData").join("Roaming"))
+    }
+}
+
+pub fn sources(home: &Path) -> Vec<Source> {
+    let base = config_dir(home);
+    EDITORS
+        .iter()
+        .map(|(name, dir, command)| Source {
+            name: name.to_string(),
+            file: base
+                .join(dir)
+                .join("User")
+                .join("globalStorage")
+                .join("state.vscdb"),
+            kind: SourceKind::VsCode(command),
+        })
+        .collect()
+}
+
+// 列表中没有时间，按顺序从数据库的修改时间往前排
+pub fn read(source: &Source, command: &str) -> Result<Vec<RecentItem>, String> {
+    let modified = fs::metadata(&source.file)
+        .and_then(|meta| meta.modified())
+        .map_err(|e| e.to_string())?;
+    let modified = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let conn = Connection::open_with_flags(
+        &source.file,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| e.to_string())?;
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM ItemTable WHERE key = ?1",
+            [RECENT_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let json: Value = match value {
+        Some(value) => serde_json::from_str(&value).map_err(|e| e.to_string())?,
+        None => return Ok(Vec::new()),
+    };
+    let entries = json["entries"].as_array().cloned().unwrap_or_default();
+    Ok(entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            // 三种条目：文件、文件夹和 .code-workspace 工作区
+            let uri = entry["fileUri"]
+                .as_str()
+                .or(entry["folderUri"].as_str())
+                .or(entry["workspace"]["configPath"].as_str())?;
+            let path = file_uri_to_path(uri)?;
+            Some(RecentItem {
+                name: RecentItem::file_name(&path),
+                path,
+                mime: None,
+                app: Some(source.name.clone()),
+                exec: Some(format!("{} %f", command)),
+                time: modified - i as i64,
+                source: source.name.clone(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,162 @@
+use roxmltree::{Document, Node};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{file_uri_to_path, RecentItem, Source, SourceKind};
+
+// GLib 的最近使用文件，`$XDG_DATA_HOME/recently-used.xbel`
+pub fn sources(home: &Path) -> Vec<Source> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or(home.join(".local").join("share"));
+    vec![Source {
+        name: "recently-used".to_string(),
+        file: data_dir.join("recently-used.xbel"),
+        kind: SourceKind::Xbel,
+    }]
+}
+
+// 时间格式为 RFC 3339，如 `2024-01-01T10:00:00.123456Z`
+fn timestamp(value: Option<&str>) -> i64 {
+    value
+        .and_then(|value| chrono::DateTime::parse_from_rfc3339(value).ok())
+        .map(|time| time.timestamp())
+        .unwrap_or_default()
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+// `<bookmark>` 下的 `<info><metadata>`
+fn metadata<'a, 'i>(bookmark: Node<'a, 'i>) -> Option<Node<'a, 'i>> {
+    child(bookmark, "info").and_then(|info| child(info, "metadata"))
+}
+
+pub fn read(source: &Source) -> Result<Vec<RecentItem>, String> {
+    let content = fs::read_to_string(&source.file).map_err(|e| e.to_string())?;
+    let doc = Document::parse(&content).map_err(|e| e.to_string())?;
+    let mut items = Vec::new();
+    for bookmark in doc
+        .root_element()
+        .children()
+        .filter(|node| node.is_element() && node.tag_name().name() == "bookmark")
+    {
+        // 只保留本地文件，跳过 sftp:// 等远程地址
+        let path = match bookmark.attribute("href").and_then(file_uri_to_path) {
+            Some(path) => path,
+            None => continue,
+        };
+        let mut time = ["visited", "modified", "added"]
+            .iter()
+            .map(|name| timestamp(bookmark.attribute(*name)))
+            .max()
+            .unwrap_or_default();
+        let mut mime = None;
+        let mut app = None;
+        let mut exec = None;
+        if let Some(metadata) = metadata(bookmark) {
+            mime = child(metadata, "mime-type")
+                .and_then(|node| node.attribute("type"))
+                .map(str::to_string);
+            // 同一文件可能被多个应用记录，取最近一次打开它的应用
+            let latest = child(metadata, "applications").and_then(|apps| {
+                apps.children()
+                    .filter(|node| node.is_element() && node.tag_name().name() == "application")
+                    .max_by_key(|node| timestamp(node.attribute("modified")))
+            });
+            if let Some(node) = latest {
+                time = time.max(timestamp(node.attribute("modified")));
+                app = node.attribute("name").map(str::to_string);
+                // exec 整体被单引号包住，如 `'gedit %u'`
+                exec = node.attribute("exec").map(|exec| {
+                    exec.strip_prefix('\'')
+                        .and_then(|exec| exec.strip_suffix('\''))
+                        .unwrap_or(exec)
+                        .to_string()
+                });
+            }
+        }
+        items.push(RecentItem {
+            name: RecentItem::file_name(&path),
+            path,
+            mime,
+            app,
+            exec,
+            time,
+            source: source.name.clone(),
+        });
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XBEL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info">
+  <bookmark href="file:///home/u/My%20Notes.txt" added="2024-01-01T10:00:00Z" modified="2024-01-02T10:00:00.123456Z" visited="2024-01-01T12:00:00Z">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="text/plain"/>
+        <bookmark:applications>
+          <bookmark:application name="gedit" exec="&apos;gedit %u&apos;" modified="2024-01-01T10:00:00Z" count="1"/>
+          <bookmark:application name="Text Editor" exec="&apos;gnome-text-editor %u&apos;" modified="2024-01-03T08:00:00Z" count="2"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+  <bookmark href="sftp://host/remote.txt" added="2024-01-01T10:00:00Z"/>
+  <bookmark href="file://localhost/tmp/%E6%96%87%E4%BB%B6.pdf" added="bad"/>
+</xbel>"#;
+
+    #[test]
+    fn reads_local_bookmarks() {
+        let file = std::env::temp_dir().join(format!("toolbox-xbel-{}.xbel", std::process::id()));
+        fs::write(&file, XBEL).unwrap();
+        let source = Source {
+            name: "recently-used".to_string(),
+            file: file.clone(),
+            kind: SourceKind::Xbel,
+        };
+        let items = read(&source);
+        fs::remove_file(&file).unwrap();
+        let items = items.unwrap();
+        assert_eq!(items.len(), 2);
+
+        let notes = &items[0];
+        assert_eq!(notes.path, PathBuf::from("/home/u/My Notes.txt"));
+        assert_eq!(notes.name, "My Notes.txt");
+        assert_eq!(notes.mime.as_deref(), Some("text/plain"));
+        // 取最近一次打开它的应用，时间也取其中最晚的
+        assert_eq!(notes.app.as_deref(), Some("Text Editor"));
+        assert_eq!(notes.exec.as_deref(), Some("gnome-text-editor %u"));
+        assert_eq!(notes.time, timestamp(Some("2024-01-03T08:00:00Z")));
+        assert_eq!(notes.source, "recently-used");
+
+        let pdf = &items[1];
+        assert_eq!(pdf.path, PathBuf::from("/tmp/文件.pdf"));
+        assert_eq!(
+            (pdf.mime.as_ref(), pdf.app.as_ref(), pdf.time),
+            (None, None, 0)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let source = Source {
+            name: "missing".to_string(),
+            file: std::env::temp_dir().join("toolbox-xbel-missing.xbel"),
+            kind: SourceKind::Xbel,
+        };
+        assert!(read(&source).is_err());
+        assert_eq!(timestamp(Some("2024-01-01T00:00:00+08:00")), 1704038400);
+        assert_eq!(timestamp(Some("yesterday")), 0);
+    }
+}
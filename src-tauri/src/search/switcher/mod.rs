@@ -0,0 +1,114 @@
+use serde::Serialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+use super::index::{ResultKind, SearchIndex, SearchItem, SearchResult};
+
+#[cfg(target_os = "linux")]
+mod x11;
+
+// 一个已打开的顶层窗口
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    // WM_CLASS 的两部分，如 `navigator` 和 `firefox`
+    pub instance: Option<String>,
+    pub class: Option<String>,
+    pub pid: Option<u32>,
+    // 进程的可执行文件
+    pub exe: Option<PathBuf>,
+    // 所在桌面，出现在所有桌面上时为 None
+    pub desktop: Option<u32>,
+    pub active: bool,
+}
+
+impl WindowInfo {
+    fn to_item(&self) -> SearchItem {
+        let app = self.class.as_deref().or(self.instance.as_deref());
+        SearchItem::new(
+            format!("window:{}", self.id),
+            ResultKind::Window,
+            self.title.clone(),
+            json!(self),
+        )
+        .subtitle(app.map(str::to_string))
+        .keyword(self.class.as_deref().unwrap_or_default(), 0.8)
+        .keyword(self.instance.as_deref().unwrap_or_default(), 0.6)
+    }
+
+    // 是否属于某个已安装的应用：可执行文件相同，或 WM_CLASS 与应用名/文件名相同
+    fn belongs_to(&self, path: Option<&Path>, name: Option<&str>) -> bool {
+        if let (Some(exe), Some(path)) = (self.exe.as_deref(), path) {
+            if exe == path {
+                return true;
+            }
+        }
+        let stem = path
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_lowercase());
+        let name = name.map(str::to_lowercase);
+        [self.class.as_deref(), self.instance.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::to_lowercase)
+            .any(|class| Some(&class) == stem.as_ref() || Some(&class) == name.as_ref())
+    }
+}
+
+// 列出已打开的窗口，目前只支持 X11，其它平台返回空列表
+pub fn list() -> Result<Vec<WindowInfo>, String> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::list()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+pub fn search(windows: &[WindowInfo], query: &str, limit: usize) -> Vec<SearchResult> {
+    let items = windows.iter().map(WindowInfo::to_item).collect();
+    SearchIndex::new(items).search(query, limit)
+}
+
+// 给已经在运行的应用加上窗口 id，点击时切换到窗口而不是重新启动
+pub fn annotate(results: &mut [SearchResult], windows: &[WindowInfo]) {
+    for result in results
+        .iter_mut()
+        .filter(|result| result.kind == ResultKind::App)
+    {
+        let path = result.data["path"].as_str().map(Path::new);
+        let name = result.data["name"].as_str();
+        // 有多个窗口时优先当前活动窗口以外最前面的一个
+        let window = windows
+            .iter()
+            .filter(|window| window.belongs_to(path, name))
+            .min_by_key(|window| window.active);
+        if let Some(window) = window {
+            result.data["window"] = json!(window.id);
+        }
+    }
+}
+
+#[command]
+pub fn get_open_windows() -> Result<Vec<WindowInfo>, String> {
+    list()
+}
+
+// 切换到指定窗口
+#[command]
+pub fn activate_window(id: u32) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        x11::activate(id)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = id;
+        Err("Window switching is only supported on X11".to_string())
+    }
+}
@@ -0,0 +1,426 @@
+use std::fs;
+use x11rb::{
+    connection::Connection,
+    cookie::Cookie,
+    protocol::xproto::{
+        Atom, AtomEnum, ClientMessageEvent, ConfigureWindowAux, ConnectionExt as _, EventMask,
+        GetPropertyReply, InputFocus, StackMode, Window,
+    },
+    rust_connection::RustConnection,
+    CURRENT_TIME, NONE,
+};
+
+use super::WindowInfo;
+
+// 窗口属性最多读取的长度（32 位为单位）
+const MAX_PROPERTY_LENGTH: u32 = 1024;
+// 出现在所有桌面上的窗口
+const ALL_DESKTOPS: u32 = 0xFFFFFFFF;
+// _NET_ACTIVE_WINDOW 的来源：来自任务栏等工具
+const SOURCE_PAGER: u32 = 2;
+
+struct Atoms {
+    client_list: Atom,
+    active_window: Atom,
+    current_desktop: Atom,
+    supported: Atom,
+    wm_name: Atom,
+    wm_pid: Atom,
+    wm_desktop: Atom,
+    wm_state: Atom,
+    wm_state_skip_taskbar: Atom,
+    wm_window_type: Atom,
+    wm_window_type_normal: Atom,
+    wm_window_type_dialog: Atom,
+}
+
+impl Atoms {
+    fn new(conn: &RustConnection) -> Result<Self, String> {
+        let names = [
+            "_NET_CLIENT_LIST",
+            "_NET_ACTIVE_WINDOW",
+            "_NET_CURRENT_DESKTOP",
+            "_NET_SUPPORTED",
+            "_NET_WM_NAME",
+            "_NET_WM_PID",
+            "_NET_WM_DESKTOP",
+            "_NET_WM_STATE",
+            "_NET_WM_STATE_SKIP_TASKBAR",
+            "_NET_WM_WINDOW_TYPE",
+            "_NET_WM_WINDOW_TYPE_NORMAL",
+            "_NET_WM_WINDOW_TYPE_DIALOG",
+        ];
+        // 先发出全部请求再等待回应，减少往返
+        let cookies = names
+            .iter()
+            .map(|name| conn.intern_atom(false, name.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let mut atoms = Vec::with_capacity(cookies.len());
+        for cookie in cookies {
+            atoms.push(cookie.reply().map_err(|e| e.to_string())?.atom);
+        }
+        Ok(Atoms {
+            client_list: atoms[0],
+            active_window: atoms[1],
+            current_desktop: atoms[2],
+            supported: atoms[3],
+            wm_name: atoms[4],
+            wm_pid: atoms[5],
+            wm_desktop: atoms[6],
+            wm_state: atoms[7],
+            wm_state_skip_taskbar: atoms[8],
+            wm_window_type: atoms[9],
+            wm_window_type_normal: atoms[10],
+            wm_window_type_dialog: atoms[11],
+        })
+    }
+}
+
+fn connect(display: Option<&str>) -> Result<(RustConnection, Window, Atoms), String> {
+    let (conn, screen_num) = x11rb::connect(display).map_err(|e| e.to_string())?;
+    let root = conn.setup().roots[screen_num].root;
+    let atoms = Atoms::new(&conn)?;
+    Ok((conn, root, atoms))
+}
+
+fn property(
+    conn: &RustConnection,
+    window: Window,
+    property: Atom,
+) -> Result<Cookie<'_, RustConnection, GetPropertyReply>, String> {
+    conn.get_property(
+        false,
+        window,
+        property,
+        AtomEnum::ANY,
+        0,
+        MAX_PROPERTY_LENGTH,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn first_u32(reply: Option<GetPropertyReply>) -> Option<u32> {
+    reply?.value32()?.next()
+}
+
+fn all_u32(reply: Option<GetPropertyReply>) -> Vec<u32> {
+    reply
+        .and_then(|reply| reply.value32().map(|values| values.collect()))
+        .unwrap_or_default()
+}
+
+fn text(reply: Option<GetPropertyReply>) -> Option<String> {
+    let reply = reply?;
+    let text = String::from_utf8_lossy(&reply.value).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+// 当前的活动窗口
+fn active(conn: &RustConnection, root: Window, atoms: &Atoms) -> Option<Window> {
+    first_u32(property(conn, root, atoms.active_window).ok()?.reply().ok())
+        .filter(|window| *window != NONE)
+}
+
+// 通过 _NET_CLIENT_LIST 列出顶层窗口，跳过不在任务栏中显示的窗口和自身的窗口
+pub fn list() -> Result<Vec<WindowInfo>, String> {
+    list_display(None)
+}
+
+fn list_display(display: Option<&str>) -> Result<Vec<WindowInfo>, String> {
+    let (conn, root, atoms) = connect(display)?;
+    let clients = all_u32(
+        property(&conn, root, atoms.client_list)?
+            .reply()
+            .map_err(|e| e.to_string())
+            .ok(),
+    );
+    if clients.is_empty() {
+        return Err("Window manager does not provide _NET_CLIENT_LIST".to_string());
+    }
+    let active = active(&conn, root, &atoms);
+    let own_pid = std::process::id();
+
+    // 每个窗口的属性请求一起发出
+    let mut pending = Vec::with_capacity(clients.len());
+    for window in clients {
+        pending.push((
+            window,
+            property(&conn, window, atoms.wm_name)?,
+            property(&conn, window, AtomEnum::WM_NAME.into())?,
+            property(&conn, window, AtomEnum::WM_CLASS.into())?,
+            property(&conn, window, atoms.wm_pid)?,
+            property(&conn, window, atoms.wm_desktop)?,
+            property(&conn, window, atoms.wm_state)?,
+            property(&conn, window, atoms.wm_window_type)?,
+        ));
+    }
+
+    let mut windows = Vec::new();
+    for (window, net_name, name, class, pid, desktop, state, window_type) in pending {
+        let state = all_u32(state.reply().ok());
+        if state.contains(&atoms.wm_state_skip_taskbar) {
+            continue;
+        }
+        // 没有类型的窗口按普通窗口处理
+        let window_type = all_u32(window_type.reply().ok());
+        if !window_type.is_empty()
+            && !window_type.contains(&atoms.wm_window_type_normal)
+            && !window_type.contains(&atoms.wm_window_type_dialog)
+        {
+            continue;
+        }
+        let pid = first_u32(pid.reply().ok());
+        if pid == Some(own_pid) {
+            continue;
+        }
+        // WM_CLASS 格式为 `instance\0class\0`
+        let class = class
+            .reply()
+            .ok()
+            .map(|reply| String::from_utf8_lossy(&reply.value).to_string())
+            .unwrap_or_default();
+        let mut parts = class.split('\0').filter(|part| !part.is_empty());
+        let instance = parts.next().map(str::to_string);
+        let class = parts.next().map(str::to_string);
+        let title = text(net_name.reply().ok())
+            .or(text(name.reply().ok()))
+            .or(class.clone())
+            .unwrap_or_default();
+        windows.push(WindowInfo {
+            id: window,
+            title,
+            instance,
+            class,
+            pid,
+            exe: pid.and_then(|pid| fs::read_link(format!("/proc/{}/exe", pid)).ok()),
+            desktop: first_u32(desktop.reply().ok()).filter(|desktop| *desktop != ALL_DESKTOPS),
+            active: active == Some(window),
+        });
+    }
+    Ok(windows)
+}
+
+fn send_client_message(
+    conn: &RustConnection,
+    root: Window,
+    window: Window,
+    message_type: Atom,
+    data: [u32; 5],
+) -> Result<(), String> {
+    let event = ClientMessageEvent::new(32, window, message_type, data);
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 激活窗口，窗口在其他桌面上时先切换桌面
+// 没有支持 EWMH 的窗口管理器时直接映射、置顶并设置焦点
+pub fn activate(window: Window) -> Result<(), String> {
+    activate_display(None, window)
+}
+
+fn activate_display(display: Option<&str>, window: Window) -> Result<(), String> {
+    let (conn, root, atoms) = connect(display)?;
+    let supported = all_u32(property(&conn, root, atoms.supported)?.reply().ok());
+    if supported.contains(&atoms.active_window) {
+        let desktop = first_u32(property(&conn, window, atoms.wm_desktop)?.reply().ok());
+        let current = first_u32(property(&conn, root, atoms.current_desktop)?.reply().ok());
+        if let Some(desktop) = desktop.filter(|desktop| *desktop != ALL_DESKTOPS) {
+            if current != Some(desktop) && supported.contains(&atoms.current_desktop) {
+                send_client_message(
+                    &conn,
+                    root,
+                    root,
+                    atoms.current_desktop,
+                    [desktop, CURRENT_TIME, 0, 0, 0],
+                )?;
+            }
+        }
+        let current_active = active(&conn, root, &atoms).unwrap_or(NONE);
+        send_client_message(
+            &conn,
+            root,
+            window,
+            atoms.active_window,
+            [SOURCE_PAGER, CURRENT_TIME, current_active, 0, 0],
+        )?;
+    } else {
+        conn.map_window(window).map_err(|e| e.to_string())?;
+        conn.configure_window(
+            window,
+            &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+        )
+        .map_err(|e| e.to_string())?;
+        conn.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)
+            .map_err(|e| e.to_string())?;
+    }
+    conn.flush().map_err(|e| e.to_string())
+}
+
+// 需要 Xvfb 和支持 EWMH 的窗口管理器（默认 openbox，可用 TOOLBOX_TEST_WM 指定），默认不运行：
+// cargo test switcher -- --ignored
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::xvfb::Xvfb;
+    use std::{thread, time::Duration};
+    use x11rb::{
+        protocol::xproto::{CreateWindowAux, PropMode, WindowClass},
+        wrapper::ConnectionExt as _,
+        COPY_DEPTH_FROM_PARENT,
+    };
+
+    // 启动 Xvfb 和窗口管理器，窗口管理器声明支持 _NET_ACTIVE_WINDOW 后才算启动完成
+    fn start_session(number: u32) -> Xvfb {
+        let mut session = Xvfb::start(number);
+        let display = session.display.clone();
+        session.start_wm(|| {
+            let Ok((conn, root, atoms)) = connect(Some(&display)) else {
+                return false;
+            };
+            let supported = property(&conn, root, atoms.supported)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok());
+            all_u32(supported).contains(&atoms.active_window)
+        });
+        session
+    }
+
+    // 创建并映射一个顶层窗口，连接关闭时窗口随之销毁
+    fn map_window(
+        conn: &RustConnection,
+        title: &str,
+        class: &str,
+        pid: u32,
+        skip_taskbar: bool,
+    ) -> Window {
+        let screen = &conn.setup().roots[0];
+        let window = conn.generate_id().unwrap();
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            screen.root,
+            0,
+            0,
+            100,
+            100,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        let atoms = Atoms::new(conn).unwrap();
+        let utf8 = conn
+            .intern_atom(false, b"UTF8_STRING")
+            .unwrap()
+            .reply()
+            .unwrap()
+            .atom;
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            atoms.wm_name,
+            utf8,
+            title.as_bytes(),
+        )
+        .unwrap();
+        let wm_class = format!("{}\0{}\0", class.to_lowercase(), class);
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            wm_class.as_bytes(),
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            atoms.wm_pid,
+            AtomEnum::CARDINAL,
+            &[pid],
+        )
+        .unwrap();
+        if skip_taskbar {
+            conn.change_property32(
+                PropMode::REPLACE,
+                window,
+                atoms.wm_state,
+                AtomEnum::ATOM,
+                &[atoms.wm_state_skip_taskbar],
+            )
+            .unwrap();
+        }
+        conn.map_window(window).unwrap();
+        conn.flush().unwrap();
+        window
+    }
+
+    // 等待窗口管理器接管全部窗口后再列出
+    fn list_all(session: &Xvfb, count: usize) -> Vec<WindowInfo> {
+        let mut windows = Vec::new();
+        session.wait(|| {
+            windows = list_display(Some(&session.display)).unwrap_or_default();
+            windows.len() >= count
+        });
+        windows
+    }
+
+    #[test]
+    #[ignore]
+    fn lists_taskbar_windows() {
+        let session = start_session(94);
+        let (conn, _) = x11rb::connect(Some(&session.display)).unwrap();
+        let editor = map_window(&conn, "笔记 - Editor", "Editor", 1, false);
+        let terminal = map_window(&conn, "Terminal", "Terminal", 1, false);
+        map_window(&conn, "Tray", "Tray", 1, true);
+        map_window(&conn, "Own", "Toolbox", std::process::id(), false);
+
+        let mut windows = list_all(&session, 2);
+        // 多等一会，确认跳过的窗口不会出现
+        thread::sleep(Duration::from_millis(300));
+        windows = list_display(Some(&session.display)).unwrap_or(windows);
+        windows.sort_by_key(|window| window.id);
+        let mut expected = [
+            (editor, "笔记 - Editor", "editor", "Editor"),
+            (terminal, "Terminal", "terminal", "Terminal"),
+        ];
+        expected.sort_by_key(|(id, ..)| *id);
+        assert_eq!(windows.len(), expected.len());
+        for (window, (id, title, instance, class)) in windows.iter().zip(expected) {
+            assert_eq!(window.id, id);
+            assert_eq!(window.title, title);
+            assert_eq!(window.instance.as_deref(), Some(instance));
+            assert_eq!(window.class.as_deref(), Some(class));
+            assert_eq!(window.pid, Some(1));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn activates_window() {
+        let session = start_session(95);
+        let (conn, _) = x11rb::connect(Some(&session.display)).unwrap();
+        let first = map_window(&conn, "First", "First", 1, false);
+        let second = map_window(&conn, "Second", "Second", 1, false);
+        list_all(&session, 2);
+
+        for window in [first, second, first] {
+            activate_display(Some(&session.display), window).unwrap();
+            session.wait(|| {
+                list_display(Some(&session.display))
+                    .map(|windows| windows.iter().any(|info| info.id == window && info.active))
+                    .unwrap_or(false)
+            });
+            let (conn, root, atoms) = connect(Some(&session.display)).unwrap();
+            assert_eq!(active(&conn, root, &atoms), Some(window));
+        }
+    }
+}
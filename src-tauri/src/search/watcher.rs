@@ -0,0 +1,83 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+// 文件变化合并处理的间隔
+const DEBOUNCE: Duration = Duration::from_millis(500);
+// 没有变化时也定期回调一次，便于保存索引
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+
+// 一段时间内合并后的文件变化
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub paths: HashSet<PathBuf>,
+    // 事件队列溢出，变化可能丢失，需要重新遍历
+    pub rescan: bool,
+}
+
+// 监听 roots 及其下所有目录的变化，合并后交给 handler 处理，handler 返回 false 时停止
+// 返回的 watcher 被 drop 后监听线程也会退出
+pub fn watch<F>(roots: &[PathBuf], handler: F) -> Result<RecommendedWatcher, String>
+where
+    F: FnMut(Changes) -> bool + Send + 'static,
+{
+    watch_with(roots, RecursiveMode::Recursive, handler)
+}
+
+// 按指定的方式监听每个路径，NonRecursive 时只监听路径本身和目录中的直接子项
+pub fn watch_with<F>(
+    paths: &[PathBuf],
+    mode: RecursiveMode,
+    mut handler: F,
+) -> Result<RecommendedWatcher, String>
+where
+    F: FnMut(Changes) -> bool + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    // inotify 的监听数量有上限，部分目录监听失败时其余目录仍然可用
+    let mut failed = 0;
+    for path in paths.iter() {
+        if let Err(e) = watcher.watch(path, mode) {
+            if failed == 0 {
+                eprintln!("Failed to watch {}: {}", path.display(), e);
+            }
+            failed += 1;
+        }
+    }
+    if failed > 1 {
+        eprintln!("Failed to watch {} of {} paths", failed, paths.len());
+    }
+
+    thread::spawn(move || loop {
+        let mut changes = Changes::default();
+        match rx.recv_timeout(IDLE_INTERVAL) {
+            Ok(event) => {
+                let deadline = Instant::now() + DEBOUNCE;
+                let mut next = Some(event);
+                while let Some(event) = next.take() {
+                    match event {
+                        Ok(event) => {
+                            changes.rescan |= event.need_rescan();
+                            changes.paths.extend(event.paths);
+                        }
+                        Err(e) => eprintln!("File watcher error: {}", e),
+                    }
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    next = rx.recv_timeout(timeout).ok();
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        if !handler(changes) {
+            break;
+        }
+    });
+    Ok(watcher)
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+// 命令历史文件，位于 app_data_dir 中
+const HISTORY_FILE: &str = "shell-history.json";
+
+// 执行过的一条命令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandEntry {
+    pub command: String,
+    pub cwd: Option<String>,
+    // 最后一次执行的毫秒时间戳
+    pub time: i64,
+    pub count: u32,
+    pub exit_code: Option<i32>,
+}
+
+// 按最后执行时间排序，最近的在前
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandHistory {
+    pub entries: Vec<CommandEntry>,
+}
+
+impl CommandHistory {
+    fn file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+        app.path()
+            .app_data_dir()
+            .map(|dir| dir.join(HISTORY_FILE))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        Self::file(app)
+            .ok()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let file = Self::file(app)?;
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(file, content).map_err(|e| e.to_string())
+    }
+
+    // 记录一次执行，相同的命令移到最前面，超出 max 的旧记录被丢弃
+    pub fn record(&mut self, command: &str, cwd: Option<&str>, max: usize) {
+        let count = match self.entries.iter().position(|e| e.command == command) {
+            Some(index) => self.entries.remove(index).count,
+            None => 0,
+        };
+        self.entries.insert(
+            0,
+            CommandEntry {
+                command: command.to_string(),
+                cwd: cwd.map(str::to_string),
+                time: chrono::Local::now().timestamp_millis(),
+                count: count + 1,
+                exit_code: None,
+            },
+        );
+        self.entries.truncate(max);
+    }
+
+    // 命令结束后记录退出码
+    pub fn set_exit_code(&mut self, command: &str, exit_code: Option<i32>) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.command == command) {
+            entry.exit_code = exit_code;
+        }
+    }
+}
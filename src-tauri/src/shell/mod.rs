@@ -0,0 +1,432 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::plugins::limits::{self, ResourceLimits, Stream};
+use crate::plugins::logs::{self, PluginLogEntry};
+use crate::search::index::{ResultKind, SearchResult};
+use crate::utils::store;
+
+mod history;
+mod rules;
+mod terminal;
+
+pub use history::{CommandEntry, CommandHistory};
+
+// 搜索框中以此开头时执行命令
+pub const QUERY_PREFIX: &str = ">";
+// 命令输出和结束时发送给前端的事件
+pub const OUTPUT_EVENT: &str = "shell-output";
+pub const EXIT_EVENT: &str = "shell-exit";
+// 配置在 store 中的键
+pub const CONFIG_KEY: &str = "shell";
+// 执行记录写入插件日志目录下的 shell.log
+const LOG_ID: &str = "shell";
+// 单次执行发送给前端的最大输出，超出后只在结束时提示
+const MAX_STREAM_SIZE: usize = 1024 * 1024;
+// 输入的命令排在最前面，历史命令按最近执行的顺序
+const RUN_SCORE: f64 = 10000.0;
+const HISTORY_SCORE: f64 = 5000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShellConfig {
+    // 执行命令的 shell，为空时使用 $SHELL 或 %COMSPEC%
+    pub shell: String,
+    // 默认工作目录，为空时为用户主目录
+    pub working_dir: String,
+    // 在终端中执行时使用的终端，为空时自动查找
+    pub terminal: String,
+    // 最长运行时间（秒），0 表示不限制
+    pub timeout_seconds: u64,
+    pub history_size: usize,
+    // 命令行中的某个命令匹配这些规则时需要确认后才执行
+    // 规则为程序名加必须出现的参数，如 `rm -r`，都是不区分大小写的 glob
+    pub confirm_rules: Vec<String>,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        ShellConfig {
+            shell: String::new(),
+            working_dir: String::new(),
+            terminal: String::new(),
+            timeout_seconds: 300,
+            history_size: 200,
+            confirm_rules: [
+                "sudo",
+                "doas",
+                "rm -r",
+                "rm --recursive",
+                "mkfs*",
+                "dd of=*",
+                "* >* /dev/sd*",
+                "* >* /dev/nvme*",
+                "shutdown",
+                "reboot",
+                "chmod -r 777",
+                "format *:",
+                "del /s",
+                "rd /s",
+                "rmdir /s",
+                "Remove-Item -Recurse",
+            ]
+            .iter()
+            .map(|rule| rule.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl ShellConfig {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        store::get(app, CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn shell(&self) -> String {
+        if !self.shell.trim().is_empty() {
+            return self.shell.trim().to_string();
+        }
+        #[cfg(target_os = "windows")]
+        let shell = std::env::var("COMSPEC").unwrap_or("cmd.exe".to_string());
+        #[cfg(not(target_os = "windows"))]
+        let shell = std::env::var("SHELL").unwrap_or("/bin/sh".to_string());
+        shell
+    }
+
+    pub fn working_dir<R: Runtime>(&self, app: &AppHandle<R>) -> PathBuf {
+        if !self.working_dir.trim().is_empty() {
+            return PathBuf::from(self.working_dir.trim());
+        }
+        app.path().home_dir().unwrap_or_default()
+    }
+
+    // 返回命令行匹配的第一个确认规则，无效的规则被忽略
+    pub fn dangerous(&self, command: &str) -> Option<String> {
+        let rules: Vec<(&String, rules::Rule)> = self
+            .confirm_rules
+            .iter()
+            .filter_map(|text| Some((text, rules::Rule::parse(text)?)))
+            .collect();
+        rules::commands(command).iter().find_map(|argv| {
+            rules
+                .iter()
+                .find(|(_, rule)| rule.matches(argv))
+                .map(|(text, _)| text.to_string())
+        })
+    }
+}
+
+// 用 shell 执行一条命令行的参数
+fn shell_args(shell: &str, command: &str) -> Vec<String> {
+    let lower = shell.to_lowercase();
+    if lower.contains("powershell") || lower.contains("pwsh") {
+        vec![
+            "-NoProfile".to_string(),
+            "-Command".to_string(),
+            command.to_string(),
+        ]
+    } else if cfg!(target_os = "windows") && lower.contains("cmd") {
+        vec!["/C".to_string(), command.to_string()]
+    } else {
+        vec!["-c".to_string(), command.to_string()]
+    }
+}
+
+// 一段输出，通常是一行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShellOutput<'a> {
+    id: u64,
+    stream: Stream,
+    text: &'a str,
+}
+
+// 命令结束，error 为无法启动等错误
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShellExit {
+    id: u64,
+    exit_code: Option<i32>,
+    success: bool,
+    cancelled: bool,
+    exceeded: Option<String>,
+    truncated: bool,
+    duration_ms: u64,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ShellService {
+    config: RwLock<ShellConfig>,
+    history: Mutex<CommandHistory>,
+    // 正在执行的命令，值为结束标记
+    runs: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+    next_id: AtomicU64,
+}
+
+impl ShellService {
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let config = self.config.read().unwrap().clone();
+        let command = query.trim();
+        let lower = command.to_lowercase();
+        let result = |id: String, entry: Option<&CommandEntry>, score: f64| {
+            let command = entry.map_or(command, |entry| entry.command.as_str());
+            SearchResult {
+                id,
+                kind: ResultKind::Command,
+                title: command.to_string(),
+                subtitle: entry.and_then(|entry| entry.cwd.clone()),
+                icon: None,
+                score,
+                highlights: Vec::new(),
+                data: json!({
+                    "command": command,
+                    "cwd": entry.and_then(|entry| entry.cwd.clone()),
+                    "dangerous": config.dangerous(command),
+                    "history": entry.is_some(),
+                    "count": entry.map(|entry| entry.count),
+                    "exitCode": entry.and_then(|entry| entry.exit_code),
+                }),
+                labels: Vec::new(),
+                actions: Vec::new(),
+            }
+        };
+        let mut results = Vec::new();
+        if !command.is_empty() {
+            results.push(result("shell:run".to_string(), None, RUN_SCORE));
+        }
+        let history = self.history.lock().unwrap();
+        results.extend(
+            history
+                .entries
+                .iter()
+                .filter(|entry| entry.command != command)
+                .filter(|entry| entry.command.to_lowercase().contains(&lower))
+                .enumerate()
+                .map(|(i, entry)| {
+                    result(
+                        format!("shell:{}", entry.command),
+                        Some(entry),
+                        HISTORY_SCORE - i as f64,
+                    )
+                }),
+        );
+        results.truncate(limit);
+        results
+    }
+
+    fn record<R: Runtime>(&self, app: &AppHandle<R>, command: &str, cwd: &str) {
+        let max = self.config.read().unwrap().history_size;
+        let mut history = self.history.lock().unwrap();
+        history.record(command, Some(cwd), max);
+        if let Err(e) = history.save(app) {
+            eprintln!("Failed to save shell history: {}", e);
+        }
+    }
+
+    // 检查确认规则和工作目录，返回 shell 和工作目录
+    fn prepare<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        command: &str,
+        cwd: Option<String>,
+        confirmed: bool,
+    ) -> Result<(String, PathBuf), String> {
+        let config = self.config.read().unwrap().clone();
+        if command.is_empty() {
+            return Err("Command is empty".to_string());
+        }
+        if let Some(rule) = config.dangerous(command) {
+            if !confirmed {
+                return Err(format!("Command matches confirmation rule `{}`", rule));
+            }
+        }
+        let cwd = cwd
+            .filter(|cwd| !cwd.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or(config.working_dir(app));
+        if !cwd.is_dir() {
+            return Err(format!("Directory `{}` does not exist", cwd.display()));
+        }
+        Ok((config.shell(), cwd))
+    }
+}
+
+// 启动时读取配置和命令历史
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let shell = app.state::<ShellService>();
+    *shell.config.write().unwrap() = ShellConfig::load(app);
+    *shell.history.lock().unwrap() = CommandHistory::load(app);
+}
+
+// 在后台执行命令，输出通过 shell-output 事件逐行发送，返回本次执行的 id
+#[command]
+pub fn run_shell_command(
+    app: AppHandle,
+    shell: State<'_, ShellService>,
+    command: String,
+    cwd: Option<String>,
+    confirmed: Option<bool>,
+) -> Result<u64, String> {
+    let command = command.trim().to_string();
+    let (program, cwd) = shell.prepare(&app, &command, cwd, confirmed.unwrap_or(false))?;
+    shell.record(&app, &command, &cwd.to_string_lossy());
+
+    let id = shell.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let cancel = Arc::new(AtomicBool::new(false));
+    shell.runs.lock().unwrap().insert(id, cancel.clone());
+    let timeout_seconds = shell.config.read().unwrap().timeout_seconds;
+
+    thread::spawn(move || {
+        let args = shell_args(&program, &command);
+        let mut process = Command::new(&program);
+        process.args(&args).current_dir(&cwd);
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            // CREATE_NO_WINDOW，不弹出控制台窗口
+            process.creation_flags(0x08000000);
+        }
+        // 只限制运行时间，其余资源不限制
+        let limits = ResourceLimits {
+            cpu_seconds: 0,
+            memory_mb: 0,
+            open_files: 0,
+            timeout_seconds,
+        };
+        let sent = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let app = app.clone();
+            let sent = sent.clone();
+            move |stream: Stream, text: &str| {
+                if sent.fetch_add(text.len(), Ordering::SeqCst) < MAX_STREAM_SIZE {
+                    let _ = app.emit(OUTPUT_EVENT, ShellOutput { id, stream, text });
+                }
+            }
+        };
+        let exit = match limits::run_streaming(process, Some(&limits), &cancel, handler) {
+            Ok(output) => {
+                logs::record(&app, LOG_ID, &PluginLogEntry::new(&program, &args, &output));
+                ShellExit {
+                    id,
+                    exit_code: output.output.status.code(),
+                    success: output.output.status.success() && output.exceeded.is_none(),
+                    cancelled: output.cancelled,
+                    exceeded: output.exceeded.map(|exceeded| exceeded.to_string()),
+                    truncated: sent.load(Ordering::SeqCst) > MAX_STREAM_SIZE,
+                    duration_ms: output.duration.as_millis() as u64,
+                    error: None,
+                }
+            }
+            Err(e) => ShellExit {
+                id,
+                exit_code: None,
+                success: false,
+                cancelled: false,
+                exceeded: None,
+                truncated: false,
+                duration_ms: 0,
+                error: Some(format!("Failed to start {}: {}", program, e)),
+            },
+        };
+        let shell = app.state::<ShellService>();
+        shell.runs.lock().unwrap().remove(&id);
+        {
+            let mut history = shell.history.lock().unwrap();
+            history.set_exit_code(&command, exit.exit_code);
+            let _ = history.save(&app);
+        }
+        let _ = app.emit(EXIT_EVENT, exit);
+    });
+    Ok(id)
+}
+
+// 结束正在执行的命令，命令已结束时返回 false
+#[command]
+pub fn cancel_shell_command(shell: State<'_, ShellService>, id: u64) -> bool {
+    match shell.runs.lock().unwrap().get(&id) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+// 在终端中执行命令
+#[command]
+pub fn open_in_terminal(
+    app: AppHandle,
+    shell: State<'_, ShellService>,
+    command: String,
+    cwd: Option<String>,
+    confirmed: Option<bool>,
+) -> Result<(), String> {
+    let command = command.trim().to_string();
+    let (program, cwd) = shell.prepare(&app, &command, cwd, confirmed.unwrap_or(false))?;
+    let terminal = shell.config.read().unwrap().terminal.clone();
+    terminal::open(&terminal, &program, &command, &cwd)?;
+    shell.record(&app, &command, &cwd.to_string_lossy());
+    Ok(())
+}
+
+// 在终端中打开目录，不执行命令
+pub fn open_terminal_at<R: Runtime>(app: &AppHandle<R>, dir: &Path) -> Result<(), String> {
+    let config = app.state::<ShellService>().config.read().unwrap().clone();
+    terminal::open(&config.terminal, &config.shell(), "", dir)
+}
+
+// 在配置的终端中运行程序
+#[cfg(target_os = "linux")]
+pub fn terminal_command<R: Runtime>(
+    app: &AppHandle<R>,
+    argv: &[String],
+) -> Result<Command, String> {
+    let terminal = app
+        .try_state::<ShellService>()
+        .map(|shell| shell.config.read().unwrap().terminal.clone())
+        .unwrap_or_default();
+    terminal::command(&terminal, argv)
+}
+
+#[command]
+pub fn get_shell_history(shell: State<'_, ShellService>) -> Vec<CommandEntry> {
+    shell.history.lock().unwrap().entries.clone()
+}
+
+#[command]
+pub fn clear_shell_history(app: AppHandle, shell: State<'_, ShellService>) -> Result<(), String> {
+    let mut history = shell.history.lock().unwrap();
+    *history = CommandHistory::default();
+    history.save(&app)
+}
+
+#[command]
+pub fn get_shell_config(shell: State<'_, ShellService>) -> ShellConfig {
+    shell.config.read().unwrap().clone()
+}
+
+#[command]
+pub fn set_shell_config(
+    app: AppHandle,
+    shell: State<'_, ShellService>,
+    config: ShellConfig,
+) -> Result<(), String> {
+    let value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    store::set(&app, CONFIG_KEY, Some(value))?;
+    *shell.config.write().unwrap() = config;
+    Ok(())
+}
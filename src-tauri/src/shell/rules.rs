@@ -0,0 +1,348 @@
+use globset::{GlobBuilder, GlobMatcher};
+
+// 用来执行其它命令的命令，它们后面的参数也可能是要执行的命令
+const WRAPPERS: [&str; 12] = [
+    "sudo", "doas", "env", "command", "exec", "nohup", "time", "nice", "timeout", "watch", "xargs",
+    "find",
+];
+// 用 -c 执行命令行的 shell
+const SHELLS: [&str; 6] = ["sh", "bash", "zsh", "dash", "ksh", "fish"];
+// sh -c 最多展开的层数
+const MAX_DEPTH: usize = 4;
+
+// 一条确认规则：程序名加上必须出现的参数，如 `rm -r`、`dd of=*`、`format *:`
+// 程序名和参数都是不区分大小写的 glob，单个字母的短选项也匹配合并的写法，如 -r 匹配 -rf
+pub struct Rule {
+    program: GlobMatcher,
+    args: Vec<Arg>,
+}
+
+enum Arg {
+    Short(char),
+    Pattern(GlobMatcher),
+}
+
+fn glob(pattern: &str) -> Option<GlobMatcher> {
+    GlobBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+impl Rule {
+    // 规则无效时返回 None
+    pub fn parse(rule: &str) -> Option<Rule> {
+        let mut words = rule.split_whitespace();
+        let program = glob(words.next()?)?;
+        let args = words
+            .map(|word| {
+                let mut chars = word.chars();
+                match (chars.next(), chars.next(), chars.next()) {
+                    (Some('-'), Some(c), None) if c.is_ascii_alphanumeric() => {
+                        Some(Arg::Short(c.to_ascii_lowercase()))
+                    }
+                    _ => glob(word).map(Arg::Pattern),
+                }
+            })
+            .collect::<Option<Vec<Arg>>>()?;
+        Some(Rule { program, args })
+    }
+
+    pub fn matches(&self, argv: &[String]) -> bool {
+        let Some((program, args)) = argv.split_first() else {
+            return false;
+        };
+        self.program.is_match(program_name(program))
+            && self.args.iter().all(|rule| {
+                args.iter().any(|arg| match rule {
+                    Arg::Short(c) => {
+                        arg.starts_with('-')
+                            && !arg.starts_with("--")
+                            && arg[1..].to_ascii_lowercase().contains(*c)
+                    }
+                    Arg::Pattern(pattern) => pattern.is_match(arg),
+                })
+            })
+    }
+}
+
+// 去掉路径和 .exe 后的程序名
+fn program_name(program: &str) -> &str {
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    match name.len().checked_sub(4) {
+        Some(end) if name.is_char_boundary(end) && name[end..].eq_ignore_ascii_case(".exe") => {
+            &name[..end]
+        }
+        _ => name,
+    }
+}
+
+// 环境变量赋值，如 `LANG=C`
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+#[derive(Default)]
+struct Splitter {
+    commands: Vec<Vec<String>>,
+    args: Vec<String>,
+    // 引号中的空字符串也是一个参数，所以用 Option 区分
+    word: Option<String>,
+}
+
+impl Splitter {
+    fn push(&mut self, c: char) {
+        self.word.get_or_insert_with(String::new).push(c);
+    }
+
+    fn end_word(&mut self) {
+        if let Some(word) = self.word.take() {
+            self.args.push(word);
+        }
+    }
+
+    fn end_command(&mut self) {
+        self.end_word();
+        if !self.args.is_empty() {
+            self.commands.push(std::mem::take(&mut self.args));
+        }
+    }
+}
+
+// 把命令行拆成多个简单命令的参数列表
+// 支持单双引号和反斜杠转义，; & | 换行 ( ) ` 分隔命令，重定向符号单独作为一个参数
+// 双引号中的命令替换也会执行，其中的内容按没有引号处理
+pub fn split(line: &str) -> Vec<Vec<String>> {
+    let mut splitter = Splitter::default();
+    let mut quote: Option<char> = None;
+    // 命令替换和子 shell 外面的引号以及结束的符号
+    let mut nested: Vec<(Option<char>, char)> = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('"'), '\\') => match chars.peek() {
+                Some(&next) if matches!(next, '"' | '\\' | '$' | '`') => {
+                    splitter.push(next);
+                    chars.next();
+                }
+                _ => splitter.push(c),
+            },
+            (Some('"') | None, '$') if chars.peek() == Some(&'(') => {
+                chars.next();
+                splitter.end_command();
+                nested.push((quote.take(), ')'));
+            }
+            (Some('"'), '`') => {
+                splitter.end_command();
+                nested.push((quote.take(), '`'));
+            }
+            (Some(_), _) => splitter.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                splitter.word.get_or_insert_with(String::new);
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    splitter.push(next);
+                }
+            }
+            (None, '(') => {
+                splitter.end_command();
+                nested.push((None, ')'));
+            }
+            (None, ')' | '`') => {
+                splitter.end_command();
+                match nested.last() {
+                    Some(&(outer, end)) if end == c => {
+                        quote = outer;
+                        nested.pop();
+                    }
+                    _ if c == '`' => nested.push((None, '`')),
+                    _ => {}
+                }
+            }
+            (None, '\n' | ';' | '&' | '|') => splitter.end_command(),
+            (None, '>' | '<') => {
+                splitter.end_word();
+                splitter.push(c);
+                if chars.peek() == Some(&c) {
+                    splitter.push(c);
+                    chars.next();
+                }
+                splitter.end_word();
+            }
+            (None, c) if c.is_whitespace() => splitter.end_word(),
+            (None, _) => splitter.push(c),
+        }
+    }
+    splitter.end_command();
+    splitter.commands
+}
+
+// 命令行中会执行的各个命令：拆分后的简单命令、sudo 等后面的命令以及 sh -c 中的命令
+pub fn commands(line: &str) -> Vec<Vec<String>> {
+    let mut result = Vec::new();
+    for argv in split(line) {
+        expand(&argv, 0, &mut result);
+    }
+    result
+}
+
+fn expand(argv: &[String], depth: usize, result: &mut Vec<Vec<String>>) {
+    let start = argv
+        .iter()
+        .position(|arg| !is_assignment(arg))
+        .unwrap_or(argv.len());
+    let argv = &argv[start..];
+    let Some(program) = argv.first() else {
+        return;
+    };
+    result.push(argv.to_vec());
+    let name = program_name(program).to_ascii_lowercase();
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    if SHELLS.contains(&name.as_str()) {
+        if let Some(script) = argv
+            .iter()
+            .position(|arg| arg == "-c")
+            .and_then(|i| argv.get(i + 1))
+        {
+            for argv in split(script) {
+                expand(&argv, depth + 1, result);
+            }
+        }
+    }
+    // 包装命令的选项个数不定，把后面每个位置都当作命令的开始
+    if WRAPPERS.contains(&name.as_str()) {
+        for i in 1..argv.len() {
+            let name = program_name(&argv[i]).to_ascii_lowercase();
+            if !WRAPPERS.contains(&name.as_str()) && !is_assignment(&argv[i]) {
+                expand(&argv[i..], depth + 1, result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn splits_command_lines() {
+        assert_eq!(
+            split(r#"echo 'a b' "c \"d\"" e\ f "" && ls|wc -l; x"#),
+            [
+                vec!["echo", "a b", "c \"d\"", "e f", ""],
+                vec!["ls"],
+                vec!["wc", "-l"],
+                vec!["x"],
+            ]
+        );
+        assert_eq!(
+            split("cat a>/dev/sda 2>>log <in"),
+            [vec![
+                "cat", "a", ">", "/dev/sda", "2", ">>", "log", "<", "in"
+            ]]
+        );
+        assert_eq!(
+            split(r#"echo "$(rm -rf /)" `reboot` $(id)"#),
+            [
+                vec!["echo", ""],
+                vec!["rm", "-rf", "/"],
+                vec!["reboot"],
+                vec!["id"]
+            ]
+        );
+        assert!(split("  ; && ").is_empty());
+    }
+
+    #[test]
+    fn expands_wrappers_and_shells() {
+        let found = commands("LANG=C sudo -u root env A=1 rm -rf /tmp/x");
+        assert!(found.contains(&words("sudo -u root env A=1 rm -rf /tmp/x")));
+        assert!(found.contains(&words("rm -rf /tmp/x")));
+        let found = commands(r#"bash -c "cd / && rm -r -f x""#);
+        assert!(found.contains(&words("rm -r -f x")));
+        let found = commands("find . -name '*.o' -exec rm -fr {} ;");
+        assert!(found.contains(&words("rm -fr {}")));
+    }
+
+    #[test]
+    fn matches_rules() {
+        let rule = Rule::parse("rm -r").unwrap();
+        for command in [
+            "rm -rf /",
+            "rm -fr /",
+            "rm -f -r /",
+            "rm -R /",
+            "/bin/rm -vrf /",
+            "RM.EXE -r x",
+        ] {
+            assert!(rule.matches(&words(command)), "{}", command);
+        }
+        for command in ["rm -f x", "rm --force x", "echo rm -rf", "rmdir -p x"] {
+            assert!(!rule.matches(&words(command)), "{}", command);
+        }
+
+        let rule = Rule::parse("dd of=*").unwrap();
+        assert!(rule.matches(&words("dd if=/dev/zero of=/dev/sda")));
+        assert!(!rule.matches(&words("dd if=/dev/sda")));
+        let rule = Rule::parse("format *:").unwrap();
+        assert!(rule.matches(&words("FORMAT C: /q")));
+        let rule = Rule::parse("Remove-Item -Recurse").unwrap();
+        assert!(rule.matches(&words("remove-item -recurse C:\\x")));
+        assert!(Rule::parse("rm [").is_none());
+        assert!(Rule::parse("  ").is_none());
+    }
+
+    #[test]
+    fn default_rules_catch_rewritten_commands() {
+        let config = crate::shell::ShellConfig::default();
+        for (command, rule) in [
+            ("rm -rf /", "rm -r"),
+            ("rm  -r  -f /", "rm -r"),
+            ("'rm' -fR ~", "rm -r"),
+            ("ls; /usr/bin/rm --recursive x", "rm --recursive"),
+            ("sh -c 'rm -vrf /'", "rm -r"),
+            ("echo hi > /dev/sda1", "* >* /dev/sd*"),
+            ("sudo -i", "sudo"),
+            ("systemctl status && shutdown now", "shutdown"),
+            ("mkfs.ext4 /dev/sdb1", "mkfs*"),
+            ("del /S /Q C:\\tmp", "del /s"),
+        ] {
+            assert_eq!(
+                config.dangerous(command).as_deref(),
+                Some(rule),
+                "{}",
+                command
+            );
+        }
+        for command in ["ls -r", "echo rm -rf /", "grep -r reboot .", "rm -f a.txt"] {
+            assert_eq!(config.dangerous(command), None, "{}", command);
+        }
+    }
+
+    #[test]
+    fn finds_program_names() {
+        assert_eq!(program_name("/usr/bin/rm"), "rm");
+        assert_eq!(
+            program_name(r"C:\Windows\System32\format.com"),
+            "format.com"
+        );
+        assert_eq!(program_name("cmd.EXE"), "cmd");
+        assert_eq!(program_name("é.exe"), "é");
+        assert_eq!(program_name("ab"), "ab");
+    }
+}
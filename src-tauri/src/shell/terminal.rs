@@ -0,0 +1,166 @@
+#[cfg(target_os = "linux")]
+use std::{env, path::PathBuf};
+use std::{path::Path, process::Command};
+
+// 已知的终端及其执行命令时的参数，未配置终端时按顺序查找
+#[cfg(target_os = "linux")]
+const TERMINALS: [(&str, &[&str]); 10] = [
+    ("gnome-terminal", &["--"]),
+    ("konsole", &["-e"]),
+    ("xfce4-terminal", &["-x"]),
+    ("kitty", &[]),
+    ("alacritty", &["-e"]),
+    ("wezterm", &["start", "--"]),
+    ("foot", &[]),
+    ("tilix", &["-e"]),
+    ("x-terminal-emulator", &["-e"]),
+    ("xterm", &["-e"]),
+];
+
+// 在 PATH 中查找可执行文件
+#[cfg(target_os = "linux")]
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+    if name.is_absolute() {
+        return name.exists().then(|| name.to_path_buf());
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+// 配置的终端可以带参数，如 `kitty --single-instance`
+#[cfg(target_os = "linux")]
+fn resolve(terminal: &str) -> Option<(String, Vec<String>)> {
+    let configured = if terminal.trim().is_empty() {
+        env::var("TERMINAL").ok()
+    } else {
+        Some(terminal.to_string())
+    };
+    let mut candidates: Vec<String> = configured.into_iter().collect();
+    candidates.extend(TERMINALS.iter().map(|(name, _)| name.to_string()));
+    candidates.into_iter().find_map(|candidate| {
+        let mut parts = candidate.split_whitespace();
+        let program = parts.next()?.to_string();
+        find_in_path(&program)?;
+        let mut args: Vec<String> = parts.map(str::to_string).collect();
+        let name = Path::new(&program)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // 不认识的终端按 xterm 的习惯使用 -e
+        let exec = TERMINALS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, exec)| exec.to_vec())
+            .unwrap_or(vec!["-e"]);
+        args.extend(exec.iter().map(|arg| arg.to_string()));
+        Some((program, args))
+    })
+}
+
+// 在终端中执行命令，执行完后留在 shell 中方便查看输出，command 为空时只打开 shell
+#[cfg(target_os = "linux")]
+pub fn open(terminal: &str, shell: &str, command: &str, cwd: &Path) -> Result<(), String> {
+    let (program, args) = resolve(terminal).ok_or("No terminal emulator found".to_string())?;
+    let shell_args = if command.is_empty() {
+        vec![shell.to_string()]
+    } else {
+        vec![
+            shell.to_string(),
+            "-c".to_string(),
+            format!("{}; exec {}", command, shell),
+        ]
+    };
+    Command::new(program)
+        .args(args)
+        .args(shell_args)
+        .current_dir(cwd)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// 在终端中直接运行程序，程序退出后终端随之关闭，用于 Terminal=true 的应用
+#[cfg(target_os = "linux")]
+pub fn command(terminal: &str, argv: &[String]) -> Result<Command, String> {
+    let (program, args) = resolve(terminal).ok_or("No terminal emulator found".to_string())?;
+    let mut command = Command::new(program);
+    command.args(args).args(argv);
+    Ok(command)
+}
+
+// 通过 AppleScript 让 Terminal 执行命令，terminal 为支持 `do script` 的应用名
+#[cfg(target_os = "macos")]
+pub fn open(terminal: &str, _shell: &str, command: &str, cwd: &Path) -> Result<(), String> {
+    let terminal = if terminal.trim().is_empty() {
+        "Terminal"
+    } else {
+        terminal.trim()
+    };
+    let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut script = format!("cd '{}'", cwd.to_string_lossy().replace('\'', "'\\''"));
+    if !command.is_empty() {
+        script = format!("{} && {}", script, command);
+    }
+    Command::new("osascript")
+        .arg("-e")
+        .arg(format!(
+            "tell application \"{}\" to do script \"{}\"",
+            quote(terminal),
+            quote(&script)
+        ))
+        .arg("-e")
+        .arg(format!(
+            "tell application \"{}\" to activate",
+            quote(terminal)
+        ))
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// terminal 为 `wt` 时使用 Windows Terminal，否则新开一个控制台窗口
+#[cfg(target_os = "windows")]
+pub fn open(terminal: &str, shell: &str, command: &str, cwd: &Path) -> Result<(), String> {
+    let lower = shell.to_lowercase();
+    let window_args: Vec<&str> = if command.is_empty() {
+        vec![shell]
+    } else if lower.contains("powershell") || lower.contains("pwsh") {
+        vec![shell, "-NoExit", "-Command", command]
+    } else {
+        vec![shell, "/K", command]
+    };
+    let mut process = if terminal.trim().eq_ignore_ascii_case("wt") {
+        let mut process = Command::new("wt");
+        process.arg("-d").arg(cwd);
+        process
+    } else {
+        let mut process = Command::new("cmd");
+        process.args(["/C", "start", ""]);
+        process
+    };
+    process
+        .args(window_args)
+        .current_dir(cwd)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    // Terminal=true 的应用：配置的终端和它的参数在前，不认识的终端使用 -e
+    #[test]
+    fn wraps_command_in_terminal() {
+        let argv = vec!["htop".to_string(), "-d".to_string(), "10".to_string()];
+        let command = command("/bin/sh --login", &argv).unwrap();
+        assert_eq!(command.get_program(), "/bin/sh");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["--login", "-e", "htop", "-d", "10"]);
+    }
+}
@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use crate::clipboard::ClipboardService;
+use crate::search::index::{ResultKind, SearchResult};
+use crate::utils::list_file;
+
+pub mod template;
+
+// 片段文件，位于 app_config_dir 中
+const SNIPPETS_FILE: &str = "snippets.json";
+// 关键词完全匹配、前缀匹配和名称匹配的分数
+const EXACT_SCORE: f64 = 3000.0;
+const PREFIX_SCORE: f64 = 1500.0;
+const NAME_SCORE: f64 = 500.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    #[serde(default)]
+    pub id: String,
+    // 在搜索框中输入的关键词
+    pub keyword: String,
+    #[serde(default)]
+    pub name: String,
+    pub body: String,
+}
+
+// 导入导出时文件中的键，TOML 中为 `[[snippets]]` 数组
+pub const FILE_KEY: &str = "snippets";
+
+pub fn snippets_file<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(SNIPPETS_FILE))
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Default)]
+pub struct SnippetStore {
+    snippets: RwLock<Vec<Snippet>>,
+}
+
+impl SnippetStore {
+    fn save<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        list_file::write(
+            &snippets_file(app)?,
+            FILE_KEY,
+            &self.snippets.read().unwrap(),
+        )
+    }
+
+    fn get(&self, id: &str) -> Result<Snippet, String> {
+        self.snippets
+            .read()
+            .unwrap()
+            .iter()
+            .find(|snippet| snippet.id == id)
+            .cloned()
+            .ok_or(format!("Snippet `{}` not found", id))
+    }
+
+    fn upsert(&self, snippet: Snippet) -> Result<Snippet, String> {
+        upsert(&mut self.snippets.write().unwrap(), snippet)
+    }
+
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.snippets
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|snippet| {
+                let keyword = snippet.keyword.to_lowercase();
+                let score = if keyword == query {
+                    EXACT_SCORE
+                } else if keyword.starts_with(&query) {
+                    PREFIX_SCORE
+                } else if snippet.name.to_lowercase().contains(&query) {
+                    NAME_SCORE
+                } else {
+                    return None;
+                };
+                let title = if snippet.name.is_empty() {
+                    snippet.keyword.clone()
+                } else {
+                    snippet.name.clone()
+                };
+                let preview: String = snippet
+                    .body
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(80)
+                    .collect();
+                Some(SearchResult {
+                    id: format!("snippet:{}", snippet.id),
+                    kind: ResultKind::Snippet,
+                    title,
+                    subtitle: Some(format!("{} · {}", snippet.keyword, preview)),
+                    icon: None,
+                    score,
+                    highlights: Vec::new(),
+                    data: json!({
+                        "id": snippet.id,
+                        "keyword": snippet.keyword,
+                        "name": snippet.name,
+                        "body": snippet.body,
+                        "inputs": template::inputs(&snippet.body),
+                    }),
+                    labels: Vec::new(),
+                    actions: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+// 新增或按 id 更新，关键词不能重复
+fn upsert(snippets: &mut Vec<Snippet>, mut snippet: Snippet) -> Result<Snippet, String> {
+    snippet.keyword = snippet.keyword.trim().to_string();
+    if snippet.keyword.is_empty() {
+        return Err("Snippet keyword is required".to_string());
+    }
+    if snippet.id.is_empty() {
+        snippet.id = uuid::Uuid::new_v4().to_string();
+    }
+    if snippets
+        .iter()
+        .any(|s| s.id != snippet.id && s.keyword == snippet.keyword)
+    {
+        return Err(format!("Keyword `{}` is already used", snippet.keyword));
+    }
+    match snippets.iter_mut().find(|s| s.id == snippet.id) {
+        Some(existing) => *existing = snippet.clone(),
+        None => snippets.push(snippet.clone()),
+    }
+    Ok(snippet)
+}
+
+// 把导入的片段合并到 current 中，关键词相同的覆盖，有一个无效时全部不导入
+pub fn merge(mut current: Vec<Snippet>, imported: Vec<Snippet>) -> Result<Vec<Snippet>, String> {
+    for mut snippet in imported {
+        snippet.id = current
+            .iter()
+            .find(|s| s.keyword == snippet.keyword.trim())
+            .map(|s| s.id.clone())
+            .unwrap_or_default();
+        upsert(&mut current, snippet)?;
+    }
+    Ok(current)
+}
+
+// 读取片段文件，文件不存在时为空
+pub fn load<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<Snippet>, String> {
+    let file = snippets_file(app)?;
+    if !file.exists() {
+        return Ok(Vec::new());
+    }
+    list_file::read(&file, FILE_KEY)
+}
+
+// 启动时读取片段文件
+pub fn init<R: Runtime>(app: &AppHandle<R>) {
+    let snippets = load(app).unwrap_or_else(|e| {
+        eprintln!("Failed to read snippets: {}", e);
+        Vec::new()
+    });
+    *app.state::<SnippetStore>().snippets.write().unwrap() = snippets;
+}
+
+fn render<R: Runtime>(
+    app: &AppHandle<R>,
+    snippet: &Snippet,
+    inputs: &HashMap<String, String>,
+) -> Result<String, String> {
+    let clipboard = || {
+        app.try_state::<ClipboardService>()
+            .and_then(|service| service.get_text())
+    };
+    template::render(
+        &snippet.body,
+        &template::Context {
+            inputs,
+            clipboard: &clipboard,
+        },
+    )
+}
+
+#[command]
+pub fn get_snippets(store: State<'_, SnippetStore>) -> Vec<Snippet> {
+    store.snippets.read().unwrap().clone()
+}
+
+#[command]
+pub fn save_snippet(
+    app: AppHandle,
+    store: State<'_, SnippetStore>,
+    snippet: Snippet,
+) -> Result<Snippet, String> {
+    let snippet = store.upsert(snippet)?;
+    store.save(&app)?;
+    Ok(snippet)
+}
+
+#[command]
+pub fn delete_snippet(
+    app: AppHandle,
+    store: State<'_, SnippetStore>,
+    id: String,
+) -> Result<(), String> {
+    store
+        .snippets
+        .write()
+        .unwrap()
+        .retain(|snippet| snippet.id != id);
+    store.save(&app)
+}
+
+// 渲染片段，inputs 为 `{input:Name}` 的值
+#[command]
+pub fn render_snippet(
+    app: AppHandle,
+    store: State<'_, SnippetStore>,
+    id: String,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let snippet = store.get(&id)?;
+    render(&app, &snippet, &inputs.unwrap_or_default())
+}
+
+// 渲染片段并复制到剪贴板，返回渲染后的文本
+#[command]
+pub fn copy_snippet(
+    app: AppHandle,
+    store: State<'_, SnippetStore>,
+    clipboard: State<'_, ClipboardService>,
+    id: String,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let snippet = store.get(&id)?;
+    let text = render(&app, &snippet, &inputs.unwrap_or_default())?;
+    clipboard.set_text(&text)?;
+    Ok(text)
+}
+
+// 从 JSON 或 TOML 文件导入，关键词相同的片段会被覆盖，返回导入的数量
+#[command]
+pub fn import_snippets(
+    app: AppHandle,
+    store: State<'_, SnippetStore>,
+    path: String,
+) -> Result<usize, String> {
+    let imported: Vec<Snippet> = list_file::read(Path::new(&path), FILE_KEY)?;
+    let count = imported.len();
+    {
+        let mut current = store.snippets.write().unwrap();
+        *current = merge(current.clone(), imported)?;
+    }
+    store.save(&app)?;
+    Ok(count)
+}
+
+// 按扩展名导出为 JSON 或 TOML
+#[command]
+pub fn export_snippets(store: State<'_, SnippetStore>, path: String) -> Result<(), String> {
+    list_file::write(Path::new(&path), FILE_KEY, &store.snippets.read().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(keyword: &str, body: &str) -> Snippet {
+        Snippet {
+            id: String::new(),
+            keyword: keyword.to_string(),
+            name: String::new(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn merges_imported_snippets() {
+        let mut current = Vec::new();
+        let sig = upsert(&mut current, snippet("sig", "old")).unwrap();
+        let merged = merge(
+            current,
+            vec![snippet(" sig ", "new"), snippet("addr", "street")],
+        )
+        .unwrap();
+        assert_eq!(merged.len(), 2);
+        // 关键词相同时保留原来的 id
+        assert_eq!(merged[0].id, sig.id);
+        assert_eq!(merged[0].body, "new");
+        assert_eq!(merged[1].keyword, "addr");
+    }
+
+    #[test]
+    fn rejects_invalid_imports() {
+        let current = vec![snippet("sig", "old")];
+        let imported = vec![snippet("addr", "street"), snippet(" ", "empty")];
+        assert!(merge(current, imported).is_err());
+    }
+}
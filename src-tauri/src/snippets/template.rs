@@ -0,0 +1,221 @@
+use std::{collections::HashMap, fmt::Write};
+
+// 日期和时间占位符的默认格式
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+// 模板中的一段：普通文本或 `{name:arg}` 占位符
+#[derive(Debug, Clone, PartialEq)]
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder {
+        raw: &'a str,
+        name: &'a str,
+        arg: Option<&'a str>,
+    },
+}
+
+// 拆分模板，`{{` 和 `}}` 表示字面的花括号
+fn parse(body: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix("{{") {
+            parts.push(Part::Text("{"));
+            rest = tail;
+            continue;
+        }
+        if let Some(tail) = rest.strip_prefix("}}") {
+            parts.push(Part::Text("}"));
+            rest = tail;
+            continue;
+        }
+        if rest.starts_with('{') {
+            // 占位符不能跨行，找不到结尾时按普通文本处理
+            if let Some(end) = rest[1..].find(['}', '{', '\n']).map(|i| i + 1) {
+                if rest.as_bytes()[end] == b'}' {
+                    let inner = &rest[1..end];
+                    let (name, arg) = match inner.split_once(':') {
+                        Some((name, arg)) => (name.trim(), Some(arg)),
+                        None => (inner.trim(), None),
+                    };
+                    parts.push(Part::Placeholder {
+                        raw: &rest[..=end],
+                        name,
+                        arg,
+                    });
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+            parts.push(Part::Text("{"));
+            rest = &rest[1..];
+            continue;
+        }
+        let end = rest.find(['{', '}']).unwrap_or(rest.len());
+        // 单独的 `}` 原样保留
+        let end = if end == 0 { 1 } else { end };
+        parts.push(Part::Text(&rest[..end]));
+        rest = &rest[end..];
+    }
+    parts
+}
+
+// 模板中需要用户输入的变量名，按出现顺序去重
+pub fn inputs(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for part in parse(body) {
+        if let Part::Placeholder {
+            name: "input",
+            arg: Some(arg),
+            ..
+        } = part
+        {
+            let arg = arg.trim().to_string();
+            if !arg.is_empty() && !names.contains(&arg) {
+                names.push(arg);
+            }
+        }
+    }
+    names
+}
+
+// 渲染时用到的外部数据
+pub struct Context<'a> {
+    pub inputs: &'a HashMap<String, String>,
+    // 读取剪贴板文本，只在模板中有 `{clipboard}` 时调用
+    pub clipboard: &'a dyn Fn() -> Option<String>,
+}
+
+// 替换占位符，不认识的占位符原样保留
+pub fn render(body: &str, context: &Context) -> Result<String, String> {
+    let now = chrono::Local::now();
+    let mut clipboard: Option<Option<String>> = None;
+    let mut output = String::with_capacity(body.len());
+    for part in parse(body) {
+        let (raw, name, arg) = match part {
+            Part::Text(text) => {
+                output.push_str(text);
+                continue;
+            }
+            Part::Placeholder { raw, name, arg } => (raw, name, arg),
+        };
+        match name {
+            "date" | "time" => {
+                let default = if name == "date" {
+                    DEFAULT_DATE_FORMAT
+                } else {
+                    DEFAULT_TIME_FORMAT
+                };
+                let format = arg.filter(|arg| !arg.is_empty()).unwrap_or(default);
+                // 格式无效时 chrono 返回错误，不能用 to_string
+                write!(output, "{}", now.format(format))
+                    .map_err(|_| format!("Invalid date format `{}`", format))?;
+            }
+            "uuid" => output.push_str(&uuid::Uuid::new_v4().to_string()),
+            "clipboard" => {
+                let text = clipboard.get_or_insert_with(|| (context.clipboard)());
+                output.push_str(text.as_deref().unwrap_or_default());
+            }
+            "input" => {
+                let key = arg.map(str::trim).unwrap_or_default();
+                match context.inputs.get(key) {
+                    Some(value) => output.push_str(value),
+                    None => return Err(format!("Missing input `{}`", key)),
+                }
+            }
+            _ => output.push_str(raw),
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_with(body: &str, inputs: &[(&str, &str)]) -> Result<String, String> {
+        let inputs: HashMap<String, String> = inputs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let clipboard = || Some("copied".to_string());
+        render(
+            body,
+            &Context {
+                inputs: &inputs,
+                clipboard: &clipboard,
+            },
+        )
+    }
+
+    #[test]
+    fn parses_placeholders_and_escapes() {
+        assert_eq!(
+            parse("a {{b}} {date:%Y} }"),
+            [
+                Part::Text("a "),
+                Part::Text("{"),
+                Part::Text("b"),
+                Part::Text("}"),
+                Part::Text(" "),
+                Part::Placeholder {
+                    raw: "{date:%Y}",
+                    name: "date",
+                    arg: Some("%Y"),
+                },
+                Part::Text(" "),
+                Part::Text("}"),
+            ]
+        );
+        // 没有结尾或跨行的 `{` 按普通文本处理
+        assert_eq!(render_with("{oops", &[]).unwrap(), "{oops");
+        assert_eq!(render_with("{a\n}", &[]).unwrap(), "{a\n}");
+        assert_eq!(render_with("{{uuid}}", &[]).unwrap(), "{uuid}");
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let year = chrono::Local::now().format("%Y").to_string();
+        assert_eq!(
+            render_with("© {date:%Y}", &[]).unwrap(),
+            format!("© {}", year)
+        );
+        assert_eq!(render_with("{date}", &[]).unwrap().len(), 10);
+        assert_eq!(render_with("{time:%H:%M}", &[]).unwrap().len(), 5);
+        assert_eq!(render_with("{uuid}", &[]).unwrap().len(), 36);
+        assert_eq!(
+            render_with("{clipboard}/{clipboard}", &[]).unwrap(),
+            "copied/copied"
+        );
+        assert_eq!(
+            render_with("Hi { input : Name }", &[("Name", "Ann")]).unwrap(),
+            "Hi Ann"
+        );
+        // 不认识的占位符原样保留
+        assert_eq!(
+            render_with("{foo} {foo:bar}", &[]).unwrap(),
+            "{foo} {foo:bar}"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert_eq!(
+            render_with("{date:%Q}", &[]).unwrap_err(),
+            "Invalid date format `%Q`"
+        );
+        assert_eq!(
+            render_with("{input:Name}", &[("Other", "x")]).unwrap_err(),
+            "Missing input `Name`"
+        );
+    }
+
+    #[test]
+    fn lists_inputs_once() {
+        assert_eq!(
+            inputs("{input:B} {input:A} {input: B } {input:} {input} {{input:C}}"),
+            ["B", "A"]
+        );
+    }
+}
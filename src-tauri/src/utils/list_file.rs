@@ -0,0 +1,97 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+// 导入导出用的列表文件，按扩展名使用 JSON 或 TOML
+// 内容为 `{ key: [...] }`，TOML 中即 `[[key]]` 数组
+pub fn read<T: DeserializeOwned>(path: &Path, key: &str) -> Result<Vec<T>, String> {
+    let content = fs::read(path).map_err(|e| e.to_string())?;
+    if !is_toml(path) {
+        return parse_json(&content, key);
+    }
+    let content = String::from_utf8(content).map_err(|e| e.to_string())?;
+    let mut table: toml::Table = toml::from_str(&content).map_err(|e| e.to_string())?;
+    table
+        .remove(key)
+        .ok_or(format!("missing field `{}`", key))?
+        .try_into()
+        .map_err(|e| e.to_string())
+}
+
+// 解析 JSON 内容，也接受直接是数组的 JSON
+pub fn parse_json<T: DeserializeOwned>(content: &[u8], key: &str) -> Result<Vec<T>, String> {
+    let items = match serde_json::from_slice(content).map_err(|e| e.to_string())? {
+        Value::Object(mut object) => object
+            .remove(key)
+            .ok_or(format!("missing field `{}`", key))?,
+        value => value,
+    };
+    serde_json::from_value(items).map_err(|e| e.to_string())
+}
+
+pub fn write<T: Serialize>(path: &Path, key: &str, items: &[T]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = BTreeMap::from([(key, items)]);
+    let content = if is_toml(path) {
+        toml::to_string_pretty(&file).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?
+    };
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                name: "a".to_string(),
+                tags: vec!["x".to_string()],
+            },
+            Item {
+                name: "b".to_string(),
+                tags: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn writes_and_reads_both_formats() {
+        let dir = std::env::temp_dir().join(format!("toolbox-list-file-{}", std::process::id()));
+        for name in ["items.json", "items.TOML"] {
+            let path = dir.join(name);
+            write(&path, "items", &items()).unwrap();
+            assert_eq!(read::<Item>(&path, "items").unwrap(), items());
+            assert!(read::<Item>(&path, "other").is_err());
+        }
+        let toml = fs::read_to_string(dir.join("items.TOML")).unwrap();
+        assert!(toml.contains("[[items]]"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parses_plain_json_arrays() {
+        let parsed: Vec<Item> =
+            parse_json(br#"[{"name": "a", "tags": ["x"]}, {"name": "b"}]"#, "items").unwrap();
+        assert_eq!(parsed, items());
+        assert!(parse_json::<Item>(br#"{"items": [{"tags": []}]}"#, "items").is_err());
+        assert!(parse_json::<Item>(b"{", "items").is_err());
+    }
+}
@@ -1,12 +1,44 @@
-use std::{env, fs, io, path::Path, process::Command as StdCommand};
-use tauri::{path::BaseDirectory, AppHandle, Manager};
+use serde_json::{json, Value};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command as StdCommand,
+};
+use tauri::{AppHandle, Manager};
 
 use tauri_plugin_shell::{process::Command, ShellExt};
 use walkdir::WalkDir;
 
+use crate::plugins::discovery;
+
 pub mod capability;
 pub mod config;
+pub mod list_file;
 pub mod shortcut;
+pub mod store;
+
+// 展开 Windows 路径中的 `%SystemRoot%` 等环境变量
+pub fn expand_env(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => {
+                let name = &after[..end];
+                result.push_str(&rest[..start]);
+                match env::var(name) {
+                    Ok(value) if !name.is_empty() => result.push_str(&value),
+                    _ => result.push_str(&rest[start..start + end + 2]),
+                }
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    result.push_str(rest);
+    result
+}
 
 // 获取应用版本号的函数
 #[allow(dead_code)]
@@ -19,22 +51,62 @@ pub fn get_app_version(app: AppHandle) -> String {
     version.to_string()
 }
 
+// 根据插件发现结果生成 caddy 配置，每个插件 id 映射到各自的目录
+fn write_caddy_config(app: &AppHandle) -> io::Result<PathBuf> {
+    let routes: Vec<Value> = discovery::discover(app)
+        .plugins
+        .iter()
+        .map(|plugin| {
+            json!({
+                "match": [{ "path": [format!("/{}/*", plugin.id)] }],
+                "handle": [
+                    { "handler": "rewrite", "strip_path_prefix": format!("/{}", plugin.id) },
+                    { "handler": "file_server", "root": plugin.dir }
+                ]
+            })
+        })
+        .collect();
+    let config = json!({
+        "apps": {
+            "http": {
+                "servers": {
+                    "plugins": {
+                        "listen": ["localhost:6543"],
+                        "routes": routes
+                    }
+                }
+            }
+        }
+    });
+    let cache_dir = app.path().app_cache_dir().map_err(io::Error::other)?;
+    fs::create_dir_all(&cache_dir)?;
+    let config_path = cache_dir.join("caddy.json");
+    fs::write(&config_path, config.to_string())?;
+    Ok(config_path)
+}
+
+// 生成插件文件服务器的 caddy 配置，返回配置文件路径，由前端启动 caddy
+#[tauri::command]
+pub fn plugin_server_config(app: AppHandle) -> Result<PathBuf, String> {
+    write_caddy_config(&app).map_err(|e| e.to_string())
+}
+
 #[allow(dead_code)]
 pub fn init_local_http_server(app: AppHandle) -> u32 {
-    let binding = app
-        .path()
-        .resolve("plugins", BaseDirectory::Resource)
-        .unwrap();
+    let config_path = match write_caddy_config(&app) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Failed to write caddy config: {}", e);
+            return 0;
+        }
+    };
 
-    let static_path = binding.to_str().unwrap();
     let shell = app.shell();
     let caddy: Command = shell.sidecar("caddy").unwrap();
     let args = vec![
-        "file-server",
-        "--listen",
-        "localhost:6543",
-        "--root",
-        static_path,
+        "run".to_string(),
+        "--config".to_string(),
+        config_path.to_string_lossy().to_string(),
     ];
     let _ = match caddy.args(args).spawn() {
         Ok((_rx, child)) => {
@@ -97,7 +169,7 @@ pub fn kill_server_by_name(process_name: &str) {
                             .arg("/F")
                             .arg("/PID")
                             .arg(pid.to_string())
-                            .spawn()
+                            .status()
                             .expect("Failed to execute command");
                     }
                 }
@@ -117,7 +189,7 @@ pub fn kill_server_by_name(process_name: &str) {
                 StdCommand::new("kill")
                     .arg("-9")
                     .arg(pid.to_string())
-                    .spawn()
+                    .status()
                     .expect("Failed to execute command");
             }
         }
@@ -141,15 +213,13 @@ fn copy_to(src: &Path, dst: &Path) -> io::Result<()> {
     for entry in WalkDir::new(src).min_depth(1) {
         let entry = entry?;
         let path = entry.path();
-        let relative_path = path
-            .strip_prefix(src)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        let relative_path = path.strip_prefix(src).map_err(io::Error::other)?;
         let destination = dst.join(relative_path);
 
         if path.is_dir() {
             fs::create_dir_all(&destination)?;
         } else if path.is_file() {
-            fs::copy(&path, &destination)?;
+            fs::copy(path, &destination)?;
         }
     }
 
@@ -35,6 +35,7 @@ pub fn bind(app: AppHandle) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[allow(dead_code)]
 pub fn unbind(app: AppHandle) -> Result<(), Box<dyn Error>> {
     let alt_n_space = Shortcut::new(Some(Modifiers::ALT), Code::Space);
     app.global_shortcut().unregister(alt_n_space)?;
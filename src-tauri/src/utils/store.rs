@@ -0,0 +1,35 @@
+use serde_json::Value;
+use tauri::{path::BaseDirectory, AppHandle, Manager, Runtime};
+use tauri_plugin_store::{Store, StoreExt};
+
+// store 文件相对于资源目录的路径
+pub const STORE_PATH: &str = "config/store.bin";
+
+// 打开应用的 store，与 setup 中初始化的是同一个实例
+#[allow(dead_code)]
+pub fn open<R: Runtime>(app: &AppHandle<R>) -> Result<Store<R>, String> {
+    let store_path = app
+        .path()
+        .resolve(STORE_PATH, BaseDirectory::Resource)
+        .map_err(|e| e.to_string())?;
+    Ok(app.store(store_path))
+}
+
+// 读取 store 中的某个值
+#[allow(dead_code)]
+pub fn get<R: Runtime>(app: &AppHandle<R>, key: &str) -> Option<Value> {
+    open(app).ok().and_then(|store| store.get(key))
+}
+
+// 写入并保存 store，value 为 None 时删除该键
+#[allow(dead_code)]
+pub fn set<R: Runtime>(app: &AppHandle<R>, key: &str, value: Option<Value>) -> Result<(), String> {
+    let store = open(app)?;
+    match value {
+        Some(value) => store.set(key.to_string(), value),
+        None => {
+            store.delete(key);
+        }
+    }
+    store.save().map_err(|e| e.to_string())
+}
@@ -0,0 +1,161 @@
+# 生成 squashfs 和 AppImage 测试用的镜像：python3 gen.py
+# 不依赖 mksquashfs，按 squashfs 4.0 的格式直接写出，inode 表用 zlib 压缩，目录表不压缩
+import os, struct, zlib
+os.chdir(os.path.dirname(os.path.abspath(__file__)))
+BS = 4096
+def meta_blocks(stream, compress):
+    out = b''; starts = []
+    for i in range(0, max(len(stream),1), 8192):
+        chunk = stream[i:i+8192]
+        starts.append(len(out))
+        c = zlib.compress(chunk) if compress else None
+        if c is not None and len(c) < len(chunk):
+            out += struct.pack('<H', len(c)) + c
+        else:
+            out += struct.pack('<H', len(chunk) | 0x8000) + chunk
+    return out, starts
+
+# tamper 用于生成损坏的镜像：block 为超过块大小的数据块，dir 为过大的目录
+def build(tree, compress_data=True, tamper=None):
+    # nodes: (name, kind, payload, children)
+    nodes = []
+    def walk(name, node, parent):
+        idx = len(nodes); nodes.append({'name': name})
+        n = nodes[idx]
+        if isinstance(node, dict):
+            n['kind'] = 'dir'; n['children'] = [walk(k, v, idx) for k, v in sorted(node.items())]
+        elif isinstance(node, tuple):
+            n['kind'] = 'link'; n['target'] = node[1].encode()
+        else:
+            n['kind'] = 'file'; n['data'] = node
+        return idx
+    root = walk('', tree, None)
+    # post-order for inode numbering and table order
+    order = []
+    def post(i):
+        for c in nodes[i].get('children', []): post(c)
+        order.append(i)
+    post(root)
+    for num, i in enumerate(order, 1): nodes[i]['num'] = num
+    # data area
+    data = b''; frag_blocks = []; cur_frag = b''
+    def flush():
+        nonlocal cur_frag
+        if cur_frag: frag_blocks.append(cur_frag); cur_frag = b''
+    base = 96
+    for i in order:
+        n = nodes[i]
+        if n['kind'] != 'file': continue
+        d = n['data']; full = len(d) // BS; sizes = []
+        n['start'] = base + len(data)
+        for b in range(full):
+            blk = d[b*BS:(b+1)*BS]
+            if blk == b'\0'*BS: sizes.append(0); continue
+            c = zlib.compress(blk)
+            if compress_data and len(c) < len(blk): data += c; sizes.append(len(c))
+            else: data += blk; sizes.append(len(blk) | 0x1000000)
+        if tamper == 'block' and sizes and sizes[0]:
+            sizes[0] = (BS * 2) | 0x1000000; tamper = None
+        tail = d[full*BS:]
+        n['frag'] = 0xffffffff; n['fragoff'] = 0
+        if tail:
+            if len(cur_frag) + len(tail) > BS: flush()
+            n['frag'] = len(frag_blocks); n['fragoff'] = len(cur_frag); cur_frag += tail
+        n['sizes'] = sizes
+    flush()
+    frag_entries = []
+    for fb in frag_blocks:
+        c = zlib.compress(fb); pos = base + len(data)
+        if len(c) < len(fb): data += c; frag_entries.append((pos, len(c)))
+        else: data += fb; frag_entries.append((pos, len(fb) | 0x1000000))
+    # directory listings (uncompressed dir table) sizes and logical offsets
+    def inode_size(n):
+        if n['kind'] == 'dir' and n.get('extended'): return 40
+        return {'file': 32 + 4*len(n.get('sizes', [])), 'link': 24 + len(n.get('target', b'')), 'dir': 32}[n['kind']]
+    if tamper == 'dir': nodes[root]['extended'] = True
+    off = 0
+    for i in order:
+        nodes[i]['ioff'] = off; off += inode_size(nodes[i])
+    inode_len = off
+    # compute physical inode refs after compressing: inode stream content depends on dir positions (uncompressed dir table)
+    def dir_listing(n, ref):
+        out = b''; ch = n['children']; j = 0
+        while j < len(ch):
+            start = ref(nodes[ch[j]])[0]
+            run = [ch[j]]; j += 1
+            while j < len(ch) and ref(nodes[ch[j]])[0] == start and len(run) < 256:
+                run.append(ch[j]); j += 1
+            out += struct.pack('<III', len(run)-1, start, nodes[run[0]]['num'])
+            for c in run:
+                cn = nodes[c]; t = {'dir':1,'file':2,'link':3}[cn['kind']]
+                nm = cn['name'].encode()
+                out += struct.pack('<HhHH', ref(cn)[1], cn['num'] - nodes[run[0]]['num'], t, len(nm)-1) + nm
+        return out
+    # pass 1: dummy refs to measure (header count depends on block starts; iterate twice)
+    refs = {i: (0, 0) for i in order}
+    for _ in range(3):
+        dir_off = 0
+        for i in order:
+            n = nodes[i]
+            if n['kind'] == 'dir':
+                lst = dir_listing(n, lambda cn: refs[nodes.index(cn)])
+                n['doff'] = dir_off; n['dlen'] = len(lst); dir_off += len(lst)
+        istream = b''
+        for i in order:
+            n = nodes[i]; kind = 8 if n.get('extended') else {'dir':1,'file':2,'link':3}[n['kind']]
+            h = struct.pack('<HHHHII', kind, 0o755, 0, 0, 0, n['num'])
+            if n['kind'] == 'file':
+                body = struct.pack('<IIII', n['start'], n['frag'], n['fragoff'], len(n['data'])) + b''.join(struct.pack('<I', s) for s in n['sizes'])
+            elif n['kind'] == 'link':
+                body = struct.pack('<II', 1, len(n['target'])) + n['target']
+            elif n.get('extended'):
+                blk = (n['doff'] // 8192) * 8194
+                body = struct.pack('<IIIIHHI', 2, 0xfffffff0, blk, 0, 0, n['doff'] % 8192, 0xffffffff)
+            else:
+                blk = (n['doff'] // 8192) * 8194
+                body = struct.pack('<IIHHI', blk, 2, n['dlen'] + 3, n['doff'] % 8192, 0)
+            istream += h + body
+        itable, starts = meta_blocks(istream, True)
+        refs = {i: (starts[nodes[i]['ioff'] // 8192], nodes[i]['ioff'] % 8192) for i in order}
+    dstream = b''.join(dir_listing(nodes[i], lambda cn: refs[nodes.index(cn)]) for i in order if nodes[i]['kind'] == 'dir')
+    dtable, _ = meta_blocks(dstream, False)
+    pos = base + len(data)
+    inode_table = pos; pos += len(itable)
+    dir_table = pos; pos += len(dtable)
+    fmeta, _ = meta_blocks(b''.join(struct.pack('<QII', s, z, 0) for s, z in frag_entries), True)
+    fmeta_pos = pos; pos += len(fmeta)
+    frag_table = pos; pos += 8 * ((len(frag_entries) + 511) // 512)
+    idmeta, _ = meta_blocks(struct.pack('<I', 0), False)
+    idmeta_pos = pos; pos += len(idmeta)
+    id_table = pos; pos += 8
+    rref = (refs[root][0] << 16) | refs[root][1]
+    sb = struct.pack('<IIIIIHHHHHHQQQQQQQQ', 0x73717368, len(order), 0, BS, len(frag_entries), 1, 12, 0, 1, 4, 0,
+                     rref, pos, id_table, 0xffffffffffffffff, inode_table, dir_table, frag_table, 0xffffffffffffffff)
+    assert len(sb) == 96
+    out = sb + data + itable + dtable + fmeta + struct.pack('<Q', fmeta_pos) + idmeta + struct.pack('<Q', idmeta_pos)
+    return out
+
+png = bytes(range(256)) * 40 + b'TAIL'
+many = {('f%04d.txt' % i): (b'file %d\n' % i) * (i % 7 + 1) for i in range(900)}
+tree = {
+    'firefox.desktop': b'[Desktop Entry]\nType=Application\nName=Firefox\nName[zh_CN]=\xe7\x81\xab\xe7\x8b\x90\nIcon=firefox\nExec=AppRun %u\nX-AppImage-Version=128.0\nCategories=Network;\n',
+    'firefox.png': ('link', 'usr/share/icons/hicolor/../hicolor/firefox.png'),
+    '.DirIcon': ('link', '/firefox.png'),
+    'AppRun': ('link', 'usr/bin/firefox'),
+    'sparse': b'\0' * (BS * 2) + b'end',
+    'usr': {'bin': {'firefox': b'#!/bin/sh\n'}, 'share': {'icons': {'hicolor': {'firefox.png': png}}}, 'many': many},
+}
+
+def appimage(name, fs):
+    elf = bytearray(64)
+    elf[0:4] = b'\x7fELF'; elf[4] = 2; elf[5] = 1; elf[8:11] = b'AI\x02'
+    # 节头表在 64 字节处，2 个 64 字节的表项，squashfs 从 192 字节开始
+    struct.pack_into('<Q', elf, 0x28, 64); struct.pack_into('<H', elf, 0x3a, 64); struct.pack_into('<H', elf, 0x3c, 2)
+    open(name, 'wb').write(bytes(elf) + b'\0' * (192 - 64) + fs)
+
+appimage('Firefox.AppImage', build(tree))
+# 数据块不压缩
+appimage('Raw.AppImage', build(tree, compress_data=False))
+small = {'a.desktop': b'[Desktop Entry]\nName=A\n', 'icon.png': png}
+appimage('BadBlock.AppImage', build(small, compress_data=False, tamper='block'))
+appimage('BadDir.AppImage', build(small, tamper='dir'))
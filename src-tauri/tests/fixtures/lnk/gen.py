@@ -0,0 +1,95 @@
+# 生成 lnk 测试用的快捷方式文件：python3 gen.py
+import os, struct, uuid
+os.chdir(os.path.dirname(os.path.abspath(__file__)))
+def guid(s): return uuid.UUID(s).bytes_le
+CLSID = guid('00021401-0000-0000-C000-000000000046')
+def header(flags, icon_index=0, attrs=0x20):
+    h = struct.pack('<I', 0x4C) + CLSID + struct.pack('<II', flags, attrs) + b'\0'*24 + struct.pack('<IiIH', 1234, icon_index, 1, 0) + b'\0'*10
+    assert len(h) == 0x4C
+    return h
+def item(body): return struct.pack('<H', len(body)+2) + body
+def root_mycomputer(): return item(b'\x1f\x50' + guid('20D04FE0-3AEA-1069-A2D8-08002B30309D'))
+def drive(d): return item(b'\x2f' + d.encode() + b'\0'*(22-len(d)))
+def file_entry(short, long, is_dir, version=9):
+    t = 0x31 if is_dir else 0x32
+    body = bytes([t, 0]) + struct.pack('<IIH', 0, 0, 0x10 if is_dir else 0x20)
+    # 短文件名为系统代码页的 ANSI 字符串，可以直接传入 bytes
+    sn = (short if isinstance(short, bytes) else short.encode()) + b'\0'
+    if len(sn) % 2: sn += b'\0'
+    body += sn
+    ext = struct.pack('<HI', version, 0xBEEF0004) + b'\0'*8 + struct.pack('<H', 0x2e)
+    if version >= 7: ext += b'\0'*18
+    if version >= 3: ext += struct.pack('<H', 0)
+    if version >= 9: ext += b'\0'*4
+    if version >= 8: ext += b'\0'*4
+    ext += long.encode('utf-16-le') + b'\0\0' + struct.pack('<H', 14 + len(sn))
+    ext = struct.pack('<H', len(ext)+2) + ext
+    return item(body + ext)
+def idlist(items):
+    data = b''.join(items) + b'\0\0'
+    return struct.pack('<H', len(data)) + data
+def link_info_local(path, unicode=True):
+    hs = 0x24 if unicode else 0x1C
+    vol = struct.pack('<IIII', 0x11, 3, 0x1234, 0x10) + b'\0'
+    off_vol = hs
+    off_base = off_vol + len(vol)
+    base = path.encode() + b'\0'
+    off_suffix = off_base + len(base)
+    suffix = b'\0'
+    rest = vol + base + suffix
+    extra = b''
+    if unicode:
+        off_ubase = hs + len(rest)
+        ub = path.encode('utf-16-le') + b'\0\0'
+        off_usuf = off_ubase + len(ub)
+        extra = ub + b'\0\0'
+    size = hs + len(rest) + len(extra)
+    h = struct.pack('<IIIIIII', size, hs, 1, off_vol, off_base, 0, off_suffix)
+    if unicode: h += struct.pack('<II', off_ubase, off_usuf)
+    return h + rest + extra
+def link_info_net(share, suffix):
+    hs = 0x1C
+    net = share.encode() + b'\0'
+    cnrl = struct.pack('<IIIII', 0x14 + len(net), 0, 0x14, 0, 0x20000) + net
+    off_cnrl = hs
+    off_suffix = hs + len(cnrl)
+    suf = suffix.encode() + b'\0'
+    size = hs + len(cnrl) + len(suf)
+    return struct.pack('<IIIIIII', size, hs, 2, 0, 0, off_cnrl, off_suffix) + cnrl + suf
+def sdata(s, unicode=True):
+    if unicode: return struct.pack('<H', len(s.encode('utf-16-le'))//2) + s.encode('utf-16-le')
+    return struct.pack('<H', len(s.encode())) + s.encode()
+def env_block(sig, target):
+    a = target.encode()[:259].ljust(260, b'\0'); u = target.encode('utf-16-le')[:518].ljust(520, b'\0')
+    return struct.pack('<II', 0x314, sig) + a + u
+def known_folder(g, offset): return struct.pack('<II', 0x1C, 0xA000000B) + guid(g) + struct.pack('<I', offset)
+END = b'\0\0\0\0'
+
+items = [root_mycomputer(), drive('C:\\'), file_entry('PROGRA~1', 'Program Files', True), file_entry('MYAPP~1', '我的应用', True), file_entry('app.exe', 'app.exe', False)]
+# 1: full
+flags = 0x1|0x2|0x4|0x8|0x10|0x20|0x40|0x80|0x200|0x4000
+data = header(flags, icon_index=3) + idlist(items) + link_info_local('C:\\Program Files\\我的应用\\app.exe') \
+  + sdata('My App 描述') + sdata('..\\..\\我的应用\\app.exe') + sdata('%ProgramFiles%\\我的应用') + sdata('--flag "a b"') + sdata('%SystemRoot%\\system32\\shell32.dll') \
+  + env_block(0xA0000001, '%ProgramFiles%\\我的应用\\app.exe') + env_block(0xA0000007, '%ProgramFiles%\\我的应用\\app.ico') + END
+open('full.lnk','wb').write(data)
+# 2: IDList + known folder only
+pos = sum(len(i) for i in items[:3])
+data = header(0x1|0x80) + idlist(items) + known_folder('905e63b6-c1bf-494e-b29c-65b732d3d21a', pos) + END
+open('known_folder.lnk','wb').write(data)
+# 3: IDList only, no known folder, older ext version 3
+items3 = [root_mycomputer(), drive('D:\\'), file_entry('TOOLS', 'Tools', True, 3), file_entry('TOOL~1.EXE', 'tool long.exe', False, 8)]
+open('idlist.lnk','wb').write(header(0x1|0x80) + idlist(items3) + END)
+# 4: ANSI strings, network link info
+flags = 0x2|0x4|0x20
+open('network_ansi.lnk','wb').write(header(flags) + link_info_net('\\\\server\\share', 'dir\\prog.exe') + sdata('Net prog', False) + sdata('-x', False) + END)
+# 5: relative path only
+open('relative.lnk','wb').write(header(0x8|0x80) + sdata('..\\bin\\rel.exe') + END)
+# 6: ForceNoLinkInfo -> falls back to IDList
+open('force_no_info.lnk','wb').write(header(0x1|0x2|0x80|0x100) + idlist(items3) + link_info_local('Z:\\wrong.exe') + END)
+# 7: truncated, 8: not a link
+full = open('full.lnk','rb').read()
+open('truncated.lnk','wb').write(full[:200])
+open('not_link.lnk','wb').write(b'hello world')
+# 9: GBK 编码的短文件名
+items9 = [root_mycomputer(), drive('C:\\'), file_entry('程序'.encode('gbk'), '程序', True), file_entry('run.exe', 'run.exe', False)]
+open('gbk_short_name.lnk','wb').write(header(0x1|0x80) + idlist(items9) + END)
@@ -1,10 +1,11 @@
 import { Window, type WindowLabel } from '@tauri-apps/api/window'
 import { register, isRegistered, unregister } from '@tauri-apps/plugin-global-shortcut'
 import { Command } from '@tauri-apps/plugin-shell'
-import { join, resolveResource } from '@tauri-apps/api/path'
+import { join } from '@tauri-apps/api/path'
 import { invoke } from '@tauri-apps/api/core'
 import { type } from '@/utils/utils'
 import { Child } from '@tauri-apps/plugin-shell'
+import type { ActionOutcome, ParsedQuery, SearchResult } from '@/utils/typescript'
 
 export const getWindow = async (label: WindowLabel) => {
     const windows = await Window.getByLabel(label)
@@ -56,9 +57,10 @@ export const initHttpServer = async () => {
             })
         return
     }
-    const staticDirPath = await resolveResource('plugins')
-    console.log('staticDirPath', staticDirPath)
-    const command = Command.sidecar('caddy', ['file-server', '--listen', 'localhost:6543', '--root', staticDirPath])
+    // 后端按发现的插件目录生成配置，每个插件 id 映射到它所在的目录
+    const configPath: string = await invoke('plugin_server_config')
+    console.log('configPath', configPath)
+    const command = Command.sidecar('caddy', ['run', '--config', configPath])
     const output = await command.spawn()
 
     if (type(output.pid) === 'number') {
@@ -68,10 +70,56 @@ export const initHttpServer = async () => {
     console.log('output', output)
 }
 
-/**执行本机应用程序 */
-export const runSoftware = async (path: string) => {
-    // const command = Command.create(path)
-    // const output = await command.execute()
-    const output = await invoke('run_external_program', { executablePath: path, args: [] })
-    console.log(output)
+/**
+ * 启动本机应用程序，由后端按平台的规则处理 .desktop、.lnk 和 .app
+ * @param path 应用的路径或搜索结果的 id
+ * @param files 用应用打开的文件
+ * @param args 追加的参数
+ */
+export const runSoftware = async (path: string, files: string[] = [], args: string[] = []) => {
+    await invoke('launch_app', { appId: path, files, args })
+}
+
+/**
+ * 搜索应用和插件，支持模糊匹配、拼音全拼和首字母
+ * @param query 搜索内容
+ * @param limit 最多返回的条数
+ */
+export const search = async (query: string, limit?: number) => {
+    return (await invoke('search', { query, limit })) as SearchResult[]
+}
+
+/**
+ * 解析搜索框的输入：前缀、引号、`type:`/`ext:` 过滤条件以及插件声明的参数
+ * @param query 搜索框的输入
+ */
+export const parseQuery = async (query: string) => {
+    return (await invoke('parse_query', { query })) as ParsedQuery
+}
+
+/**
+ * 记录一次启动，用于搜索结果排序
+ * @param id 搜索结果的 id
+ * @param query 启动时输入的搜索内容
+ */
+export const recordLaunch = async (id: string, query?: string) => {
+    return await invoke('record_launch', { id, query })
+}
+
+/**
+ * 执行搜索结果上的动作，启动类的动作由后端记录启动历史
+ * @param resultId 最近一次搜索返回的结果 id
+ * @param actionId 动作 id
+ * @param inputs 动作声明的输入
+ * @param confirmed 需要确认的动作是否已确认
+ * @param query 执行时输入的搜索内容
+ */
+export const executeAction = async (
+    resultId: string,
+    actionId: string,
+    inputs: Record<string, string>,
+    confirmed: boolean,
+    query?: string
+) => {
+    return (await invoke('execute_action', { resultId, actionId, inputs, confirmed, query })) as ActionOutcome
 }
@@ -1,36 +1,34 @@
 import type { PluginConfig, ScriptEnv } from '@/utils/typescript'
 import { invoke } from '@tauri-apps/api/core'
 import { Window } from '@tauri-apps/api/window'
-import { Webview, type WebviewOptions } from '@tauri-apps/api/webview'
-import { join, resourceDir } from '@tauri-apps/api/path'
+import { Webview, getCurrentWebview, type WebviewOptions } from '@tauri-apps/api/webview'
 import { getWindow } from './window'
 
 /**
- * 获取插件根目录
+ * 获取用户插件目录
  * @returns 插件目录
  */
 export const getPluginsPath = async () => {
-    const homePath = await resourceDir()
-    return await join(homePath, '/plugins')
+    return (await invoke('get_user_plugins_dir')) as string
 }
 /**
- * 获取插件路径
- * @param name 插件名称/文件夹名称
+ * 获取插件路径（内置、系统、用户、开发目录中生效的那个）
+ * @param id 插件 id
  * @returns
  */
-export const getPluginPath = async (name: string) => {
-    const pluginsDir = await getPluginsPath()
-    return join(pluginsDir, name)
+export const getPluginPath = async (id: string) => {
+    return (await invoke('get_plugin_dir', { id })) as string
 }
 
 /**
  * 执行二进制插件
  * @param executablePath 二进制文件路径
  * @param args 参数
+ * @param pluginId 插件 id，用来读取插件声明的资源限制
  */
-export const execBinaryPlugin = async (executablePath: string, args: string[] = []) => {
+export const execBinaryPlugin = async (executablePath: string, args: string[] = [], pluginId?: string) => {
     return new Promise((resolve, reject) => {
-        invoke('run_external_program', { executablePath, args })
+        invoke('run_external_program', { executablePath, args, pluginId })
             .then((result) => {
                 console.log('run_external_program', result)
                 resolve(result)
@@ -41,7 +39,7 @@ export const execBinaryPlugin = async (executablePath: string, args: string[] =
     })
 }
 
-export const execScriptPlugin = async (env: ScriptEnv, path: string, args: string[] = []) => {
+export const execScriptPlugin = async (env: ScriptEnv, path: string, args: string[] = [], pluginId?: string) => {
     let fn = ''
     switch (env) {
         case 'node':
@@ -59,7 +57,7 @@ export const execScriptPlugin = async (env: ScriptEnv, path: string, args: strin
     }
 
     return new Promise((resolve, reject) => {
-        invoke(fn, { script: path, args })
+        invoke(fn, { script: path, args, pluginId })
             .then((result) => {
                 resolve(result)
             })
@@ -124,3 +122,76 @@ export const getPluginOfPrefix = (prefix: string, pluginList: Array<PluginConfig
     return pluginList.find((plugin) => 'prefix' in plugin ? plugin?.prefix === prefix : null)
 }
 
+
+export interface PluginLogEntry {
+    timestamp: number
+    program: string
+    args: string[]
+    exitCode: number | null
+    success: boolean
+    exceeded: string | null
+    durationMs: number
+    stdout: string
+    stderr: string
+}
+
+/**
+ * 获取插件的运行日志
+ * @param id 插件 id
+ * @param since 毫秒时间戳，只返回这之后的日志
+ * @param limit 最多返回的条数
+ */
+export const getPluginLogs = async (id: string, since?: number, limit?: number) => {
+    return (await invoke('get_plugin_logs', { id, since, limit })) as PluginLogEntry[]
+}
+
+export interface BusMessage<T = unknown> {
+    topic: string
+    from: string | null
+    payload: T
+}
+
+/**
+ * 向消息总线发布消息
+ * @param topic 主题
+ * @param payload 消息内容
+ * @returns 收到消息的订阅者数量
+ */
+export const busPublish = async (topic: string, payload: unknown) => {
+    return (await invoke('bus_publish', { topic, payload })) as number
+}
+
+// 当前 webview 中每个主题的订阅数，后端按 webview 记录主题，最后一个回调取消时才取消订阅
+const busTopics = new Map<string, number>()
+
+/**
+ * 订阅消息总线上的主题
+ * @param topic 主题，支持 `*` 结尾的前缀通配
+ * @param callback 收到消息时的回调
+ * @returns 取消订阅的函数
+ */
+export const busSubscribe = async <T = unknown>(topic: string, callback: (message: BusMessage<T>) => void) => {
+    await invoke('bus_subscribe', { topic })
+    busTopics.set(topic, (busTopics.get(topic) ?? 0) + 1)
+    const webview = getCurrentWebview()
+    const unlisten = await webview.listen<BusMessage<T>>('toolbox://bus', (event) => {
+        if (event.payload.topic === topic || (topic.endsWith('*') && event.payload.topic.startsWith(topic.slice(0, -1)))) {
+            callback(event.payload)
+        }
+    })
+    let subscribed = true
+    return async () => {
+        if (!subscribed) {
+            return
+        }
+        subscribed = false
+        unlisten()
+        const count = (busTopics.get(topic) ?? 1) - 1
+        if (count > 0) {
+            busTopics.set(topic, count)
+            return
+        }
+        busTopics.delete(topic)
+        await invoke('bus_unsubscribe', { topic })
+    }
+}
@@ -39,10 +39,140 @@ export type PluginConfig = PluginBinaryConfig | PluginModuleConfig | PluginScrip
 export interface InstalledPkg {
     name: string
     path: string
-    icon: string
+    // 缓存中的 PNG 图标，通过 convertFileSrc 显示
+    icon?: string
+    version?: string
+    // Linux 为 desktop id，macOS 为 bundle id
+    id?: string
+    description?: string
+    categories?: string[]
+    keywords?: string[]
+    // 未翻译的名称等其它名称
+    names?: string[]
+    // 安装来源，如 Flatpak、Snap、AppImage
+    source?: string
+}
+
+export type QueryMode = 'search' | 'content' | 'clipboard' | 'shell' | 'calculator' | 'plugin'
+
+// 后端解析后的搜索框输入
+export interface ParsedQuery {
+    mode: QueryMode
+    prefix?: string
+    pluginId?: string
+    // 去掉前缀和过滤条件后的内容
+    text: string
+    // 传给插件的参数
+    args: string[]
+    filters: {
+        kinds: SearchResultKind[]
+        extensions: string[]
+    }
+    // 按插件声明转换后的参数
+    arguments: Record<string, unknown>
+    error?: {
+        message: string
+        position?: number
+    }
+}
+
+export type SearchResultKind = 'calculator' | 'snippet' | 'app' | 'window' | 'plugin' | 'internalPlugin' | 'recent' | 'bookmark' | 'file' | 'content' | 'clipboard' | 'command' | 'pluginOutput' | 'shellOutput'
+
+export interface SearchResult {
+    id: string
+    kind: SearchResultKind
+    title: string
+    subtitle?: string
+    icon?: string
+    score: number
+    // title 中命中的字符下标
+    highlights: number[]
+    data: PluginConfig | InstalledPkg | FileInfo | ClipEntry | RecentItem | ShellCommand
+    // 显示在标题后面的状态，如已固定、运行中
+    labels: string[]
+    // 可执行的动作，第一个为点击时的默认动作
+    actions: ResultAction[]
+}
+
+export interface ActionInput {
+    name: string
+    label: string
+    kind: 'text' | 'directory' | 'file'
+}
+
+export interface ResultAction {
+    id: string
+    label: string
+    inputs: ActionInput[]
+    // 不为空时需要确认后才执行
+    confirm?: string
+    // 为 false 时只显示为按钮，点击结果时不执行
+    clickable: boolean
+}
+
+// execute_action 执行完后要做的事
+export type ActionOutcome =
+    | { type: 'done' }
+    | { type: 'hide' }
+    | { type: 'refresh' }
+    | { type: 'openPlugin'; config: PluginConfig }
+    | { type: 'shell'; result: SearchResult }
+    | { type: 'pluginOutput'; result: SearchResult }
+
+export interface FileInfo {
+    path: string
+    isDir: boolean
+    size: number
+    modified: number
 }
 
-export interface InputFormater {
-    prefix: string
-    value: Array<string>
+export interface RecentItem {
+    path: string
+    name: string
+    mime?: string
+    app?: string
+    exec?: string
+    time: number
+    source: string
+}
+
+export interface ShellCommand {
+    command: string
+    cwd?: string
+    // 匹配的确认规则，需要确认后才执行
+    dangerous?: string
+    history: boolean
+    count?: number
+    exitCode?: number
+}
+
+export interface ClipEntry {
+    id: string
+    kind: 'text' | 'image' | 'files'
+    text?: string
+    files?: string[]
+    image?: string
+    width: number
+    height: number
+    source?: string
+    pinned: boolean
+    created: number
+    lastUsed: number
+    count: number
+}
+
+export interface Snippet {
+    id: string
+    keyword: string
+    name: string
+    body: string
+}
+
+// 别名指向的应用或插件，插件的 args 放在输入的参数之前
+export type AliasTarget = { type: 'app'; path: string } | { type: 'plugin'; id: string; args: string[] }
+
+export interface Alias {
+    id: string
+    keyword: string
+    target: AliasTarget
 }